@@ -724,6 +724,10 @@ struct OutgoingIntentCard: View {
             Text("[FAILED]")
                 .font(TerminalTypography.label())
                 .foregroundColor(TerminalPalette.error)
+        case .cancelled:
+            Text("[CANCELLED]")
+                .font(TerminalTypography.label())
+                .foregroundColor(TerminalPalette.textDim)
         }
     }
 
@@ -733,6 +737,7 @@ struct OutgoingIntentCard: View {
         case .sending: return TerminalPalette.cyan
         case .confirmed: return TerminalPalette.success
         case .failed: return TerminalPalette.error
+        case .cancelled: return TerminalPalette.textDim
         }
     }
 }
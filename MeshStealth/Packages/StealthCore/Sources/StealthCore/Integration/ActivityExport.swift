@@ -0,0 +1,206 @@
+import Foundation
+
+// MARK: - Account Mapping
+
+/// Ledger account names used when exporting activity as double-entry postings
+public struct LedgerAccountMapping: Sendable {
+    /// Main (public) wallet
+    public let wallet: String
+
+    /// Funds held at stealth addresses
+    public let stealth: String
+
+    /// Incoming mesh payments
+    public let income: String
+
+    /// Outgoing mesh payments
+    public let expenses: String
+
+    /// Devnet faucet funding
+    public let airdrops: String
+
+    public init(
+        wallet: String = "Assets:Solana:Wallet",
+        stealth: String = "Assets:Solana:Stealth",
+        income: String = "Income:Payments",
+        expenses: String = "Expenses:Payments",
+        airdrops: String = "Income:Airdrops"
+    ) {
+        self.wallet = wallet
+        self.stealth = stealth
+        self.income = income
+        self.expenses = expenses
+        self.airdrops = airdrops
+    }
+
+    public static let `default` = LedgerAccountMapping()
+
+    /// Accounts debited and credited by an activity of the given type
+    /// - Returns: nil for movements that don't change any account balance (hops)
+    public func accounts(for type: ActivityType) -> (debit: String, credit: String)? {
+        switch type {
+        case .shield: (stealth, wallet)
+        case .unshield: (wallet, stealth)
+        case .meshSend: (expenses, stealth)
+        case .meshReceive: (stealth, income)
+        case .airdrop: (wallet, airdrops)
+        case .hop: nil  // Stealth → stealth, same account on both sides
+        }
+    }
+}
+
+// MARK: - Fiat Valuation
+
+/// Fiat value of SOL at the time of each activity, for value-at-receipt reporting
+///
+/// The exporters don't fetch prices themselves; the app looks them up (e.g. from
+/// a price history it keeps) and hands them over through `price`.
+public struct FiatValuation: Sendable {
+    /// Currency code written next to the values, e.g. "USD"
+    public let currency: String
+
+    /// Price of 1 SOL in `currency` at the given time, nil if unknown
+    public let price: @Sendable (Date) -> Decimal?
+
+    public init(currency: String, price: @escaping @Sendable (Date) -> Decimal?) {
+        self.currency = currency
+        self.price = price
+    }
+}
+
+// MARK: - Exporters
+
+/// Converts the activity feed into formats bookkeeping tools can import
+///
+/// Only completed activity is exported; pending, in-flight and failed items haven't
+/// moved funds.
+public enum ActivityExporter {
+
+    /// Export activity as CSV, one row per completed item, oldest first
+    /// - Parameters:
+    ///   - items: Activity items (e.g. `StealthWalletManager.activityItems`)
+    ///   - valuation: Adds a `value_<currency>` column with each amount's value at
+    ///     its timestamp, empty where the price is unknown
+    /// - Returns: CSV text with a header row
+    public static func csv(_ items: [ActivityItem], valuation: FiatValuation? = nil) -> String {
+        var header = "date,type,amount_lamports,amount_sol,stealth_address,signature,peer"
+        if let valuation {
+            header += ",value_\(valuation.currency.lowercased())"
+        }
+        var lines = [header]
+
+        for item in completed(items) {
+            var fields = [
+                isoDate(item.timestamp),
+                item.type.rawValue,
+                String(item.amount),
+                sol(item.amount),
+                item.stealthAddress ?? "",
+                item.transactionSignature ?? "",
+                item.peerName ?? ""
+            ]
+            if let valuation {
+                let price = valuation.price(item.timestamp)
+                fields.append(price.map { fiat(Decimal(item.amount) / 1_000_000_000 * $0) } ?? "")
+            }
+            lines.append(fields.map(csvField).joined(separator: ","))
+        }
+
+        return lines.joined(separator: "\n") + "\n"
+    }
+
+    /// Export activity as ledger-cli transactions, oldest first
+    /// - Parameters:
+    ///   - items: Activity items (e.g. `StealthWalletManager.activityItems`)
+    ///   - mapping: Ledger account names to post to
+    ///   - valuation: Prices each posting (`@ <price> <currency>`) at its timestamp
+    ///     where the price is known
+    /// - Returns: Journal text, one transaction per balance-changing item
+    public static func ledger(
+        _ items: [ActivityItem],
+        mapping: LedgerAccountMapping = .default,
+        valuation: FiatValuation? = nil
+    ) -> String {
+        var entries: [String] = []
+
+        for item in completed(items) {
+            guard let accounts = mapping.accounts(for: item.type) else {
+                continue
+            }
+
+            var entry = "\(ledgerDate(item.timestamp)) * \(description(for: item))\n"
+            if let signature = item.transactionSignature {
+                entry += "    ; signature: \(signature)\n"
+            }
+            var posting = "\(sol(item.amount)) SOL"
+            if let valuation, let price = valuation.price(item.timestamp) {
+                posting += " @ \(fiat(price)) \(valuation.currency)"
+            }
+            entry += "    \(accounts.debit)  \(posting)\n"
+            entry += "    \(accounts.credit)\n"
+            entries.append(entry)
+        }
+
+        return entries.joined(separator: "\n")
+    }
+
+    // MARK: - Private Helpers
+
+    private static func completed(_ items: [ActivityItem]) -> [ActivityItem] {
+        items
+            .filter { $0.status == .completed }
+            .sorted { $0.timestamp < $1.timestamp }
+    }
+
+    private static func description(for item: ActivityItem) -> String {
+        switch item.type {
+        case .shield: "Shield"
+        case .unshield: "Unshield"
+        case .meshSend: item.peerName.map { "Mesh payment to \($0)" } ?? "Mesh payment sent"
+        case .meshReceive: item.peerName.map { "Mesh payment from \($0)" } ?? "Mesh payment received"
+        case .hop: "Privacy hop"
+        case .airdrop: "Devnet airdrop"
+        }
+    }
+
+    /// Exact lamports → SOL with 9 decimals (no floating point)
+    private static func sol(_ lamports: UInt64) -> String {
+        let fraction = String(lamports % 1_000_000_000)
+        let padded = String(repeating: "0", count: 9 - fraction.count) + fraction
+        return "\(lamports / 1_000_000_000).\(padded)"
+    }
+
+    /// Fiat amount with 2 decimals
+    private static func fiat(_ value: Decimal) -> String {
+        let formatter = NumberFormatter()
+        formatter.locale = Locale(identifier: "en_US_POSIX")
+        formatter.numberStyle = .decimal
+        formatter.usesGroupingSeparator = false
+        formatter.minimumFractionDigits = 2
+        formatter.maximumFractionDigits = 2
+        formatter.roundingMode = .halfEven
+        return formatter.string(from: value as NSDecimalNumber) ?? "\(value)"
+    }
+
+    private static func isoDate(_ date: Date) -> String {
+        let formatter = ISO8601DateFormatter()
+        formatter.timeZone = TimeZone(identifier: "UTC")
+        return formatter.string(from: date)
+    }
+
+    private static func ledgerDate(_ date: Date) -> String {
+        let formatter = DateFormatter()
+        formatter.locale = Locale(identifier: "en_US_POSIX")
+        formatter.timeZone = TimeZone(identifier: "UTC")
+        formatter.dateFormat = "yyyy/MM/dd"
+        return formatter.string(from: date)
+    }
+
+    /// Quote a CSV field if it contains a separator, quote or newline
+    private static func csvField(_ value: String) -> String {
+        guard value.contains(where: { $0 == "," || $0 == "\"" || $0.isNewline }) else {
+            return value
+        }
+        return "\"" + value.replacingOccurrences(of: "\"", with: "\"\"") + "\""
+    }
+}
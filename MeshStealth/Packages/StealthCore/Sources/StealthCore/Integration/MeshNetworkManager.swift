@@ -218,11 +218,20 @@ public class MeshNetworkManager: ObservableObject {
     ///   - amount: Amount in lamports
     ///   - tokenMint: SPL token mint (nil for native SOL)
     ///   - memo: Optional memo for recipient
+    ///   - idempotencyKey: Caller-chosen key for the payment. A retry with the same
+    ///     key doesn't queue a second payment; the first is left to the queue.
+    ///   - delay: Window (in seconds) to draw a random broadcast delay from, so the
+    ///     on-chain transfer isn't timed by whatever triggered the payment. The delay
+    ///     is persisted with the intent, and a delayed payment is never pre-signed for
+    ///     the receiver to settle early. Cancel it with
+    ///     `StealthWalletManager.cancelOutgoingPayment(id:)` before it is sent.
     public func sendPayment(
         to recipientMetaAddress: String,
         amount: UInt64,
         tokenMint: String? = nil,
-        memo: String? = nil
+        memo: String? = nil,
+        idempotencyKey: String? = nil,
+        delay: ClosedRange<TimeInterval>? = nil
     ) async throws {
         DebugLogger.log("========== STARTING MESH PAYMENT ==========", category: "MESH-SEND")
         DebugLogger.log("Amount: \(amount) lamports (\(Double(amount) / 1_000_000_000) SOL)", category: "MESH-SEND")
@@ -237,6 +246,11 @@ public class MeshNetworkManager: ObservableObject {
         }
         DebugLogger.log("Wallet exists", category: "MESH-SEND")
 
+        if let key = idempotencyKey, let existing = walletManager.outgoingIntent(idempotencyKey: key) {
+            DebugLogger.log("Payment \(key) already queued as \(existing.id) (\(existing.status.rawValue))", category: "MESH-SEND")
+            return
+        }
+
         // Parse meta-address
         DebugLogger.log("Parsing meta-address...", category: "MESH-SEND")
         let (spendingPubKey, viewingPubKey, mlkemPubKey): (Data, Data, Data?)
@@ -279,6 +293,11 @@ public class MeshNetworkManager: ObservableObject {
             throw error
         }
 
+        guard walletManager.recordOutgoingEphemeralKey(stealthResult.ephemeralPublicKey) else {
+            DebugLogger.error("Ephemeral key was already used by an earlier payment", category: "MESH-SEND")
+            throw MeshNetworkError.ephemeralKeyReused
+        }
+
         // Step 1: Create outgoing payment intent FIRST (so it's always queued)
         let intent = OutgoingPaymentIntent(
             recipientMetaAddress: recipientMetaAddress,
@@ -286,7 +305,9 @@ public class MeshNetworkManager: ObservableObject {
             ephemeralPublicKey: stealthResult.ephemeralPublicKey,
             mlkemCiphertext: stealthResult.mlkemCiphertext,
             amount: amount,
-            memo: memo
+            memo: memo,
+            idempotencyKey: idempotencyKey,
+            notBefore: delay.map { Date().addingTimeInterval(TimeInterval.random(in: $0)) }
         )
 
         // Step 2: Queue the payment intent immediately (this also records activity)
@@ -298,46 +319,49 @@ public class MeshNetworkManager: ObservableObject {
         var preSignedTransaction: String? = nil
         var nonceAccountAddress: String? = nil
 
-        do {
-            let nonceEntry = try await nonceManager.reserveNonce()  // Actor-isolated call
-            DebugLogger.log("Reserved nonce: \(nonceEntry.address)", category: "MESH-SEND")
+        // A delayed payment isn't pre-signed: the receiver could settle it at once
+        if intent.notBefore == nil {
+            do {
+                let nonceEntry = try await nonceManager.reserveNonce()  // Actor-isolated call
+                DebugLogger.log("Reserved nonce: \(nonceEntry.address)", category: "MESH-SEND")
 
-            // Build durable nonce transfer
-            guard let mainWallet = walletManager.mainWallet else {
-                throw MeshNetworkError.walletNotInitialized
-            }
+                // Build durable nonce transfer
+                guard let mainWallet = walletManager.mainWallet else {
+                    throw MeshNetworkError.walletNotInitialized
+                }
 
-            let senderPubkey = await mainWallet.publicKeyData
-            guard let stealthAddressPubkey = Data(base58Decoding: stealthResult.stealthAddress) else {
-                throw MeshNetworkError.invalidStealthAddress
-            }
-            guard let nonceAccountPubkey = Data(base58Decoding: nonceEntry.address) else {
-                throw MeshNetworkError.invalidStealthAddress
-            }
+                let senderPubkey = await mainWallet.publicKeyData
+                guard let stealthAddressPubkey = Data(base58Decoding: stealthResult.stealthAddress) else {
+                    throw MeshNetworkError.invalidStealthAddress
+                }
+                guard let nonceAccountPubkey = Data(base58Decoding: nonceEntry.address) else {
+                    throw MeshNetworkError.invalidStealthAddress
+                }
 
-            let message = try SolanaTransaction.buildDurableNonceTransfer(
-                from: senderPubkey,
-                to: stealthAddressPubkey,
-                lamports: amount,
-                nonceAccount: nonceAccountPubkey,
-                nonceAuthority: senderPubkey,
-                nonceValue: nonceEntry.nonceValue
-            )
+                let message = try SolanaTransaction.buildDurableNonceTransfer(
+                    from: senderPubkey,
+                    to: stealthAddressPubkey,
+                    lamports: amount,
+                    nonceAccount: nonceAccountPubkey,
+                    nonceAuthority: senderPubkey,
+                    nonceValue: nonceEntry.nonceValue
+                )
 
-            let signature = try await mainWallet.sign(message.serialize())
-            preSignedTransaction = try SolanaTransaction.buildSignedTransaction(
-                message: message,
-                signature: signature
-            )
-            nonceAccountAddress = nonceEntry.address
+                let signature = try await mainWallet.sign(message.serialize())
+                preSignedTransaction = try SolanaTransaction.buildSignedTransaction(
+                    message: message,
+                    signature: signature
+                )
+                nonceAccountAddress = nonceEntry.address
 
-            DebugLogger.log("Pre-signed transaction created (v2 protocol)", category: "MESH-SEND")
-        } catch NonceError.poolEmpty {
-            // No nonces available - use v1 protocol (sender settles)
-            DebugLogger.log("No nonces available - using v1 protocol (sender settles)", category: "MESH-SEND")
-        } catch {
-            // Pre-signing failed - fall back to v1 protocol
-            DebugLogger.error("Pre-signing failed, using v1 protocol", error: error, category: "MESH-SEND")
+                DebugLogger.log("Pre-signed transaction created (v2 protocol)", category: "MESH-SEND")
+            } catch NonceError.poolEmpty {
+                // No nonces available - use v1 protocol (sender settles)
+                DebugLogger.log("No nonces available - using v1 protocol (sender settles)", category: "MESH-SEND")
+            } catch {
+                // Pre-signing failed - fall back to v1 protocol
+                DebugLogger.error("Pre-signing failed, using v1 protocol", error: error, category: "MESH-SEND")
+            }
         }
 
         // Step 4: Create mesh payload with pre-signed tx if available
@@ -364,7 +388,10 @@ public class MeshNetworkManager: ObservableObject {
 
         // Step 4: If online, try to execute on-chain; otherwise stay queued
         // Network errors are caught and payment stays queued (graceful offline fallback)
-        if isOnline {
+        if let notBefore = intent.notBefore {
+            DebugLogger.log("Delayed payment - broadcast scheduled for \(notBefore)", category: "MESH-SEND")
+            scheduleDelayedSend(intent)
+        } else if isOnline {
             do {
                 DebugLogger.log("Online - attempting on-chain transfer", category: "MESH-SEND")
                 try await executeOutgoingPayment(intent)
@@ -385,7 +412,18 @@ public class MeshNetworkManager: ObservableObject {
     }
 
     /// Execute a queued outgoing payment on-chain
-    private func executeOutgoingPayment(_ intent: OutgoingPaymentIntent) async throws {
+    ///
+    /// The signature and blockhash of each attempt are persisted before it is
+    /// broadcast. A retry first checks whether an earlier attempt landed, by its
+    /// signature or by the stealth address balance (the address is fresh for every
+    /// intent, so any balance comes from this payment), and doesn't send while an
+    /// earlier attempt's blockhash can still land.
+    private func executeOutgoingPayment(_ queued: OutgoingPaymentIntent) async throws {
+        let intent = walletManager.outgoingIntent(id: queued.id) ?? queued
+        guard intent.status != .cancelled else {
+            DebugLogger.log("Payment \(intent.id) was cancelled, not sending", category: "MESH-SEND")
+            return
+        }
         DebugLogger.log("========== EXECUTING ON-CHAIN PAYMENT ==========", category: "MESH-SEND")
         DebugLogger.log("Intent ID: \(intent.id)", category: "MESH-SEND")
         DebugLogger.log("Amount: \(intent.amount) lamports", category: "MESH-SEND")
@@ -396,6 +434,24 @@ public class MeshNetworkManager: ObservableObject {
             throw MeshNetworkError.walletNotInitialized
         }
 
+        if intent.transactionSignature != nil || intent.blockhash != nil {
+            let landed: Bool
+            if let signature = intent.transactionSignature, try await faucet.transactionSucceeded(signature: signature) {
+                landed = true
+            } else {
+                landed = try await faucet.getBalance(address: intent.stealthAddress) >= intent.amount
+            }
+            if landed {
+                DebugLogger.log("An earlier attempt already paid \(intent.stealthAddress)", category: "MESH-SEND")
+                walletManager.updateOutgoingIntent(id: intent.id, status: .confirmed)
+                return
+            }
+            if let blockhash = intent.blockhash, try await faucet.isBlockhashValid(blockhash) {
+                DebugLogger.log("An earlier attempt may still land, not resending yet", category: "MESH-SEND")
+                throw MeshNetworkError.previousAttemptPending
+            }
+        }
+
         // Update status to sending
         walletManager.updateOutgoingIntent(id: intent.id, status: .sending)
 
@@ -446,6 +502,14 @@ public class MeshNetworkManager: ObservableObject {
                 signature: signature
             )
 
+            // Record the attempt before it can land
+            walletManager.updateOutgoingIntent(
+                id: intent.id,
+                status: .sending,
+                signature: signature.base58EncodedString,
+                blockhash: blockhash
+            )
+
             // Check if privacy routing should be used for sender anonymity
             let txSignature: String
             if let privacyService = privacyRoutingService,
@@ -502,6 +566,33 @@ public class MeshNetworkManager: ObservableObject {
         }
     }
 
+    /// Send a delayed payment once its `notBefore` passes, unless it was cancelled or
+    /// sent by `executeQueuedPayments` meanwhile
+    ///
+    /// The timer doesn't survive a restart; the persisted intent is then sent by the
+    /// first `executeQueuedPayments` after its `notBefore`.
+    private func scheduleDelayedSend(_ intent: OutgoingPaymentIntent) {
+        guard let notBefore = intent.notBefore else { return }
+
+        Task { @MainActor [weak self] in
+            let delay = notBefore.timeIntervalSinceNow
+            if delay > 0 {
+                try? await Task.sleep(nanoseconds: UInt64(delay * 1_000_000_000))
+            }
+            guard let self = self,
+                  self.isOnline,
+                  let current = self.walletManager.outgoingIntent(id: intent.id),
+                  current.status == .queued else {
+                return
+            }
+            do {
+                try await self.executeOutgoingPayment(current)
+            } catch {
+                DebugLogger.error("Failed to execute delayed payment \(intent.id)", error: error, category: "MESH-SEND")
+            }
+        }
+    }
+
     /// Execute all queued outgoing payments (called when coming online)
     public func executeQueuedPayments() async {
         let queuedPayments = walletManager.getQueuedOutgoingPayments()
@@ -543,6 +634,10 @@ public class MeshNetworkManager: ObservableObject {
             )
         }
 
+        guard walletManager.recordOutgoingEphemeralKey(stealthResult.ephemeralPublicKey) else {
+            throw MeshNetworkError.ephemeralKeyReused
+        }
+
         // Create payload
         let payload = MeshStealthPayload(
             from: stealthResult,
@@ -699,6 +794,8 @@ public enum MeshNetworkError: Error, LocalizedError {
     case insufficientBalance(available: UInt64, required: UInt64)
     case invalidStealthAddress
     case signingFailed
+    case ephemeralKeyReused
+    case previousAttemptPending
 
     public var errorDescription: String? {
         switch self {
@@ -720,6 +817,10 @@ public enum MeshNetworkError: Error, LocalizedError {
             return "Invalid stealth address generated"
         case .signingFailed:
             return "Failed to sign transaction"
+        case .ephemeralKeyReused:
+            return "Ephemeral key was already used by an earlier payment"
+        case .previousAttemptPending:
+            return "An earlier attempt of this payment may still land; retry once its blockhash expires"
         }
     }
 }
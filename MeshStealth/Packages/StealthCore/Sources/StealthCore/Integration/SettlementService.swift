@@ -21,13 +21,18 @@ public struct SettlementConfiguration: Sendable {
     /// Timeout for settlement transactions
     public let transactionTimeout: TimeInterval
 
+    /// Base58 cold wallet address to sweep settlements to
+    /// nil settles to a fresh stealth address of our own instead
+    public let coldAddress: String?
+
     public init(
         maxAttempts: Int = 5,
         retryDelay: TimeInterval = 30,
         autoSettle: Bool = true,
         minBalanceForSettlement: UInt64 = 10_000,  // 0.00001 SOL
         preferWiFi: Bool = false,
-        transactionTimeout: TimeInterval = 60
+        transactionTimeout: TimeInterval = 60,
+        coldAddress: String? = nil
     ) {
         self.maxAttempts = maxAttempts
         self.retryDelay = retryDelay
@@ -35,6 +40,7 @@ public struct SettlementConfiguration: Sendable {
         self.minBalanceForSettlement = minBalanceForSettlement
         self.preferWiFi = preferWiFi
         self.transactionTimeout = transactionTimeout
+        self.coldAddress = coldAddress
     }
 
     public static let `default` = SettlementConfiguration()
@@ -297,7 +303,7 @@ public class SettlementService: ObservableObject {
                 )
             }
 
-            // 3. Generate new stealth destination (privacy hop to ourselves)
+            // 3. Pick the destination (privacy hop to ourselves, or the cold wallet)
             let destination = try getSettlementDestination()
             DebugLogger.log("[SETTLE] New stealth destination: \(destination.address)")
 
@@ -312,6 +318,30 @@ public class SettlementService: ObservableObject {
                 spendingKey: spendingKey
             )
             DebugLogger.log("[SETTLE] Transaction confirmed: \(signature)")
+            walletManager.recordSweep(
+                from: payment,
+                to: destination.isOwn ? .stealth(destination.address) : .external,
+                lamports: transferAmount,
+                fee: estimatedFee,
+                signature: signature
+            )
+
+            // Funds swept to the cold wallet are no longer ours to track
+            guard destination.isOwn else {
+                walletManager.updatePaymentStatus(
+                    id: payment.id,
+                    status: .settled,
+                    signature: signature
+                )
+
+                return SettlementResult(
+                    paymentId: payment.id,
+                    success: true,
+                    signature: signature,
+                    attemptNumber: attemptNumber,
+                    settledBy: .sender
+                )
+            }
 
             // 6. Create new PendingPayment for the destination
             let newPayment = PendingPayment(
@@ -518,11 +548,25 @@ public class SettlementService: ObservableObject {
         let ephemeralKey: Data
         let ciphertext: Data?
         let viewTag: UInt8
+        /// Whether this is a stealth address of ours (false for the cold wallet)
+        let isOwn: Bool
     }
 
-    /// Generate a new stealth address as settlement destination
-    /// This provides a privacy hop - funds settle to a NEW stealth address rather than main wallet
+    /// Pick the settlement destination
+    /// Normally a privacy hop - funds settle to a NEW stealth address rather than main wallet.
+    /// With `coldAddress` configured, funds go straight to the cold wallet instead.
     private func getSettlementDestination() throws -> SettlementDestination {
+        // Cold wallet: a plain address, so there is no announcement to publish
+        if let coldAddress = config.coldAddress {
+            return SettlementDestination(
+                address: coldAddress,
+                ephemeralKey: Data(),
+                ciphertext: nil,
+                viewTag: 0,
+                isOwn: false
+            )
+        }
+
         guard let keyPair = walletManager.keyPair else {
             throw SettlementError.noDestinationAddress
         }
@@ -541,7 +585,8 @@ public class SettlementService: ObservableObject {
             address: result.stealthAddress,
             ephemeralKey: result.ephemeralPublicKey,
             ciphertext: result.mlkemCiphertext,
-            viewTag: result.viewTag
+            viewTag: result.viewTag,
+            isOwn: true
         )
     }
 
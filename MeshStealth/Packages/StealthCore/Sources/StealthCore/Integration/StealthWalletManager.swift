@@ -9,6 +9,30 @@ public enum PendingPaymentStatus: String, Codable, Sendable {
     case settled        // Successfully unshielded to main wallet
     case failed         // Failed (will retry)
     case expired        // Payment expired before settlement
+
+    /// Whether a payment in this status may move to `next`
+    ///
+    /// `received → awaitingFunds` is how a funding transaction that disappears
+    /// (dropped in a fork before finalization) is rolled back. `settled` and
+    /// `expired` are terminal. Re-entering the current status is always allowed.
+    public func canTransition(to next: PendingPaymentStatus) -> Bool {
+        if next == self {
+            return true
+        }
+
+        switch self {
+        case .awaitingFunds:
+            return [.received, .failed, .expired].contains(next)
+        case .received:
+            return [.awaitingFunds, .settling, .expired].contains(next)
+        case .settling:
+            return [.received, .settled, .failed].contains(next)
+        case .failed:
+            return [.awaitingFunds, .received, .settling, .expired].contains(next)
+        case .settled, .expired:
+            return false
+        }
+    }
 }
 
 // MARK: - Outgoing Payment Intent (Sender-Side Queue)
@@ -19,6 +43,7 @@ public enum OutgoingPaymentStatus: String, Codable, Sendable {
     case sending        // Transaction in progress
     case confirmed      // Transaction confirmed on-chain
     case failed         // Failed (will retry)
+    case cancelled      // Cancelled before it was broadcast
 }
 
 /// An outgoing payment intent queued for when sender comes online
@@ -36,6 +61,20 @@ public struct OutgoingPaymentIntent: Codable, Sendable, Identifiable {
     public var errorMessage: String?
     public var attempts: Int
 
+    /// Received payment this one refunds (nil for an ordinary payment)
+    public let refundOf: UUID?
+
+    /// Caller-supplied key; queueing a second intent with the same key returns
+    /// the first instead
+    public let idempotencyKey: String?
+
+    /// Blockhash the last attempt was signed with. Until it expires that attempt
+    /// can still land, so no new one is sent.
+    public let blockhash: String?
+
+    /// Earliest time the payment may be broadcast (nil to send as soon as online)
+    public let notBefore: Date?
+
     public init(
         id: UUID = UUID(),
         recipientMetaAddress: String,
@@ -48,7 +87,11 @@ public struct OutgoingPaymentIntent: Codable, Sendable, Identifiable {
         status: OutgoingPaymentStatus = .queued,
         transactionSignature: String? = nil,
         errorMessage: String? = nil,
-        attempts: Int = 0
+        attempts: Int = 0,
+        refundOf: UUID? = nil,
+        idempotencyKey: String? = nil,
+        blockhash: String? = nil,
+        notBefore: Date? = nil
     ) {
         self.id = id
         self.recipientMetaAddress = recipientMetaAddress
@@ -62,6 +105,10 @@ public struct OutgoingPaymentIntent: Codable, Sendable, Identifiable {
         self.transactionSignature = transactionSignature
         self.errorMessage = errorMessage
         self.attempts = attempts
+        self.refundOf = refundOf
+        self.idempotencyKey = idempotencyKey
+        self.blockhash = blockhash
+        self.notBefore = notBefore
     }
 
     /// Amount in SOL
@@ -157,6 +204,24 @@ public struct ActivityItem: Codable, Sendable, Identifiable {
     }
 }
 
+/// A stealth address whose on-chain balance is below what the wallet tracks for it
+public struct BalanceDiscrepancy: Sendable, Equatable {
+    /// The stealth address
+    public let stealthAddress: String
+
+    /// Lamports (base units for token payments) the wallet's ledger (or, without ledger entries, its pending payments) says should be there
+    public let expected: UInt64
+
+    /// Amount actually held on-chain, or credited by the funding transfer
+    public let actual: UInt64
+
+    public init(stealthAddress: String, expected: UInt64, actual: UInt64) {
+        self.stealthAddress = stealthAddress
+        self.expected = expected
+        self.actual = actual
+    }
+}
+
 /// A pending stealth payment awaiting settlement
 public struct PendingPayment: Codable, Sendable, Identifiable {
     /// Unique identifier
@@ -232,6 +297,18 @@ public struct PendingPayment: Codable, Sendable, Identifiable {
     /// Who settled this payment
     public var settledBy: SettledBy?
 
+    /// Outgoing payment refunding this one, once queued
+    public var refundIntentId: UUID?
+
+    /// Amount the funding transfer actually credited, from its SplTransferEvent
+    /// (below `amount` when the mint withholds a Token-2022 transfer fee)
+    public var receivedAmount: UInt64?
+
+    /// Amount the recipient holds: `receivedAmount` once known, `amount` otherwise
+    public var creditedAmount: UInt64 {
+        receivedAmount ?? amount
+    }
+
     public init(
         id: UUID = UUID(),
         stealthAddress: String,
@@ -365,6 +442,9 @@ public class StealthWalletManager: ObservableObject {
     /// Unified activity feed
     @Published public private(set) var activityItems: [ActivityItem] = []
 
+    /// Double-entry ledger of inflows, sweeps, fees and rent reclaims per stealth address
+    @Published public private(set) var ledger = StealthLedger()
+
     // MARK: - Private
 
     private let keychainService: KeychainService
@@ -374,6 +454,13 @@ public class StealthWalletManager: ObservableObject {
     private let settledPaymentsKey = "meshstealth.settled_payments"
     private let outgoingIntentsKey = "meshstealth.outgoing_intents"
     private let activityItemsKey = "meshstealth.activity_items"
+    private let sentEphemeralKeysKey = "meshstealth.sent_ephemeral_keys"
+    private let receivedEphemeralKeysKey = "meshstealth.received_ephemeral_keys"
+    private let ledgerKey = "meshstealth.ledger"
+
+    /// Ephemeral keys of payments we've sent and received
+    private var sentEphemeralKeys = EphemeralKeyTracker()
+    private var receivedEphemeralKeys = EphemeralKeyTracker()
 
     /// Publisher for new payments
     private let newPaymentSubject = PassthroughSubject<PendingPayment, Never>()
@@ -387,6 +474,12 @@ public class StealthWalletManager: ObservableObject {
         settledPaymentSubject.eraseToAnyPublisher()
     }
 
+    /// Publisher for incoming payments whose ephemeral key was seen before
+    private let reusedEphemeralKeySubject = PassthroughSubject<PendingPayment, Never>()
+    public var reusedEphemeralKeyWarnings: AnyPublisher<PendingPayment, Never> {
+        reusedEphemeralKeySubject.eraseToAnyPublisher()
+    }
+
     // MARK: - Initialization
 
     public init(
@@ -419,6 +512,8 @@ public class StealthWalletManager: ObservableObject {
         loadSettledPayments()
         loadOutgoingIntents()
         loadActivityItems()
+        loadEphemeralKeys()
+        loadLedger()
         updatePendingBalance()
 
         isInitialized = true
@@ -498,23 +593,59 @@ public class StealthWalletManager: ObservableObject {
     // MARK: - Payment Management
 
     /// Add a new pending payment (received via mesh)
-    public func addPendingPayment(_ payment: PendingPayment) {
-        // Check for duplicates
-        guard !pendingPayments.contains(where: { $0.id == payment.id }) else {
-            return
+    /// - Returns: false if the payment was already known (same id, or the same
+    ///   stealth address and ephemeral key delivered again)
+    @discardableResult
+    public func addPendingPayment(_ payment: PendingPayment) -> Bool {
+        // Redelivered payloads get a fresh id, so also match on the payment itself
+        guard !pendingPayments.contains(where: {
+            $0.id == payment.id
+                || ($0.stealthAddress == payment.stealthAddress && $0.ephemeralPublicKey == payment.ephemeralPublicKey)
+        }) else {
+            return false
+        }
+
+        switch receivedEphemeralKeys.insert(payment.ephemeralPublicKey, stealthAddress: payment.stealthAddress) {
+        case .new:
+            break
+        case .repeated:
+            // Redelivery of a payment that has since been settled
+            return false
+        case .reused:
+            // The same ephemeral key for another stealth address links the two payments
+            DebugLogger.log("WARNING: Payment to \(payment.stealthAddress) reuses an ephemeral key seen before")
+            reusedEphemeralKeySubject.send(payment)
         }
+        saveEphemeralKeys()
 
         pendingPayments.append(payment)
         savePendingPayments()
         updatePendingBalance()
 
         newPaymentSubject.send(payment)
+        return true
+    }
+
+    /// Record the ephemeral key of a payment about to be sent
+    /// - Parameter ephemeralPublicKey: Ephemeral public key of the new stealth address
+    /// - Returns: false if an earlier payment already used the key (the payment must not be sent)
+    public func recordOutgoingEphemeralKey(_ ephemeralPublicKey: Data) -> Bool {
+        guard sentEphemeralKeys.insert(ephemeralPublicKey) else {
+            DebugLogger.log("Refusing to reuse ephemeral key \(ephemeralPublicKey.base58EncodedString)")
+            return false
+        }
+
+        saveEphemeralKeys()
+        return true
     }
 
     /// Add pending payment from mesh payload
     public func addPendingPayment(from payload: MeshStealthPayload) {
         let payment = PendingPayment(from: payload)
-        addPendingPayment(payment)
+        guard addPendingPayment(payment) else {
+            return
+        }
+        recordInflow(for: payment)
 
         // Record mesh receive activity (mesh payments are not shielded)
         recordMeshReceiveActivity(
@@ -536,6 +667,11 @@ public class StealthWalletManager: ObservableObject {
             return
         }
 
+        guard pendingPayments[index].status.canTransition(to: status) else {
+            DebugLogger.log("Ignoring invalid status change \(pendingPayments[index].status) → \(status) for payment \(id)")
+            return
+        }
+
         pendingPayments[index].status = status
         pendingPayments[index].settlementAttempts += 1
         pendingPayments[index].lastAttemptAt = Date()
@@ -544,6 +680,10 @@ public class StealthWalletManager: ObservableObject {
             pendingPayments[index].settlementSignature = sig
         }
 
+        if status == .received {
+            recordInflow(for: pendingPayments[index], signature: signature)
+        }
+
         if let err = error {
             pendingPayments[index].errorMessage = err
         }
@@ -571,6 +711,11 @@ public class StealthWalletManager: ObservableObject {
             return
         }
 
+        guard pendingPayments[index].status.canTransition(to: status) else {
+            DebugLogger.log("Ignoring invalid status change \(pendingPayments[index].status) → \(status) for payment \(id)")
+            return
+        }
+
         pendingPayments[index].status = status
         pendingPayments[index].settlementAttempts += 1
         pendingPayments[index].lastAttemptAt = Date()
@@ -584,6 +729,199 @@ public class StealthWalletManager: ObservableObject {
         updatePendingBalance()
     }
 
+    /// Reconcile a payment's status with the observed stealth address balance
+    ///
+    /// Moves `awaitingFunds → received` once the funds are visible, and back to
+    /// `awaitingFunds` if a balance seen earlier is gone (the funding transaction
+    /// was rolled back). Doesn't count as a settlement attempt.
+    /// - Parameters:
+    ///   - id: Payment to reconcile
+    ///   - balance: Current balance of the payment's stealth address in lamports
+    public func reconcileFunding(id: UUID, balance: UInt64) {
+        guard let index = pendingPayments.firstIndex(where: { $0.id == id }) else {
+            return
+        }
+
+        let funded = balance >= pendingPayments[index].amount
+        let current = pendingPayments[index].status
+        let next: PendingPaymentStatus
+
+        switch (current, funded) {
+        case (.awaitingFunds, true):
+            next = .received
+            recordInflow(for: pendingPayments[index])
+        case (.received, false):
+            DebugLogger.log("Funds for payment \(id) disappeared, waiting for them again")
+            next = .awaitingFunds
+            let address = pendingPayments[index].stealthAddress
+            let booked = ledger.stealthBalances[address] ?? 0
+            if booked > balance {
+                ledger.recordReversal(from: address, lamports: booked - balance, paymentId: id)
+                saveLedger()
+            }
+        default:
+            return
+        }
+
+        pendingPayments[index].status = next
+        savePendingPayments()
+        updatePendingBalance()
+    }
+
+    /// Reconcile a token payment with the SplTransferEvent of its funding transaction
+    ///
+    /// Marks an `awaitingFunds` payment `received` and records the amount the event says
+    /// was credited, so the payment is counted at its net value. Token amounts aren't
+    /// lamports, so nothing is booked in the ledger.
+    /// - Parameters:
+    ///   - id: Payment to reconcile
+    ///   - transferEvent: Event from `StealthPQClient.getSplTransferEvents(signature:)`
+    /// - Returns: The shortfall if less arrived than the payment expects (the transfer fee), nil otherwise
+    @discardableResult
+    public func reconcileFunding(id: UUID, transferEvent: SplTransferEventData) -> BalanceDiscrepancy? {
+        guard let index = pendingPayments.firstIndex(where: { $0.id == id }) else {
+            return nil
+        }
+
+        let payment = pendingPayments[index]
+        guard transferEvent.stealthPubkey == payment.stealthAddress,
+              transferEvent.mint == payment.tokenMint else {
+            DebugLogger.log("Transfer event for \(transferEvent.stealthPubkey) doesn't fund payment \(id)")
+            return nil
+        }
+
+        pendingPayments[index].receivedAmount = transferEvent.received
+        if payment.status == .awaitingFunds {
+            pendingPayments[index].status = .received
+        }
+        savePendingPayments()
+        updatePendingBalance()
+
+        guard transferEvent.received < payment.amount else {
+            return nil
+        }
+        return BalanceDiscrepancy(
+            stealthAddress: payment.stealthAddress,
+            expected: payment.amount,
+            actual: transferEvent.received
+        )
+    }
+
+    /// Check tracked payments against on-chain balances
+    ///
+    /// Fetches the balance of every stealth address with a funded or awaited payment
+    /// or a nonzero ledger balance, reconciles each payment's funding status, and
+    /// reports addresses holding less than the ledger says they should. Addresses the
+    /// ledger has no entries for are checked against their payments instead.
+    /// - Returns: Addresses that are short (empty if history and chain agree)
+    public func reconcileWithChain() async -> [BalanceDiscrepancy] {
+        let tracked = pendingPayments.filter {
+            $0.status == .awaitingFunds || $0.status == .received || $0.status == .failed
+        }
+        let booked = ledger.stealthBalances.filter { $0.value > 0 }
+
+        var balances: [String: UInt64] = [:]
+        for address in Set(tracked.map(\.stealthAddress)).union(booked.keys) {
+            do {
+                balances[address] = try await faucet.getBalance(address: address)
+            } catch {
+                DebugLogger.log("Error fetching balance for \(address): \(error)")
+            }
+        }
+
+        // Compare against the statuses and ledger from before reconciling: reconcileFunding
+        // moves short `received` payments back to `awaitingFunds` and reverses their inflow,
+        // which would hide them
+        let untracked = tracked.filter { !ledger.contains($0.stealthAddress) }
+        let discrepancies = (
+            Self.balanceDiscrepancies(payments: untracked, balances: balances)
+                + ledger.discrepancies(balances: balances)
+        )
+        .sorted { $0.stealthAddress < $1.stealthAddress }
+
+        for payment in tracked {
+            if let balance = balances[payment.stealthAddress] {
+                reconcileFunding(id: payment.id, balance: balance)
+            }
+        }
+
+        return discrepancies
+    }
+
+    /// Compare what `received`/`failed` payments expect at each address with its balance
+    /// - Parameters:
+    ///   - payments: Tracked payments
+    ///   - balances: On-chain balances by stealth address; addresses missing here are skipped
+    /// - Returns: Addresses whose balance is below the sum of their payments, sorted by address
+    nonisolated static func balanceDiscrepancies(
+        payments: [PendingPayment],
+        balances: [String: UInt64]
+    ) -> [BalanceDiscrepancy] {
+        var expected: [String: UInt64] = [:]
+        for payment in payments where payment.status == .received || payment.status == .failed {
+            expected[payment.stealthAddress, default: 0] += payment.amount
+        }
+
+        return expected.compactMap { address, amount in
+            guard let actual = balances[address], actual < amount else {
+                return nil
+            }
+            return BalanceDiscrepancy(stealthAddress: address, expected: amount, actual: actual)
+        }
+        .sorted { $0.stealthAddress < $1.stealthAddress }
+    }
+
+    // MARK: - Ledger
+
+    /// Book a transfer out of a payment's stealth address
+    /// - Parameters:
+    ///   - payment: Payment whose stealth address the funds left
+    ///   - destination: `.stealth` for one of our addresses, `.external` otherwise
+    ///   - lamports: Amount transferred
+    ///   - fee: Network fee paid by the stealth address
+    ///   - signature: Transaction signature
+    public func recordSweep(
+        from payment: PendingPayment,
+        to destination: LedgerAccount,
+        lamports: UInt64,
+        fee: UInt64 = LAMPORTS_PER_SIGNATURE,
+        signature: String? = nil
+    ) {
+        ledger.recordSweep(
+            from: payment.stealthAddress,
+            to: destination,
+            lamports: lamports,
+            fee: fee,
+            paymentId: payment.id,
+            signature: signature
+        )
+        saveLedger()
+    }
+
+    /// Book CiphertextAccount rent returned to a stealth address by `reclaim_rent`
+    public func recordRentReclaim(stealthAddress: String, lamports: UInt64, signature: String? = nil) {
+        ledger.recordRentReclaim(to: stealthAddress, lamports: lamports, signature: signature)
+        saveLedger()
+    }
+
+    /// Book a payment's funds as arrived, topping its address up to the payment amount
+    ///
+    /// Stealth addresses are single-use, so a payment is funded once its address holds
+    /// its amount in the ledger; calling this again books nothing.
+    private func recordInflow(for payment: PendingPayment, signature: String? = nil) {
+        let booked = ledger.stealthBalances[payment.stealthAddress] ?? 0
+        guard payment.amount > booked else {
+            return
+        }
+        ledger.recordInflow(
+            to: payment.stealthAddress,
+            lamports: payment.amount - booked,
+            paymentId: payment.id,
+            signature: signature
+        )
+        saveLedger()
+    }
+
     /// Get payments ready for settlement
     public func getPaymentsForSettlement() -> [PendingPayment] {
         pendingPayments.filter { payment in
@@ -792,7 +1130,7 @@ public class StealthWalletManager: ObservableObject {
     private func updatePendingBalance() {
         pendingBalance = pendingPayments
             .filter { $0.status == .received || $0.status == .failed || $0.status == .awaitingFunds }
-            .reduce(0) { $0 + $1.amount }
+            .reduce(0) { $0 + $1.creditedAmount }
     }
 
     // MARK: - Outgoing Payment Intent Persistence
@@ -811,7 +1149,15 @@ public class StealthWalletManager: ObservableObject {
 
         let decoder = JSONDecoder()
         if let loaded = try? decoder.decode([OutgoingPaymentIntent].self, from: data) {
-            outgoingPaymentIntents = loaded
+            // An intent still sending was interrupted; retry it like a failed one,
+            // which first checks whether the interrupted attempt landed
+            outgoingPaymentIntents = loaded.map { intent in
+                var intent = intent
+                if intent.status == .sending {
+                    intent.status = .failed
+                }
+                return intent
+            }
         }
     }
 
@@ -837,10 +1183,61 @@ public class StealthWalletManager: ObservableObject {
         }
     }
 
+    // MARK: - Ephemeral Key Persistence
+
+    private func saveEphemeralKeys() {
+        let encoder = JSONEncoder()
+        if let data = try? encoder.encode(sentEphemeralKeys) {
+            userDefaults.set(data, forKey: sentEphemeralKeysKey)
+        }
+        if let data = try? encoder.encode(receivedEphemeralKeys) {
+            userDefaults.set(data, forKey: receivedEphemeralKeysKey)
+        }
+    }
+
+    private func loadEphemeralKeys() {
+        let decoder = JSONDecoder()
+        if let data = userDefaults.data(forKey: sentEphemeralKeysKey),
+           let loaded = try? decoder.decode(EphemeralKeyTracker.self, from: data) {
+            sentEphemeralKeys = loaded
+        }
+        if let data = userDefaults.data(forKey: receivedEphemeralKeysKey),
+           let loaded = try? decoder.decode(EphemeralKeyTracker.self, from: data) {
+            receivedEphemeralKeys = loaded
+        }
+    }
+
+    // MARK: - Ledger Persistence
+
+    private func saveLedger() {
+        let encoder = JSONEncoder()
+        if let data = try? encoder.encode(ledger) {
+            userDefaults.set(data, forKey: ledgerKey)
+        }
+    }
+
+    private func loadLedger() {
+        guard let data = userDefaults.data(forKey: ledgerKey) else {
+            return
+        }
+
+        let decoder = JSONDecoder()
+        if let loaded = try? decoder.decode(StealthLedger.self, from: data) {
+            ledger = loaded
+        }
+    }
+
     // MARK: - Outgoing Payment Queue Management
 
     /// Queue an outgoing payment for later execution (when offline)
-    public func queueOutgoingPayment(_ intent: OutgoingPaymentIntent) {
+    /// - Returns: The queued intent, or the one already queued with the same
+    ///   idempotency key
+    @discardableResult
+    public func queueOutgoingPayment(_ intent: OutgoingPaymentIntent) -> OutgoingPaymentIntent {
+        if let key = intent.idempotencyKey, let existing = outgoingIntent(idempotencyKey: key) {
+            return existing
+        }
+
         outgoingPaymentIntents.append(intent)
         saveOutgoingIntents()
 
@@ -854,6 +1251,17 @@ public class StealthWalletManager: ObservableObject {
             stealthAddress: intent.stealthAddress
         )
         addActivityItem(activity)
+        return intent
+    }
+
+    /// Look up an outgoing payment intent
+    public func outgoingIntent(id: UUID) -> OutgoingPaymentIntent? {
+        outgoingPaymentIntents.first { $0.id == id }
+    }
+
+    /// Look up the outgoing payment intent queued with an idempotency key
+    public func outgoingIntent(idempotencyKey: String) -> OutgoingPaymentIntent? {
+        outgoingPaymentIntents.first { $0.idempotencyKey == idempotencyKey }
     }
 
     /// Update outgoing payment intent status
@@ -861,7 +1269,8 @@ public class StealthWalletManager: ObservableObject {
         id: UUID,
         status: OutgoingPaymentStatus,
         signature: String? = nil,
-        error: String? = nil
+        error: String? = nil,
+        blockhash: String? = nil
     ) {
         guard let index = outgoingPaymentIntents.firstIndex(where: { $0.id == id }) else {
             return
@@ -882,7 +1291,11 @@ public class StealthWalletManager: ObservableObject {
             status: status,
             transactionSignature: signature ?? current.transactionSignature,
             errorMessage: error ?? current.errorMessage,
-            attempts: current.attempts + 1
+            attempts: current.attempts + 1,
+            refundOf: current.refundOf,
+            idempotencyKey: current.idempotencyKey,
+            blockhash: blockhash ?? current.blockhash,
+            notBefore: current.notBefore
         )
 
         saveOutgoingIntents()
@@ -892,14 +1305,114 @@ public class StealthWalletManager: ObservableObject {
         case .queued: .pending
         case .sending: .inProgress
         case .confirmed: .completed
-        case .failed: .failed
+        case .failed, .cancelled: .failed
         }
         updateActivityStatus(id: id, status: activityStatus, signature: signature, error: error)
     }
 
+    /// Cancel a queued outgoing payment before it is broadcast
+    ///
+    /// Only payments without a recorded attempt can be cancelled; once a
+    /// transaction was signed it may still land.
+    /// - Throws: `WalletError.paymentNotFound`, or `.alreadyBroadcast` if an attempt
+    ///   was recorded or the payment is no longer queued
+    public func cancelOutgoingPayment(id: UUID) throws {
+        guard let intent = outgoingIntent(id: id) else {
+            throw WalletError.paymentNotFound
+        }
+        guard intent.status == .queued, intent.transactionSignature == nil, intent.blockhash == nil else {
+            throw WalletError.alreadyBroadcast
+        }
+
+        updateOutgoingIntent(id: id, status: .cancelled, error: "Cancelled before broadcast")
+    }
+
+    /// Queue a refund of a received payment as a new stealth payment
+    ///
+    /// Generates a stealth address for the refund recipient, queues the payment and
+    /// links it to the refunded one through `OutgoingPaymentIntent.refundOf` and
+    /// `PendingPayment.refundIntentId`.
+    /// - Parameters:
+    ///   - paymentId: Received payment to refund
+    ///   - recipientMetaAddress: Meta-address of the original sender
+    ///   - amount: Lamports to refund, at most what the payment credited
+    ///   - memo: Optional memo for the refund
+    /// - Returns: The queued refund
+    /// - Throws: `WalletError.paymentNotFound`, `.alreadySettled` if the payment was
+    ///   already refunded, `.insufficientBalance` for an amount the payment doesn't cover
+    @discardableResult
+    public func queueRefund(
+        of paymentId: UUID,
+        to recipientMetaAddress: String,
+        amount: UInt64,
+        memo: String? = nil
+    ) throws -> OutgoingPaymentIntent {
+        guard let index = pendingPayments.firstIndex(where: { $0.id == paymentId }) else {
+            throw WalletError.paymentNotFound
+        }
+        guard pendingPayments[index].refundIntentId == nil else {
+            throw WalletError.alreadySettled
+        }
+        guard amount > 0, amount <= pendingPayments[index].creditedAmount else {
+            throw WalletError.insufficientBalance
+        }
+
+        let stealthResult = try StealthAddressGenerator.generateStealthAddressAuto(
+            metaAddressString: recipientMetaAddress
+        )
+        let intent = OutgoingPaymentIntent(
+            recipientMetaAddress: recipientMetaAddress,
+            stealthAddress: stealthResult.stealthAddress,
+            ephemeralPublicKey: stealthResult.ephemeralPublicKey,
+            mlkemCiphertext: stealthResult.mlkemCiphertext,
+            amount: amount,
+            memo: memo,
+            refundOf: paymentId
+        )
+
+        pendingPayments[index].refundIntentId = intent.id
+        savePendingPayments()
+        queueOutgoingPayment(intent)
+
+        return intent
+    }
+
+    /// Refund a received payment to the return address its sender attached
+    ///
+    /// Decrypts the return address from the payment's announcement, resolves the
+    /// wallet's registered meta-address and queues the refund with `queueRefund`.
+    /// - Parameters:
+    ///   - paymentId: Received payment to refund
+    ///   - ciphertextAccount: The payment's announcement
+    ///   - amount: Lamports to refund
+    ///   - client: Client used to look up the sender's meta-address
+    /// - Returns: The queued refund
+    /// - Throws: `WalletError.noReturnAddress` if the announcement has no verifiable
+    ///   return address or its wallet has no registered meta-address
+    @discardableResult
+    public func refund(
+        paymentId: UUID,
+        ciphertextAccount: CiphertextAccountData,
+        amount: UInt64,
+        client: StealthPQClient
+    ) async throws -> OutgoingPaymentIntent {
+        guard let keyPair = keyPair else {
+            throw WalletError.notInitialized
+        }
+        guard let wallet = try StealthScanner(keyPair: keyPair).openReturnAddress(ciphertextAccount: ciphertextAccount),
+              let record = try await client.getMetaAddress(owner: wallet) else {
+            throw WalletError.noReturnAddress
+        }
+
+        return try queueRefund(of: paymentId, to: record.metaAddressString, amount: amount)
+    }
+
     /// Get queued outgoing payments that need to be executed
-    public func getQueuedOutgoingPayments() -> [OutgoingPaymentIntent] {
-        outgoingPaymentIntents.filter { $0.status == .queued || $0.status == .failed }
+    /// - Parameter now: Time to compare delayed payments' `notBefore` against
+    public func getQueuedOutgoingPayments(now: Date = Date()) -> [OutgoingPaymentIntent] {
+        outgoingPaymentIntents.filter { intent in
+            (intent.status == .queued || intent.status == .failed) && (intent.notBefore ?? now) <= now
+        }
     }
 
     /// Remove confirmed outgoing payment from queue
@@ -1125,6 +1638,7 @@ public class StealthWalletManager: ObservableObject {
             isShielded: true
         )
         addPendingPayment(initialPayment)
+        recordInflow(for: initialPayment, signature: result.signature)
 
         // Record shield activity BEFORE mixing so we have the parent ID
         let shieldActivityId = recordShieldActivity(amount: lamports, stealthAddress: result.stealthAddress, signature: result.signature)
@@ -1311,6 +1825,7 @@ public class StealthWalletManager: ObservableObject {
         )
 
         DebugLogger.log("Unshield transaction completed: \(result.signature)", category: "WM-UNSHIELD")
+        recordSweep(from: payment, to: .external, lamports: result.amount, signature: result.signature)
 
         // Record unshield activity (unless already recorded by caller)
         if !skipActivityRecord {
@@ -1401,6 +1916,12 @@ public class StealthWalletManager: ObservableObject {
         DebugLogger.log("New ephemeral key: \(result.ephemeralPublicKey.base58EncodedString)", category: "WM-HOP")
         DebugLogger.log("New amount: \(result.amount) lamports", category: "WM-HOP")
         DebugLogger.log("Signature: \(result.signature)", category: "WM-HOP")
+        recordSweep(
+            from: payment,
+            to: .stealth(result.destinationStealthAddress),
+            lamports: result.amount,
+            signature: result.signature
+        )
 
         // Remove source payment from pending
         pendingPayments.removeAll { $0.id == payment.id }
@@ -1530,6 +2051,23 @@ public class StealthWalletManager: ObservableObject {
             )
 
             DebugLogger.log("Split \(index + 1) transaction: \(signature)", category: "SPLIT")
+            if isLastSplit {
+                ledger.recordSweepAll(
+                    from: payment.stealthAddress,
+                    to: .stealth(stealthResult.stealthAddress),
+                    fee: LAMPORTS_PER_SIGNATURE,
+                    paymentId: payment.id,
+                    signature: signature
+                )
+                saveLedger()
+            } else {
+                recordSweep(
+                    from: payment,
+                    to: .stealth(stealthResult.stealthAddress),
+                    lamports: amount,
+                    signature: signature
+                )
+            }
 
             // Create new pending payment for this split
             let newPayment = PendingPayment(
@@ -1673,6 +2211,14 @@ public class StealthWalletManager: ObservableObject {
             )
 
             DebugLogger.log("Transaction \(index + 1): \(signature)", category: "RECOMBINE")
+            ledger.recordSweepAll(
+                from: payment.stealthAddress,
+                to: .stealth(finalResult.stealthAddress),
+                fee: LAMPORTS_PER_SIGNATURE,
+                paymentId: payment.id,
+                signature: signature
+            )
+            saveLedger()
             signatures.append(signature)
             totalAmount += estimatedSendAmount
 
@@ -1744,6 +2290,9 @@ public class StealthWalletManager: ObservableObject {
         settledPayments = []
         activityItems = []
         outgoingPaymentIntents = []
+        sentEphemeralKeys = EphemeralKeyTracker()
+        receivedEphemeralKeys = EphemeralKeyTracker()
+        ledger = StealthLedger()
         pendingBalance = 0
         isInitialized = false
 
@@ -1751,6 +2300,9 @@ public class StealthWalletManager: ObservableObject {
         userDefaults.removeObject(forKey: settledPaymentsKey)
         userDefaults.removeObject(forKey: activityItemsKey)
         userDefaults.removeObject(forKey: outgoingIntentsKey)
+        userDefaults.removeObject(forKey: sentEphemeralKeysKey)
+        userDefaults.removeObject(forKey: receivedEphemeralKeysKey)
+        userDefaults.removeObject(forKey: ledgerKey)
     }
 }
 
@@ -1765,6 +2317,8 @@ public enum WalletError: Error, LocalizedError {
     case invalidKeyLength
     case signingFailed
     case insufficientBalance
+    case noReturnAddress
+    case alreadyBroadcast
 
     public var errorDescription: String? {
         switch self {
@@ -1784,6 +2338,10 @@ public enum WalletError: Error, LocalizedError {
             return "Failed to sign message"
         case .insufficientBalance:
             return "Insufficient balance for operation"
+        case .noReturnAddress:
+            return "Payment has no return address to refund to"
+        case .alreadyBroadcast:
+            return "Payment may already have been broadcast and can't be cancelled"
         }
     }
 }
@@ -0,0 +1,173 @@
+import Foundation
+
+/// Where BlockchainScanner reads CiphertextAccount announcements from
+///
+/// StealthPQClient (RPC polling) is the default. Deployments with an indexer or a
+/// websocket feed can plug in their own, and tests can serve fixtures with
+/// `StaticAnnouncementSource`.
+public protocol AnnouncementSource: Sendable {
+    /// Fetch the raw CiphertextAccount data for a stealth address
+    /// - Parameters:
+    ///   - stealthAddress: Base58-encoded stealth address
+    ///   - appId: App namespace to look in
+    /// - Returns: Account data (including discriminator) or nil if there is no announcement
+    func rawAnnouncement(stealthAddress: String, appId: UInt32) async throws -> Data?
+
+    /// Fetch the transaction that created the CiphertextAccount for a stealth address
+    /// - Parameters:
+    ///   - stealthAddress: Base58-encoded stealth address
+    ///   - appId: App namespace to look in
+    /// - Returns: Signature and slot of the creating transaction, or nil if unknown
+    func announcementOrigin(stealthAddress: String, appId: UInt32) async throws -> AnnouncementOrigin?
+}
+
+/// Transaction that created an announcement
+public struct AnnouncementOrigin: Equatable, Sendable {
+    /// Base58-encoded transaction signature
+    public let signature: String
+
+    /// Slot the transaction landed in
+    public let slot: UInt64
+
+    public init(signature: String, slot: UInt64) {
+        self.signature = signature
+        self.slot = slot
+    }
+}
+
+extension AnnouncementSource {
+    /// Sources without transaction history don't know where an announcement came from
+    public func announcementOrigin(stealthAddress: String, appId: UInt32) async throws -> AnnouncementOrigin? {
+        nil
+    }
+
+    /// Fetch and parse the CiphertextAccount for a stealth address
+    /// - Parameters:
+    ///   - stealthAddress: Base58-encoded stealth address
+    ///   - appId: App namespace to look in
+    /// - Returns: CiphertextAccountData or nil if not found or unparseable
+    public func announcement(stealthAddress: String, appId: UInt32) async throws -> CiphertextAccountData? {
+        guard let data = try await rawAnnouncement(stealthAddress: stealthAddress, appId: appId) else {
+            return nil
+        }
+
+        return CiphertextAccountData.parse(from: data)
+    }
+}
+
+extension StealthPQClient: AnnouncementSource {
+    public func rawAnnouncement(stealthAddress: String, appId: UInt32) async throws -> Data? {
+        try await getRawCiphertextAccount(stealthAddress: stealthAddress, appId: appId)
+    }
+
+    public func announcementOrigin(stealthAddress: String, appId: UInt32) async throws -> AnnouncementOrigin? {
+        try await getAnnouncementOrigin(stealthAddress: stealthAddress, appId: appId)
+    }
+}
+
+/// Announcement source backed by an in-memory snapshot
+public struct StaticAnnouncementSource: AnnouncementSource {
+    /// Raw CiphertextAccount data keyed by stealth address, then app namespace
+    public let announcements: [String: [UInt32: Data]]
+
+    /// Creating transactions keyed by stealth address, then app namespace
+    public let origins: [String: [UInt32: AnnouncementOrigin]]
+
+    public init(
+        announcements: [String: [UInt32: Data]],
+        origins: [String: [UInt32: AnnouncementOrigin]] = [:]
+    ) {
+        self.announcements = announcements
+        self.origins = origins
+    }
+
+    public func rawAnnouncement(stealthAddress: String, appId: UInt32) async throws -> Data? {
+        announcements[stealthAddress]?[appId]
+    }
+
+    public func announcementOrigin(stealthAddress: String, appId: UInt32) async throws -> AnnouncementOrigin? {
+        origins[stealthAddress]?[appId]
+    }
+}
+
+/// Announcement source backed by a downloaded accounts snapshot
+///
+/// Restoring a wallet would otherwise take one RPC call per candidate address.
+/// The snapshot is a sequence of `[u32 LE length][CiphertextAccount data]` records
+/// (e.g. an indexer dump of all program accounts). The file is memory-mapped and
+/// indexed once; lookups then slice the mapped data without copying or network access.
+public struct SnapshotAnnouncementSource: AnnouncementSource {
+    /// Record offsets keyed by stealth address, then app namespace
+    private let index: [String: [UInt32: Range<Int>]]
+    private let snapshot: Data
+
+    /// Map and index a snapshot file
+    /// - Parameter url: Location of the snapshot file
+    public init(contentsOf url: URL) throws {
+        try self.init(snapshot: Data(contentsOf: url, options: .alwaysMapped))
+    }
+
+    /// Index snapshot data
+    /// - Parameter snapshot: Snapshot records
+    public init(snapshot: Data) throws {
+        var index: [String: [UInt32: Range<Int>]] = [:]
+        var offset = snapshot.startIndex
+
+        while offset < snapshot.endIndex {
+            guard offset + 4 <= snapshot.endIndex else {
+                throw SnapshotError.truncatedRecord(offset: offset - snapshot.startIndex)
+            }
+            let length = Int(Data(snapshot[offset..<offset + 4]).withUnsafeBytes { $0.loadUnaligned(as: UInt32.self) })
+            let range = (offset + 4)..<(offset + 4 + length)
+            guard range.upperBound <= snapshot.endIndex else {
+                throw SnapshotError.truncatedRecord(offset: offset - snapshot.startIndex)
+            }
+
+            // Skip records that aren't CiphertextAccounts instead of failing the restore
+            if let account = CiphertextAccountData.parse(from: Data(snapshot[range])) {
+                index[account.stealthPubkey.base58EncodedString, default: [:]][account.appId] = range
+            }
+            offset = range.upperBound
+        }
+
+        self.index = index
+        self.snapshot = snapshot
+    }
+
+    /// Stealth addresses with an announcement in the snapshot
+    public var stealthAddresses: [String] {
+        Array(index.keys)
+    }
+
+    /// Serialize raw CiphertextAccount data into the snapshot format
+    /// - Parameter accounts: Raw account data (including discriminator)
+    /// - Returns: Snapshot records
+    public static func encode(_ accounts: [Data]) -> Data {
+        var data = Data()
+        for account in accounts {
+            var length = UInt32(account.count).littleEndian
+            data.append(Data(bytes: &length, count: 4))
+            data.append(account)
+        }
+        return data
+    }
+
+    public func rawAnnouncement(stealthAddress: String, appId: UInt32) async throws -> Data? {
+        guard let range = index[stealthAddress]?[appId] else {
+            return nil
+        }
+        return Data(snapshot[range])
+    }
+}
+
+/// Errors reading an announcement snapshot
+public enum SnapshotError: Error, LocalizedError {
+    case truncatedRecord(offset: Int)
+
+    public var errorDescription: String? {
+        switch self {
+        case .truncatedRecord(let offset):
+            return "Snapshot record at offset \(offset) is truncated"
+        }
+    }
+}
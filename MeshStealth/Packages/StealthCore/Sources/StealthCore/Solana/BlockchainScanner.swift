@@ -1,5 +1,18 @@
 import Foundation
 
+/// Suspicious pattern noticed while scanning a payment
+public enum PaymentWarning: String, Sendable, Equatable {
+    /// Another payment in the same scan uses the same ephemeral public key
+    case reusedEphemeralKey
+
+    /// Nothing arrived, or it was already swept: the stealth address holds no
+    /// lamports, or for an SPL payment its token account holds no tokens
+    case unfunded
+
+    /// The announcement's extension area can't be parsed
+    case malformedMetadata
+}
+
 /// Extended payment info that includes on-chain data
 public struct OnChainStealthPayment: Sendable {
     /// The core detected payment with spending key
@@ -8,13 +21,32 @@ public struct OnChainStealthPayment: Sendable {
     /// Amount in lamports (from on-chain balance)
     public let lamports: UInt64
 
+    /// Token balance of the payment's token account, for an SPL payment (nil for native SOL)
+    public let tokenAmount: UInt64?
+
     /// CiphertextAccount PDA address
     public let ciphertextPDA: String
 
-    public init(payment: DetectedStealthPayment, lamports: UInt64, ciphertextPDA: String) {
+    /// App namespace the announcement was found in
+    public let appId: UInt32
+
+    /// Suspicious patterns to surface to the user (empty if none)
+    public var warnings: [PaymentWarning]
+
+    public init(
+        payment: DetectedStealthPayment,
+        lamports: UInt64,
+        tokenAmount: UInt64? = nil,
+        ciphertextPDA: String,
+        appId: UInt32 = DEFAULT_APP_ID,
+        warnings: [PaymentWarning] = []
+    ) {
         self.payment = payment
         self.lamports = lamports
+        self.tokenAmount = tokenAmount
         self.ciphertextPDA = ciphertextPDA
+        self.appId = appId
+        self.warnings = warnings
     }
 }
 
@@ -25,57 +57,119 @@ public actor BlockchainScanner {
     private let rpcClient: SolanaRPCClient
     private let stealthPQClient: StealthPQClient
     private let stealthScanner: StealthScanner
+    private let source: any AnnouncementSource
+    private let verificationSources: [any AnnouncementSource]
 
     /// Initialize the blockchain scanner
     /// - Parameters:
     ///   - rpcClient: Solana RPC client
     ///   - stealthScanner: Stealth scanner with recipient's viewing keys
     ///   - programId: stealth-pq program ID
+    ///   - verificationRPCClients: Independent RPC endpoints that must return identical
+    ///     announcement data and creating transaction before a payment is reported
+    ///     (empty to trust `rpcClient` alone)
     public init(
         rpcClient: SolanaRPCClient,
         stealthScanner: StealthScanner,
-        programId: String = STEALTH_PQ_PROGRAM_ID
+        programId: String = STEALTH_PQ_PROGRAM_ID,
+        verificationRPCClients: [SolanaRPCClient] = []
+    ) {
+        let stealthPQClient = StealthPQClient(rpcClient: rpcClient, programId: programId)
+        self.init(
+            rpcClient: rpcClient,
+            stealthScanner: stealthScanner,
+            programId: programId,
+            source: stealthPQClient,
+            verificationSources: verificationRPCClients.map {
+                StealthPQClient(rpcClient: $0, programId: programId)
+            }
+        )
+    }
+
+    /// Initialize the blockchain scanner with custom announcement sources
+    /// - Parameters:
+    ///   - rpcClient: Solana RPC client (used for balances)
+    ///   - stealthScanner: Stealth scanner with recipient's viewing keys
+    ///   - programId: stealth-pq program ID
+    ///   - source: Where announcements are read from
+    ///   - verificationSources: Independent sources that must return identical
+    ///     announcement data and creating transaction before a payment is reported
+    ///     (empty to trust `source` alone)
+    public init(
+        rpcClient: SolanaRPCClient,
+        stealthScanner: StealthScanner,
+        programId: String = STEALTH_PQ_PROGRAM_ID,
+        source: any AnnouncementSource,
+        verificationSources: [any AnnouncementSource] = []
     ) {
         self.rpcClient = rpcClient
         self.stealthScanner = stealthScanner
         self.stealthPQClient = StealthPQClient(rpcClient: rpcClient, programId: programId)
+        self.source = source
+        self.verificationSources = verificationSources
     }
 
     // MARK: - Scanning
 
     /// Scan a list of potential stealth addresses for payments
-    /// - Parameter potentialStealthAddresses: Base58-encoded stealth addresses to check
+    ///
+    /// Every address is checked in each of the given app namespaces and the results
+    /// are merged into one feed; `OnChainStealthPayment.appId` records where each
+    /// payment was found.
+    /// - Parameters:
+    ///   - potentialStealthAddresses: Base58-encoded stealth addresses to check
+    ///   - appIds: App namespaces to scan (defaults to the default namespace only)
+    ///   - includeExpired: Whether to return payments whose sender expiry hint has passed
     /// - Returns: Array of detected stealth payments with spending keys
     public func scanForPayments(
-        potentialStealthAddresses: [String]
+        potentialStealthAddresses: [String],
+        appIds: [UInt32] = [DEFAULT_APP_ID],
+        includeExpired: Bool = true
     ) async -> [OnChainStealthPayment] {
         var detected: [OnChainStealthPayment] = []
 
         for address in potentialStealthAddresses {
-            do {
-                if let payment = try await scanAddress(address) {
-                    detected.append(payment)
+            for appId in Self.uniqueAppIds(appIds) {
+                do {
+                    if let payment = try await scanAddress(address, appId: appId, includeExpired: includeExpired) {
+                        detected.append(payment)
+                    }
+                } catch {
+                    // Log but continue scanning
+                    DebugLogger.log("Error scanning address \(address) in app \(appId): \(error)")
                 }
-            } catch {
-                // Log but continue scanning
-                DebugLogger.log("Error scanning address \(address): \(error)")
             }
         }
 
-        return detected
+        return Self.flagReusedEphemeralKeys(detected)
     }
 
     /// Scan a single address for a stealth payment
-    /// - Parameter stealthAddress: Base58-encoded stealth address
+    /// - Parameters:
+    ///   - stealthAddress: Base58-encoded stealth address
+    ///   - appId: App namespace to look in
+    ///   - includeExpired: Whether to return the payment if its sender expiry hint has passed
     /// - Returns: Detected payment if found and valid, nil otherwise
-    public func scanAddress(_ stealthAddress: String) async throws -> OnChainStealthPayment? {
+    public func scanAddress(
+        _ stealthAddress: String,
+        appId: UInt32 = DEFAULT_APP_ID,
+        includeExpired: Bool = true
+    ) async throws -> OnChainStealthPayment? {
         // 1. Fetch CiphertextAccount PDA data
-        guard let ciphertextData = try await stealthPQClient.getCiphertextAccount(stealthAddress: stealthAddress) else {
+        guard let ciphertextData = try await source.announcement(
+            stealthAddress: stealthAddress,
+            appId: appId
+        ) else {
             return nil  // No ciphertext stored for this address
         }
 
+        // Skip stale announcements without paying for decapsulation
+        if !includeExpired && ciphertextData.isExpired() {
+            return nil
+        }
+
         // 2. Get the PDA address
-        let (pdaAddress, _) = try await stealthPQClient.deriveCiphertextPDA(stealthAddress: stealthAddress)
+        let (pdaAddress, _) = try await stealthPQClient.deriveCiphertextPDA(stealthAddress: stealthAddress, appId: appId)
 
         // 3. Attempt to scan using hybrid decryption
         let detectedPayment: DetectedStealthPayment?
@@ -84,6 +178,15 @@ public actor BlockchainScanner {
         let hasMLKEMCiphertext = ciphertextData.mlkemCiphertext.contains(where: { $0 != 0 })
 
         if hasMLKEMCiphertext {
+            // Reject on the view tag before paying for decapsulation (legacy announcements have none)
+            if !ciphertextData.isLegacy,
+               try !stealthScanner.quickFilter(
+                ephemeralPublicKey: ciphertextData.ephemeralPubkey,
+                expectedViewTag: ciphertextData.viewTag
+            ) {
+                return nil
+            }
+
             // Hybrid mode: X25519 + MLKEM768
             detectedPayment = try stealthScanner.scanHybridTransaction(
                 stealthAddress: stealthAddress,
@@ -102,17 +205,126 @@ public actor BlockchainScanner {
             return nil  // Not for us
         }
 
-        // 4. Get balance of stealth address
+        // Don't report a payment on the word of a single RPC
+        guard try await verifyAnnouncement(stealthAddress, appId: appId) else {
+            DebugLogger.log("Announcement for \(stealthAddress) not confirmed by verification sources")
+            return nil
+        }
+
+        // 4. Get balance of stealth address, and of its token account for an SPL payment
         let balance = try await rpcClient.getBalance(pubkey: stealthAddress)
+        var tokenAmount: UInt64?
+        if let tokenAccount = ciphertextData.tokenAccount {
+            tokenAmount = try await rpcClient.getTokenAccountBalance(pubkey: tokenAccount) ?? 0
+        }
 
-        // 5. Build on-chain payment
+        // 5. Build on-chain payment, flagging anything suspicious about this announcement on its own
         return OnChainStealthPayment(
             payment: payment,
             lamports: balance,
-            ciphertextPDA: pdaAddress
+            tokenAmount: tokenAmount,
+            ciphertextPDA: pdaAddress,
+            appId: appId,
+            warnings: Self.warnings(for: ciphertextData, lamports: balance, tokenAmount: tokenAmount)
         )
     }
 
+    /// Warnings that follow from a single announcement and its balances
+    ///
+    /// An SPL payment arrives in the stealth address's token account, so it is
+    /// judged by the token balance rather than the stealth address's lamports.
+    /// - Parameters:
+    ///   - ciphertextData: The payment's announcement
+    ///   - lamports: Balance of the stealth address
+    ///   - tokenAmount: Balance of the token account, for an SPL payment
+    /// - Returns: `.unfunded` and `.malformedMetadata` where they apply
+    static func warnings(
+        for ciphertextData: CiphertextAccountData,
+        lamports: UInt64,
+        tokenAmount: UInt64?
+    ) -> [PaymentWarning] {
+        var warnings: [PaymentWarning] = []
+        if (tokenAmount ?? lamports) == 0 {
+            warnings.append(.unfunded)
+        }
+        if ciphertextData.extensionEntries == nil {
+            warnings.append(.malformedMetadata)
+        }
+        return warnings
+    }
+
+    /// Flag payments that share an ephemeral public key with another payment
+    ///
+    /// Honest senders use a fresh ephemeral key per payment, so a repeat means a
+    /// buggy sender or a replayed announcement.
+    /// - Parameter payments: Payments from one scan
+    /// - Returns: The same payments with `.reusedEphemeralKey` added where it applies
+    static func flagReusedEphemeralKeys(_ payments: [OnChainStealthPayment]) -> [OnChainStealthPayment] {
+        var counts: [Data: Int] = [:]
+        for payment in payments {
+            counts[payment.payment.ephemeralPublicKey, default: 0] += 1
+        }
+
+        return payments.map { payment in
+            var payment = payment
+            if counts[payment.payment.ephemeralPublicKey, default: 0] > 1 {
+                payment.warnings.append(.reusedEphemeralKey)
+            }
+            return payment
+        }
+    }
+
+    /// Check that every verification source returns the same announcement as the primary source
+    ///
+    /// Both the account data and the transaction that created it (signature and
+    /// slot) must match, so a source can't pass off a replayed or forked
+    /// announcement with the same bytes. Sources that don't track transaction
+    /// history report no origin and only agree with each other.
+    ///
+    /// Sources may briefly disagree while an announcement is still being written
+    /// (e.g. between init_ciphertext and complete_ciphertext); callers should retry
+    /// a failed verification later rather than treat it as proof of forgery.
+    /// - Parameters:
+    ///   - stealthAddress: Base58-encoded stealth address
+    ///   - appId: App namespace to look in
+    /// - Returns: True if all sources agree (always true without verification sources)
+    public func verifyAnnouncement(
+        _ stealthAddress: String,
+        appId: UInt32 = DEFAULT_APP_ID
+    ) async throws -> Bool {
+        guard !verificationSources.isEmpty else {
+            return true
+        }
+
+        guard let primary = try await source.rawAnnouncement(
+            stealthAddress: stealthAddress,
+            appId: appId
+        ) else {
+            return false
+        }
+        let primaryOrigin = try await source.announcementOrigin(stealthAddress: stealthAddress, appId: appId)
+
+        for verificationSource in verificationSources {
+            let data = try await verificationSource.rawAnnouncement(stealthAddress: stealthAddress, appId: appId)
+            guard data == primary else {
+                return false
+            }
+
+            let origin = try await verificationSource.announcementOrigin(stealthAddress: stealthAddress, appId: appId)
+            guard origin == primaryOrigin else {
+                return false
+            }
+        }
+
+        return true
+    }
+
+    /// Deduplicate app namespaces, keeping the caller's order
+    static func uniqueAppIds(_ appIds: [UInt32]) -> [UInt32] {
+        var seen = Set<UInt32>()
+        return appIds.filter { seen.insert($0).inserted }
+    }
+
     /// Generate potential stealth addresses from recent program transactions
     /// This is a heuristic approach for discovery
     /// - Parameters:
@@ -149,7 +361,7 @@ public actor BlockchainScanner {
     /// - Parameter stealthAddress: Base58-encoded stealth address
     /// - Returns: Raw ciphertext account data
     public func getCiphertextData(_ stealthAddress: String) async throws -> CiphertextAccountData? {
-        return try await stealthPQClient.getCiphertextAccount(stealthAddress: stealthAddress)
+        return try await source.announcement(stealthAddress: stealthAddress, appId: DEFAULT_APP_ID)
     }
 }
 
@@ -160,20 +372,25 @@ extension BlockchainScanner {
     /// Batch scan multiple addresses with concurrency limit
     /// - Parameters:
     ///   - addresses: Addresses to scan
+    ///   - appIds: App namespaces to scan each address in
     ///   - concurrency: Maximum concurrent requests
-    /// - Returns: Array of detected payments
+    /// - Returns: Array of detected payments, merged across namespaces
     public func batchScan(
         addresses: [String],
+        appIds: [UInt32] = [DEFAULT_APP_ID],
         concurrency: Int = 5
     ) async -> [OnChainStealthPayment] {
         // Process in chunks to limit concurrency
         var results: [OnChainStealthPayment] = []
+        let targets = addresses.flatMap { address in
+            Self.uniqueAppIds(appIds).map { (address, $0) }
+        }
 
-        for chunk in addresses.chunked(into: concurrency) {
+        for chunk in targets.chunked(into: concurrency) {
             await withTaskGroup(of: OnChainStealthPayment?.self) { group in
-                for address in chunk {
+                for (address, appId) in chunk {
                     group.addTask {
-                        try? await self.scanAddress(address)
+                        try? await self.scanAddress(address, appId: appId)
                     }
                 }
 
@@ -185,7 +402,7 @@ extension BlockchainScanner {
             }
         }
 
-        return results
+        return Self.flagReusedEphemeralKeys(results)
     }
 }
 
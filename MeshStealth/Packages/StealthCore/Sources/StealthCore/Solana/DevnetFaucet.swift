@@ -127,6 +127,67 @@ public actor DevnetFaucet {
         return blockhash
     }
 
+    /// Check whether a transaction landed and executed successfully
+    ///
+    /// Searches the full transaction history, so it also finds transactions older
+    /// than the recent status cache.
+    /// - Parameter signature: Transaction signature
+    /// - Returns: true if the transaction is confirmed without error
+    public func transactionSucceeded(signature: String) async throws -> Bool {
+        let body: [String: Any] = [
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "getSignatureStatuses",
+            "params": [[signature], ["searchTransactionHistory": true]]
+        ]
+
+        var request = URLRequest(url: rpcEndpoint)
+        request.httpMethod = "POST"
+        request.setValue("application/json", forHTTPHeaderField: "Content-Type")
+        request.httpBody = try JSONSerialization.data(withJSONObject: body)
+
+        let (data, _) = try await URLSession.shared.data(for: request)
+        let response = try JSONDecoder().decode(FaucetSignatureStatusResponse.self, from: data)
+
+        guard let status = response.result?.value.first ?? nil, status.err == nil else {
+            return false
+        }
+        return status.confirmationStatus == "confirmed" || status.confirmationStatus == "finalized"
+    }
+
+    /// Check whether transactions signed with a blockhash can still land
+    /// - Parameter blockhash: Base58-encoded blockhash
+    /// - Returns: false once the blockhash has expired
+    public func isBlockhashValid(_ blockhash: String) async throws -> Bool {
+        let body: [String: Any] = [
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "isBlockhashValid",
+            "params": [blockhash, ["commitment": "processed"]]
+        ]
+
+        var request = URLRequest(url: rpcEndpoint)
+        request.httpMethod = "POST"
+        request.setValue("application/json", forHTTPHeaderField: "Content-Type")
+        request.httpBody = try JSONSerialization.data(withJSONObject: body)
+
+        let (data, response) = try await URLSession.shared.data(for: request)
+
+        if let httpResponse = response as? HTTPURLResponse {
+            guard (200...299).contains(httpResponse.statusCode) else {
+                throw FaucetError.httpError(statusCode: httpResponse.statusCode)
+            }
+        }
+
+        let rpcResponse = try JSONDecoder().decode(BlockhashValidResponse.self, from: data)
+
+        if let error = rpcResponse.error {
+            throw FaucetError.rpcError(code: error.code, message: error.message)
+        }
+
+        return rpcResponse.result?.value ?? false
+    }
+
     /// Send a signed transaction to the network
     /// - Parameter signedTransaction: Base64-encoded signed transaction
     /// - Returns: Transaction signature
@@ -219,7 +280,7 @@ public actor DevnetFaucet {
         }
 
         // Check state is initialized (bytes 4-7)
-        let state = decodedData.subdata(in: 4..<8).withUnsafeBytes { $0.load(as: UInt32.self) }
+        let state = decodedData.subdata(in: 4..<8).withUnsafeBytes { $0.loadUnaligned(as: UInt32.self) }
         guard state == 1 else {
             throw FaucetError.rpcError(code: -1, message: "Nonce account not initialized")
         }
@@ -324,6 +385,18 @@ private struct FaucetBlockhashValue: Decodable {
     let lastValidBlockHeight: UInt64
 }
 
+private struct BlockhashValidResponse: Decodable {
+    let jsonrpc: String
+    let id: Int
+    let result: BlockhashValidResult?
+    let error: RPCError?
+}
+
+private struct BlockhashValidResult: Decodable {
+    let context: RPCContext?
+    let value: Bool
+}
+
 private struct SendTransactionResponse: Decodable {
     let jsonrpc: String
     let id: Int
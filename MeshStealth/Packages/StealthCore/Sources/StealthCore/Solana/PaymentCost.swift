@@ -0,0 +1,119 @@
+import Foundation
+
+/// CiphertextAccount space for an MLKEM768 ciphertext with an empty extension area
+/// (including the Anchor discriminator)
+public let CIPHERTEXT_ACCOUNT_SPACE = 1444
+
+/// Base fee per transaction signature in lamports
+public let LAMPORTS_PER_SIGNATURE: UInt64 = 5000
+
+/// What an outgoing stealth payment costs the sender, on top of the amount sent
+///
+/// All values are in lamports. Announcement rent isn't spent: the recipient gets it
+/// back when closing the CiphertextAccount, minus the share returned to the rent payer.
+public struct PaymentCost: Sendable, Equatable {
+    /// Number of transactions the payment takes
+    public let transactionCount: Int
+
+    /// Base signature fees across all transactions
+    public let transactionFees: UInt64
+
+    /// Compute-unit price paid on top of the base fees
+    public let priorityFees: UInt64
+
+    /// Rent deposited in the CiphertextAccount announcement (zero for classic payments)
+    public let announcementRent: UInt64
+
+    /// Part of the announcement rent returned to the rent payer on close
+    public let reclaimableByRentPayer: UInt64
+
+    /// Lamports leaving the sender's wallet besides the payment amount
+    public var total: UInt64 {
+        transactionFees + priorityFees + announcementRent
+    }
+
+    /// Cost to the sender once the rent payer's share is returned
+    public var netCost: UInt64 {
+        total - reclaimableByRentPayer
+    }
+
+    /// Announcement rent the recipient recovers on close
+    public var reclaimableByRecipient: UInt64 {
+        announcementRent - reclaimableByRentPayer
+    }
+
+    public init(
+        transactionCount: Int,
+        transactionFees: UInt64,
+        priorityFees: UInt64,
+        announcementRent: UInt64,
+        reclaimableByRentPayer: UInt64
+    ) {
+        self.transactionCount = transactionCount
+        self.transactionFees = transactionFees
+        self.priorityFees = priorityFees
+        self.announcementRent = announcementRent
+        self.reclaimableByRentPayer = reclaimableByRentPayer
+    }
+
+    // MARK: - Estimation
+
+    /// Preflight estimate for an outgoing payment
+    ///
+    /// Assumes the sender pays fees and rent, and that the namespace counter already
+    /// exists (its rent is only paid by the first announcement in an app namespace).
+    /// - Parameters:
+    ///   - hybrid: Whether the payment publishes a CiphertextAccount announcement
+    ///   - extensionsLength: Bytes of TLV extensions written to the announcement
+    ///   - rentPayerShareBps: Share of the rent (basis points) returned to the rent payer
+    ///   - computeUnitPrice: Priority fee in micro-lamports per compute unit
+    ///   - computeUnitLimit: Compute units requested per transaction
+    /// - Returns: Cost breakdown
+    public static func estimate(
+        hybrid: Bool,
+        extensionsLength: Int = 0,
+        rentPayerShareBps: UInt16 = 0,
+        computeUnitPrice: UInt64 = 0,
+        computeUnitLimit: UInt32 = 200_000
+    ) -> PaymentCost {
+        // Classic: one system transfer. Hybrid: init_ciphertext, complete_ciphertext
+        // with finalize_ciphertext and the transfer, plus one write_extensions per chunk.
+        let transactionCount = hybrid
+            ? 2 + (extensionsLength + MAX_CHUNK_SIZE - 1) / MAX_CHUNK_SIZE
+            : 1
+
+        let rent = hybrid
+            ? rentExemptMinimum(dataLength: CIPHERTEXT_ACCOUNT_SPACE + extensionsLength)
+            : 0
+
+        return PaymentCost(
+            transactionCount: transactionCount,
+            transactionFees: UInt64(transactionCount) * LAMPORTS_PER_SIGNATURE,
+            priorityFees: UInt64(transactionCount) * priorityFee(
+                computeUnitPrice: computeUnitPrice,
+                computeUnitLimit: computeUnitLimit
+            ),
+            announcementRent: rent,
+            reclaimableByRentPayer: rentPayerShare(of: rent, bps: rentPayerShareBps)
+        )
+    }
+
+    /// Minimum balance for a rent-exempt account of the given size
+    /// (default rent: 3480 lamports per byte-year, two-year exemption threshold)
+    public static func rentExemptMinimum(dataLength: Int) -> UInt64 {
+        UInt64(dataLength + 128) * 3480 * 2
+    }
+
+    /// Priority fee of one transaction, rounded up to whole lamports like the runtime does
+    public static func priorityFee(computeUnitPrice: UInt64, computeUnitLimit: UInt32) -> UInt64 {
+        let microLamports = computeUnitPrice * UInt64(computeUnitLimit)
+        return (microLamports + 999_999) / 1_000_000
+    }
+
+    /// Rent payer share for a given rent amount, rounded down as on-chain
+    public static func rentPayerShare(of lamports: UInt64, bps: UInt16) -> UInt64 {
+        // floor(lamports * bps / 10_000) without overflowing
+        let bps = UInt64(bps)
+        return lamports / 10_000 * bps + lamports % 10_000 * bps / 10_000
+    }
+}
@@ -118,6 +118,32 @@ public actor SolanaRPCClient {
         return result.value.value
     }
 
+    /// Get the balance of an SPL token account
+    /// - Parameter pubkey: Base58-encoded token account address
+    /// - Returns: Token amount in base units, or nil if the account doesn't exist
+    public func getTokenAccountBalance(pubkey: String) async throws -> UInt64? {
+        guard let accountInfo = try await getAccountInfo(pubkey: pubkey, encoding: "base64"),
+              accountInfo.data.count > 0,
+              let accountData = Data(base64Encoded: accountInfo.data[0]) else {
+            return nil
+        }
+        return Self.tokenAccountAmount(from: accountData)
+    }
+
+    /// Read the amount of an SPL token account
+    ///
+    /// Token and Token-2022 accounts share the base layout: mint (32), owner (32),
+    /// amount (u64 LE) and further fields, 165 bytes in all.
+    /// - Parameter data: Raw token account data
+    /// - Returns: Token amount, or nil if the data is too short for a token account
+    public static func tokenAccountAmount(from data: Data) -> UInt64? {
+        guard data.count >= 165 else {
+            return nil
+        }
+        let base = data.startIndex
+        return data[(base + 64)..<(base + 72)].withUnsafeBytes { $0.loadUnaligned(as: UInt64.self) }
+    }
+
     /// Get the latest blockhash for transaction building
     /// - Returns: Blockhash and last valid block height
     public func getLatestBlockhash() async throws -> BlockhashResult {
@@ -182,6 +208,23 @@ public actor SolanaRPCClient {
         return result.value
     }
 
+    /// Get the log messages of a confirmed transaction
+    /// - Parameter signature: Transaction signature
+    /// - Returns: `meta.logMessages` (empty if the node didn't record any)
+    /// - Throws: `SolanaError.decodingError` if the transaction isn't found
+    public func getTransactionLogs(signature: String) async throws -> [String] {
+        let params: [Any] = [
+            signature,
+            [
+                "encoding": "json",
+                "commitment": "confirmed",
+                "maxSupportedTransactionVersion": 0
+            ]
+        ]
+        let result: RPCResult<GetTransactionResponse> = try await request(method: "getTransaction", params: params)
+        return result.value.meta?.logMessages ?? []
+    }
+
     /// Confirm a transaction by checking its status
     /// - Parameters:
     ///   - signature: Transaction signature to check
@@ -292,6 +335,14 @@ private struct GetSignatureStatusesResponse: Codable {
     let value: [SignatureStatus?]
 }
 
+private struct GetTransactionResponse: Codable {
+    let meta: TransactionMeta?
+}
+
+private struct TransactionMeta: Codable {
+    let logMessages: [String]?
+}
+
 private struct SignatureStatus: Codable {
     let slot: UInt64?
     let confirmations: UInt64?
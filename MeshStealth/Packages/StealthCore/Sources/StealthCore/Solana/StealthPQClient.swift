@@ -8,15 +8,147 @@ public let STEALTH_PQ_PROGRAM_ID = "5YXYyH7i9WnQz1Hzh8kEuxSU5ws3n1Kor2KdTxnJkv6y
 /// System program ID
 public let SYSTEM_PROGRAM_ID = "11111111111111111111111111111111"
 
+/// SPL Token program ID
+public let TOKEN_PROGRAM_ID = "TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA"
+
+/// SPL Token-2022 program ID
+public let TOKEN_2022_PROGRAM_ID = "TokenzQdBNbLqP5VEhdkAS6EPFLC1PHnBqCXEpPxuEb"
+
+/// Associated token account program ID
+public let ASSOCIATED_TOKEN_PROGRAM_ID = "ATokenGPvbdGVxr1b2hvZbsiqW5xWH25efTNsLJA8knL"
+
 /// MLKEM768 ciphertext size in bytes
 public let MLKEM_CIPHERTEXT_SIZE = 1088
 
+/// CiphertextAccount `kem_variant` values, selecting the ML-KEM parameter set
+/// (and so the ciphertext size: 1088, 768 or 1568 bytes)
+public let KEM_VARIANT_ML_KEM_768: UInt8 = 0
+public let KEM_VARIANT_ML_KEM_512: UInt8 = 1
+public let KEM_VARIANT_ML_KEM_1024: UInt8 = 2
+
+/// ML-KEM ciphertext size in bytes for a `kem_variant` (nil if unknown)
+public func mlkemCiphertextSize(kemVariant: UInt8) -> Int? {
+    switch kemVariant {
+    case KEM_VARIANT_ML_KEM_768: return MLKEM_CIPHERTEXT_SIZE
+    case KEM_VARIANT_ML_KEM_512: return 768
+    case KEM_VARIANT_ML_KEM_1024: return 1568
+    default: return nil
+    }
+}
+
 /// Ephemeral X25519 public key size in bytes
 public let EPHEMERAL_PUBKEY_SIZE = 32
 
 /// Maximum chunk size per transaction
 public let CHUNK_SIZE = 512
 
+/// Capacity of a fixed-size ciphertext chunk argument
+public let MAX_CHUNK_SIZE = 576
+
+/// App namespace with the original ["ciphertext", stealth_pubkey] PDA seeds
+public let DEFAULT_APP_ID: UInt32 = 0
+
+/// Maximum size of the TLV extension area of a CiphertextAccount
+public let MAX_EXTENSIONS_SIZE = 512
+
+/// Maximum size of the encrypted memo of a CiphertextAccount
+public let MAX_MEMO_SIZE = 128
+
+/// A single entry of the CiphertextAccount TLV extension area
+public struct CiphertextExtension: Sendable, Equatable {
+    /// Extension area end marker (remaining bytes are padding)
+    public static let typeEnd: UInt8 = 0x00
+
+    /// Amount encrypted to the hybrid shared secret
+    public static let typeEncryptedAmount: UInt8 = 0x01
+
+    /// Fuzzy message detection clue
+    public static let typeFMDClue: UInt8 = 0x02
+
+    /// Registry epoch (u32 LE) of the meta-address the sender used
+    public static let typeRegistryEpoch: UInt8 = 0x03
+
+    /// Associated token account (32 bytes) an SPL payment is sent to
+    public static let typeTokenAccount: UInt8 = 0x04
+
+    /// First application-defined type (0x80...0xEF)
+    public static let typeAppDataStart: UInt8 = 0x80
+
+    /// Entry type
+    public let type: UInt8
+
+    /// Entry value
+    public let value: Data
+
+    public init(type: UInt8, value: Data) {
+        self.type = type
+        self.value = value
+    }
+
+    /// Parse TLV entries (`type u8 || length u16 LE || value`)
+    /// - Parameter data: Raw extension area bytes
+    /// - Returns: Entries up to the end marker, or nil if an entry is truncated.
+    ///   Unknown types are returned as-is so callers can skip them.
+    public static func parseAll(from data: Data) -> [CiphertextExtension]? {
+        let bytes = [UInt8](data)
+        var entries: [CiphertextExtension] = []
+        var index = 0
+
+        while index < bytes.count {
+            let type = bytes[index]
+            if type == typeEnd {
+                break
+            }
+            guard index + 3 <= bytes.count else {
+                return nil
+            }
+            let length = Int(bytes[index + 1]) | (Int(bytes[index + 2]) << 8)
+            let valueStart = index + 3
+            guard valueStart + length <= bytes.count else {
+                return nil
+            }
+            entries.append(CiphertextExtension(type: type, value: Data(bytes[valueStart..<(valueStart + length)])))
+            index = valueStart + length
+        }
+
+        return entries
+    }
+
+    /// Registry epoch entry for the meta-address the sender paid to
+    /// - Parameter epoch: Registry epoch (opaque index)
+    public static func registryEpoch(_ epoch: UInt32) -> CiphertextExtension {
+        var epochLE = epoch.littleEndian
+        return CiphertextExtension(type: typeRegistryEpoch, value: Data(bytes: &epochLE, count: 4))
+    }
+
+    /// Token account entry for an SPL payment, which `transfer_spl_to_stealth` then enforces
+    /// - Parameters:
+    ///   - stealthAddress: Stealth address receiving the tokens
+    ///   - mint: Token mint
+    ///   - tokenProgram: Token program owning the mint (Token or Token-2022)
+    public static func tokenAccount(
+        stealthAddress: String,
+        mint: String,
+        tokenProgram: String = TOKEN_PROGRAM_ID
+    ) throws -> CiphertextExtension {
+        let tokenAccount = try StealthPQClient.deriveAssociatedTokenAddress(
+            owner: stealthAddress,
+            mint: mint,
+            tokenProgram: tokenProgram
+        )
+        return CiphertextExtension(type: typeTokenAccount, value: try SolanaRPCClient.decodePublicKey(tokenAccount))
+    }
+
+    /// Serialize this entry as TLV bytes
+    public func encoded() -> Data {
+        var data = Data([type])
+        var length = UInt16(value.count).littleEndian
+        data.append(Data(bytes: &length, count: 2))
+        data.append(value)
+        return data
+    }
+}
+
 /// Ciphertext account data fetched from the stealth-pq program
 public struct CiphertextAccountData: Sendable {
     /// The stealth address this ciphertext is for (32 bytes)
@@ -25,7 +157,13 @@ public struct CiphertextAccountData: Sendable {
     /// Ephemeral X25519 public key (R) used for ECDH shared secret (32 bytes)
     public let ephemeralPubkey: Data
 
-    /// MLKEM768 ciphertext from encapsulation (1088 bytes)
+    /// ML-KEM parameter set of the ciphertext (`KEM_VARIANT_*`)
+    public let kemVariant: UInt8
+
+    /// Memo (such as an order ID) encrypted to the hybrid shared secret (nil if none)
+    public let encryptedMemo: Data?
+
+    /// ML-KEM ciphertext from encapsulation (768, 1088 or 1568 bytes by `kemVariant`)
     public let mlkemCiphertext: Data
 
     /// Unix timestamp when the transfer was created
@@ -34,39 +172,502 @@ public struct CiphertextAccountData: Sendable {
     /// Bump seed for PDA derivation
     public let bump: UInt8
 
+    /// Sender-chosen Unix timestamp after which the payment is stale (nil if none)
+    public let expiresAt: Int64?
+
+    /// Sender's return address encrypted to the hybrid shared secret (nil if none)
+    public let encryptedReturnAddress: Data?
+
+    /// AEAD tag over the encrypted metadata (nil if the announcement carries none).
+    /// Verify it with the hybrid shared secret before trusting any decrypted metadata.
+    public let payloadTag: Data?
+
+    /// Integrator namespace the announcement was made in (DEFAULT_APP_ID if none)
+    public let appId: UInt32
+
+    /// Account that paid the rent (the sender itself if unsponsored)
+    public let rentPayer: Data
+
+    /// Share of the rent (basis points) returned to `rentPayer` on close
+    public let rentPayerShareBps: UInt16
+
+    /// View tag over the X25519 shared secret.
+    /// Check it with `StealthScanner.quickFilter` before decapsulating.
+    public let viewTag: UInt8
+
+    /// Sender who created the announcement, the only signer allowed to write it
+    /// (all zeros for legacy announcements, which didn't record one)
+    public let sender: Data
+
+    /// Whether the sender has finished writing the announcement; transfers are only accepted after
+    public let isFinalized: Bool
+
+    /// Index of the announcement log entry (nil if the announcement was not logged)
+    public let announcementIndex: UInt64?
+
+    /// Raw TLV extension area (empty if none; covered by `payloadTag`)
+    public let extensions: Data
+
+    /// Whether the account is still in the legacy layout. `reclaim_rent` can't
+    /// close it until `migrate_ciphertext` has rewritten it.
+    public let needsMigration: Bool
+
+    /// Whether the announcement was made under the legacy layout, migrated or not.
+    /// Legacy announcements have no view tag, so don't filter them on `viewTag`.
+    public var isLegacy: Bool {
+        sender.allSatisfy { $0 == 0 }
+    }
+
+    /// Parsed TLV extension entries (nil if the area is malformed)
+    public var extensionEntries: [CiphertextExtension]? {
+        CiphertextExtension.parseAll(from: extensions)
+    }
+
+    /// Registry epoch the sender used (nil if not recorded or malformed)
+    public var registryEpoch: UInt32? {
+        guard let entry = extensionEntries?.first(where: { $0.type == CiphertextExtension.typeRegistryEpoch }),
+              entry.value.count == 4 else {
+            return nil
+        }
+        return entry.value.withUnsafeBytes { $0.loadUnaligned(as: UInt32.self) }
+    }
+
+    /// Base58-encoded token account of an SPL payment (nil if not recorded or malformed)
+    public var tokenAccount: String? {
+        guard let entry = extensionEntries?.first(where: { $0.type == CiphertextExtension.typeTokenAccount }),
+              entry.value.count == 32 else {
+            return nil
+        }
+        return SolanaRPCClient.encodePublicKey(entry.value)
+    }
+
+    /// Whether the sender's expiry hint has passed
+    /// - Parameter date: Reference time (defaults to now)
+    /// - Returns: True if an expiry is set and lies before `date`
+    public func isExpired(at date: Date = Date()) -> Bool {
+        guard let expiresAt = expiresAt else {
+            return false
+        }
+        return expiresAt <= Int64(date.timeIntervalSince1970)
+    }
+
+    /// Anchor account discriminator of CiphertextAccount (first 8 bytes of SHA-256 of "account:CiphertextAccount")
+    public static let discriminator = Data(SHA256.hash(data: Data("account:CiphertextAccount".utf8)).prefix(8))
+
+    /// Size of a CiphertextAccount in the legacy layout, with discriminator
+    public static let legacyAccountSize = 8 + 32 + 32 + MLKEM_CIPHERTEXT_SIZE + 8 + 1
+
     /// Parse CiphertextAccountData from raw account data
     /// - Parameter data: Raw account data (includes 8-byte Anchor discriminator)
-    /// - Returns: Parsed CiphertextAccountData or nil if invalid
+    /// - Returns: Parsed CiphertextAccountData or nil if invalid or not a CiphertextAccount
     public static func parse(from data: Data) -> CiphertextAccountData? {
+        guard data.prefix(8) == discriminator else {
+            return nil
+        }
+
+        let current = parseCurrentLayout(from: data)
+
+        // A legacy account can happen to parse in the current layout, but not
+        // with a ciphertext of its variant's size
+        if data.count == legacyAccountSize,
+           current.map({ $0.mlkemCiphertext.count != mlkemCiphertextSize(kemVariant: $0.kemVariant) }) ?? true {
+            return parseLegacyLayout(from: data)
+        }
+        return current
+    }
+
+    /// Parse an account created before namespaces, ML-KEM variants and
+    /// announcement metadata. It is a finalized ML-KEM-768 announcement in the
+    /// default namespace with no sender, view tag or metadata.
+    private static func parseLegacyLayout(from data: Data) -> CiphertextAccountData? {
+        // [0..8]       - Anchor discriminator
+        // [8..40]      - stealth_pubkey (32 bytes)
+        // [40..72]     - ephemeral_pubkey (32 bytes)
+        // [72..1160]   - mlkem_ciphertext (1088 bytes)
+        // [1160..1168] - created_at (i64)
+        // [1168]       - bump (u8)
+        let base = data.startIndex
+        func slice(_ offset: Int, _ length: Int) -> Data {
+            Data(data[(base + offset)..<(base + offset + length)])
+        }
+
+        return CiphertextAccountData(
+            stealthPubkey: slice(8, 32),
+            ephemeralPubkey: slice(40, 32),
+            kemVariant: KEM_VARIANT_ML_KEM_768,
+            encryptedMemo: nil,
+            mlkemCiphertext: slice(72, MLKEM_CIPHERTEXT_SIZE),
+            createdAt: slice(1160, 8).withUnsafeBytes { $0.loadUnaligned(as: Int64.self) },
+            bump: data[base + 1168],
+            expiresAt: nil,
+            encryptedReturnAddress: nil,
+            payloadTag: nil,
+            appId: DEFAULT_APP_ID,
+            rentPayer: Data(repeating: 0, count: 32),
+            rentPayerShareBps: 0,
+            viewTag: 0,
+            sender: Data(repeating: 0, count: 32),
+            isFinalized: true,
+            announcementIndex: nil,
+            extensions: Data(),
+            needsMigration: true
+        )
+    }
+
+    private static func parseCurrentLayout(from data: Data) -> CiphertextAccountData? {
         // Account layout (with 8-byte Anchor discriminator):
-        // [0..8]    - Anchor discriminator
-        // [8..40]   - stealth_pubkey (32 bytes)
-        // [40..72]  - ephemeral_pubkey (32 bytes)
-        // [72..1160] - mlkem_ciphertext (1088 bytes)
-        // [1160..1168] - created_at (i64, 8 bytes)
-        // [1168]    - bump (u8, 1 byte)
-        // Total: 8 + 32 + 32 + 1088 + 8 + 1 = 1169 bytes
-
-        guard data.count >= 1169 else {
+        // [0..8]     - Anchor discriminator
+        // [8..40]    - stealth_pubkey (32 bytes)
+        // [40..72]   - ephemeral_pubkey (32 bytes)
+        // [72..80]   - created_at (i64, 8 bytes)
+        // [80]       - bump (u8, 1 byte)
+        // [81..89]   - expires_at (i64, 8 bytes, 0 = none)
+        // [89..121]  - encrypted_return_address (32 bytes, zero = none)
+        // [121..137] - payload_tag (16 bytes, zero = none)
+        // [137..141] - app_id (u32)
+        // [141..173] - rent_payer (32 bytes)
+        // [173..175] - rent_payer_share_bps (u16)
+        // [175]      - view_tag (u8)
+        // [176..208] - sender (32 bytes)
+        // [208]      - finalized (bool)
+        // [209]      - kem_variant (u8)
+        // [210]      - memo_len (u8, 0 = none)
+        // [211..339] - encrypted_memo (128 bytes, zero-padded after memo_len)
+        // [339]      - logged (bool)
+        // [340..348] - announcement_index (u64, meaningful only if logged)
+        // [348..352] - mlkem_ciphertext length (u32)
+        // [352..]    - mlkem_ciphertext (768, 1088 or 1568 bytes by kem_variant)
+        // then       - extensions length (u32) and extensions (TLV, up to 512 bytes)
+        // Total: 8 + 348 bytes + ciphertext + extensions (1444 bytes for MLKEM768 without extensions)
+
+        guard data.count >= 8 + 348 else {
             return nil
         }
 
-        let stealthPubkey = data[8..<40]
-        let ephemeralPubkey = data[40..<72]
-        let mlkemCiphertext = data[72..<1160]
+        let base = data.startIndex
+        func slice(_ offset: Int, _ length: Int) -> Data {
+            Data(data[(base + offset)..<(base + offset + length)])
+        }
+
+        let stealthPubkey = slice(8, 32)
+        let ephemeralPubkey = slice(40, 32)
 
         // Parse i64 timestamp (little-endian)
-        let timestampData = data[1160..<1168]
-        let createdAt = timestampData.withUnsafeBytes { $0.load(as: Int64.self) }
+        let createdAt = slice(72, 8).withUnsafeBytes { $0.loadUnaligned(as: Int64.self) }
+
+        let bump = data[base + 80]
+
+        let expiry = slice(81, 8).withUnsafeBytes { $0.loadUnaligned(as: Int64.self) }
+        let expiresAt: Int64? = expiry == 0 ? nil : expiry
+
+        let returnAddress = slice(89, 32)
+        let encryptedReturnAddress = returnAddress.allSatisfy { $0 == 0 } ? nil : returnAddress
+
+        let tag = slice(121, 16)
+        let payloadTag = tag.allSatisfy { $0 == 0 } ? nil : tag
+
+        let appId = slice(137, 4).withUnsafeBytes { $0.loadUnaligned(as: UInt32.self) }
 
-        let bump = data[1168]
+        let rentPayer = slice(141, 32)
+        let rentPayerShareBps = slice(173, 2).withUnsafeBytes { $0.loadUnaligned(as: UInt16.self) }
+
+        let viewTag = data[base + 175]
+
+        let sender = slice(176, 32)
+        let isFinalized = data[base + 208] != 0
+
+        let kemVariant = data[base + 209]
+
+        let memoLength = Int(data[base + 210])
+        guard memoLength <= MAX_MEMO_SIZE else {
+            return nil
+        }
+        let encryptedMemo = memoLength == 0 ? nil : slice(211, memoLength)
+
+        let isLogged = data[base + 339] != 0
+        let announcementIndex: UInt64? = isLogged
+            ? slice(340, 8).withUnsafeBytes { $0.loadUnaligned(as: UInt64.self) }
+            : nil
+
+        let ciphertextLength = Int(slice(348, 4).withUnsafeBytes { $0.loadUnaligned(as: UInt32.self) })
+        let extensionsLengthOffset = 352 + ciphertextLength
+        guard data.count >= extensionsLengthOffset + 4 else {
+            return nil
+        }
+        let mlkemCiphertext = slice(352, ciphertextLength)
+
+        let extensionsLength = Int(slice(extensionsLengthOffset, 4).withUnsafeBytes { $0.loadUnaligned(as: UInt32.self) })
+        guard data.count >= extensionsLengthOffset + 4 + extensionsLength else {
+            return nil
+        }
+        let extensions = slice(extensionsLengthOffset + 4, extensionsLength)
 
         return CiphertextAccountData(
-            stealthPubkey: Data(stealthPubkey),
-            ephemeralPubkey: Data(ephemeralPubkey),
-            mlkemCiphertext: Data(mlkemCiphertext),
+            stealthPubkey: stealthPubkey,
+            ephemeralPubkey: ephemeralPubkey,
+            kemVariant: kemVariant,
+            encryptedMemo: encryptedMemo,
+            mlkemCiphertext: mlkemCiphertext,
             createdAt: createdAt,
-            bump: bump
+            bump: bump,
+            expiresAt: expiresAt,
+            encryptedReturnAddress: encryptedReturnAddress,
+            payloadTag: payloadTag,
+            appId: appId,
+            rentPayer: rentPayer,
+            rentPayerShareBps: rentPayerShareBps,
+            viewTag: viewTag,
+            sender: sender,
+            isFinalized: isFinalized,
+            announcementIndex: announcementIndex,
+            extensions: extensions,
+            needsMigration: false
+        )
+    }
+}
+
+/// Global opt-in transfer statistics fetched from the stealth-pq program
+public struct StatsAccountData: Sendable {
+    /// Number of transfers whose sender opted in
+    public let transferCount: UInt64
+
+    /// Total lamports moved by opted-in transfers
+    public let totalLamports: UInt64
+
+    /// Bump seed for PDA derivation
+    public let bump: UInt8
+
+    /// Anchor account discriminator: first 8 bytes of SHA256("account:StatsAccount")
+    public static let discriminator = Data(SHA256.hash(data: Data("account:StatsAccount".utf8)).prefix(8))
+
+    /// Parse StatsAccountData from raw account data
+    /// - Parameter data: Raw account data (includes 8-byte Anchor discriminator)
+    /// - Returns: Parsed StatsAccountData or nil if invalid or not a StatsAccount
+    public static func parse(from data: Data) -> StatsAccountData? {
+        // Account layout (with 8-byte Anchor discriminator):
+        // [0..8]   - Anchor discriminator
+        // [8..16]  - transfer_count (u64)
+        // [16..24] - total_lamports (u64)
+        // [24]     - bump (u8)
+        guard data.count >= 25, data.prefix(8) == discriminator else {
+            return nil
+        }
+
+        let base = data.startIndex
+        let transferCount = Data(data[(base + 8)..<(base + 16)]).withUnsafeBytes { $0.loadUnaligned(as: UInt64.self) }
+        let totalLamports = Data(data[(base + 16)..<(base + 24)]).withUnsafeBytes { $0.loadUnaligned(as: UInt64.self) }
+
+        return StatsAccountData(
+            transferCount: transferCount,
+            totalLamports: totalLamports,
+            bump: data[base + 24]
+        )
+    }
+}
+
+/// Parsed Announcement log entry
+public struct AnnouncementRecord: Sendable {
+    /// Position in the announcement log
+    public let index: UInt64
+
+    /// App namespace of the announcement
+    public let appId: UInt32
+
+    /// Base58-encoded stealth address the announcement is for
+    public let stealthPubkey: String
+
+    /// Base58-encoded CiphertextAccount PDA holding the full announcement
+    public let ciphertextAccount: String
+
+    /// Ephemeral X25519 public key (R)
+    public let ephemeralPubkey: Data
+
+    /// View tag, copied from the ciphertext account
+    public let viewTag: UInt8
+
+    /// Bump seed for PDA derivation
+    public let bump: UInt8
+
+    /// Base58-encoded account that paid the entry's rent
+    public let payer: String
+
+    /// Anchor account discriminator: first 8 bytes of SHA256("account:Announcement")
+    public static let discriminator = Data(SHA256.hash(data: Data("account:Announcement".utf8)).prefix(8))
+
+    /// Parse an AnnouncementRecord from raw account data
+    /// - Parameter data: Raw account data (includes 8-byte Anchor discriminator)
+    /// - Returns: Parsed AnnouncementRecord or nil if invalid or not an Announcement
+    public static func parse(from data: Data) -> AnnouncementRecord? {
+        // Account layout (with 8-byte Anchor discriminator):
+        // [0..8]     - Anchor discriminator
+        // [8..16]    - index (u64)
+        // [16..20]   - app_id (u32)
+        // [20..52]   - stealth_pubkey (32 bytes)
+        // [52..84]   - ciphertext_account (32 bytes)
+        // [84..116]  - ephemeral_pubkey (32 bytes)
+        // [116]      - view_tag (u8)
+        // [117]      - bump (u8)
+        // [118..150] - payer (32 bytes)
+        guard data.count >= 150, data.prefix(8) == discriminator else {
+            return nil
+        }
+
+        let base = data.startIndex
+        func slice(_ offset: Int, _ length: Int) -> Data {
+            Data(data[(base + offset)..<(base + offset + length)])
+        }
+
+        return AnnouncementRecord(
+            index: slice(8, 8).withUnsafeBytes { $0.loadUnaligned(as: UInt64.self) },
+            appId: slice(16, 4).withUnsafeBytes { $0.loadUnaligned(as: UInt32.self) },
+            stealthPubkey: SolanaRPCClient.encodePublicKey(slice(20, 32)),
+            ciphertextAccount: SolanaRPCClient.encodePublicKey(slice(52, 32)),
+            ephemeralPubkey: slice(84, 32),
+            viewTag: data[base + 116],
+            bump: data[base + 117],
+            payer: SolanaRPCClient.encodePublicKey(slice(118, 32))
+        )
+    }
+}
+
+/// `SplTransferEvent` emitted by `transfer_spl_to_stealth`
+public struct SplTransferEventData: Sendable, Equatable {
+    /// Base58-encoded stealth address receiving the tokens
+    public let stealthPubkey: String
+
+    /// Base58-encoded token mint
+    public let mint: String
+
+    /// Base58-encoded token account the tokens were credited to
+    public let tokenAccount: String
+
+    /// Amount the sender transferred, in base units
+    public let amount: UInt64
+
+    /// Amount the token account was actually credited, in base units
+    public let received: UInt64
+
+    /// Token-2022 transfer fee withheld by the mint (zero without the extension)
+    public var transferFee: UInt64 {
+        amount - received
+    }
+
+    /// Anchor event discriminator: first 8 bytes of SHA256("event:SplTransferEvent")
+    public static let discriminator = Data(SHA256.hash(data: Data("event:SplTransferEvent".utf8)).prefix(8))
+
+    /// Prefix of the log line Anchor's `emit!` writes the base64 event to
+    static let logPrefix = "Program data: "
+
+    /// Parse an SplTransferEvent from raw event data
+    /// - Parameter data: Raw event data (includes 8-byte Anchor discriminator)
+    /// - Returns: Parsed event or nil if invalid or not an SplTransferEvent
+    public static func parse(from data: Data) -> SplTransferEventData? {
+        // Event layout (with 8-byte Anchor discriminator):
+        // [0..8]     - Anchor discriminator
+        // [8..40]    - stealth_pubkey (32 bytes)
+        // [40..72]   - mint (32 bytes)
+        // [72..104]  - token_account (32 bytes)
+        // [104..112] - amount (u64)
+        // [112..120] - received (u64)
+        guard data.count >= 120, data.prefix(8) == discriminator else {
+            return nil
+        }
+
+        let base = data.startIndex
+        func slice(_ offset: Int, _ length: Int) -> Data {
+            Data(data[(base + offset)..<(base + offset + length)])
+        }
+
+        let amount = slice(104, 8).withUnsafeBytes { $0.loadUnaligned(as: UInt64.self) }
+        let received = slice(112, 8).withUnsafeBytes { $0.loadUnaligned(as: UInt64.self) }
+        guard received <= amount else {
+            return nil
+        }
+
+        return SplTransferEventData(
+            stealthPubkey: SolanaRPCClient.encodePublicKey(slice(8, 32)),
+            mint: SolanaRPCClient.encodePublicKey(slice(40, 32)),
+            tokenAccount: SolanaRPCClient.encodePublicKey(slice(72, 32)),
+            amount: amount,
+            received: received
+        )
+    }
+
+    /// Collect the SplTransferEvents from a transaction's log messages
+    /// - Parameter logs: `meta.logMessages` of the transaction
+    /// - Returns: Events in log order
+    public static func parseAll(fromLogs logs: [String]) -> [SplTransferEventData] {
+        logs.compactMap { line in
+            guard line.hasPrefix(logPrefix),
+                  let data = Data(base64Encoded: String(line.dropFirst(logPrefix.count))) else {
+                return nil
+            }
+            return parse(from: data)
+        }
+    }
+}
+
+/// Parsed MetaAddressRegistry account data
+public struct MetaAddressRecord: Sendable {
+    /// Wallet the meta-address belongs to
+    public let owner: Data
+
+    /// Spending public key (M)
+    public let spendingPubkey: Data
+
+    /// X25519 viewing public key (V)
+    public let viewingPubkey: Data
+
+    /// MLKEM768 encapsulation key, nil if not (yet) uploaded
+    public let mlkemPubkey: Data?
+
+    /// Key set version, recorded by senders in the registry epoch extension
+    public let epoch: UInt32
+
+    /// Unix timestamp of the last change
+    public let updatedAt: Int64
+
+    /// Bump seed for PDA derivation
+    public let bump: UInt8
+
+    /// Base58-encoded meta-address: hybrid (M || V || K_pub) when the
+    /// encapsulation key is present, classical (M || V) otherwise
+    public var metaAddressString: String {
+        (spendingPubkey + viewingPubkey + (mlkemPubkey ?? Data())).base58EncodedString
+    }
+
+    /// Parse MetaAddressRecord from raw account data
+    /// - Parameter data: Raw account data (includes 8-byte Anchor discriminator)
+    /// - Returns: Parsed MetaAddressRecord or nil if invalid
+    public static func parse(from data: Data) -> MetaAddressRecord? {
+        // Account layout (with 8-byte Anchor discriminator):
+        // [0..8]       - Anchor discriminator
+        // [8..40]      - owner (32 bytes)
+        // [40..72]     - spending_pubkey (32 bytes)
+        // [72..104]    - viewing_pubkey (32 bytes)
+        // [104..1288]  - mlkem_encapsulation_key (1184 bytes, zero = none)
+        // [1288..1292] - epoch (u32)
+        // [1292..1300] - updated_at (i64)
+        // [1300]       - bump (u8)
+        guard data.count >= 1301 else {
+            return nil
+        }
+
+        let base = data.startIndex
+        func slice(_ offset: Int, _ length: Int) -> Data {
+            Data(data[(base + offset)..<(base + offset + length)])
+        }
+
+        let mlkemPubkey = slice(104, 1184)
+
+        return MetaAddressRecord(
+            owner: slice(8, 32),
+            spendingPubkey: slice(40, 32),
+            viewingPubkey: slice(72, 32),
+            mlkemPubkey: mlkemPubkey.allSatisfy { $0 == 0 } ? nil : mlkemPubkey,
+            epoch: slice(1288, 4).withUnsafeBytes { $0.loadUnaligned(as: UInt32.self) },
+            updatedAt: slice(1292, 8).withUnsafeBytes { $0.loadUnaligned(as: Int64.self) },
+            bump: data[base + 1300]
         )
     }
 }
@@ -89,28 +690,42 @@ public actor StealthPQClient {
     // MARK: - PDA Derivation
 
     /// Derive the CiphertextAccount PDA address for a stealth address
-    /// - Parameter stealthAddress: Base58-encoded stealth address
+    /// - Parameters:
+    ///   - stealthAddress: Base58-encoded stealth address
+    ///   - appId: App namespace of the announcement
     /// - Returns: Base58-encoded PDA address and bump seed
-    public func deriveCiphertextPDA(stealthAddress: String) throws -> (address: String, bump: UInt8) {
+    public func deriveCiphertextPDA(
+        stealthAddress: String,
+        appId: UInt32 = DEFAULT_APP_ID
+    ) throws -> (address: String, bump: UInt8) {
         let stealthPubkey = try SolanaRPCClient.decodePublicKey(stealthAddress)
-        return try Self.deriveCiphertextPDA(stealthPubkey: stealthPubkey, programId: programId)
+        return try Self.deriveCiphertextPDA(stealthPubkey: stealthPubkey, programId: programId, appId: appId)
     }
 
     /// Derive the CiphertextAccount PDA address (static version)
     /// - Parameters:
     ///   - stealthPubkey: 32-byte stealth public key
     ///   - programId: Program ID
+    ///   - appId: App namespace of the announcement
     /// - Returns: Base58-encoded PDA address and bump seed
-    public static func deriveCiphertextPDA(stealthPubkey: Data, programId: String) throws -> (address: String, bump: UInt8) {
+    public static func deriveCiphertextPDA(
+        stealthPubkey: Data,
+        programId: String,
+        appId: UInt32 = DEFAULT_APP_ID
+    ) throws -> (address: String, bump: UInt8) {
         guard stealthPubkey.count == 32 else {
             throw SolanaError.invalidPublicKey
         }
 
         let programIdBytes = try SolanaRPCClient.decodePublicKey(programId)
 
-        // Seeds: ["ciphertext", stealth_pubkey]
+        // Seeds: ["ciphertext", stealth_pubkey, app_id (u32 LE, omitted for the default namespace)]
         let seed1 = "ciphertext".data(using: .utf8)!
-        let seeds = [seed1, stealthPubkey]
+        var seeds = [seed1, stealthPubkey]
+        if appId != DEFAULT_APP_ID {
+            var appIdLE = appId.littleEndian
+            seeds.append(Data(bytes: &appIdLE, count: 4))
+        }
 
         // Find PDA
         for bump in stride(from: 255, through: 0, by: -1) {
@@ -124,28 +739,274 @@ public actor StealthPQClient {
             }
         }
 
-        throw SolanaError.decodingError("Failed to find valid PDA bump")
+        throw SolanaError.decodingError("Failed to find valid PDA bump")
+    }
+
+    /// Derive the global StatsAccount PDA address
+    /// - Parameter programId: Program ID
+    /// - Returns: Base58-encoded PDA address and bump seed
+    public static func deriveStatsPDA(programId: String) throws -> (address: String, bump: UInt8) {
+        let programIdBytes = try SolanaRPCClient.decodePublicKey(programId)
+
+        // Seeds: ["stats"]
+        let seeds = ["stats".data(using: .utf8)!]
+
+        return try findProgramAddress(seeds: seeds, programId: programIdBytes)
+    }
+
+    /// Derive the global AnnouncementLog PDA address
+    /// - Parameter programId: Program ID
+    /// - Returns: Base58-encoded PDA address and bump seed
+    public static func deriveAnnouncementLogPDA(programId: String) throws -> (address: String, bump: UInt8) {
+        let programIdBytes = try SolanaRPCClient.decodePublicKey(programId)
+
+        // Seeds: ["announcement_log"]
+        let seeds = ["announcement_log".data(using: .utf8)!]
+
+        return try findProgramAddress(seeds: seeds, programId: programIdBytes)
+    }
+
+    /// Derive the Announcement PDA address at a log index
+    /// - Parameters:
+    ///   - index: Position in the announcement log
+    ///   - programId: Program ID
+    /// - Returns: Base58-encoded PDA address and bump seed
+    public static func deriveAnnouncementPDA(
+        index: UInt64,
+        programId: String
+    ) throws -> (address: String, bump: UInt8) {
+        let programIdBytes = try SolanaRPCClient.decodePublicKey(programId)
+
+        // Seeds: ["announcement", index (u64 LE)]
+        var indexLE = index.littleEndian
+        let seeds = ["announcement".data(using: .utf8)!, Data(bytes: &indexLE, count: 8)]
+
+        return try findProgramAddress(seeds: seeds, programId: programIdBytes)
+    }
+
+    /// Derive the NamespaceCounter PDA address for an app namespace
+    /// - Parameters:
+    ///   - appId: App namespace
+    ///   - programId: Program ID
+    /// - Returns: Base58-encoded PDA address and bump seed
+    public static func deriveNamespaceCounterPDA(
+        appId: UInt32,
+        programId: String
+    ) throws -> (address: String, bump: UInt8) {
+        let programIdBytes = try SolanaRPCClient.decodePublicKey(programId)
+
+        // Seeds: ["namespace", app_id (u32 LE)]
+        var appIdLE = appId.littleEndian
+        let seeds = ["namespace".data(using: .utf8)!, Data(bytes: &appIdLE, count: 4)]
+
+        return try findProgramAddress(seeds: seeds, programId: programIdBytes)
+    }
+
+    /// Derive the MetaAddressRegistry PDA address of a wallet
+    /// - Parameters:
+    ///   - owner: Base58-encoded wallet address
+    ///   - programId: Program ID
+    /// - Returns: Base58-encoded PDA address and bump seed
+    public static func deriveMetaAddressPDA(
+        owner: String,
+        programId: String
+    ) throws -> (address: String, bump: UInt8) {
+        let programIdBytes = try SolanaRPCClient.decodePublicKey(programId)
+
+        // Seeds: ["meta", owner]
+        let seeds = ["meta".data(using: .utf8)!, try SolanaRPCClient.decodePublicKey(owner)]
+
+        return try findProgramAddress(seeds: seeds, programId: programIdBytes)
+    }
+
+    /// Derive the associated token account address of an owner for a mint
+    /// - Parameters:
+    ///   - owner: Base58-encoded token account owner (e.g. a stealth address)
+    ///   - mint: Base58-encoded token mint
+    ///   - tokenProgram: Token program owning the mint (Token or Token-2022)
+    /// - Returns: Base58-encoded associated token account address
+    public static func deriveAssociatedTokenAddress(
+        owner: String,
+        mint: String,
+        tokenProgram: String = TOKEN_PROGRAM_ID
+    ) throws -> String {
+        let programIdBytes = try SolanaRPCClient.decodePublicKey(ASSOCIATED_TOKEN_PROGRAM_ID)
+
+        // Seeds: [owner, token_program, mint]
+        let seeds = [
+            try SolanaRPCClient.decodePublicKey(owner),
+            try SolanaRPCClient.decodePublicKey(tokenProgram),
+            try SolanaRPCClient.decodePublicKey(mint)
+        ]
+
+        return try findProgramAddress(seeds: seeds, programId: programIdBytes).address
+    }
+
+    // MARK: - Account Operations
+
+    /// Fetch CiphertextAccount data for a stealth address
+    /// - Parameters:
+    ///   - stealthAddress: Base58-encoded stealth address
+    ///   - appId: App namespace to look in
+    /// - Returns: CiphertextAccountData or nil if not found
+    public func getCiphertextAccount(
+        stealthAddress: String,
+        appId: UInt32 = DEFAULT_APP_ID
+    ) async throws -> CiphertextAccountData? {
+        guard let accountData = try await getRawCiphertextAccount(stealthAddress: stealthAddress, appId: appId) else {
+            return nil
+        }
+
+        return CiphertextAccountData.parse(from: accountData)
+    }
+
+    /// Fetch the transaction that created the CiphertextAccount for a stealth address
+    ///
+    /// The creating transaction is the oldest in the PDA's signature history, so
+    /// the history is paged through to its end.
+    /// - Parameters:
+    ///   - stealthAddress: Base58-encoded stealth address
+    ///   - appId: App namespace to look in
+    /// - Returns: Signature and slot of the creating transaction, or nil if the PDA has no history
+    public func getAnnouncementOrigin(
+        stealthAddress: String,
+        appId: UInt32 = DEFAULT_APP_ID
+    ) async throws -> AnnouncementOrigin? {
+        let (pdaAddress, _) = try deriveCiphertextPDA(stealthAddress: stealthAddress, appId: appId)
+        let pageSize = 1000
+
+        var oldest: SignatureInfo?
+        while true {
+            let page = try await rpcClient.getSignaturesForAddress(
+                address: pdaAddress,
+                limit: pageSize,
+                before: oldest?.signature
+            )
+            oldest = page.last ?? oldest
+            if page.count < pageSize {
+                break
+            }
+        }
+
+        return oldest.map { AnnouncementOrigin(signature: $0.signature, slot: $0.slot) }
+    }
+
+    /// Fetch the raw CiphertextAccount data for a stealth address
+    /// - Parameters:
+    ///   - stealthAddress: Base58-encoded stealth address
+    ///   - appId: App namespace to look in
+    /// - Returns: Account data (including discriminator) or nil if not found
+    public func getRawCiphertextAccount(
+        stealthAddress: String,
+        appId: UInt32 = DEFAULT_APP_ID
+    ) async throws -> Data? {
+        let (pdaAddress, _) = try deriveCiphertextPDA(stealthAddress: stealthAddress, appId: appId)
+
+        guard let accountInfo = try await rpcClient.getAccountInfo(pubkey: pdaAddress, encoding: "base64") else {
+            return nil
+        }
+
+        // Decode base64 data
+        guard accountInfo.data.count > 0,
+              let accountData = Data(base64Encoded: accountInfo.data[0]) else {
+            return nil
+        }
+
+        return accountData
+    }
+
+    /// Fetch the global opt-in transfer statistics
+    /// - Returns: StatsAccountData or nil if the stats account has not been created
+    public func getStats() async throws -> StatsAccountData? {
+        let (pdaAddress, _) = try Self.deriveStatsPDA(programId: programId)
+
+        guard let accountInfo = try await rpcClient.getAccountInfo(pubkey: pdaAddress, encoding: "base64"),
+              accountInfo.data.count > 0,
+              let accountData = Data(base64Encoded: accountInfo.data[0]) else {
+            return nil
+        }
+
+        return StatsAccountData.parse(from: accountData)
+    }
+
+    /// Fetch the number of announcements logged so far
+    /// - Returns: Index of the next announcement, or nil if the log has not been created
+    public func getAnnouncementCount() async throws -> UInt64? {
+        let (pdaAddress, _) = try Self.deriveAnnouncementLogPDA(programId: programId)
+
+        guard let accountInfo = try await rpcClient.getAccountInfo(pubkey: pdaAddress, encoding: "base64"),
+              accountInfo.data.count > 0,
+              let accountData = Data(base64Encoded: accountInfo.data[0]) else {
+            return nil
+        }
+
+        return Self.parseAnnouncementCount(from: accountData)
+    }
+
+    /// Anchor account discriminator of the AnnouncementLog: first 8 bytes of SHA256("account:AnnouncementLog")
+    public static let announcementLogDiscriminator = Data(SHA256.hash(data: Data("account:AnnouncementLog".utf8)).prefix(8))
+
+    /// Read the announcement count from raw AnnouncementLog account data
+    /// - Parameter data: Raw account data (includes 8-byte Anchor discriminator)
+    /// - Returns: Index of the next announcement, or nil if invalid or not an AnnouncementLog
+    public static func parseAnnouncementCount(from data: Data) -> UInt64? {
+        // [0..8] discriminator, [8..16] count (u64), [16] bump
+        guard data.count >= 17, data.prefix(8) == announcementLogDiscriminator else {
+            return nil
+        }
+
+        let base = data.startIndex
+        return Data(data[(base + 8)..<(base + 16)]).withUnsafeBytes { $0.loadUnaligned(as: UInt64.self) }
     }
 
-    // MARK: - Account Operations
-
-    /// Fetch CiphertextAccount data for a stealth address
-    /// - Parameter stealthAddress: Base58-encoded stealth address
-    /// - Returns: CiphertextAccountData or nil if not found
-    public func getCiphertextAccount(stealthAddress: String) async throws -> CiphertextAccountData? {
-        let (pdaAddress, _) = try deriveCiphertextPDA(stealthAddress: stealthAddress)
+    /// Fetch the announcements logged in an index range
+    ///
+    /// Entries closed on reclaim are skipped, so the result may be shorter than the range.
+    /// - Parameter indices: Log indices to fetch, e.g. from the last scanned index to the current count
+    /// - Returns: Announcements in index order
+    public func getAnnouncements(in indices: Range<UInt64>) async throws -> [AnnouncementRecord] {
+        var announcements: [AnnouncementRecord] = []
+
+        for index in indices {
+            let (pdaAddress, _) = try Self.deriveAnnouncementPDA(index: index, programId: programId)
+
+            guard let accountInfo = try await rpcClient.getAccountInfo(pubkey: pdaAddress, encoding: "base64"),
+                  accountInfo.data.count > 0,
+                  let accountData = Data(base64Encoded: accountInfo.data[0]),
+                  let announcement = AnnouncementRecord.parse(from: accountData) else {
+                continue
+            }
 
-        guard let accountInfo = try await rpcClient.getAccountInfo(pubkey: pdaAddress, encoding: "base64") else {
-            return nil
+            announcements.append(announcement)
         }
 
-        // Decode base64 data
-        guard accountInfo.data.count > 0,
+        return announcements
+    }
+
+    /// Fetch the SplTransferEvents a confirmed transaction emitted
+    ///
+    /// Use `received` rather than the requested amount when crediting a token payment:
+    /// mints with the Token-2022 transfer-fee extension withhold part of every transfer.
+    /// - Parameter signature: Signature of the funding transaction
+    /// - Returns: Events in log order, empty if the transaction emitted none
+    public func getSplTransferEvents(signature: String) async throws -> [SplTransferEventData] {
+        let logs = try await rpcClient.getTransactionLogs(signature: signature)
+        return SplTransferEventData.parseAll(fromLogs: logs)
+    }
+
+    /// Resolve a wallet address to its registered stealth meta-address
+    /// - Parameter owner: Base58-encoded wallet address
+    /// - Returns: MetaAddressRecord or nil if the wallet has not registered one
+    public func getMetaAddress(owner: String) async throws -> MetaAddressRecord? {
+        let (pdaAddress, _) = try Self.deriveMetaAddressPDA(owner: owner, programId: programId)
+
+        guard let accountInfo = try await rpcClient.getAccountInfo(pubkey: pdaAddress, encoding: "base64"),
+              accountInfo.data.count > 0,
               let accountData = Data(base64Encoded: accountInfo.data[0]) else {
             return nil
         }
 
-        return CiphertextAccountData.parse(from: accountData)
+        return MetaAddressRecord.parse(from: accountData)
     }
 
     /// Check if a CiphertextAccount exists for a stealth address
@@ -163,10 +1024,20 @@ public actor StealthPQClient {
     /// - Parameters:
     ///   - ephemeralPubkey: 32-byte ephemeral X25519 public key
     ///   - ciphertextPart1: First chunk of ciphertext (max 512 bytes)
+    ///   - expiresAt: Optional Unix timestamp expiry hint
+    ///   - appId: App namespace of the announcement
+    ///   - rentPayerShareBps: Share of the rent returned to the rent payer on close
+    ///   - viewTag: `StealthAddressResult.classicalViewTag` of the payment
+    ///   - kemVariant: ML-KEM parameter set of the ciphertext
     /// - Returns: Serialized instruction data
     public static func buildInitCiphertextData(
         ephemeralPubkey: Data,
-        ciphertextPart1: Data
+        ciphertextPart1: Data,
+        expiresAt: Int64? = nil,
+        appId: UInt32 = DEFAULT_APP_ID,
+        rentPayerShareBps: UInt16 = 0,
+        viewTag: UInt8 = 0,
+        kemVariant: UInt8 = KEM_VARIANT_ML_KEM_768
     ) -> Data {
         // Anchor discriminator for init_ciphertext
         // sha256("global:init_ciphertext")[0..8]
@@ -178,10 +1049,66 @@ public actor StealthPQClient {
         // ephemeral_pubkey: [u8; 32]
         data.append(ephemeralPubkey)
 
-        // ciphertext_part1: Vec<u8> (4-byte length prefix + data)
-        var length = UInt32(ciphertextPart1.count).littleEndian
-        data.append(Data(bytes: &length, count: 4))
-        data.append(ciphertextPart1)
+        // ciphertext_part1: DataChunk
+        appendChunk(ciphertextPart1, to: &data)
+
+        // expires_at: Option<i64>
+        appendOptionalInt64(expiresAt, to: &data)
+
+        // app_id: u32
+        var appIdLE = appId.littleEndian
+        data.append(Data(bytes: &appIdLE, count: 4))
+
+        // rent_payer_share_bps: u16
+        var shareLE = rentPayerShareBps.littleEndian
+        data.append(Data(bytes: &shareLE, count: 2))
+
+        // view_tag: u8
+        data.append(viewTag)
+
+        // kem_variant: u8
+        data.append(kemVariant)
+
+        return data
+    }
+
+    /// Build the init_ciphertext_and_fund instruction data
+    /// - Parameters:
+    ///   - ephemeralPubkey: 32-byte ephemeral X25519 public key
+    ///   - ciphertextPart1: First chunk of ciphertext (max 512 bytes)
+    ///   - expiresAt: Optional Unix timestamp expiry hint
+    ///   - appId: App namespace of the announcement
+    ///   - rentPayerShareBps: Share of the rent returned to the rent payer on close
+    ///   - viewTag: `StealthAddressResult.classicalViewTag` of the payment
+    ///   - lamports: Amount of SOL to transfer
+    ///   - kemVariant: ML-KEM parameter set of the ciphertext
+    /// - Returns: Serialized instruction data
+    public static func buildInitCiphertextAndFundData(
+        ephemeralPubkey: Data,
+        ciphertextPart1: Data,
+        expiresAt: Int64? = nil,
+        appId: UInt32 = DEFAULT_APP_ID,
+        rentPayerShareBps: UInt16 = 0,
+        viewTag: UInt8 = 0,
+        lamports: UInt64,
+        kemVariant: UInt8 = KEM_VARIANT_ML_KEM_768
+    ) -> Data {
+        // Same arguments as init_ciphertext up to view_tag, followed by
+        // lamports: u64 and kem_variant: u8
+        var data = computeDiscriminator(name: "init_ciphertext_and_fund")
+        data.append(buildInitCiphertextData(
+            ephemeralPubkey: ephemeralPubkey,
+            ciphertextPart1: ciphertextPart1,
+            expiresAt: expiresAt,
+            appId: appId,
+            rentPayerShareBps: rentPayerShareBps,
+            viewTag: viewTag
+        ).dropFirst(8).dropLast())
+
+        var lamportsLE = lamports.littleEndian
+        data.append(Data(bytes: &lamportsLE, count: 8))
+
+        data.append(kemVariant)
 
         return data
     }
@@ -200,10 +1127,97 @@ public actor StealthPQClient {
         var data = Data()
         data.append(discriminator)
 
-        // ciphertext_part2: Vec<u8>
-        var length = UInt32(ciphertextPart2.count).littleEndian
-        data.append(Data(bytes: &length, count: 4))
-        data.append(ciphertextPart2)
+        // ciphertext_part2: DataChunk
+        appendChunk(ciphertextPart2, to: &data)
+
+        // offset: u16
+        var offsetLE = offset.littleEndian
+        data.append(Data(bytes: &offsetLE, count: 2))
+
+        return data
+    }
+
+    /// Build the set_return_address instruction data
+    /// - Parameter encryptedReturnAddress: 32-byte return address encrypted to the shared secret
+    /// - Returns: Serialized instruction data
+    public static func buildSetReturnAddressData(encryptedReturnAddress: Data) -> Data {
+        let discriminator = computeDiscriminator(name: "set_return_address")
+
+        var data = Data()
+        data.append(discriminator)
+
+        // encrypted_return_address: [u8; 32]
+        data.append(encryptedReturnAddress)
+
+        return data
+    }
+
+    /// Build the set_payload_tag instruction data
+    /// - Parameter payloadTag: 16-byte AEAD tag over the encrypted metadata
+    /// - Returns: Serialized instruction data
+    public static func buildSetPayloadTagData(payloadTag: Data) -> Data {
+        let discriminator = computeDiscriminator(name: "set_payload_tag")
+
+        var data = Data()
+        data.append(discriminator)
+
+        // payload_tag: [u8; 16]
+        data.append(payloadTag)
+
+        return data
+    }
+
+    /// Build the set_memo instruction data
+    /// - Parameter encryptedMemo: Memo encrypted to the shared secret (max MAX_MEMO_SIZE bytes, empty to clear)
+    /// - Returns: Serialized instruction data
+    public static func buildSetMemoData(encryptedMemo: Data) -> Data {
+        let discriminator = computeDiscriminator(name: "set_memo")
+
+        var data = Data()
+        data.append(discriminator)
+
+        // encrypted_memo: [u8; MAX_MEMO_SIZE], zero-padded
+        let memo = encryptedMemo.prefix(MAX_MEMO_SIZE)
+        data.append(memo)
+        data.append(Data(repeating: 0, count: MAX_MEMO_SIZE - memo.count))
+
+        // len: u8 (oversized memos keep an out-of-range length so the program rejects them)
+        data.append(UInt8(clamping: encryptedMemo.count))
+
+        return data
+    }
+
+    /// Build the complete_and_finalize instruction data
+    /// - Parameters:
+    ///   - ciphertextPart2: Remaining chunk of ciphertext
+    ///   - offset: Offset in the ciphertext array
+    /// - Returns: Serialized instruction data
+    public static func buildCompleteAndFinalizeData(ciphertextPart2: Data, offset: UInt16) -> Data {
+        // Same arguments as complete_ciphertext
+        var data = computeDiscriminator(name: "complete_and_finalize")
+        data.append(buildCompleteCiphertextData(ciphertextPart2: ciphertextPart2, offset: offset).dropFirst(8))
+        return data
+    }
+
+    /// Build the finalize_ciphertext instruction data
+    /// - Returns: Serialized instruction data
+    public static func buildFinalizeCiphertextData() -> Data {
+        return computeDiscriminator(name: "finalize_ciphertext")
+    }
+
+    /// Build the write_extensions instruction data
+    /// - Parameters:
+    ///   - chunk: Extension area bytes to write (at most MAX_CHUNK_SIZE)
+    ///   - offset: Offset in the extension area to write to
+    /// - Returns: Serialized instruction data
+    public static func buildWriteExtensionsData(chunk: Data, offset: UInt16) -> Data {
+        let discriminator = computeDiscriminator(name: "write_extensions")
+
+        var data = Data()
+        data.append(discriminator)
+
+        // chunk: DataChunk
+        appendChunk(chunk, to: &data)
 
         // offset: u16
         var offsetLE = offset.littleEndian
@@ -228,6 +1242,110 @@ public actor StealthPQClient {
         return data
     }
 
+    /// Build the batch_transfer_to_stealth instruction data
+    /// - Parameter amounts: Lamports per recipient, in the order of the account pairs
+    /// - Returns: Serialized instruction data
+    public static func buildBatchTransferToStealthData(amounts: [UInt64]) -> Data {
+        let discriminator = computeDiscriminator(name: "batch_transfer_to_stealth")
+
+        var data = Data()
+        data.append(discriminator)
+
+        // amounts: Vec<u64>
+        var count = UInt32(amounts.count).littleEndian
+        data.append(Data(bytes: &count, count: 4))
+        for amount in amounts {
+            var amountLE = amount.littleEndian
+            data.append(Data(bytes: &amountLE, count: 8))
+        }
+
+        return data
+    }
+
+    /// Build the transfer_spl_to_stealth instruction data
+    /// - Parameter amount: Amount of tokens to transfer, in base units
+    /// - Returns: Serialized instruction data
+    public static func buildTransferSplToStealthData(amount: UInt64) -> Data {
+        let discriminator = computeDiscriminator(name: "transfer_spl_to_stealth")
+
+        var data = Data()
+        data.append(discriminator)
+
+        // amount: u64
+        var amountLE = amount.littleEndian
+        data.append(Data(bytes: &amountLE, count: 8))
+
+        return data
+    }
+
+    /// Build the register_meta_address (or update_meta_address) instruction data
+    /// - Parameters:
+    ///   - spendingPubkey: 32-byte spending public key
+    ///   - viewingPubkey: 32-byte X25519 viewing public key
+    ///   - update: Build update_meta_address for an existing entry instead
+    /// - Returns: Serialized instruction data
+    public static func buildRegisterMetaAddressData(
+        spendingPubkey: Data,
+        viewingPubkey: Data,
+        update: Bool = false
+    ) -> Data {
+        let discriminator = computeDiscriminator(name: update ? "update_meta_address" : "register_meta_address")
+
+        var data = Data()
+        data.append(discriminator)
+
+        // spending_pubkey: [u8; 32], viewing_pubkey: [u8; 32]
+        data.append(spendingPubkey)
+        data.append(viewingPubkey)
+
+        return data
+    }
+
+    /// Build the write_meta_address_key instruction data
+    /// - Parameters:
+    ///   - chunk: Encapsulation key bytes to write (at most MAX_CHUNK_SIZE)
+    ///   - offset: Offset in the encapsulation key to write to
+    /// - Returns: Serialized instruction data
+    public static func buildWriteMetaAddressKeyData(chunk: Data, offset: UInt16) -> Data {
+        let discriminator = computeDiscriminator(name: "write_meta_address_key")
+
+        var data = Data()
+        data.append(discriminator)
+
+        // chunk: DataChunk
+        appendChunk(chunk, to: &data)
+
+        // offset: u16
+        var offsetLE = offset.littleEndian
+        data.append(Data(bytes: &offsetLE, count: 2))
+
+        return data
+    }
+
+    /// Build the close_meta_address instruction data
+    /// - Returns: Serialized instruction data
+    public static func buildCloseMetaAddressData() -> Data {
+        return computeDiscriminator(name: "close_meta_address")
+    }
+
+    /// Build the init_announcement_log instruction data
+    /// - Returns: Serialized instruction data
+    public static func buildInitAnnouncementLogData() -> Data {
+        return computeDiscriminator(name: "init_announcement_log")
+    }
+
+    /// Build the log_announcement instruction data
+    /// - Returns: Serialized instruction data
+    public static func buildLogAnnouncementData() -> Data {
+        return computeDiscriminator(name: "log_announcement")
+    }
+
+    /// Build the snapshot_stats instruction data
+    /// - Returns: Serialized instruction data
+    public static func buildSnapshotStatsData() -> Data {
+        return computeDiscriminator(name: "snapshot_stats")
+    }
+
     /// Build the reclaim_rent instruction data
     /// - Returns: Serialized instruction data
     public static func buildReclaimRentData() -> Data {
@@ -235,8 +1353,43 @@ public actor StealthPQClient {
         return discriminator
     }
 
+    /// Build the migrate_ciphertext instruction data
+    /// - Returns: Serialized instruction data
+    public static func buildMigrateCiphertextData() -> Data {
+        return computeDiscriminator(name: "migrate_ciphertext")
+    }
+
+    /// Build the refund_expired instruction data
+    /// - Returns: Serialized instruction data
+    public static func buildRefundExpiredData() -> Data {
+        return computeDiscriminator(name: "refund_expired")
+    }
+
     // MARK: - Private Helpers
 
+    /// Append a Borsh `Option<i64>` (1-byte tag, then the little-endian value if present)
+    private static func appendOptionalInt64(_ value: Int64?, to data: inout Data) {
+        guard let value = value else {
+            data.append(0)
+            return
+        }
+        data.append(1)
+        var valueLE = value.littleEndian
+        data.append(Data(bytes: &valueLE, count: 8))
+    }
+
+    /// Append a ciphertext chunk argument as a `DataChunk`
+    /// - Parameters:
+    ///   - chunk: Chunk bytes (at most MAX_CHUNK_SIZE)
+    ///   - data: Instruction data to append to
+    private static func appendChunk(_ chunk: Data, to data: inout Data) {
+        // DataChunk: u16 length + zero-padded [u8; MAX_CHUNK_SIZE]
+        var length = UInt16(chunk.count).littleEndian
+        data.append(Data(bytes: &length, count: 2))
+        data.append(chunk)
+        data.append(Data(repeating: 0, count: MAX_CHUNK_SIZE - chunk.count))
+    }
+
     /// Compute Anchor instruction discriminator
     /// - Parameter name: Instruction name
     /// - Returns: 8-byte discriminator
@@ -246,6 +1399,24 @@ public actor StealthPQClient {
         return Data(hash.prefix(8))
     }
 
+    /// Find the canonical (highest-bump, off-curve) program address for seeds
+    /// - Parameters:
+    ///   - seeds: Array of seed data, without the bump
+    ///   - programId: Program ID bytes
+    /// - Returns: Base58-encoded PDA address and bump seed
+    private static func findProgramAddress(seeds: [Data], programId: Data) throws -> (address: String, bump: UInt8) {
+        for bump in stride(from: 255, through: 0, by: -1) {
+            let bumpByte = UInt8(bump)
+            if let pda = try? deriveAddress(seeds: seeds + [Data([bumpByte])], programId: programId) {
+                if isOffCurve(pda) {
+                    return (SolanaRPCClient.encodePublicKey(pda), bumpByte)
+                }
+            }
+        }
+
+        throw SolanaError.decodingError("Failed to find valid PDA bump")
+    }
+
     /// Derive a program address from seeds
     /// - Parameters:
     ///   - seeds: Array of seed data
@@ -295,29 +1466,62 @@ extension StealthPQClient {
 
     /// Get account metas for init_ciphertext instruction
     /// - Parameters:
-    ///   - sender: Sender wallet (payer, signer)
+    ///   - sender: Sender wallet (signer)
     ///   - stealthAddress: Stealth address receiving funds
+    ///   - appId: App namespace of the announcement
+    ///   - rentPayer: Account sponsoring the rent (signer; the sender pays if nil)
     /// - Returns: Array of account metas
     public func getInitCiphertextAccounts(
         sender: String,
-        stealthAddress: String
+        stealthAddress: String,
+        appId: UInt32 = DEFAULT_APP_ID,
+        rentPayer: String? = nil
     ) throws -> [AccountMeta] {
-        let (ciphertextPDA, _) = try deriveCiphertextPDA(stealthAddress: stealthAddress)
+        let (ciphertextPDA, _) = try deriveCiphertextPDA(stealthAddress: stealthAddress, appId: appId)
+        let (counterPDA, _) = try Self.deriveNamespaceCounterPDA(appId: appId, programId: programId)
+
+        let rentPayerMeta = rentPayer.map { AccountMeta(pubkey: $0, isSigner: true, isWritable: true) }
+            ?? AccountMeta(pubkey: programId, isSigner: false, isWritable: false)
 
         return [
             AccountMeta(pubkey: sender, isSigner: true, isWritable: true),           // sender
+            rentPayerMeta,                                                           // rent_payer
             AccountMeta(pubkey: stealthAddress, isSigner: false, isWritable: true),  // stealth_address
             AccountMeta(pubkey: ciphertextPDA, isSigner: false, isWritable: true),   // ciphertext_account
+            AccountMeta(pubkey: counterPDA, isSigner: false, isWritable: true),      // namespace_counter
             AccountMeta(pubkey: SYSTEM_PROGRAM_ID, isSigner: false, isWritable: false) // system_program
         ]
     }
 
-    /// Get account metas for complete_ciphertext instruction
+    /// Get account metas for init_ciphertext_and_fund instruction
+    /// - Parameters:
+    ///   - sender: Sender wallet (signer, pays the transfer)
+    ///   - stealthAddress: Stealth address receiving funds
+    ///   - appId: App namespace of the announcement
+    ///   - rentPayer: Account sponsoring the rent (signer; the sender pays if nil)
+    /// - Returns: Array of account metas
+    public func getInitCiphertextAndFundAccounts(
+        sender: String,
+        stealthAddress: String,
+        appId: UInt32 = DEFAULT_APP_ID,
+        rentPayer: String? = nil
+    ) throws -> [AccountMeta] {
+        // Same accounts as init_ciphertext, which never passes the instructions sysvar
+        return try getInitCiphertextAccounts(
+            sender: sender,
+            stealthAddress: stealthAddress,
+            appId: appId,
+            rentPayer: rentPayer
+        )
+    }
+
+    /// Get account metas for complete_ciphertext (and complete_and_finalize / set_return_address / set_payload_tag / finalize_ciphertext) instruction
     public func getCompleteCiphertextAccounts(
         sender: String,
-        stealthAddress: String
+        stealthAddress: String,
+        appId: UInt32 = DEFAULT_APP_ID
     ) throws -> [AccountMeta] {
-        let (ciphertextPDA, _) = try deriveCiphertextPDA(stealthAddress: stealthAddress)
+        let (ciphertextPDA, _) = try deriveCiphertextPDA(stealthAddress: stealthAddress, appId: appId)
 
         return [
             AccountMeta(pubkey: sender, isSigner: true, isWritable: true),           // sender
@@ -325,30 +1529,243 @@ extension StealthPQClient {
         ]
     }
 
+    /// Get account metas for write_extensions instruction
+    public func getWriteExtensionsAccounts(
+        sender: String,
+        stealthAddress: String,
+        appId: UInt32 = DEFAULT_APP_ID
+    ) throws -> [AccountMeta] {
+        let (ciphertextPDA, _) = try deriveCiphertextPDA(stealthAddress: stealthAddress, appId: appId)
+
+        return [
+            AccountMeta(pubkey: sender, isSigner: true, isWritable: true),           // sender
+            AccountMeta(pubkey: ciphertextPDA, isSigner: false, isWritable: true),   // ciphertext_account
+            AccountMeta(pubkey: SYSTEM_PROGRAM_ID, isSigner: false, isWritable: false) // system_program
+        ]
+    }
+
     /// Get account metas for transfer_to_stealth instruction
+    /// - Parameters:
+    ///   - sender: Sender wallet (signer)
+    ///   - stealthAddress: Stealth address receiving funds
+    ///   - appId: App namespace of the announcement
+    ///   - countInStats: Opt in to the global aggregate statistics
     public func getTransferToStealthAccounts(
         sender: String,
-        stealthAddress: String
+        stealthAddress: String,
+        appId: UInt32 = DEFAULT_APP_ID,
+        countInStats: Bool = false
     ) throws -> [AccountMeta] {
-        let (ciphertextPDA, _) = try deriveCiphertextPDA(stealthAddress: stealthAddress)
+        let (ciphertextPDA, _) = try deriveCiphertextPDA(stealthAddress: stealthAddress, appId: appId)
 
-        return [
+        var accounts = [
             AccountMeta(pubkey: sender, isSigner: true, isWritable: true),           // sender
             AccountMeta(pubkey: stealthAddress, isSigner: false, isWritable: true),  // stealth_address
             AccountMeta(pubkey: ciphertextPDA, isSigner: false, isWritable: false),  // ciphertext_account
             AccountMeta(pubkey: SYSTEM_PROGRAM_ID, isSigner: false, isWritable: false) // system_program
         ]
+
+        if countInStats {
+            let (statsPDA, _) = try Self.deriveStatsPDA(programId: programId)
+            accounts.append(AccountMeta(pubkey: statsPDA, isSigner: false, isWritable: true)) // stats
+        }
+
+        return accounts
+    }
+
+    /// Get account metas for batch_transfer_to_stealth instruction
+    ///
+    /// The batch is all-or-nothing: if any recipient fails validation the whole
+    /// transaction reverts. Split large batches across transactions to stay within
+    /// the transaction size limit.
+    /// - Parameters:
+    ///   - sender: Sender wallet (signer, pays every transfer)
+    ///   - stealthAddresses: Recipient stealth addresses, in the order of the amounts
+    ///   - appId: App namespace of the announcements
+    ///   - countInStats: Pass the global stats account to count each transfer
+    public func getBatchTransferToStealthAccounts(
+        sender: String,
+        stealthAddresses: [String],
+        appId: UInt32 = DEFAULT_APP_ID,
+        countInStats: Bool = false
+    ) throws -> [AccountMeta] {
+        var accounts = [
+            AccountMeta(pubkey: sender, isSigner: true, isWritable: true),              // sender
+            AccountMeta(pubkey: SYSTEM_PROGRAM_ID, isSigner: false, isWritable: false)  // system_program
+        ]
+
+        if countInStats {
+            let (statsPDA, _) = try Self.deriveStatsPDA(programId: programId)
+            accounts.append(AccountMeta(pubkey: statsPDA, isSigner: false, isWritable: true)) // stats
+        } else {
+            // Anchor reads the program ID in an optional slot as None
+            accounts.append(AccountMeta(pubkey: programId, isSigner: false, isWritable: false)) // stats
+        }
+
+        for stealthAddress in stealthAddresses {
+            let (ciphertextPDA, _) = try deriveCiphertextPDA(stealthAddress: stealthAddress, appId: appId)
+            accounts.append(AccountMeta(pubkey: stealthAddress, isSigner: false, isWritable: true))  // stealth_address
+            accounts.append(AccountMeta(pubkey: ciphertextPDA, isSigner: false, isWritable: false))  // ciphertext_account
+        }
+
+        return accounts
+    }
+
+    /// Get account metas for transfer_spl_to_stealth instruction
+    /// - Parameters:
+    ///   - sender: Sender wallet (signer, owner of the source tokens)
+    ///   - stealthAddress: Stealth address receiving the tokens
+    ///   - mint: Token mint
+    ///   - tokenProgram: Token program owning the mint (Token or Token-2022)
+    ///   - appId: App namespace of the announcement
+    ///   - extraAccounts: For a mint with a transfer hook, the hook program, its
+    ///     extra-account-metas PDA and the accounts that lists, forwarded to the transfer
+    public func getTransferSplToStealthAccounts(
+        sender: String,
+        stealthAddress: String,
+        mint: String,
+        tokenProgram: String = TOKEN_PROGRAM_ID,
+        appId: UInt32 = DEFAULT_APP_ID,
+        extraAccounts: [AccountMeta] = []
+    ) throws -> [AccountMeta] {
+        let (ciphertextPDA, _) = try deriveCiphertextPDA(stealthAddress: stealthAddress, appId: appId)
+        let senderTokenAccount = try Self.deriveAssociatedTokenAddress(owner: sender, mint: mint, tokenProgram: tokenProgram)
+        let stealthTokenAccount = try Self.deriveAssociatedTokenAddress(owner: stealthAddress, mint: mint, tokenProgram: tokenProgram)
+
+        return [
+            AccountMeta(pubkey: sender, isSigner: true, isWritable: true),                 // sender
+            AccountMeta(pubkey: stealthAddress, isSigner: false, isWritable: false),       // stealth_address
+            AccountMeta(pubkey: ciphertextPDA, isSigner: false, isWritable: false),        // ciphertext_account
+            AccountMeta(pubkey: mint, isSigner: false, isWritable: false),                 // mint
+            AccountMeta(pubkey: senderTokenAccount, isSigner: false, isWritable: true),    // sender_token_account
+            AccountMeta(pubkey: stealthTokenAccount, isSigner: false, isWritable: true),   // stealth_token_account
+            AccountMeta(pubkey: tokenProgram, isSigner: false, isWritable: false),         // token_program
+            AccountMeta(pubkey: ASSOCIATED_TOKEN_PROGRAM_ID, isSigner: false, isWritable: false), // associated_token_program
+            AccountMeta(pubkey: SYSTEM_PROGRAM_ID, isSigner: false, isWritable: false)     // system_program
+        ] + extraAccounts
+    }
+
+    /// Get account metas for the meta-address registry instructions
+    /// - Parameters:
+    ///   - owner: Wallet publishing the meta-address (signer)
+    ///   - includeSystemProgram: Append the system program (register_meta_address only)
+    public func getMetaAddressAccounts(
+        owner: String,
+        includeSystemProgram: Bool = false
+    ) throws -> [AccountMeta] {
+        let (registryPDA, _) = try Self.deriveMetaAddressPDA(owner: owner, programId: programId)
+
+        var accounts = [
+            AccountMeta(pubkey: owner, isSigner: true, isWritable: true),          // owner
+            AccountMeta(pubkey: registryPDA, isSigner: false, isWritable: true)    // meta_address
+        ]
+
+        if includeSystemProgram {
+            accounts.append(AccountMeta(pubkey: SYSTEM_PROGRAM_ID, isSigner: false, isWritable: false)) // system_program
+        }
+
+        return accounts
+    }
+
+    /// Get account metas for log_announcement instruction
+    /// - Parameters:
+    ///   - sender: Sender wallet (signer, pays the log entry rent)
+    ///   - stealthAddress: Stealth address of the announcement
+    ///   - index: Current announcement count, the index the entry is logged at
+    ///   - appId: App namespace of the announcement
+    public func getLogAnnouncementAccounts(
+        sender: String,
+        stealthAddress: String,
+        index: UInt64,
+        appId: UInt32 = DEFAULT_APP_ID
+    ) throws -> [AccountMeta] {
+        let (ciphertextPDA, _) = try deriveCiphertextPDA(stealthAddress: stealthAddress, appId: appId)
+        let (logPDA, _) = try Self.deriveAnnouncementLogPDA(programId: programId)
+        let (announcementPDA, _) = try Self.deriveAnnouncementPDA(index: index, programId: programId)
+
+        return [
+            AccountMeta(pubkey: sender, isSigner: true, isWritable: true),              // sender
+            AccountMeta(pubkey: ciphertextPDA, isSigner: false, isWritable: true),      // ciphertext_account
+            AccountMeta(pubkey: logPDA, isSigner: false, isWritable: true),             // announcement_log
+            AccountMeta(pubkey: announcementPDA, isSigner: false, isWritable: true),    // announcement
+            AccountMeta(pubkey: SYSTEM_PROGRAM_ID, isSigner: false, isWritable: false)  // system_program
+        ]
+    }
+
+    /// Get account metas for migrate_ciphertext instruction
+    /// - Parameters:
+    ///   - payer: Pays the rent of the larger layout (signer)
+    ///   - stealthAddress: Stealth address of the legacy announcement (always in the default namespace)
+    public func getMigrateCiphertextAccounts(payer: String, stealthAddress: String) throws -> [AccountMeta] {
+        let (ciphertextPDA, _) = try deriveCiphertextPDA(stealthAddress: stealthAddress, appId: DEFAULT_APP_ID)
+
+        return [
+            AccountMeta(pubkey: payer, isSigner: true, isWritable: true),               // payer
+            AccountMeta(pubkey: ciphertextPDA, isSigner: false, isWritable: true),      // ciphertext_account
+            AccountMeta(pubkey: SYSTEM_PROGRAM_ID, isSigner: false, isWritable: false)  // system_program
+        ]
     }
 
     /// Get account metas for reclaim_rent instruction
+    /// - Parameters:
+    ///   - stealthSigner: Stealth address closing its announcement (signer)
+    ///   - appId: App namespace of the announcement
+    ///   - rentPayer: Rent payer recorded in the announcement, if it has a share
+    ///   - announcement: Announcement log entry to close along with it, if one was logged
     public func getReclaimRentAccounts(
-        stealthSigner: String
+        stealthSigner: String,
+        appId: UInt32 = DEFAULT_APP_ID,
+        rentPayer: String? = nil,
+        announcement: String? = nil
     ) throws -> [AccountMeta] {
-        let (ciphertextPDA, _) = try deriveCiphertextPDA(stealthAddress: stealthSigner)
+        let (ciphertextPDA, _) = try deriveCiphertextPDA(stealthAddress: stealthSigner, appId: appId)
 
-        return [
+        var accounts = [
             AccountMeta(pubkey: stealthSigner, isSigner: true, isWritable: true),    // stealth_signer
             AccountMeta(pubkey: ciphertextPDA, isSigner: false, isWritable: true)    // ciphertext_account
         ]
+
+        // Required when the announcement has a rent payer share
+        if let rentPayer {
+            accounts.append(AccountMeta(pubkey: rentPayer, isSigner: false, isWritable: true)) // rent_payer
+        } else if announcement != nil {
+            // Anchor reads the program ID in an optional slot as None
+            accounts.append(AccountMeta(pubkey: programId, isSigner: false, isWritable: false)) // rent_payer
+        }
+
+        if let announcement {
+            accounts.append(AccountMeta(pubkey: announcement, isSigner: false, isWritable: true)) // announcement
+        }
+
+        return accounts
+    }
+
+    /// Get account metas for refund_expired instruction
+    /// - Parameters:
+    ///   - sender: Sender who created the announcement (signer)
+    ///   - stealthAddress: Stealth address of the expired announcement
+    ///   - appId: App namespace of the announcement
+    ///   - rentPayer: Rent payer recorded in the announcement (defaults to the sender)
+    ///   - announcement: Announcement log entry to close along with it, if one was logged
+    public func getRefundExpiredAccounts(
+        sender: String,
+        stealthAddress: String,
+        appId: UInt32 = DEFAULT_APP_ID,
+        rentPayer: String? = nil,
+        announcement: String? = nil
+    ) throws -> [AccountMeta] {
+        let (ciphertextPDA, _) = try deriveCiphertextPDA(stealthAddress: stealthAddress, appId: appId)
+
+        var accounts = [
+            AccountMeta(pubkey: sender, isSigner: true, isWritable: true),                // sender
+            AccountMeta(pubkey: ciphertextPDA, isSigner: false, isWritable: true),        // ciphertext_account
+            AccountMeta(pubkey: rentPayer ?? sender, isSigner: false, isWritable: true)   // rent_payer
+        ]
+
+        if let announcement {
+            accounts.append(AccountMeta(pubkey: announcement, isSigner: false, isWritable: true)) // announcement
+        }
+
+        return accounts
     }
 }
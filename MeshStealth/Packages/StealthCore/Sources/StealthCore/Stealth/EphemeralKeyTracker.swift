@@ -0,0 +1,85 @@
+import Foundation
+import CryptoKit
+
+/// Set of ephemeral public keys seen so far
+///
+/// A reused ephemeral key links the payments that share it, and usually means a
+/// broken or compromised sender. Keys are stored as truncated SHA-256 digests so
+/// the persisted set stays small and doesn't hold the keys themselves. Received
+/// keys also keep a digest of the stealth address they paid, so a payment
+/// delivered twice isn't mistaken for reuse.
+public struct EphemeralKeyTracker: Codable, Sendable {
+    /// Bytes of SHA-256 kept per key (collisions are negligible at this size)
+    public static let digestSize = 16
+
+    /// How a received key relates to the keys recorded before
+    public enum Sighting: Sendable {
+        /// First time the key is seen
+        case new
+
+        /// Seen before for the same stealth address (the same payment delivered again)
+        case repeated
+
+        /// Seen before for another stealth address, or recorded without one
+        case reused
+    }
+
+    private var digests: Set<Data>
+
+    /// Digest of the stealth address each received key paid, by key digest
+    private var addresses: [Data: Data]
+
+    public init() {
+        self.digests = []
+        self.addresses = [:]
+    }
+
+    private enum CodingKeys: String, CodingKey {
+        case digests
+        case addresses
+    }
+
+    public init(from decoder: Decoder) throws {
+        let container = try decoder.container(keyedBy: CodingKeys.self)
+        self.digests = try container.decode(Set<Data>.self, forKey: .digests)
+        // Trackers persisted before addresses were recorded have none
+        self.addresses = try container.decodeIfPresent([Data: Data].self, forKey: .addresses) ?? [:]
+    }
+
+    /// Number of keys recorded
+    public var count: Int {
+        digests.count
+    }
+
+    /// Whether the key has been recorded before
+    public func contains(_ ephemeralPublicKey: Data) -> Bool {
+        digests.contains(Self.digest(of: ephemeralPublicKey))
+    }
+
+    /// Record a key
+    /// - Parameter ephemeralPublicKey: Ephemeral public key of a payment
+    /// - Returns: false if the key had already been recorded
+    @discardableResult
+    public mutating func insert(_ ephemeralPublicKey: Data) -> Bool {
+        digests.insert(Self.digest(of: ephemeralPublicKey)).inserted
+    }
+
+    /// Record a received key with the stealth address it paid
+    /// - Parameters:
+    ///   - ephemeralPublicKey: Ephemeral public key of the payment
+    ///   - stealthAddress: Stealth address the payment went to
+    /// - Returns: Whether the key is new, a redelivery of the same payment, or reused
+    public mutating func insert(_ ephemeralPublicKey: Data, stealthAddress: String) -> Sighting {
+        let key = Self.digest(of: ephemeralPublicKey)
+        let address = Self.digest(of: Data(stealthAddress.utf8))
+        guard digests.insert(key).inserted else {
+            return addresses[key] == address ? .repeated : .reused
+        }
+        addresses[key] = address
+        return .new
+    }
+
+    private static func digest(of key: Data) -> Data {
+        Data(SHA256.hash(data: key).prefix(digestSize))
+    }
+}
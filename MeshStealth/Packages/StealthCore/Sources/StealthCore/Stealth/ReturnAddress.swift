@@ -0,0 +1,78 @@
+import Foundation
+import CryptoKit
+
+/// Encryption of the sender's return address stored in a CiphertextAccount.
+///
+/// The sender's wallet address is sealed with ChaChaPoly under a key derived from
+/// the payment's shared secret S. The 32-byte ciphertext goes in
+/// `encrypted_return_address` (via `set_return_address`) and the 16-byte tag in
+/// `payload_tag` (via `set_payload_tag`); the tag also covers the announcement's
+/// TLV extension area. S is unique to the payment, so the fixed
+/// nonce never repeats under the same key.
+public enum ReturnAddress {
+
+    /// HKDF info string separating the return-address key from other uses of S
+    static let keyInfo = Data("stealth-pq return address".utf8)
+
+    /// Encrypt a wallet address to a payment's shared secret
+    /// - Parameters:
+    ///   - wallet: Base58-encoded wallet address to be refunded at
+    ///   - sharedSecret: Shared secret S of the payment
+    ///   - extensions: Encoded TLV extension area of the announcement
+    /// - Returns: Encrypted return address (32 bytes) and payload tag (16 bytes)
+    /// - Throws: StealthError if the wallet address isn't a public key
+    public static func seal(
+        wallet: String,
+        sharedSecret: Data,
+        extensions: Data = Data()
+    ) throws -> (encryptedReturnAddress: Data, payloadTag: Data) {
+        guard let walletBytes = try? SolanaRPCClient.decodePublicKey(wallet) else {
+            throw StealthError.invalidStealthAddress
+        }
+
+        let sealed = try ChaChaPoly.seal(
+            walletBytes,
+            using: key(sharedSecret),
+            nonce: try nonce(),
+            authenticating: extensions
+        )
+        return (sealed.ciphertext, sealed.tag)
+    }
+
+    /// Decrypt the return address of a detected payment
+    /// - Parameters:
+    ///   - encryptedReturnAddress: `CiphertextAccountData.encryptedReturnAddress`
+    ///   - payloadTag: `CiphertextAccountData.payloadTag`
+    ///   - sharedSecret: Shared secret S of the payment
+    ///   - extensions: `CiphertextAccountData.extensions`
+    /// - Returns: Base58-encoded wallet address, or nil if the tag doesn't verify
+    public static func open(
+        encryptedReturnAddress: Data,
+        payloadTag: Data,
+        sharedSecret: Data,
+        extensions: Data = Data()
+    ) -> String? {
+        guard let box = try? ChaChaPoly.SealedBox(
+            nonce: try nonce(),
+            ciphertext: encryptedReturnAddress,
+            tag: payloadTag
+        ),
+              let wallet = try? ChaChaPoly.open(box, using: key(sharedSecret), authenticating: extensions),
+              wallet.count == 32 else {
+            return nil
+        }
+        return SolanaRPCClient.encodePublicKey(wallet)
+    }
+
+    private static func key(_ sharedSecret: Data) -> SymmetricKey {
+        HKDF<SHA256>.deriveKey(
+            inputKeyMaterial: SymmetricKey(data: sharedSecret),
+            info: keyInfo,
+            outputByteCount: 32
+        )
+    }
+
+    private static func nonce() throws -> ChaChaPoly.Nonce {
+        try ChaChaPoly.Nonce(data: Data(repeating: 0, count: 12))
+    }
+}
@@ -15,10 +15,20 @@ public struct StealthAddressResult {
     /// View tag (first byte of hashed secret) for fast filtering
     public let viewTag: UInt8
 
+    /// View tag over the X25519 shared secret alone, stored in the CiphertextAccount.
+    /// Recipients can check it without ML-KEM decapsulation; equals `viewTag` in classical mode.
+    public let classicalViewTag: UInt8
+
     /// MLKEM768 ciphertext for hybrid mode (1088 bytes)
     /// nil if classical-only mode was used
     public let mlkemCiphertext: Data?
 
+    /// X25519 shared secret S_classical, kept for the sender-side self-check
+    let classicalSecret: Data
+
+    /// ML-KEM shared secret S_kyber, kept for the sender-side self-check (nil in classical mode)
+    let kyberSecret: Data?
+
     /// Ephemeral public key as base58 string (for memo)
     public var ephemeralPublicKeyString: String {
         ephemeralPublicKey.base58EncodedString
@@ -29,6 +39,26 @@ public struct StealthAddressResult {
         mlkemCiphertext != nil
     }
 
+    /// Shared secret S the stealth address was derived from
+    func sharedSecret() throws -> Data {
+        guard let kyberSecret = kyberSecret else {
+            return classicalSecret
+        }
+        return try SodiumWrapper.sha256(classicalSecret + kyberSecret)
+    }
+
+    /// Encrypt the sender's wallet as this payment's return address
+    /// - Parameters:
+    ///   - wallet: Base58-encoded wallet address to be refunded at
+    ///   - extensions: Encoded TLV extension area of the announcement
+    /// - Returns: Data for `buildSetReturnAddressData` and `buildSetPayloadTagData`
+    public func sealReturnAddress(
+        wallet: String,
+        extensions: Data = Data()
+    ) throws -> (encryptedReturnAddress: Data, payloadTag: Data) {
+        try ReturnAddress.seal(wallet: wallet, sharedSecret: sharedSecret(), extensions: extensions)
+    }
+
     /// Combined memo data for hybrid mode: R (32) || ciphertext (1088)
     /// Returns just ephemeralPublicKey for classical mode
     public var memoData: Data {
@@ -93,7 +123,7 @@ public struct StealthAddressGenerator {
     ///   - spendingPublicKey: Receiver's ed25519 spending public key M (32 bytes)
     ///   - viewingPublicKey: Receiver's X25519 viewing public key V (32 bytes)
     /// - Returns: StealthAddressResult
-    /// - Throws: StealthError if generation fails
+    /// - Throws: StealthError if generation fails, `.selfCheckFailed` if the receiver couldn't detect the result
     public static func generateStealthAddress(
         spendingPublicKey: Data,
         viewingPublicKey: Data
@@ -141,13 +171,26 @@ public struct StealthAddressGenerator {
         // 9. Encode as Solana address (base58 of raw ed25519 pubkey)
         let stealthAddress = stealthPubKey.base58EncodedString
 
-        return StealthAddressResult(
+        let result = StealthAddressResult(
             stealthAddress: stealthAddress,
             stealthPublicKey: stealthPubKey,
             ephemeralPublicKey: ephemeralPublicKey,
             viewTag: viewTag,
-            mlkemCiphertext: nil
+            classicalViewTag: viewTag,
+            mlkemCiphertext: nil,
+            classicalSecret: sharedSecretData,
+            kyberSecret: nil
+        )
+
+        // 10. Refuse a payment the receiver couldn't detect
+        try selfCheck(
+            result,
+            spendingPublicKey: spendingPublicKey,
+            viewingPublicKey: viewingPublicKey,
+            mlkemPublicKey: nil
         )
+
+        return result
     }
 
     /// Generate a hybrid stealth address using X25519 + MLKEM768
@@ -157,7 +200,7 @@ public struct StealthAddressGenerator {
     ///   - viewingPublicKey: Receiver's X25519 viewing public key V (32 bytes)
     ///   - mlkemPublicKey: Receiver's MLKEM768 public key K (1184 bytes)
     /// - Returns: StealthAddressResult with MLKEM ciphertext
-    /// - Throws: StealthError if generation fails
+    /// - Throws: StealthError if generation fails, `.selfCheckFailed` if the receiver couldn't detect the result
     public static func generateHybridStealthAddress(
         spendingPublicKey: Data,
         viewingPublicKey: Data,
@@ -215,13 +258,91 @@ public struct StealthAddressGenerator {
         // 11. Encode as Solana address (base58 of raw ed25519 pubkey)
         let stealthAddress = stealthPubKey.base58EncodedString
 
-        return StealthAddressResult(
+        let result = StealthAddressResult(
             stealthAddress: stealthAddress,
             stealthPublicKey: stealthPubKey,
             ephemeralPublicKey: ephemeralPublicKey,
             viewTag: viewTag,
-            mlkemCiphertext: mlkemCiphertext
+            classicalViewTag: Data(SHA256.hash(data: classicalSecretData))[0],
+            mlkemCiphertext: mlkemCiphertext,
+            classicalSecret: classicalSecretData,
+            kyberSecret: kyberSecret
+        )
+
+        // 12. Refuse a payment the receiver couldn't detect
+        try selfCheck(
+            result,
+            spendingPublicKey: spendingPublicKey,
+            viewingPublicKey: viewingPublicKey,
+            mlkemPublicKey: mlkemPublicKey
         )
+
+        return result
+    }
+
+    // MARK: - Sender Self-Check
+
+    /// Check that the receiver will detect a generated payment
+    ///
+    /// Replays the receiver's side of detection with the secrets the sender holds:
+    /// the meta-address keys must be usable, the ciphertext must match the mode of
+    /// the meta-address, and re-deriving the view tags and stealth address from the
+    /// shared secrets must give the values that will be announced. Generation runs
+    /// this before returning, so a corrupted meta-address or a faulty encapsulation
+    /// fails before any funds move.
+    /// - Parameters:
+    ///   - result: Generated payment
+    ///   - spendingPublicKey: Receiver's spending public key M from the meta-address
+    ///   - viewingPublicKey: Receiver's viewing public key V from the meta-address
+    ///   - mlkemPublicKey: Receiver's MLKEM768 public key K (nil for a classical meta-address)
+    /// - Throws: `StealthError.selfCheckFailed` if the receiver would not find the payment
+    public static func selfCheck(
+        _ result: StealthAddressResult,
+        spendingPublicKey: Data,
+        viewingPublicKey: Data,
+        mlkemPublicKey: Data?
+    ) throws {
+        // Keys the receiver derives from: M must be a point, V must not be low order
+        // (the X25519 secret would be all zeros, the same for every receiver)
+        guard SodiumWrapper.isValidPoint(spendingPublicKey),
+              viewingPublicKey.count == 32,
+              result.ephemeralPublicKey.count == 32,
+              result.classicalSecret.count == 32,
+              result.classicalSecret != Data(repeating: 0, count: 32) else {
+            throw StealthError.selfCheckFailed
+        }
+
+        // The receiver decapsulates only in hybrid mode, and only a well-formed ciphertext
+        let sharedSecret: Data
+        switch (mlkemPublicKey, result.mlkemCiphertext, result.kyberSecret) {
+        case (nil, nil, nil):
+            sharedSecret = result.classicalSecret
+        case let (publicKey?, ciphertext?, kyberSecret?):
+            guard MLKEMWrapper.isValidPublicKey(publicKey),
+                  MLKEMWrapper.isValidCiphertext(ciphertext),
+                  kyberSecret.count == MLKEMWrapper.sharedSecretBytes else {
+                throw StealthError.selfCheckFailed
+            }
+            sharedSecret = try SodiumWrapper.sha256(result.classicalSecret + kyberSecret)
+        default:
+            throw StealthError.selfCheckFailed
+        }
+
+        // The stored view tag is checked before anything else
+        guard result.classicalViewTag == Data(SHA256.hash(data: result.classicalSecret))[0] else {
+            throw StealthError.selfCheckFailed
+        }
+
+        // P' = M + hash(S)*G must be the address being paid
+        let hashData = Data(SHA256.hash(data: sharedSecret))
+        guard result.viewTag == hashData[0],
+              let reducedHash = SodiumWrapper.scalarReduce32(hashData),
+              let hashPoint = SodiumWrapper.scalarMultBaseNoclamp(reducedHash),
+              let expectedPubKey = SodiumWrapper.pointAdd(spendingPublicKey, hashPoint),
+              expectedPubKey == result.stealthPublicKey,
+              expectedPubKey.base58EncodedString == result.stealthAddress else {
+            throw StealthError.selfCheckFailed
+        }
     }
 
     /// Verify a stealth address matches expected derivation (for testing/verification)
@@ -390,6 +390,8 @@ public enum StealthError: Error, LocalizedError, Equatable {
     case invalidMLKEMCiphertext
     case mlkemEncapsulationFailed
     case mlkemDecapsulationFailed
+    // Sender-side checks
+    case selfCheckFailed
 
     public var errorDescription: String? {
         switch self {
@@ -423,6 +425,8 @@ public enum StealthError: Error, LocalizedError, Equatable {
             return "MLKEM768 encapsulation failed"
         case .mlkemDecapsulationFailed:
             return "MLKEM768 decapsulation failed"
+        case .selfCheckFailed:
+            return "Receiver would not detect this payment; not sending"
         }
     }
 
@@ -441,7 +445,8 @@ public enum StealthError: Error, LocalizedError, Equatable {
              (.invalidMLKEMPrivateKey, .invalidMLKEMPrivateKey),
              (.invalidMLKEMCiphertext, .invalidMLKEMCiphertext),
              (.mlkemEncapsulationFailed, .mlkemEncapsulationFailed),
-             (.mlkemDecapsulationFailed, .mlkemDecapsulationFailed):
+             (.mlkemDecapsulationFailed, .mlkemDecapsulationFailed),
+             (.selfCheckFailed, .selfCheckFailed):
             return true
         case (.keychainError(let a), .keychainError(let b)):
             return a == b
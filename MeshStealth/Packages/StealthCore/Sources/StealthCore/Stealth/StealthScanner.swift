@@ -74,6 +74,8 @@ public struct StealthScanner {
     ///
     /// Use this as a fast pre-filter before doing the full scan.
     /// If the view tag doesn't match, the transaction is definitely not ours.
+    /// Only needs the X25519 secret, so it also checks the view tag of hybrid
+    /// CiphertextAccounts before MLKEM decapsulation.
     ///
     /// - Parameters:
     ///   - ephemeralPublicKey: The ephemeral key from transaction memo (32 bytes)
@@ -237,6 +239,34 @@ public struct StealthScanner {
         )
     }
 
+    /// Decrypt the sender's return address from a payment's CiphertextAccount
+    ///
+    /// Uses the hybrid shared secret when this keypair has an MLKEM key, the
+    /// X25519 secret otherwise, and verifies the payload tag before decrypting.
+    /// - Parameter ciphertextAccount: The payment's announcement
+    /// - Returns: Base58 wallet address, or nil if the announcement has none or its tag doesn't verify
+    /// - Throws: StealthError if the shared secret can't be computed
+    public func openReturnAddress(ciphertextAccount: CiphertextAccountData) throws -> String? {
+        guard let encryptedReturnAddress = ciphertextAccount.encryptedReturnAddress,
+              let payloadTag = ciphertextAccount.payloadTag else {
+            return nil
+        }
+
+        let sharedSecret = keyPair.hasPostQuantum
+            ? try keyPair.computeHybridSharedSecret(
+                ephemeralPubKey: ciphertextAccount.ephemeralPubkey,
+                mlkemCiphertext: ciphertextAccount.mlkemCiphertext
+            )
+            : try keyPair.computeSharedSecret(ephemeralPubKey: ciphertextAccount.ephemeralPubkey)
+
+        return ReturnAddress.open(
+            encryptedReturnAddress: encryptedReturnAddress,
+            payloadTag: payloadTag,
+            sharedSecret: sharedSecret,
+            extensions: ciphertextAccount.extensions
+        )
+    }
+
     /// Scan a transaction with automatic mode detection
     /// Uses hybrid mode if ciphertext is provided and keypair has PQ keys
     /// - Parameters:
@@ -373,3 +403,26 @@ public struct StealthScanner {
         return detected
     }
 }
+
+// MARK: - Key Rotation
+
+extension StealthScanner {
+
+    /// Order per-epoch key material for trying against an announcement
+    ///
+    /// The keys for the sender's recorded registry epoch come first, then the
+    /// rest from newest to oldest epoch.
+    /// - Parameters:
+    ///   - keysByEpoch: Key material (e.g. scanners) indexed by registry epoch
+    ///   - registryEpoch: Epoch recorded in the announcement, if any
+    /// - Returns: Key material in the order it should be tried
+    public static func prioritized<Key>(_ keysByEpoch: [UInt32: Key], registryEpoch: UInt32?) -> [Key] {
+        keysByEpoch
+            .sorted { lhs, rhs in
+                if lhs.key == registryEpoch { return true }
+                if rhs.key == registryEpoch { return false }
+                return lhs.key > rhs.key
+            }
+            .map(\.value)
+    }
+}
@@ -0,0 +1,211 @@
+import Foundation
+
+/// What a ledger entry records
+public enum LedgerEntryKind: String, Codable, Sendable {
+    case inflow       // Funds arrived at a stealth address from outside the wallet
+    case reversal     // A booked inflow disappeared (funding transaction rolled back)
+    case sweep        // Funds moved out of a stealth address (settle, unshield, hop, split, recombine)
+    case fee          // Network fee paid by a stealth address
+    case rentReclaim  // CiphertextAccount rent returned to a stealth address
+}
+
+/// An account in the ledger
+public enum LedgerAccount: Hashable, Codable, Sendable {
+    /// A stealth address the wallet holds the spending key for
+    case stealth(String)
+
+    /// Everything outside the wallet: senders, the main wallet, the cold wallet
+    case external
+
+    /// Network fees
+    case fees
+
+    /// Rent held in CiphertextAccounts
+    case rent
+}
+
+/// One posting: `lamports` move from `credit` to `debit`
+public struct LedgerEntry: Codable, Sendable, Identifiable {
+    public let id: UUID
+    public let kind: LedgerEntryKind
+
+    /// Account receiving the lamports
+    public let debit: LedgerAccount
+
+    /// Account the lamports leave
+    public let credit: LedgerAccount
+
+    public let lamports: UInt64
+
+    /// Payment the entry belongs to, if any
+    public let paymentId: UUID?
+
+    /// Transaction signature, if known
+    public let signature: String?
+
+    public let recordedAt: Date
+
+    public init(
+        id: UUID = UUID(),
+        kind: LedgerEntryKind,
+        debit: LedgerAccount,
+        credit: LedgerAccount,
+        lamports: UInt64,
+        paymentId: UUID? = nil,
+        signature: String? = nil,
+        recordedAt: Date = Date()
+    ) {
+        self.id = id
+        self.kind = kind
+        self.debit = debit
+        self.credit = credit
+        self.lamports = lamports
+        self.paymentId = paymentId
+        self.signature = signature
+        self.recordedAt = recordedAt
+    }
+}
+
+/// Double-entry ledger of the lamports moving through the wallet's stealth addresses
+///
+/// Every entry debits one account and credits another by the same amount, so the
+/// balances of all accounts sum to zero. A stealth address's balance is what the
+/// wallet's history says it holds on-chain; `discrepancies(balances:)` compares
+/// the two.
+public struct StealthLedger: Codable, Sendable {
+    public private(set) var entries: [LedgerEntry]
+
+    public init() {
+        self.entries = []
+    }
+
+    /// Net balance of an account in lamports (negative for accounts funds came from)
+    public func balance(of account: LedgerAccount) -> Int64 {
+        entries.reduce(0) { total, entry in
+            var total = total
+            if entry.debit == account {
+                total += Int64(entry.lamports)
+            }
+            if entry.credit == account {
+                total -= Int64(entry.lamports)
+            }
+            return total
+        }
+    }
+
+    /// Balance of every stealth address with entries, in lamports
+    public var stealthBalances: [String: UInt64] {
+        var balances: [String: Int64] = [:]
+        for entry in entries {
+            if case .stealth(let address) = entry.debit {
+                balances[address, default: 0] += Int64(entry.lamports)
+            }
+            if case .stealth(let address) = entry.credit {
+                balances[address, default: 0] -= Int64(entry.lamports)
+            }
+        }
+        return balances.mapValues { UInt64(max($0, 0)) }
+    }
+
+    /// Whether any entry touches the stealth address
+    public func contains(_ stealthAddress: String) -> Bool {
+        let account = LedgerAccount.stealth(stealthAddress)
+        return entries.contains { $0.debit == account || $0.credit == account }
+    }
+
+    /// Book funds arriving at a stealth address from outside the wallet
+    public mutating func recordInflow(
+        to stealthAddress: String,
+        lamports: UInt64,
+        paymentId: UUID? = nil,
+        signature: String? = nil
+    ) {
+        post(.inflow, debit: .stealth(stealthAddress), credit: .external, lamports: lamports, paymentId: paymentId, signature: signature)
+    }
+
+    /// Undo a booked inflow whose funds are gone from the chain
+    public mutating func recordReversal(from stealthAddress: String, lamports: UInt64, paymentId: UUID? = nil) {
+        post(.reversal, debit: .external, credit: .stealth(stealthAddress), lamports: lamports, paymentId: paymentId)
+    }
+
+    /// Book a transfer out of a stealth address and the fee it paid
+    ///
+    /// Lamports the ledger hasn't seen arrive are booked as an inflow first, so a
+    /// sweep never takes an address below zero.
+    /// - Parameters:
+    ///   - stealthAddress: Address the funds left
+    ///   - destination: Where they went (another stealth address, or `.external`)
+    ///   - lamports: Amount transferred
+    ///   - fee: Network fee paid by `stealthAddress`
+    public mutating func recordSweep(
+        from stealthAddress: String,
+        to destination: LedgerAccount,
+        lamports: UInt64,
+        fee: UInt64,
+        paymentId: UUID? = nil,
+        signature: String? = nil
+    ) {
+        let source = LedgerAccount.stealth(stealthAddress)
+        let shortfall = Int64(lamports + fee) - balance(of: source)
+        if shortfall > 0 {
+            post(.inflow, debit: source, credit: .external, lamports: UInt64(shortfall), paymentId: paymentId, signature: signature)
+        }
+        post(.sweep, debit: destination, credit: source, lamports: lamports, paymentId: paymentId, signature: signature)
+        post(.fee, debit: .fees, credit: source, lamports: fee, paymentId: paymentId, signature: signature)
+    }
+
+    /// Book a transfer that empties a stealth address
+    /// - Returns: Lamports booked to `destination` (the address's balance minus `fee`)
+    @discardableResult
+    public mutating func recordSweepAll(
+        from stealthAddress: String,
+        to destination: LedgerAccount,
+        fee: UInt64,
+        paymentId: UUID? = nil,
+        signature: String? = nil
+    ) -> UInt64 {
+        let available = UInt64(max(balance(of: .stealth(stealthAddress)), 0))
+        let lamports = available > fee ? available - fee : 0
+        recordSweep(from: stealthAddress, to: destination, lamports: lamports, fee: fee, paymentId: paymentId, signature: signature)
+        return lamports
+    }
+
+    /// Book CiphertextAccount rent returned to a stealth address by `reclaim_rent`
+    public mutating func recordRentReclaim(to stealthAddress: String, lamports: UInt64, signature: String? = nil) {
+        post(.rentReclaim, debit: .stealth(stealthAddress), credit: .rent, lamports: lamports, signature: signature)
+    }
+
+    /// Compare ledger balances with on-chain balances
+    /// - Parameter balances: On-chain balances by stealth address; addresses missing here are skipped
+    /// - Returns: Addresses holding less than the ledger says, sorted by address
+    public func discrepancies(balances: [String: UInt64]) -> [BalanceDiscrepancy] {
+        stealthBalances.compactMap { address, expected in
+            guard let actual = balances[address], actual < expected else {
+                return nil
+            }
+            return BalanceDiscrepancy(stealthAddress: address, expected: expected, actual: actual)
+        }
+        .sorted { $0.stealthAddress < $1.stealthAddress }
+    }
+
+    private mutating func post(
+        _ kind: LedgerEntryKind,
+        debit: LedgerAccount,
+        credit: LedgerAccount,
+        lamports: UInt64,
+        paymentId: UUID? = nil,
+        signature: String? = nil
+    ) {
+        guard lamports > 0 else {
+            return
+        }
+        entries.append(LedgerEntry(
+            kind: kind,
+            debit: debit,
+            credit: credit,
+            lamports: lamports,
+            paymentId: paymentId,
+            signature: signature
+        ))
+    }
+}
@@ -581,6 +581,161 @@ final class CryptoRoundtripTests: XCTestCase {
         XCTAssert(SodiumWrapper.isValidPoint(result.stealthPublicKey))
     }
 
+    func testSenderSelfCheck() throws {
+        let receiverKeyPair = try StealthKeyPair.generate(withPostQuantum: true)
+        let otherKeyPair = try StealthKeyPair.generate(withPostQuantum: true)
+
+        let result = try StealthAddressGenerator.generateHybridStealthAddress(
+            spendingPublicKey: receiverKeyPair.spendingPublicKey,
+            viewingPublicKey: receiverKeyPair.viewingPublicKey,
+            mlkemPublicKey: receiverKeyPair.mlkemPublicKey!
+        )
+        XCTAssertNoThrow(try StealthAddressGenerator.selfCheck(
+            result,
+            spendingPublicKey: receiverKeyPair.spendingPublicKey,
+            viewingPublicKey: receiverKeyPair.viewingPublicKey,
+            mlkemPublicKey: receiverKeyPair.mlkemPublicKey
+        ))
+
+        // Checked against another receiver's keys, the address doesn't match
+        XCTAssertThrowsError(try StealthAddressGenerator.selfCheck(
+            result,
+            spendingPublicKey: otherKeyPair.spendingPublicKey,
+            viewingPublicKey: receiverKeyPair.viewingPublicKey,
+            mlkemPublicKey: receiverKeyPair.mlkemPublicKey
+        )) { error in
+            XCTAssertEqual(error as? StealthError, .selfCheckFailed)
+        }
+
+        // A classical payment to a hybrid meta-address would be decapsulated without a ciphertext
+        XCTAssertThrowsError(try StealthAddressGenerator.selfCheck(
+            result,
+            spendingPublicKey: receiverKeyPair.spendingPublicKey,
+            viewingPublicKey: receiverKeyPair.viewingPublicKey,
+            mlkemPublicKey: nil
+        )) { error in
+            XCTAssertEqual(error as? StealthError, .selfCheckFailed)
+        }
+
+        // A truncated ciphertext can't be decapsulated
+        let truncated = StealthAddressResult(
+            stealthAddress: result.stealthAddress,
+            stealthPublicKey: result.stealthPublicKey,
+            ephemeralPublicKey: result.ephemeralPublicKey,
+            viewTag: result.viewTag,
+            classicalViewTag: result.classicalViewTag,
+            mlkemCiphertext: result.mlkemCiphertext?.prefix(1000),
+            classicalSecret: result.classicalSecret,
+            kyberSecret: result.kyberSecret
+        )
+        XCTAssertThrowsError(try StealthAddressGenerator.selfCheck(
+            truncated,
+            spendingPublicKey: receiverKeyPair.spendingPublicKey,
+            viewingPublicKey: receiverKeyPair.viewingPublicKey,
+            mlkemPublicKey: receiverKeyPair.mlkemPublicKey
+        )) { error in
+            XCTAssertEqual(error as? StealthError, .selfCheckFailed)
+        }
+
+        // A wrong stored view tag would make the receiver skip the announcement
+        let mistagged = StealthAddressResult(
+            stealthAddress: result.stealthAddress,
+            stealthPublicKey: result.stealthPublicKey,
+            ephemeralPublicKey: result.ephemeralPublicKey,
+            viewTag: result.viewTag,
+            classicalViewTag: result.classicalViewTag ^ 0xFF,
+            mlkemCiphertext: result.mlkemCiphertext,
+            classicalSecret: result.classicalSecret,
+            kyberSecret: result.kyberSecret
+        )
+        XCTAssertThrowsError(try StealthAddressGenerator.selfCheck(
+            mistagged,
+            spendingPublicKey: receiverKeyPair.spendingPublicKey,
+            viewingPublicKey: receiverKeyPair.viewingPublicKey,
+            mlkemPublicKey: receiverKeyPair.mlkemPublicKey
+        )) { error in
+            XCTAssertEqual(error as? StealthError, .selfCheckFailed)
+        }
+    }
+
+    func testSelfCheckRejectsCorruptedMetaAddress() throws {
+        let receiverKeyPair = try StealthKeyPair.generate()
+        let result = try StealthAddressGenerator.generateStealthAddress(
+            metaAddressString: receiverKeyPair.metaAddressString
+        )
+
+        // A spending key that isn't a curve point can't be the base of any stealth address
+        XCTAssertThrowsError(try StealthAddressGenerator.selfCheck(
+            result,
+            spendingPublicKey: Data(repeating: 0xFF, count: 32),
+            viewingPublicKey: receiverKeyPair.viewingPublicKey,
+            mlkemPublicKey: nil
+        )) { error in
+            XCTAssertEqual(error as? StealthError, .selfCheckFailed)
+        }
+    }
+
+    func testReturnAddressRoundtrip() throws {
+        let receiverKeyPair = try StealthKeyPair.generate(withPostQuantum: true)
+        let senderWallet = SolanaRPCClient.encodePublicKey(Data(repeating: 0x07, count: 32))
+        let extensions = CiphertextExtension.registryEpoch(1).encoded()
+
+        let result = try StealthAddressGenerator.generateHybridStealthAddress(
+            spendingPublicKey: receiverKeyPair.spendingPublicKey,
+            viewingPublicKey: receiverKeyPair.viewingPublicKey,
+            mlkemPublicKey: receiverKeyPair.mlkemPublicKey!
+        )
+        let sealed = try result.sealReturnAddress(wallet: senderWallet, extensions: extensions)
+        XCTAssertEqual(sealed.encryptedReturnAddress.count, 32)
+        XCTAssertEqual(sealed.payloadTag.count, 16)
+
+        // The receiver recomputes S from the announcement
+        let sharedSecret = try receiverKeyPair.computeHybridSharedSecret(
+            ephemeralPubKey: result.ephemeralPublicKey,
+            mlkemCiphertext: result.mlkemCiphertext!
+        )
+        XCTAssertEqual(ReturnAddress.open(
+            encryptedReturnAddress: sealed.encryptedReturnAddress,
+            payloadTag: sealed.payloadTag,
+            sharedSecret: sharedSecret,
+            extensions: extensions
+        ), senderWallet)
+
+        // Tampered extensions or a wrong secret fail the tag
+        XCTAssertNil(ReturnAddress.open(
+            encryptedReturnAddress: sealed.encryptedReturnAddress,
+            payloadTag: sealed.payloadTag,
+            sharedSecret: sharedSecret
+        ))
+        XCTAssertNil(ReturnAddress.open(
+            encryptedReturnAddress: sealed.encryptedReturnAddress,
+            payloadTag: sealed.payloadTag,
+            sharedSecret: Data(repeating: 0, count: 32),
+            extensions: extensions
+        ))
+    }
+
+    func testHybridClassicalViewTagPassesQuickFilter() throws {
+        let receiverKeyPair = try StealthKeyPair.generate(withPostQuantum: true)
+        let scanner = StealthScanner(keyPair: receiverKeyPair)
+
+        let result = try StealthAddressGenerator.generateHybridStealthAddress(
+            spendingPublicKey: receiverKeyPair.spendingPublicKey,
+            viewingPublicKey: receiverKeyPair.viewingPublicKey,
+            mlkemPublicKey: receiverKeyPair.mlkemPublicKey!
+        )
+
+        // The tag stored on-chain needs no decapsulation to check
+        XCTAssertTrue(try scanner.quickFilter(
+            ephemeralPublicKey: result.ephemeralPublicKey,
+            expectedViewTag: result.classicalViewTag
+        ))
+        XCTAssertFalse(try scanner.quickFilter(
+            ephemeralPublicKey: result.ephemeralPublicKey,
+            expectedViewTag: result.classicalViewTag ^ 0xFF
+        ))
+    }
+
     func testHybridMemoDataFormat() throws {
         let receiverKeyPair = try StealthKeyPair.generate(withPostQuantum: true)
 
@@ -197,6 +197,14 @@ final class IntegrationTests: XCTestCase {
         XCTAssertEqual(config.minBalanceForSettlement, 25_000)
         XCTAssertTrue(config.preferWiFi)
         XCTAssertEqual(config.transactionTimeout, 90)
+        XCTAssertNil(config.coldAddress)
+    }
+
+    func testSettlementConfigurationColdAddress() {
+        let config = SettlementConfiguration(coldAddress: "11111111111111111111111111111111")
+
+        XCTAssertEqual(config.coldAddress, "11111111111111111111111111111111")
+        XCTAssertNil(SettlementConfiguration.default.coldAddress)
     }
 
     // MARK: - SettlementResult Tests
@@ -311,6 +319,449 @@ final class IntegrationTests: XCTestCase {
         XCTAssertEqual(PendingPaymentStatus.expired.rawValue, "expired")
     }
 
+    func testPendingPaymentStatusTransitions() {
+        XCTAssertTrue(PendingPaymentStatus.awaitingFunds.canTransition(to: .received))
+        XCTAssertTrue(PendingPaymentStatus.received.canTransition(to: .awaitingFunds))
+        XCTAssertTrue(PendingPaymentStatus.received.canTransition(to: .settling))
+        XCTAssertTrue(PendingPaymentStatus.settling.canTransition(to: .settled))
+        XCTAssertTrue(PendingPaymentStatus.failed.canTransition(to: .settling))
+
+        XCTAssertFalse(PendingPaymentStatus.awaitingFunds.canTransition(to: .settled))
+        XCTAssertFalse(PendingPaymentStatus.settled.canTransition(to: .received))
+        XCTAssertFalse(PendingPaymentStatus.expired.canTransition(to: .settling))
+    }
+
+    // MARK: - Balance Reconciliation Tests
+
+    func testBalanceDiscrepancies() {
+        let payments = [
+            PendingPayment(stealthAddress: "A", ephemeralPublicKey: Data(), mlkemCiphertext: nil, amount: 100, tokenMint: nil, viewTag: 0),
+            PendingPayment(stealthAddress: "A", ephemeralPublicKey: Data(), mlkemCiphertext: nil, amount: 50, tokenMint: nil, viewTag: 0),
+            PendingPayment(stealthAddress: "B", ephemeralPublicKey: Data(), mlkemCiphertext: nil, amount: 10, tokenMint: nil, viewTag: 0),
+            PendingPayment(stealthAddress: "C", ephemeralPublicKey: Data(), mlkemCiphertext: nil, amount: 10, tokenMint: nil, viewTag: 0, status: .settled)
+        ]
+
+        let discrepancies = StealthWalletManager.balanceDiscrepancies(
+            payments: payments,
+            balances: ["A": 120, "B": 10, "C": 0]
+        )
+
+        XCTAssertEqual(discrepancies, [BalanceDiscrepancy(stealthAddress: "A", expected: 150, actual: 120)])
+    }
+
+    @MainActor
+    func testReconcileWithChainReportsShortPayment() async {
+        StubBalanceURLProtocol.balance = 40
+        URLProtocol.registerClass(StubBalanceURLProtocol.self)
+        defer { URLProtocol.unregisterClass(StubBalanceURLProtocol.self) }
+
+        let manager = StealthWalletManager(
+            userDefaults: UserDefaults(suiteName: "test.\(UUID().uuidString)")!,
+            faucet: DevnetFaucet(rpcEndpoint: URL(string: "https://rpc.stub.invalid")!)
+        )
+        manager.addPendingPayment(
+            PendingPayment(stealthAddress: "A", ephemeralPublicKey: Data(), mlkemCiphertext: nil, amount: 100, tokenMint: nil, viewTag: 0, status: .received)
+        )
+
+        let discrepancies = await manager.reconcileWithChain()
+
+        // Reported even though reconciling moves the payment back to awaitingFunds
+        XCTAssertEqual(discrepancies, [BalanceDiscrepancy(stealthAddress: "A", expected: 100, actual: 40)])
+        XCTAssertEqual(manager.pendingPayments.first?.status, .awaitingFunds)
+    }
+
+    @MainActor
+    func testReconcileWithChainChecksLedger() async {
+        StubBalanceURLProtocol.balance = 40
+        URLProtocol.registerClass(StubBalanceURLProtocol.self)
+        defer { URLProtocol.unregisterClass(StubBalanceURLProtocol.self) }
+
+        let manager = StealthWalletManager(
+            userDefaults: UserDefaults(suiteName: "test.\(UUID().uuidString)")!,
+            faucet: DevnetFaucet(rpcEndpoint: URL(string: "https://rpc.stub.invalid")!)
+        )
+        manager.addPendingPayment(from: MeshStealthPayload(
+            stealthAddress: "A",
+            ephemeralPublicKey: Data(repeating: 0x01, count: 32),
+            mlkemCiphertext: nil,
+            amount: 100,
+            tokenMint: nil,
+            viewTag: 0,
+            memo: nil
+        ))
+        XCTAssertEqual(manager.ledger.stealthBalances, ["A": 100])
+
+        let discrepancies = await manager.reconcileWithChain()
+
+        // Reported once, then the ledger books the missing funds as reversed
+        XCTAssertEqual(discrepancies, [BalanceDiscrepancy(stealthAddress: "A", expected: 100, actual: 40)])
+        XCTAssertEqual(manager.ledger.stealthBalances, ["A": 40])
+        XCTAssertEqual(manager.ledger.entries.map(\.kind), [.inflow, .reversal])
+    }
+
+    @MainActor
+    func testReconcileFundingFromTransferEvent() {
+        let manager = StealthWalletManager(userDefaults: UserDefaults(suiteName: "test.\(UUID().uuidString)")!)
+        let stealthAddress = SolanaRPCClient.encodePublicKey(Data(repeating: 0x01, count: 32))
+        let mint = SolanaRPCClient.encodePublicKey(Data(repeating: 0x02, count: 32))
+        let payment = PendingPayment(
+            stealthAddress: stealthAddress,
+            ephemeralPublicKey: Data(),
+            mlkemCiphertext: nil,
+            amount: 1_000_000,
+            tokenMint: mint,
+            viewTag: 0,
+            status: .awaitingFunds
+        )
+        manager.addPendingPayment(payment)
+
+        let otherMint = SplTransferEventData(
+            stealthPubkey: stealthAddress,
+            mint: SolanaRPCClient.encodePublicKey(Data(repeating: 0x03, count: 32)),
+            tokenAccount: stealthAddress,
+            amount: 1_000_000,
+            received: 1_000_000
+        )
+        XCTAssertNil(manager.reconcileFunding(id: payment.id, transferEvent: otherMint))
+        XCTAssertEqual(manager.pendingPayments.first?.status, .awaitingFunds)
+
+        // A transfer-fee mint credits less than was sent
+        let event = SplTransferEventData(
+            stealthPubkey: stealthAddress,
+            mint: mint,
+            tokenAccount: stealthAddress,
+            amount: 1_000_000,
+            received: 990_000
+        )
+        let shortfall = manager.reconcileFunding(id: payment.id, transferEvent: event)
+
+        XCTAssertEqual(shortfall, BalanceDiscrepancy(stealthAddress: stealthAddress, expected: 1_000_000, actual: 990_000))
+        XCTAssertEqual(manager.pendingPayments.first?.status, .received)
+        XCTAssertEqual(manager.pendingPayments.first?.creditedAmount, 990_000)
+        XCTAssertEqual(manager.pendingBalance, 990_000)
+        XCTAssertTrue(manager.ledger.entries.isEmpty)
+    }
+
+    @MainActor
+    func testQueueRefundLinksPayments() throws {
+        XCTAssertTrue(initializeStealth())
+        let manager = StealthWalletManager(userDefaults: UserDefaults(suiteName: "test.\(UUID().uuidString)")!)
+        let sender = try StealthKeyPair.generate(withPostQuantum: true)
+        let payment = PendingPayment(
+            stealthAddress: "A",
+            ephemeralPublicKey: Data(),
+            mlkemCiphertext: nil,
+            amount: 100_000,
+            tokenMint: nil,
+            viewTag: 0
+        )
+        manager.addPendingPayment(payment)
+
+        XCTAssertThrowsError(try manager.queueRefund(of: payment.id, to: sender.hybridMetaAddressString, amount: 100_001))
+        XCTAssertThrowsError(try manager.queueRefund(of: UUID(), to: sender.hybridMetaAddressString, amount: 1))
+
+        let refund = try manager.queueRefund(of: payment.id, to: sender.hybridMetaAddressString, amount: 60_000)
+
+        XCTAssertEqual(refund.refundOf, payment.id)
+        XCTAssertEqual(refund.amount, 60_000)
+        XCTAssertNotNil(refund.mlkemCiphertext)
+        XCTAssertEqual(manager.pendingPayments.first?.refundIntentId, refund.id)
+        XCTAssertEqual(manager.getQueuedOutgoingPayments().map(\.id), [refund.id])
+
+        // The link survives status updates
+        manager.updateOutgoingIntent(id: refund.id, status: .sending)
+        XCTAssertEqual(manager.outgoingPaymentIntents.first?.refundOf, payment.id)
+
+        // A payment is refunded at most once
+        XCTAssertThrowsError(try manager.queueRefund(of: payment.id, to: sender.hybridMetaAddressString, amount: 1))
+    }
+
+    @MainActor
+    func testOutgoingIntentIdempotencyKey() {
+        let manager = StealthWalletManager(userDefaults: UserDefaults(suiteName: "test.\(UUID().uuidString)")!)
+        let intent = OutgoingPaymentIntent(
+            recipientMetaAddress: "M",
+            stealthAddress: "A",
+            ephemeralPublicKey: Data(),
+            mlkemCiphertext: nil,
+            amount: 1_000,
+            memo: nil,
+            idempotencyKey: "withdrawal-42"
+        )
+        let retry = OutgoingPaymentIntent(
+            recipientMetaAddress: "M",
+            stealthAddress: "B",
+            ephemeralPublicKey: Data(),
+            mlkemCiphertext: nil,
+            amount: 1_000,
+            memo: nil,
+            idempotencyKey: "withdrawal-42"
+        )
+
+        XCTAssertEqual(manager.queueOutgoingPayment(intent).id, intent.id)
+        XCTAssertEqual(manager.queueOutgoingPayment(retry).id, intent.id)
+        XCTAssertEqual(manager.outgoingPaymentIntents.map(\.id), [intent.id])
+
+        // Each attempt's signature and blockhash are kept for the next retry's checks
+        manager.updateOutgoingIntent(id: intent.id, status: .sending, signature: "S", blockhash: "H")
+        manager.updateOutgoingIntent(id: intent.id, status: .failed, error: "timeout")
+        let recorded = manager.outgoingIntent(idempotencyKey: "withdrawal-42")
+        XCTAssertEqual(recorded?.transactionSignature, "S")
+        XCTAssertEqual(recorded?.blockhash, "H")
+        XCTAssertEqual(recorded?.idempotencyKey, "withdrawal-42")
+    }
+
+    @MainActor
+    func testDelayedOutgoingIntentAndCancellation() throws {
+        let manager = StealthWalletManager(userDefaults: UserDefaults(suiteName: "test.\(UUID().uuidString)")!)
+        let notBefore = Date().addingTimeInterval(600)
+        let delayed = OutgoingPaymentIntent(
+            recipientMetaAddress: "M",
+            stealthAddress: "A",
+            ephemeralPublicKey: Data(),
+            mlkemCiphertext: nil,
+            amount: 1_000,
+            memo: nil,
+            notBefore: notBefore
+        )
+        let sent = OutgoingPaymentIntent(
+            recipientMetaAddress: "M",
+            stealthAddress: "B",
+            ephemeralPublicKey: Data(),
+            mlkemCiphertext: nil,
+            amount: 1_000,
+            memo: nil
+        )
+        manager.queueOutgoingPayment(delayed)
+        manager.queueOutgoingPayment(sent)
+
+        // A delayed payment isn't due before its notBefore
+        XCTAssertEqual(manager.getQueuedOutgoingPayments().map(\.id), [sent.id])
+        XCTAssertEqual(
+            manager.getQueuedOutgoingPayments(now: notBefore).map(\.id),
+            [delayed.id, sent.id]
+        )
+
+        try manager.cancelOutgoingPayment(id: delayed.id)
+        XCTAssertEqual(manager.outgoingIntent(id: delayed.id)?.status, .cancelled)
+        XCTAssertEqual(manager.outgoingIntent(id: delayed.id)?.notBefore, notBefore)
+        XCTAssertEqual(manager.getQueuedOutgoingPayments(now: notBefore).map(\.id), [sent.id])
+
+        // Once an attempt is recorded it may land, so it can't be cancelled
+        manager.updateOutgoingIntent(id: sent.id, status: .failed, signature: "S", blockhash: "H")
+        XCTAssertThrowsError(try manager.cancelOutgoingPayment(id: sent.id))
+    }
+
+    // MARK: - Ledger Tests
+
+    func testLedgerDoubleEntry() {
+        var ledger = StealthLedger()
+        ledger.recordInflow(to: "A", lamports: 10_000)
+        ledger.recordSweep(from: "A", to: .stealth("B"), lamports: 4_000, fee: 5_000)
+        XCTAssertEqual(ledger.recordSweepAll(from: "B", to: .external, fee: 1_000), 3_000)
+        ledger.recordRentReclaim(to: "A", lamports: 500)
+
+        XCTAssertEqual(ledger.stealthBalances, ["A": 1_500, "B": 0])
+        XCTAssertEqual(ledger.balance(of: .fees), 6_000)
+        XCTAssertEqual(ledger.balance(of: .rent), -500)
+        XCTAssertEqual(ledger.balance(of: .external), -7_000)
+
+        // Every entry moves lamports between two accounts, so all balances sum to zero
+        let accounts: [LedgerAccount] = [.stealth("A"), .stealth("B"), .external, .fees, .rent]
+        XCTAssertEqual(accounts.map { ledger.balance(of: $0) }.reduce(0, +), 0)
+    }
+
+    func testLedgerSweepBooksUnseenInflow() {
+        var ledger = StealthLedger()
+        ledger.recordSweep(from: "A", to: .external, lamports: 100, fee: 10)
+
+        XCTAssertEqual(ledger.entries.map(\.kind), [.inflow, .sweep, .fee])
+        XCTAssertEqual(ledger.entries.first?.lamports, 110)
+        XCTAssertEqual(ledger.stealthBalances, ["A": 0])
+    }
+
+    func testLedgerDiscrepancies() {
+        var ledger = StealthLedger()
+        ledger.recordInflow(to: "A", lamports: 100)
+        ledger.recordInflow(to: "B", lamports: 50)
+
+        XCTAssertTrue(ledger.contains("A"))
+        XCTAssertFalse(ledger.contains("C"))
+        XCTAssertEqual(
+            ledger.discrepancies(balances: ["A": 60, "B": 80]),
+            [BalanceDiscrepancy(stealthAddress: "A", expected: 100, actual: 60)]
+        )
+    }
+
+    // MARK: - Ephemeral Key Reuse Tests
+
+    func testEphemeralKeyTracker() throws {
+        let first = Data(repeating: 0x01, count: 32)
+        let second = Data(repeating: 0x02, count: 32)
+
+        var tracker = EphemeralKeyTracker()
+        XCTAssertTrue(tracker.insert(first))
+        XCTAssertFalse(tracker.insert(first))
+        XCTAssertTrue(tracker.insert(second))
+        XCTAssertEqual(tracker.count, 2)
+
+        // Survives persistence
+        let encoded = try JSONEncoder().encode(tracker)
+        let decoded = try JSONDecoder().decode(EphemeralKeyTracker.self, from: encoded)
+        XCTAssertTrue(decoded.contains(first))
+        XCTAssertFalse(decoded.contains(Data(repeating: 0x03, count: 32)))
+    }
+
+    func testEphemeralKeyTrackerSightings() throws {
+        let key = Data(repeating: 0x01, count: 32)
+
+        var tracker = EphemeralKeyTracker()
+        XCTAssertEqual(tracker.insert(key, stealthAddress: "A"), .new)
+        XCTAssertEqual(tracker.insert(key, stealthAddress: "A"), .repeated)
+        XCTAssertEqual(tracker.insert(key, stealthAddress: "B"), .reused)
+
+        // Keys recorded without an address can't be told apart from reuse
+        var legacy = EphemeralKeyTracker()
+        legacy.insert(key)
+        XCTAssertEqual(legacy.insert(key, stealthAddress: "A"), .reused)
+
+        // Addresses survive persistence, and trackers persisted without them still decode
+        let decoded = try JSONDecoder().decode(EphemeralKeyTracker.self, from: JSONEncoder().encode(tracker))
+        var restored = decoded
+        XCTAssertEqual(restored.insert(key, stealthAddress: "A"), .repeated)
+        let old = try JSONDecoder().decode(EphemeralKeyTracker.self, from: Data(#"{"digests":[]}"#.utf8))
+        XCTAssertEqual(old.count, 0)
+    }
+
+    @MainActor
+    func testAddPendingPaymentIgnoresRedeliveredPayload() {
+        let manager = StealthWalletManager(userDefaults: UserDefaults(suiteName: "test.\(UUID().uuidString)")!)
+        var warnings: [PendingPayment] = []
+        let cancellable = manager.reusedEphemeralKeyWarnings.sink { warnings.append($0) }
+        defer { cancellable.cancel() }
+
+        let payload = MeshStealthPayload(
+            stealthAddress: "A",
+            ephemeralPublicKey: Data(repeating: 0x01, count: 32),
+            mlkemCiphertext: nil,
+            amount: 100,
+            tokenMint: nil,
+            viewTag: 0,
+            memo: nil
+        )
+        manager.addPendingPayment(from: payload)
+        manager.addPendingPayment(from: payload)
+
+        XCTAssertEqual(manager.pendingPayments.count, 1)
+        XCTAssertEqual(manager.ledger.stealthBalances, ["A": 100])
+        XCTAssertTrue(warnings.isEmpty)
+
+        // Still a duplicate once the first delivery is no longer pending
+        manager.pruneExpiredPayments(maxAge: -60)
+        XCTAssertTrue(manager.pendingPayments.isEmpty)
+        XCTAssertFalse(manager.addPendingPayment(PendingPayment(from: payload)))
+        XCTAssertTrue(warnings.isEmpty)
+    }
+
+    @MainActor
+    func testAddPendingPaymentWarnsOnReusedEphemeralKey() {
+        let manager = StealthWalletManager(userDefaults: UserDefaults(suiteName: "test.\(UUID().uuidString)")!)
+        var warnings: [PendingPayment] = []
+        let cancellable = manager.reusedEphemeralKeyWarnings.sink { warnings.append($0) }
+        defer { cancellable.cancel() }
+
+        let ephemeralPublicKey = Data(repeating: 0x01, count: 32)
+        for stealthAddress in ["A", "B"] {
+            manager.addPendingPayment(from: MeshStealthPayload(
+                stealthAddress: stealthAddress,
+                ephemeralPublicKey: ephemeralPublicKey,
+                mlkemCiphertext: nil,
+                amount: 100,
+                tokenMint: nil,
+                viewTag: 0,
+                memo: nil
+            ))
+        }
+
+        XCTAssertEqual(manager.pendingPayments.count, 2)
+        XCTAssertEqual(warnings.map(\.stealthAddress), ["B"])
+    }
+
+    // MARK: - ActivityExporter Tests
+
+    func testActivityExporterCSV() {
+        let items = [
+            ActivityItem(
+                type: .meshReceive,
+                amount: 1_500_000_000,
+                timestamp: Date(timeIntervalSince1970: 1704067200),
+                status: .completed,
+                stealthAddress: "Stealth1",
+                transactionSignature: "sig1",
+                peerName: "Bob, Jr."
+            ),
+            ActivityItem(type: .meshSend, amount: 1, status: .pending)
+        ]
+
+        let csv = ActivityExporter.csv(items)
+
+        XCTAssertEqual(csv, """
+        date,type,amount_lamports,amount_sol,stealth_address,signature,peer
+        2024-01-01T00:00:00Z,meshReceive,1500000000,1.500000000,Stealth1,sig1,"Bob, Jr."
+
+        """)
+    }
+
+    func testActivityExporterLedger() {
+        let items = [
+            ActivityItem(
+                type: .shield,
+                amount: 250_000_000,
+                timestamp: Date(timeIntervalSince1970: 1704067200),
+                status: .completed,
+                transactionSignature: "sig1"
+            ),
+            ActivityItem(type: .hop, amount: 250_000_000, status: .completed)
+        ]
+
+        let mapping = LedgerAccountMapping(wallet: "Assets:Wallet", stealth: "Assets:Stealth")
+        let journal = ActivityExporter.ledger(items, mapping: mapping)
+
+        XCTAssertEqual(journal, """
+        2024/01/01 * Shield
+            ; signature: sig1
+            Assets:Stealth  0.250000000 SOL
+            Assets:Wallet
+
+        """)
+    }
+
+    func testActivityExporterFiatValuation() {
+        let known = Date(timeIntervalSince1970: 1704067200)
+        let items = [
+            ActivityItem(type: .meshReceive, amount: 1_500_000_000, timestamp: known, status: .completed),
+            ActivityItem(
+                type: .meshReceive,
+                amount: 1,
+                timestamp: Date(timeIntervalSince1970: 1704153600),
+                status: .completed
+            )
+        ]
+        let valuation = FiatValuation(currency: "USD") { $0 == known ? Decimal(string: "101.25") : nil }
+
+        let csv = ActivityExporter.csv(items, valuation: valuation)
+        XCTAssertEqual(csv, """
+        date,type,amount_lamports,amount_sol,stealth_address,signature,peer,value_usd
+        2024-01-01T00:00:00Z,meshReceive,1500000000,1.500000000,,,,151.88
+        2024-01-02T00:00:00Z,meshReceive,1,0.000000001,,,,
+
+        """)
+
+        let journal = ActivityExporter.ledger(items, valuation: valuation)
+        XCTAssertTrue(journal.contains("Assets:Solana:Stealth  1.500000000 SOL @ 101.25 USD\n"))
+        XCTAssertTrue(journal.contains("Assets:Solana:Stealth  0.000000001 SOL\n"))
+    }
+
     // MARK: - NetworkStatus Tests
 
     func testNetworkStatusValues() {
@@ -511,3 +962,26 @@ final class IntegrationTests: XCTestCase {
         ))
     }
 }
+
+/// Answers every RPC request with a getBalance result of `balance` lamports
+private final class StubBalanceURLProtocol: URLProtocol {
+    nonisolated(unsafe) static var balance: UInt64 = 0
+
+    override class func canInit(with request: URLRequest) -> Bool {
+        request.url?.host == "rpc.stub.invalid"
+    }
+
+    override class func canonicalRequest(for request: URLRequest) -> URLRequest {
+        request
+    }
+
+    override func startLoading() {
+        let body = Data(#"{"jsonrpc":"2.0","id":1,"result":{"context":{"slot":1},"value":\#(Self.balance)}}"#.utf8)
+        let response = HTTPURLResponse(url: request.url!, statusCode: 200, httpVersion: nil, headerFields: nil)!
+        client?.urlProtocol(self, didReceive: response, cacheStoragePolicy: .notAllowed)
+        client?.urlProtocol(self, didLoad: body)
+        client?.urlProtocolDidFinishLoading(self)
+    }
+
+    override func stopLoading() {}
+}
@@ -78,7 +78,44 @@ final class SolanaIntegrationTests: XCTestCase {
         XCTAssertLessThanOrEqual(bump, 255)
     }
 
-    func testBuildInitCiphertextData() {
+    func testDeriveCiphertextPDAAppNamespace() throws {
+        let stealthPubkey = try SolanaRPCClient.decodePublicKey("11111111111111111111111111111111")
+
+        let (defaultPDA, _) = try StealthPQClient.deriveCiphertextPDA(
+            stealthPubkey: stealthPubkey,
+            programId: STEALTH_PQ_PROGRAM_ID
+        )
+        let (explicitDefaultPDA, _) = try StealthPQClient.deriveCiphertextPDA(
+            stealthPubkey: stealthPubkey,
+            programId: STEALTH_PQ_PROGRAM_ID,
+            appId: DEFAULT_APP_ID
+        )
+        let (appPDA, _) = try StealthPQClient.deriveCiphertextPDA(
+            stealthPubkey: stealthPubkey,
+            programId: STEALTH_PQ_PROGRAM_ID,
+            appId: 42
+        )
+
+        // The default namespace keeps the original address; other apps get their own
+        XCTAssertEqual(defaultPDA, explicitDefaultPDA)
+        XCTAssertNotEqual(defaultPDA, appPDA)
+    }
+
+    func testDeriveNamespaceCounterPDA() throws {
+        let (defaultCounter, _) = try StealthPQClient.deriveNamespaceCounterPDA(
+            appId: DEFAULT_APP_ID,
+            programId: STEALTH_PQ_PROGRAM_ID
+        )
+        let (appCounter, _) = try StealthPQClient.deriveNamespaceCounterPDA(
+            appId: 42,
+            programId: STEALTH_PQ_PROGRAM_ID
+        )
+
+        XCTAssertTrue(SolanaRPCClient.isValidPublicKey(defaultCounter))
+        XCTAssertNotEqual(defaultCounter, appCounter)
+    }
+
+    func testBuildInitCiphertextDataFixedChunk() {
         let ephemeralPubkey = Data(repeating: 0xAB, count: 32)
         let ciphertextPart1 = Data(repeating: 0xCD, count: 512)
 
@@ -87,15 +124,58 @@ final class SolanaIntegrationTests: XCTestCase {
             ciphertextPart1: ciphertextPart1
         )
 
-        // 8 (discriminator) + 32 (ephemeral) + 4 (vec length) + 512 (data) = 556 bytes
-        XCTAssertEqual(instructionData.count, 556)
+        // 8 (discriminator) + 32 (ephemeral) + 2 (chunk length) + 576 (chunk capacity)
+        // + 1 (expires_at: None) + 4 (app_id) + 2 (rent_payer_share_bps) + 1 (view_tag)
+        // + 1 (kem_variant) = 627 bytes
+        XCTAssertEqual(instructionData.count, 627)
+        XCTAssertEqual(instructionData.suffix(8), Data([0, 0, 0, 0, 0, 0, 0, 0]))
+
+        let mlkem1024 = StealthPQClient.buildInitCiphertextData(
+            ephemeralPubkey: ephemeralPubkey,
+            ciphertextPart1: ciphertextPart1,
+            kemVariant: KEM_VARIANT_ML_KEM_1024
+        )
+        XCTAssertEqual(mlkem1024.last, KEM_VARIANT_ML_KEM_1024)
+
+        // Chunk length (little-endian u16) follows the ephemeral key
+        let length = UInt16(instructionData[40]) | (UInt16(instructionData[41]) << 8)
+        XCTAssertEqual(length, 512)
 
-        // Check discriminator is present (first 8 bytes)
-        let discriminator = instructionData.prefix(8)
-        XCTAssertEqual(discriminator.count, 8)
+        // Padding after the chunk data is zeroed
+        XCTAssertTrue(instructionData[554..<618].allSatisfy { $0 == 0 })
     }
 
-    func testBuildCompleteCiphertextData() {
+    func testBuildInitCiphertextAndFundData() {
+        let instructionData = StealthPQClient.buildInitCiphertextAndFundData(
+            ephemeralPubkey: Data(repeating: 0xAB, count: 32),
+            ciphertextPart1: Data(repeating: 0xCD, count: 512),
+            viewTag: 0x5A,
+            lamports: 1_000_000,
+            kemVariant: KEM_VARIANT_ML_KEM_512
+        )
+
+        // init_ciphertext arguments up to view_tag (626 bytes with the discriminator)
+        // + 8 (lamports) + 1 (kem_variant)
+        XCTAssertEqual(instructionData.count, 635)
+        XCTAssertEqual(instructionData[625], 0x5A)
+        XCTAssertEqual(instructionData[626..<634], Data([0x40, 0x42, 0x0F, 0, 0, 0, 0, 0]))
+        XCTAssertEqual(instructionData.last, KEM_VARIANT_ML_KEM_512)
+
+        let plain = StealthPQClient.buildInitCiphertextData(
+            ephemeralPubkey: Data(repeating: 0xAB, count: 32),
+            ciphertextPart1: Data(repeating: 0xCD, count: 512)
+        )
+        XCTAssertNotEqual(instructionData.prefix(8), plain.prefix(8))
+
+        let finalize = StealthPQClient.buildCompleteAndFinalizeData(
+            ciphertextPart2: Data(repeating: 0xEF, count: 576),
+            offset: 512
+        )
+        // 8 (discriminator) + 2 (chunk length) + 576 (chunk) + 2 (offset)
+        XCTAssertEqual(finalize.count, 588)
+    }
+
+    func testBuildCompleteCiphertextDataFixedChunk() {
         let ciphertextPart2 = Data(repeating: 0xEF, count: 576)
         let offset: UInt16 = 512
 
@@ -104,8 +184,153 @@ final class SolanaIntegrationTests: XCTestCase {
             offset: offset
         )
 
-        // 8 (discriminator) + 4 (vec length) + 576 (data) + 2 (offset) = 590 bytes
-        XCTAssertEqual(instructionData.count, 590)
+        // 8 (discriminator) + 2 (chunk length) + 576 (chunk capacity) + 2 (offset) = 588 bytes
+        XCTAssertEqual(instructionData.count, 588)
+    }
+
+    func testBuildSetReturnAddressData() {
+        let encryptedReturnAddress = Data(repeating: 0x42, count: 32)
+
+        let instructionData = StealthPQClient.buildSetReturnAddressData(
+            encryptedReturnAddress: encryptedReturnAddress
+        )
+
+        // 8 (discriminator) + 32 (encrypted return address) = 40 bytes
+        XCTAssertEqual(instructionData.count, 40)
+        XCTAssertEqual(instructionData.suffix(32), encryptedReturnAddress)
+    }
+
+    func testBuildSetPayloadTagData() {
+        let payloadTag = Data(repeating: 0x17, count: 16)
+
+        let instructionData = StealthPQClient.buildSetPayloadTagData(payloadTag: payloadTag)
+
+        // 8 (discriminator) + 16 (tag) = 24 bytes
+        XCTAssertEqual(instructionData.count, 24)
+        XCTAssertEqual(instructionData.suffix(16), payloadTag)
+    }
+
+    func testBuildWriteExtensionsData() {
+        let chunk = CiphertextExtension(type: CiphertextExtension.typeEncryptedAmount, value: Data(repeating: 0x33, count: 8)).encoded()
+
+        let instructionData = StealthPQClient.buildWriteExtensionsData(chunk: chunk, offset: 0)
+
+        // 8 (discriminator) + 2 (chunk length) + 576 (chunk capacity) + 2 (offset) = 588 bytes
+        XCTAssertEqual(instructionData.count, 588)
+        XCTAssertEqual(instructionData[8], UInt8(chunk.count))
+        XCTAssertEqual(instructionData[10..<(10 + chunk.count)], chunk)
+    }
+
+    func testCiphertextExtensionParsing() {
+        var area = CiphertextExtension(type: CiphertextExtension.typeEncryptedAmount, value: Data(repeating: 0x07, count: 8)).encoded()
+        area.append(CiphertextExtension(type: 0x90, value: Data()).encoded())
+        area.append(Data([CiphertextExtension.typeEnd, 0xFF, 0xFF]))
+
+        let entries = CiphertextExtension.parseAll(from: area)
+
+        XCTAssertEqual(entries?.count, 2)
+        XCTAssertEqual(entries?[0].type, CiphertextExtension.typeEncryptedAmount)
+        XCTAssertEqual(entries?[0].value, Data(repeating: 0x07, count: 8))
+        XCTAssertEqual(entries?[1].type, 0x90)
+        XCTAssertEqual(CiphertextExtension.parseAll(from: Data()), [])
+
+        // Declared length runs past the end of the area
+        XCTAssertNil(CiphertextExtension.parseAll(from: Data([CiphertextExtension.typeFMDClue, 4, 0, 1, 2])))
+    }
+
+    func testRegistryEpochExtension() {
+        var area = CiphertextExtension(type: CiphertextExtension.typeFMDClue, value: Data([0xFF])).encoded()
+        area.append(CiphertextExtension.registryEpoch(9).encoded())
+
+        let entries = CiphertextExtension.parseAll(from: area)
+        XCTAssertEqual(entries?.last, CiphertextExtension(type: CiphertextExtension.typeRegistryEpoch, value: Data([9, 0, 0, 0])))
+    }
+
+    func testTokenAccountExtension() throws {
+        let stealthAddress = "11111111111111111111111111111112"
+        let mint = "So11111111111111111111111111111111111111112"
+        let entry = try CiphertextExtension.tokenAccount(stealthAddress: stealthAddress, mint: mint)
+        let ata = try StealthPQClient.deriveAssociatedTokenAddress(owner: stealthAddress, mint: mint, tokenProgram: TOKEN_PROGRAM_ID)
+
+        XCTAssertEqual(entry.type, CiphertextExtension.typeTokenAccount)
+        XCTAssertEqual(entry.encoded().count, 3 + 32)
+
+        // Replace the mock's empty extension area
+        var mockData = mockCiphertextAccount().dropLast(4)
+        let area = CiphertextExtension.registryEpoch(2).encoded() + entry.encoded()
+        var length = UInt32(area.count).littleEndian
+        mockData.append(Data(bytes: &length, count: 4))
+        mockData.append(area)
+
+        let parsed = CiphertextAccountData.parse(from: Data(mockData))
+        XCTAssertEqual(parsed?.tokenAccount, ata)
+        XCTAssertEqual(parsed?.registryEpoch, 2)
+        XCTAssertNil(CiphertextAccountData.parse(from: mockCiphertextAccount())?.tokenAccount)
+    }
+
+    func testTokenAccountAmountParsing() {
+        var account = Data(repeating: 0x01, count: 64)  // mint, owner
+        var amount = UInt64(750_000).littleEndian
+        account.append(Data(bytes: &amount, count: 8))
+        account.append(Data(repeating: 0, count: 93))
+
+        XCTAssertEqual(SolanaRPCClient.tokenAccountAmount(from: account), 750_000)
+        XCTAssertEqual(SolanaRPCClient.tokenAccountAmount(from: (Data([0xFF]) + account).dropFirst()), 750_000)
+        XCTAssertNil(SolanaRPCClient.tokenAccountAmount(from: account.prefix(164)))
+    }
+
+    func testUnfundedWarningForSplPayments() throws {
+        let ciphertextData = try XCTUnwrap(CiphertextAccountData.parse(from: mockCiphertextAccount()))
+
+        // Native SOL is judged by the stealth address balance
+        XCTAssertEqual(BlockchainScanner.warnings(for: ciphertextData, lamports: 0, tokenAmount: nil), [.unfunded])
+        XCTAssertEqual(BlockchainScanner.warnings(for: ciphertextData, lamports: 5_000, tokenAmount: nil), [])
+
+        // An SPL payment by its token balance, whatever the stealth address holds
+        XCTAssertEqual(BlockchainScanner.warnings(for: ciphertextData, lamports: 0, tokenAmount: 1_000), [])
+        XCTAssertEqual(BlockchainScanner.warnings(for: ciphertextData, lamports: 5_000, tokenAmount: 0), [.unfunded])
+    }
+
+    func testSplTransferEventParsing() {
+        var eventData = SplTransferEventData.discriminator
+        eventData.append(Data(repeating: 0x01, count: 32))  // stealth_pubkey
+        eventData.append(Data(repeating: 0x02, count: 32))  // mint
+        eventData.append(Data(repeating: 0x03, count: 32))  // token_account
+        var amount = UInt64(1_000_000).littleEndian
+        eventData.append(Data(bytes: &amount, count: 8))
+        var received = UInt64(990_000).littleEndian
+        eventData.append(Data(bytes: &received, count: 8))
+
+        let logs = [
+            "Program \(STEALTH_PQ_PROGRAM_ID) invoke [1]",
+            "Program data: \(Data(repeating: 0xAA, count: 48).base64EncodedString())",
+            "Program data: \(eventData.base64EncodedString())",
+            "Program log: Transferred 1000000 tokens to stealth address",
+            "Program \(STEALTH_PQ_PROGRAM_ID) success"
+        ]
+
+        let events = SplTransferEventData.parseAll(fromLogs: logs)
+        XCTAssertEqual(events.count, 1)
+        XCTAssertEqual(events.first?.stealthPubkey, SolanaRPCClient.encodePublicKey(Data(repeating: 0x01, count: 32)))
+        XCTAssertEqual(events.first?.mint, SolanaRPCClient.encodePublicKey(Data(repeating: 0x02, count: 32)))
+        XCTAssertEqual(events.first?.tokenAccount, SolanaRPCClient.encodePublicKey(Data(repeating: 0x03, count: 32)))
+        XCTAssertEqual(events.first?.received, 990_000)
+        XCTAssertEqual(events.first?.transferFee, 10_000)
+
+        // Sliced data, as from a larger buffer
+        let sliced = (Data([0xFF]) + eventData).dropFirst()
+        XCTAssertEqual(SplTransferEventData.parse(from: sliced), events.first)
+
+        XCTAssertNil(SplTransferEventData.parse(from: eventData.prefix(119)))
+        XCTAssertNil(SplTransferEventData.parse(from: Data(repeating: 0, count: 8) + eventData.dropFirst(8)))
+    }
+
+    func testPrioritizedKeysByRegistryEpoch() {
+        let keys: [UInt32: String] = [1: "old", 2: "previous", 3: "current"]
+
+        XCTAssertEqual(StealthScanner.prioritized(keys, registryEpoch: 1), ["old", "current", "previous"])
+        XCTAssertEqual(StealthScanner.prioritized(keys, registryEpoch: nil), ["current", "previous", "old"])
+        XCTAssertEqual(StealthScanner.prioritized(keys, registryEpoch: 7), ["current", "previous", "old"])
     }
 
     func testBuildTransferToStealthData() {
@@ -122,6 +347,20 @@ final class SolanaIntegrationTests: XCTestCase {
         XCTAssertEqual(decodedLamports, lamports)
     }
 
+    func testBuildTransferSplToStealthData() {
+        let instructionData = StealthPQClient.buildTransferSplToStealthData(amount: 1_500_000)
+
+        // 8 (discriminator) + 8 (amount) = 16 bytes, with a different discriminator than the SOL transfer
+        XCTAssertEqual(instructionData.count, 16)
+        XCTAssertNotEqual(
+            instructionData.prefix(8),
+            StealthPQClient.buildTransferToStealthData(lamports: 1_500_000).prefix(8)
+        )
+
+        let decodedAmount = instructionData.suffix(8).withUnsafeBytes { $0.load(as: UInt64.self) }
+        XCTAssertEqual(decodedAmount, 1_500_000)
+    }
+
     func testBuildReclaimRentData() {
         let instructionData = StealthPQClient.buildReclaimRentData()
 
@@ -129,14 +368,63 @@ final class SolanaIntegrationTests: XCTestCase {
         XCTAssertEqual(instructionData.count, 8)
     }
 
+    func testBatchTransferToStealthLayout() throws {
+        let data = StealthPQClient.buildBatchTransferToStealthData(amounts: [1_000, 2_000])
+
+        // Discriminator (8) + Vec length (4) + two u64 amounts (16)
+        XCTAssertEqual(data.count, 28)
+        XCTAssertEqual(data[8..<12], Data([2, 0, 0, 0]))
+
+        let client = StealthPQClient(rpcClient: SolanaRPCClient(cluster: .devnet))
+        let accounts = try client.getBatchTransferToStealthAccounts(
+            sender: "11111111111111111111111111111112",
+            stealthAddresses: [STEALTH_PQ_PROGRAM_ID, SYSTEM_PROGRAM_ID]
+        )
+
+        // sender, system_program, stats placeholder, then one pair per recipient
+        XCTAssertEqual(accounts.count, 7)
+        XCTAssertEqual(accounts[2].pubkey, STEALTH_PQ_PROGRAM_ID)
+        XCTAssertEqual(accounts[5].pubkey, SYSTEM_PROGRAM_ID)
+        XCTAssertTrue(accounts[5].isWritable)
+        XCTAssertFalse(accounts[6].isWritable)
+    }
+
+    func testInitCiphertextAccountsOptionalRentPayer() throws {
+        let client = StealthPQClient(rpcClient: SolanaRPCClient(cluster: .devnet))
+        let sender = "11111111111111111111111111111112"
+        let sponsor = SYSTEM_PROGRAM_ID
+
+        let sponsored = try client.getInitCiphertextAccounts(sender: sender, stealthAddress: STEALTH_PQ_PROGRAM_ID, rentPayer: sponsor)
+        XCTAssertEqual(sponsored[1].pubkey, sponsor)
+        XCTAssertTrue(sponsored[1].isSigner && sponsored[1].isWritable)
+
+        // Without a sponsor the rent payer is omitted and the writable sender pays
+        let unsponsored = try client.getInitCiphertextAndFundAccounts(sender: sender, stealthAddress: STEALTH_PQ_PROGRAM_ID)
+        XCTAssertEqual(unsponsored.count, 6)
+        XCTAssertTrue(unsponsored[0].isSigner && unsponsored[0].isWritable)
+        XCTAssertEqual(unsponsored[1].pubkey, STEALTH_PQ_PROGRAM_ID)
+        XCTAssertFalse(unsponsored[1].isSigner || unsponsored[1].isWritable)
+    }
+
+    func testRefundExpiredAccountsDefaultRentPayerToSender() throws {
+        XCTAssertEqual(StealthPQClient.buildRefundExpiredData().count, 8)
+
+        let client = StealthPQClient(rpcClient: SolanaRPCClient(cluster: .devnet))
+        let sender = "11111111111111111111111111111112"
+        let accounts = try client.getRefundExpiredAccounts(sender: sender, stealthAddress: STEALTH_PQ_PROGRAM_ID)
+
+        XCTAssertEqual(accounts.count, 3)
+        XCTAssertTrue(accounts[0].isSigner)
+        XCTAssertEqual(accounts[2].pubkey, sender)
+    }
+
     func testCiphertextAccountDataParsing() {
         // Build a mock account data matching the on-chain format
-        // [8 discriminator] + [32 stealth_pubkey] + [32 ephemeral] + [1088 ciphertext] + [8 timestamp] + [1 bump]
-        var mockData = Data(repeating: 0, count: 1169)
+        let ciphertext = Data(repeating: 0xCC, count: 1088)
+        var mockData = mockCiphertextAccount(ciphertext: ciphertext)
 
-        // Set discriminator (first 8 bytes)
-        let discriminator = Data([0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08])
-        mockData.replaceSubrange(0..<8, with: discriminator)
+        // The CiphertextAccount discriminator (first 8 bytes) is set by the helper
+        XCTAssertEqual(mockData.prefix(8), CiphertextAccountData.discriminator)
 
         // Set stealth pubkey (bytes 8-40)
         let stealthPubkey = Data(repeating: 0xAA, count: 32)
@@ -146,18 +434,14 @@ final class SolanaIntegrationTests: XCTestCase {
         let ephemeralPubkey = Data(repeating: 0xBB, count: 32)
         mockData.replaceSubrange(40..<72, with: ephemeralPubkey)
 
-        // Set ciphertext (bytes 72-1160)
-        let ciphertext = Data(repeating: 0xCC, count: 1088)
-        mockData.replaceSubrange(72..<1160, with: ciphertext)
-
-        // Set timestamp (bytes 1160-1168) - use a known value
+        // Set timestamp (bytes 72-80) - use a known value
         var timestamp: Int64 = 1704067200  // 2024-01-01 00:00:00 UTC
         withUnsafeBytes(of: &timestamp) { bytes in
-            mockData.replaceSubrange(1160..<1168, with: bytes)
+            mockData.replaceSubrange(72..<80, with: bytes)
         }
 
-        // Set bump (byte 1168)
-        mockData[1168] = 254
+        // Set bump (byte 80)
+        mockData[80] = 254
 
         // Parse the data
         let parsed = CiphertextAccountData.parse(from: mockData)
@@ -165,15 +449,408 @@ final class SolanaIntegrationTests: XCTestCase {
         XCTAssertNotNil(parsed)
         XCTAssertEqual(parsed!.stealthPubkey, stealthPubkey)
         XCTAssertEqual(parsed!.ephemeralPubkey, ephemeralPubkey)
+        XCTAssertEqual(parsed!.kemVariant, KEM_VARIANT_ML_KEM_768)
+        XCTAssertNil(parsed!.encryptedMemo)
         XCTAssertEqual(parsed!.mlkemCiphertext, ciphertext)
         XCTAssertEqual(parsed!.createdAt, timestamp)
         XCTAssertEqual(parsed!.bump, 254)
+        XCTAssertNil(parsed!.expiresAt)
+        XCTAssertNil(parsed!.encryptedReturnAddress)
+        XCTAssertNil(parsed!.payloadTag)
+        XCTAssertEqual(parsed!.appId, DEFAULT_APP_ID)
+        XCTAssertEqual(parsed!.rentPayer, Data(repeating: 0, count: 32))
+        XCTAssertEqual(parsed!.rentPayerShareBps, 0)
+        XCTAssertEqual(parsed!.viewTag, 0)
+        XCTAssertEqual(parsed!.sender, Data(repeating: 0, count: 32))
+        XCTAssertFalse(parsed!.isFinalized)
+        XCTAssertTrue(parsed!.extensions.isEmpty)
+    }
+
+    func testMetaAddressRecordParsing() throws {
+        let keyPair = try StealthKeyPair.generate(withPostQuantum: true)
+
+        // [8 discriminator] + [32 owner] + [M || V || K_pub] + [4 epoch] + [8 updated_at] + [1 bump]
+        var mockData = Data(repeating: 0, count: 1301)
+        mockData.replaceSubrange(40..<1288, with: keyPair.hybridMetaAddress)
+        mockData.replaceSubrange(1288..<1292, with: Data([2, 0, 0, 0]))
+        mockData.replaceSubrange(1292..<1300, with: Data([0x00, 0xF1, 0x53, 0x65, 0x00, 0x00, 0x00, 0x00]))
+        mockData[1300] = 254
+
+        let record = MetaAddressRecord.parse(from: mockData)
+        XCTAssertNotNil(record)
+        XCTAssertEqual(record?.epoch, 2)
+        XCTAssertEqual(record?.updatedAt, 1_700_000_000)
+
+        // Fields at odd offsets into a sliced buffer are read without an aligned load
+        let sliced = MetaAddressRecord.parse(from: (Data([0xFF]) + mockData).dropFirst())
+        XCTAssertEqual(sliced?.updatedAt, 1_700_000_000)
+        XCTAssertEqual(sliced?.bump, 254)
+        XCTAssertEqual(record?.bump, 254)
+        XCTAssertEqual(record?.metaAddressString, keyPair.hybridMetaAddressString)
+
+        // Without an uploaded encapsulation key the entry is a classical meta-address
+        mockData.replaceSubrange(104..<1288, with: Data(repeating: 0, count: 1184))
+        let classical = MetaAddressRecord.parse(from: mockData)
+        XCTAssertNil(classical?.mlkemPubkey)
+        XCTAssertEqual(classical?.metaAddressString, keyPair.metaAddressString)
+
+        XCTAssertNil(MetaAddressRecord.parse(from: Data(repeating: 0, count: 1300)))
+    }
+
+    func testAnnouncementRecordParsing() throws {
+        let stealthAddress = "11111111111111111111111111111112"
+        let ephemeral = Data(repeating: 0xAB, count: 32)
+
+        // [8 discriminator] + [8 index] + [4 app_id] + [32 stealth] + [32 ciphertext PDA] + [32 R] + [1 tag] + [1 bump] + [32 payer]
+        var mockData = Data(repeating: 0, count: 150)
+        mockData.replaceSubrange(0..<8, with: AnnouncementRecord.discriminator)
+        mockData.replaceSubrange(8..<16, with: Data([7, 0, 0, 0, 0, 0, 0, 0]))
+        mockData.replaceSubrange(16..<20, with: Data([3, 0, 0, 0]))
+        mockData.replaceSubrange(20..<52, with: try SolanaRPCClient.decodePublicKey(stealthAddress))
+        mockData.replaceSubrange(84..<116, with: ephemeral)
+        mockData[116] = 0x2A
+        mockData[117] = 253
+        mockData.replaceSubrange(118..<150, with: try SolanaRPCClient.decodePublicKey(stealthAddress))
+
+        let record = AnnouncementRecord.parse(from: mockData)
+        XCTAssertEqual(record?.index, 7)
+        XCTAssertEqual(record?.appId, 3)
+        XCTAssertEqual(record?.stealthPubkey, stealthAddress)
+        XCTAssertEqual(record?.ephemeralPubkey, ephemeral)
+        XCTAssertEqual(record?.viewTag, 0x2A)
+        XCTAssertEqual(record?.bump, 253)
+        XCTAssertEqual(record?.payer, stealthAddress)
+
+        // A slice of a larger buffer parses relative to its start
+        XCTAssertEqual(AnnouncementRecord.parse(from: (Data([0xFF]) + mockData).dropFirst())?.index, 7)
+
+        XCTAssertNil(AnnouncementRecord.parse(from: Data(repeating: 0, count: 149)))
+
+        // Nor does another account type of the same size
+        mockData[0] ^= 0xFF
+        XCTAssertNil(AnnouncementRecord.parse(from: mockData))
+
+        // Entries are keyed by index
+        let first = try StealthPQClient.deriveAnnouncementPDA(index: 0, programId: STEALTH_PQ_PROGRAM_ID)
+        let second = try StealthPQClient.deriveAnnouncementPDA(index: 1, programId: STEALTH_PQ_PROGRAM_ID)
+        XCTAssertNotEqual(first.address, second.address)
+
+        XCTAssertEqual(StealthPQClient.buildLogAnnouncementData().count, 8)
+    }
+
+    func testPaymentCostEstimate() {
+        let classic = PaymentCost.estimate(hybrid: false)
+        XCTAssertEqual(classic.transactionCount, 1)
+        XCTAssertEqual(classic.total, 5000)
+        XCTAssertEqual(classic.announcementRent, 0)
+
+        // (1444 + 128) bytes * 3480 * 2
+        let hybrid = PaymentCost.estimate(hybrid: true, rentPayerShareBps: 2500, computeUnitPrice: 1000)
+        XCTAssertEqual(hybrid.transactionCount, 2)
+        XCTAssertEqual(hybrid.transactionFees, 10_000)
+        XCTAssertEqual(hybrid.priorityFees, 400)
+        XCTAssertEqual(hybrid.announcementRent, 10_941_120)
+        XCTAssertEqual(hybrid.reclaimableByRentPayer, 2_735_280)
+        XCTAssertEqual(hybrid.reclaimableByRecipient, 8_205_840)
+        XCTAssertEqual(hybrid.total, 10_951_520)
+        XCTAssertEqual(hybrid.netCost, 8_216_240)
+
+        // A non-empty extension area takes an extra write_extensions transaction
+        let withExtensions = PaymentCost.estimate(hybrid: true, extensionsLength: 6)
+        XCTAssertEqual(withExtensions.transactionCount, 3)
+        XCTAssertEqual(withExtensions.announcementRent, 10_982_880)
+
+        XCTAssertEqual(PaymentCost.priorityFee(computeUnitPrice: 1, computeUnitLimit: 1), 1)
+        XCTAssertEqual(PaymentCost.rentPayerShare(of: 9_999, bps: 10_000), 9_999)
+    }
+
+    func testCiphertextAccountDataParsingRentPayer() {
+        var mockData = mockCiphertextAccount()
+        mockData.replaceSubrange(141..<173, with: Data(repeating: 0x11, count: 32))
+        mockData[175] = 0x5A
+        mockData.replaceSubrange(176..<208, with: Data(repeating: 0x22, count: 32))
+        mockData[208] = 1
+
+        var shareBps: UInt16 = 2500
+        withUnsafeBytes(of: &shareBps) { bytes in
+            mockData.replaceSubrange(173..<175, with: bytes)
+        }
+
+        let parsed = CiphertextAccountData.parse(from: mockData)
+
+        XCTAssertEqual(parsed?.rentPayer, Data(repeating: 0x11, count: 32))
+        XCTAssertEqual(parsed?.rentPayerShareBps, 2500)
+        XCTAssertEqual(parsed?.viewTag, 0x5A)
+        XCTAssertEqual(parsed?.sender, Data(repeating: 0x22, count: 32))
+        XCTAssertEqual(parsed?.isFinalized, true)
+        XCTAssertEqual(parsed?.extensions, Data())
+    }
+
+    func testCiphertextAccountDataParsingExpiry() {
+        var mockData = mockCiphertextAccount()
+
+        var expiresAt: Int64 = 1704067200  // 2024-01-01 00:00:00 UTC
+        withUnsafeBytes(of: &expiresAt) { bytes in
+            mockData.replaceSubrange(81..<89, with: bytes)
+        }
+
+        let parsed = CiphertextAccountData.parse(from: mockData)
+
+        XCTAssertEqual(parsed?.expiresAt, expiresAt)
+        XCTAssertEqual(parsed?.isExpired(at: Date(timeIntervalSince1970: 1704067199)), false)
+        XCTAssertEqual(parsed?.isExpired(at: Date(timeIntervalSince1970: 1704067200)), true)
+    }
+
+    func testStatsAccountDataParsing() {
+        var mockData = Data(repeating: 0, count: 25)
+        mockData.replaceSubrange(0..<8, with: StatsAccountData.discriminator)
+
+        var transferCount: UInt64 = 42
+        withUnsafeBytes(of: &transferCount) { bytes in
+            mockData.replaceSubrange(8..<16, with: bytes)
+        }
+
+        var totalLamports: UInt64 = 5_000_000_000
+        withUnsafeBytes(of: &totalLamports) { bytes in
+            mockData.replaceSubrange(16..<24, with: bytes)
+        }
+
+        mockData[24] = 255
+
+        let parsed = StatsAccountData.parse(from: mockData)
+
+        XCTAssertEqual(parsed?.transferCount, 42)
+        XCTAssertEqual(parsed?.totalLamports, 5_000_000_000)
+        XCTAssertEqual(parsed?.bump, 255)
+        XCTAssertEqual(StatsAccountData.parse(from: (Data([0xFF]) + mockData).dropFirst())?.transferCount, 42)
+        XCTAssertNil(StatsAccountData.parse(from: Data(repeating: 0, count: 24)))
+
+        mockData[0] ^= 0xFF
+        XCTAssertNil(StatsAccountData.parse(from: mockData))
+    }
+
+    func testAnnouncementCountParsing() {
+        var mockData = StealthPQClient.announcementLogDiscriminator
+        mockData.append(Data([9, 0, 0, 0, 0, 0, 0, 0, 254]))
+
+        XCTAssertEqual(StealthPQClient.parseAnnouncementCount(from: mockData), 9)
+        XCTAssertEqual(StealthPQClient.parseAnnouncementCount(from: (Data([0xFF]) + mockData).dropFirst()), 9)
+        XCTAssertNil(StealthPQClient.parseAnnouncementCount(from: mockData.dropLast()))
+
+        mockData[0] ^= 0xFF
+        XCTAssertNil(StealthPQClient.parseAnnouncementCount(from: mockData))
+    }
+
+    func testStaticAnnouncementSource() async throws {
+        var mockData = mockCiphertextAccount()
+        mockData.replaceSubrange(40..<72, with: Data(repeating: 0xBB, count: 32))
+
+        let address = "11111111111111111111111111111111"
+        let source = StaticAnnouncementSource(announcements: [address: [7: mockData]])
+
+        let raw = try await source.rawAnnouncement(stealthAddress: address, appId: 7)
+        XCTAssertEqual(raw, mockData)
+
+        let parsed = try await source.announcement(stealthAddress: address, appId: 7)
+        XCTAssertEqual(parsed?.ephemeralPubkey, Data(repeating: 0xBB, count: 32))
+
+        let missing = try await source.announcement(stealthAddress: address, appId: DEFAULT_APP_ID)
+        XCTAssertNil(missing)
+    }
+
+    func testVerifyAnnouncementComparesOrigin() async throws {
+        let address = "11111111111111111111111111111111"
+        let announcements = [address: [DEFAULT_APP_ID: mockCiphertextAccount()]]
+        let origin = AnnouncementOrigin(signature: "5VERv8NMvzbJMEkV8xnrLkEaWRtSz9CosKDYjCJjBRnb", slot: 1_000)
+
+        func verify(_ verifierOrigin: AnnouncementOrigin?) async throws -> Bool {
+            let verifier = StaticAnnouncementSource(
+                announcements: announcements,
+                origins: verifierOrigin.map { [address: [DEFAULT_APP_ID: $0]] } ?? [:]
+            )
+            let scanner = BlockchainScanner(
+                rpcClient: SolanaRPCClient(cluster: .devnet),
+                stealthScanner: StealthScanner(keyPair: try StealthKeyPair.generate()),
+                source: StaticAnnouncementSource(announcements: announcements, origins: [address: [DEFAULT_APP_ID: origin]]),
+                verificationSources: [verifier]
+            )
+            return try await scanner.verifyAnnouncement(address)
+        }
+
+        let verified = try await verify(origin)
+        XCTAssertTrue(verified)
+
+        // Same bytes from another transaction or slot aren't the same announcement
+        let otherSlot = try await verify(AnnouncementOrigin(signature: origin.signature, slot: 1_001))
+        XCTAssertFalse(otherSlot)
+        let otherSignature = try await verify(AnnouncementOrigin(signature: "4vJ9JU1bJJE96FWSJKvHsmmFADCg4gpZQff4P3bkLKi", slot: 1_000))
+        XCTAssertFalse(otherSignature)
+        let unknown = try await verify(nil)
+        XCTAssertFalse(unknown)
+    }
+
+    func testSnapshotAnnouncementSource() async throws {
+        // Two announcements in app 7, plus a full-size record of another account type
+        var first = mockCiphertextAccount()
+        first.replaceSubrange(8..<40, with: Data(repeating: 0xAA, count: 32))
+        first.replaceSubrange(137..<141, with: Data([7, 0, 0, 0]))
+        var second = first
+        second.replaceSubrange(8..<40, with: Data(repeating: 0xCC, count: 32))
+        var other = first
+        other.replaceSubrange(0..<8, with: Data(repeating: 0xFF, count: 8))
+        other.replaceSubrange(8..<40, with: Data(repeating: 0xDD, count: 32))
+
+        let snapshot = SnapshotAnnouncementSource.encode([first, other, second])
+        let source = try SnapshotAnnouncementSource(snapshot: snapshot)
+
+        let firstAddress = Data(repeating: 0xAA, count: 32).base58EncodedString
+        let secondAddress = Data(repeating: 0xCC, count: 32).base58EncodedString
+        XCTAssertEqual(Set(source.stealthAddresses), [firstAddress, secondAddress])
+
+        let raw = try await source.rawAnnouncement(stealthAddress: secondAddress, appId: 7)
+        XCTAssertEqual(raw, second)
+        let missing = try await source.rawAnnouncement(stealthAddress: firstAddress, appId: DEFAULT_APP_ID)
+        XCTAssertNil(missing)
+
+        // A record running past the end of the file is rejected
+        XCTAssertThrowsError(try SnapshotAnnouncementSource(snapshot: snapshot.dropLast()))
     }
 
     func testCiphertextAccountDataParsingTooShort() {
         // Data that's too short should return nil
         let shortData = Data(repeating: 0, count: 100)
         XCTAssertNil(CiphertextAccountData.parse(from: shortData))
+
+        // So should a ciphertext running past the end of the account
+        XCTAssertNil(CiphertextAccountData.parse(from: mockCiphertextAccount().dropLast()))
+    }
+
+    func testCiphertextAccountDataParsingWrongDiscriminator() {
+        // Full-size data of another account type isn't a CiphertextAccount
+        var mockData = mockCiphertextAccount()
+        XCTAssertNotNil(CiphertextAccountData.parse(from: mockData))
+        mockData[0] ^= 0xFF
+        XCTAssertNil(CiphertextAccountData.parse(from: mockData))
+    }
+
+    func testCiphertextAccountDataParsingKemVariant() {
+        let ciphertext = Data(repeating: 0xCC, count: 1568)
+        var mockData = mockCiphertextAccount(ciphertext: ciphertext, kemVariant: KEM_VARIANT_ML_KEM_1024)
+        mockData.replaceSubrange((mockData.count - 4)..<mockData.count, with: Data([3, 0, 0, 0]))
+        mockData.append(Data([0x01, 0x01, 0x00]))
+
+        let parsed = CiphertextAccountData.parse(from: mockData)
+
+        XCTAssertEqual(mockData.count, 8 + 348 + 1568 + 3)
+        XCTAssertEqual(parsed?.kemVariant, KEM_VARIANT_ML_KEM_1024)
+        XCTAssertEqual(parsed?.mlkemCiphertext, ciphertext)
+        XCTAssertEqual(parsed?.extensions, Data([0x01, 0x01, 0x00]))
+    }
+
+    func testCiphertextAccountDataParsingMemo() {
+        var mockData = mockCiphertextAccount()
+        mockData[210] = 5
+        mockData.replaceSubrange(211..<216, with: Data([1, 2, 3, 4, 5]))
+
+        XCTAssertEqual(CiphertextAccountData.parse(from: mockData)?.encryptedMemo, Data([1, 2, 3, 4, 5]))
+
+        // A length beyond the memo field is malformed
+        mockData[210] = UInt8(MAX_MEMO_SIZE + 1)
+        XCTAssertNil(CiphertextAccountData.parse(from: mockData))
+    }
+
+    func testCiphertextAccountDataParsingAnnouncementIndex() {
+        var mockData = mockCiphertextAccount()
+        XCTAssertNil(CiphertextAccountData.parse(from: mockData)?.announcementIndex)
+
+        mockData[339] = 1
+        mockData.replaceSubrange(340..<348, with: Data([7, 0, 0, 0, 0, 0, 0, 0]))
+        XCTAssertEqual(CiphertextAccountData.parse(from: mockData)?.announcementIndex, 7)
+    }
+
+    func testCiphertextAccountDataParsingLegacyLayout() {
+        // Legacy layout: discriminator, stealth, ephemeral, ciphertext, created_at, bump
+        let ciphertext = Data((0..<MLKEM_CIPHERTEXT_SIZE).map { UInt8(truncatingIfNeeded: $0 * 7 + 3) })
+        var legacy = CiphertextAccountData.discriminator
+        legacy.append(Data(repeating: 0xAA, count: 32))
+        legacy.append(Data(repeating: 0xBB, count: 32))
+        legacy.append(ciphertext)
+        legacy.append(Data([0x00, 0xF1, 0x53, 0x65, 0x00, 0x00, 0x00, 0x00]))  // 1_700_000_000
+        legacy.append(254)
+
+        XCTAssertEqual(legacy.count, CiphertextAccountData.legacyAccountSize)
+
+        // Parse from a non-zero start index, as when sliced out of a larger buffer
+        let parsed = CiphertextAccountData.parse(from: (Data([0xFF]) + legacy).dropFirst())
+
+        XCTAssertEqual(parsed?.stealthPubkey, Data(repeating: 0xAA, count: 32))
+        XCTAssertEqual(parsed?.ephemeralPubkey, Data(repeating: 0xBB, count: 32))
+        XCTAssertEqual(parsed?.mlkemCiphertext, ciphertext)
+        XCTAssertEqual(parsed?.createdAt, 1_700_000_000)
+        XCTAssertEqual(parsed?.bump, 254)
+        XCTAssertEqual(parsed?.kemVariant, KEM_VARIANT_ML_KEM_768)
+        XCTAssertEqual(parsed?.appId, DEFAULT_APP_ID)
+        XCTAssertEqual(parsed?.isFinalized, true)
+        XCTAssertEqual(parsed?.needsMigration, true)
+        XCTAssertEqual(parsed?.isLegacy, true)
+
+        // A current-layout account of the same size isn't mistaken for a legacy one
+        var current = mockCiphertextAccount(
+            ciphertext: Data(repeating: 0xCC, count: 768),
+            kemVariant: KEM_VARIANT_ML_KEM_512
+        )
+        current.replaceSubrange(176..<208, with: Data(repeating: 0x11, count: 32))
+        let extensionsLength = CiphertextAccountData.legacyAccountSize - current.count
+        current.replaceSubrange((current.count - 4)..<current.count, with: Data([UInt8(extensionsLength), 0, 0, 0]))
+        current.append(Data(repeating: 0, count: extensionsLength))
+
+        XCTAssertEqual(current.count, CiphertextAccountData.legacyAccountSize)
+        XCTAssertEqual(CiphertextAccountData.parse(from: current)?.needsMigration, false)
+        XCTAssertEqual(CiphertextAccountData.parse(from: current)?.isLegacy, false)
+    }
+
+    func testMigrateCiphertextAccounts() throws {
+        XCTAssertEqual(StealthPQClient.buildMigrateCiphertextData().count, 8)
+
+        let client = StealthPQClient(rpcClient: SolanaRPCClient(cluster: .devnet))
+        let payer = "11111111111111111111111111111112"
+        let accounts = try client.getMigrateCiphertextAccounts(payer: payer, stealthAddress: STEALTH_PQ_PROGRAM_ID)
+        let (ciphertextPDA, _) = try client.deriveCiphertextPDA(stealthAddress: STEALTH_PQ_PROGRAM_ID)
+
+        XCTAssertEqual(accounts.count, 3)
+        XCTAssertEqual(accounts[0].pubkey, payer)
+        XCTAssertTrue(accounts[0].isSigner && accounts[0].isWritable)
+        XCTAssertEqual(accounts[1].pubkey, ciphertextPDA)
+        XCTAssertTrue(accounts[1].isWritable)
+        XCTAssertEqual(accounts[2].pubkey, SYSTEM_PROGRAM_ID)
+    }
+
+    func testBuildSetMemoData() {
+        let data = StealthPQClient.buildSetMemoData(encryptedMemo: Data(repeating: 0xAB, count: 40))
+
+        // 8 (discriminator) + 128 (zero-padded memo) + 1 (length)
+        XCTAssertEqual(data.count, 8 + MAX_MEMO_SIZE + 1)
+        XCTAssertEqual(data[8..<48], Data(repeating: 0xAB, count: 40))
+        XCTAssertEqual(data[48..<(8 + MAX_MEMO_SIZE)], Data(repeating: 0, count: MAX_MEMO_SIZE - 40))
+        XCTAssertEqual(data.last, 40)
+
+        // Oversized memos aren't truncated: the length stays out of range
+        XCTAssertEqual(StealthPQClient.buildSetMemoData(encryptedMemo: Data(count: MAX_MEMO_SIZE + 1)).last, UInt8(MAX_MEMO_SIZE + 1))
+    }
+
+    /// Zeroed CiphertextAccount data holding `ciphertext`, with an empty extension area
+    private func mockCiphertextAccount(
+        ciphertext: Data = Data(repeating: 0, count: MLKEM_CIPHERTEXT_SIZE),
+        kemVariant: UInt8 = KEM_VARIANT_ML_KEM_768
+    ) -> Data {
+        var data = Data(repeating: 0, count: 352)
+        data.replaceSubrange(0..<8, with: CiphertextAccountData.discriminator)
+        data[209] = kemVariant
+        var length = UInt32(ciphertext.count).littleEndian
+        data.replaceSubrange(348..<352, with: Data(bytes: &length, count: 4))
+        data.append(ciphertext)
+        data.append(Data(repeating: 0, count: 4))
+        return data
     }
 
     // MARK: - Network Tests (require devnet access)
@@ -269,4 +946,33 @@ final class SolanaIntegrationTests: XCTestCase {
         // Scanner should be initialized
         XCTAssertNotNil(blockchainScanner)
     }
+
+    func testBlockchainScannerFlagsReusedEphemeralKeys() {
+        func makePayment(ephemeralKey: UInt8) -> OnChainStealthPayment {
+            OnChainStealthPayment(
+                payment: DetectedStealthPayment(
+                    stealthAddress: "11111111111111111111111111111111",
+                    stealthPublicKey: Data(repeating: 0, count: 32),
+                    spendingPrivateKey: Data(repeating: 0, count: 32),
+                    ephemeralPublicKey: Data(repeating: ephemeralKey, count: 32),
+                    viewTag: 0
+                ),
+                lamports: 1,
+                ciphertextPDA: "11111111111111111111111111111111"
+            )
+        }
+
+        let flagged = BlockchainScanner.flagReusedEphemeralKeys([
+            makePayment(ephemeralKey: 1),
+            makePayment(ephemeralKey: 2),
+            makePayment(ephemeralKey: 1)
+        ])
+
+        XCTAssertEqual(flagged.map(\.warnings), [[.reusedEphemeralKey], [], [.reusedEphemeralKey]])
+    }
+
+    func testBlockchainScannerUniqueAppIds() {
+        XCTAssertEqual(BlockchainScanner.uniqueAppIds([7, DEFAULT_APP_ID, 7, 3, DEFAULT_APP_ID]), [7, DEFAULT_APP_ID, 3])
+        XCTAssertEqual(BlockchainScanner.uniqueAppIds([]), [])
+    }
 }
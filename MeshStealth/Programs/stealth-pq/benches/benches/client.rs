@@ -0,0 +1,130 @@
+use anchor_lang::prelude::Pubkey;
+use criterion::{black_box, criterion_group, criterion_main, BatchSize, Criterion, Throughput};
+use ml_kem::kem::{Decapsulate, Encapsulate};
+use ml_kem::{KemCore, MlKem768};
+use rand_core::OsRng;
+use stealth_pq_benches::{foreign_payments, message, payment_messages, PAGE_SIZE};
+use stealth_pq_client::{Payee, Payroll, StealthKeys, StealthPayment};
+
+fn mlkem(c: &mut Criterion) {
+    let (decapsulation_key, encapsulation_key) = MlKem768::generate(&mut OsRng);
+    let (ciphertext, _) = encapsulation_key.encapsulate(&mut OsRng).unwrap();
+
+    let mut group = c.benchmark_group("mlkem768");
+    group.bench_function("encapsulate", |b| {
+        b.iter(|| encapsulation_key.encapsulate(&mut OsRng).unwrap())
+    });
+    group.bench_function("decapsulate", |b| {
+        b.iter(|| {
+            decapsulation_key
+                .decapsulate(black_box(&ciphertext))
+                .unwrap()
+        })
+    });
+    group.finish();
+}
+
+fn derivation(c: &mut Criterion) {
+    let hybrid = StealthKeys::generate(&mut OsRng, true).meta_address();
+    let classical = StealthKeys::generate(&mut OsRng, false).meta_address();
+
+    let mut group = c.benchmark_group("derivation");
+    group.bench_function("hybrid", |b| {
+        b.iter(|| StealthPayment::generate(black_box(&hybrid), &mut OsRng).unwrap())
+    });
+    group.bench_function("classical", |b| {
+        b.iter(|| StealthPayment::generate(black_box(&classical), &mut OsRng).unwrap())
+    });
+    group.finish();
+}
+
+fn detection(c: &mut Criterion) {
+    let keys = StealthKeys::generate(&mut OsRng, true);
+    let own = StealthPayment::generate(&keys.meta_address(), &mut OsRng).unwrap();
+    let page = foreign_payments(PAGE_SIZE, &mut OsRng);
+
+    let mut group = c.benchmark_group("detection");
+    group.bench_function("own payment", |b| {
+        b.iter(|| {
+            keys.detect(
+                &own.stealth_address,
+                &own.ephemeral_pubkey,
+                own.mlkem_ciphertext.as_deref(),
+            )
+            .unwrap()
+            .unwrap()
+        })
+    });
+
+    // Per announcement of a page addressed to someone else
+    group.throughput(Throughput::Elements(PAGE_SIZE as u64));
+    group.bench_function("page with view tags", |b| {
+        b.iter(|| {
+            page.iter()
+                .filter(|payment| keys.view_tag(&payment.ephemeral_pubkey) == payment.view_tag)
+                .filter_map(|payment| {
+                    keys.detect(
+                        &payment.stealth_address,
+                        &payment.ephemeral_pubkey,
+                        payment.mlkem_ciphertext.as_deref(),
+                    )
+                    .unwrap()
+                })
+                .count()
+        })
+    });
+    group.bench_function("page without view tags", |b| {
+        b.iter(|| {
+            page.iter()
+                .filter_map(|payment| {
+                    keys.detect(
+                        &payment.stealth_address,
+                        &payment.ephemeral_pubkey,
+                        payment.mlkem_ciphertext.as_deref(),
+                    )
+                    .unwrap()
+                })
+                .count()
+        })
+    });
+    group.finish();
+}
+
+fn transactions(c: &mut Criterion) {
+    let sender = Pubkey::new_unique();
+    let meta_address = StealthKeys::generate(&mut OsRng, true).meta_address();
+    let roster: Vec<Payee> = (0..PAGE_SIZE as u64)
+        .map(|index| Payee {
+            meta_address: meta_address.clone(),
+            lamports: 1_000 * (index + 1),
+        })
+        .collect();
+
+    let mut group = c.benchmark_group("transactions");
+    group.bench_function("payment", |b| {
+        b.iter_batched(
+            || StealthPayment::generate(&meta_address, &mut OsRng).unwrap(),
+            |payment| payment_messages(&sender, &payment, 1_000_000),
+            BatchSize::SmallInput,
+        )
+    });
+
+    // Stealth addresses are generated up front by `Payroll::new`; this is
+    // only the announcement and transfer transactions
+    let payroll = Payroll::new(sender, &roster, &mut OsRng).unwrap();
+    group.throughput(Throughput::Elements(PAGE_SIZE as u64));
+    group.bench_function("payroll", |b| {
+        b.iter(|| {
+            let announcements = payroll.announcements(None).unwrap();
+            announcements
+                .iter()
+                .chain(&payroll.transfers(false))
+                .map(|instruction| message(&sender, instruction))
+                .collect::<Vec<_>>()
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, mlkem, derivation, detection, transactions);
+criterion_main!(benches);
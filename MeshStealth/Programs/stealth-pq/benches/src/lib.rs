@@ -0,0 +1,64 @@
+//! Fixtures for the stealth-pq client benchmarks.
+//!
+//! The benchmarks live in their own package so the workspace doesn't depend on
+//! criterion. Run them from this directory:
+//!
+//! ```text
+//! cargo bench
+//! ```
+//!
+//! Criterion keeps the last run under `target/criterion` and reports the change
+//! against it. To track results over time, save a named baseline on the
+//! reference commit and compare later runs with it:
+//!
+//! ```text
+//! cargo bench -- --save-baseline main
+//! cargo bench -- --baseline main
+//! ```
+//!
+//! HTML reports for every benchmark are written to `target/criterion/report`.
+
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::solana_program::instruction::Instruction;
+use rand_core::CryptoRngCore;
+use solana_message::Message;
+use stealth_pq_client::{instructions, StealthKeys, StealthPayment, DEFAULT_APP_ID};
+
+/// Announcements in a scanned page
+pub const PAGE_SIZE: usize = 100;
+
+/// Hybrid payments to `count` unrelated recipients, as a scan mostly sees them
+pub fn foreign_payments(count: usize, rng: &mut impl CryptoRngCore) -> Vec<StealthPayment> {
+    (0..count)
+        .map(|_| {
+            let recipient = StealthKeys::generate(rng, true);
+            StealthPayment::generate(&recipient.meta_address(), rng).unwrap()
+        })
+        .collect()
+}
+
+/// Serialized messages of a complete payment: the three announcement
+/// transactions followed by the transfer
+pub fn payment_messages(sender: &Pubkey, payment: &StealthPayment, lamports: u64) -> Vec<Vec<u8>> {
+    let instructions = [
+        instructions::init_ciphertext(sender, None, payment, DEFAULT_APP_ID, None, 0).unwrap(),
+        instructions::complete_ciphertext(sender, payment, DEFAULT_APP_ID).unwrap(),
+        instructions::finalize_ciphertext(sender, &payment.stealth_address, DEFAULT_APP_ID),
+        instructions::transfer_to_stealth(
+            sender,
+            &payment.stealth_address,
+            DEFAULT_APP_ID,
+            lamports,
+            false,
+        ),
+    ];
+    instructions
+        .iter()
+        .map(|instruction| message(sender, instruction))
+        .collect()
+}
+
+/// Serialized legacy message carrying one instruction paid for by `payer`
+pub fn message(payer: &Pubkey, instruction: &Instruction) -> Vec<u8> {
+    Message::new(std::slice::from_ref(instruction), Some(payer)).serialize()
+}
@@ -0,0 +1,121 @@
+//! Clusters to run the client against.
+//!
+//! The program has the same address on every cluster it is deployed to
+//! (`Anchor.toml`), so a [`Cluster`] only selects the RPC endpoint. A deployment
+//! at another address needs the program rebuilt with its `declare_id!`: Anchor
+//! checks account owners against the compiled-in ID, and the client takes the
+//! ID, PDAs and instruction layouts from that same program crate.
+
+use std::fmt;
+
+use solana_rpc_client::nonblocking::rpc_client::RpcClient;
+
+use crate::{Error, Result, PROGRAM_ID};
+
+/// A cluster, by name or RPC URL.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Cluster {
+    /// `solana-test-validator` on this machine
+    Localnet,
+    /// Public devnet
+    Devnet,
+    /// Public testnet, without a deployment
+    Testnet,
+    /// Mainnet beta, without a deployment
+    Mainnet,
+    /// Any other RPC endpoint
+    Custom(String),
+}
+
+impl Cluster {
+    /// RPC endpoint of the cluster
+    pub fn url(&self) -> &str {
+        match self {
+            Cluster::Localnet => "http://127.0.0.1:8899",
+            Cluster::Devnet => "https://api.devnet.solana.com",
+            Cluster::Testnet => "https://api.testnet.solana.com",
+            Cluster::Mainnet => "https://api.mainnet-beta.solana.com",
+            Cluster::Custom(url) => url,
+        }
+    }
+
+    /// Whether `Anchor.toml` deploys the program to the cluster. Custom
+    /// endpoints count as deployed; [`check_deployment`](Self::check_deployment)
+    /// asks the cluster itself.
+    pub fn is_deployed(&self) -> bool {
+        !matches!(self, Cluster::Testnet | Cluster::Mainnet)
+    }
+
+    /// RPC client for the cluster
+    pub fn rpc(&self) -> RpcClient {
+        RpcClient::new(self.url().to_string())
+    }
+
+    /// Fail with [`Error::ProgramNotDeployed`] unless the cluster holds an
+    /// executable account at [`PROGRAM_ID`].
+    pub async fn check_deployment(&self, rpc: &RpcClient) -> Result<()> {
+        match rpc
+            .get_account_with_commitment(&PROGRAM_ID, rpc.commitment())
+            .await?
+            .value
+        {
+            Some(account) if account.executable => Ok(()),
+            _ => Err(Error::ProgramNotDeployed(self.to_string())),
+        }
+    }
+}
+
+/// Parses `localnet`, `devnet`, `testnet` and `mainnet` (or `mainnet-beta`);
+/// anything else is taken as a URL.
+impl From<&str> for Cluster {
+    fn from(value: &str) -> Self {
+        match value {
+            "localnet" => Cluster::Localnet,
+            "devnet" => Cluster::Devnet,
+            "testnet" => Cluster::Testnet,
+            "mainnet" | "mainnet-beta" => Cluster::Mainnet,
+            url => Cluster::Custom(url.to_string()),
+        }
+    }
+}
+
+impl fmt::Display for Cluster {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Cluster::Localnet => f.write_str("localnet"),
+            Cluster::Devnet => f.write_str("devnet"),
+            Cluster::Testnet => f.write_str("testnet"),
+            Cluster::Mainnet => f.write_str("mainnet"),
+            Cluster::Custom(url) => f.write_str(url),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::executor::block_on;
+
+    #[test]
+    fn test_cluster_from_name_or_url() {
+        assert_eq!(Cluster::from("devnet"), Cluster::Devnet);
+        assert_eq!(Cluster::from("mainnet-beta"), Cluster::Mainnet);
+        assert_eq!(Cluster::from("localnet").url(), "http://127.0.0.1:8899");
+
+        let custom = Cluster::from("http://rpc.example:8899");
+        assert_eq!(custom.url(), "http://rpc.example:8899");
+        assert_eq!(Cluster::from(custom.to_string().as_str()), custom);
+        assert!(custom.is_deployed());
+        assert!(!Cluster::Mainnet.is_deployed());
+    }
+
+    #[test]
+    fn test_check_deployment_without_program() {
+        // The mock cluster has no accounts
+        let rpc = RpcClient::new_mock("succeeds".to_string());
+        assert!(matches!(
+            block_on(Cluster::Devnet.check_deployment(&rpc)),
+            Err(Error::ProgramNotDeployed(cluster)) if cluster == "devnet"
+        ));
+    }
+}
@@ -0,0 +1,177 @@
+//! Health-checked randomness for key generation.
+//!
+//! [`CheckedRng`] draws a block of bytes from the system RNG and runs the two
+//! continuous health tests of NIST SP 800-90B (section 4.4) over it, taking
+//! each byte as a sample with full entropy:
+//!
+//! - repetition count: no byte repeated [`REPETITION_CUTOFF`] times in a row
+//! - adaptive proportion: no byte appearing [`PROPORTION_CUTOFF`] times in a
+//!   window of [`PROPORTION_WINDOW`] samples starting with it
+//!
+//! Both cutoffs give a false alarm rate of about 2^-20 per sample. The tests
+//! catch a stuck or badly broken source, not a subtly weak one, so the block is
+//! hashed together with caller-supplied entropy (dice rolls, a passphrase) and
+//! keys are drawn from SHA-512 over that seed: a failure of either source alone
+//! doesn't make the keys predictable.
+
+use rand_core::{CryptoRng, CryptoRngCore, RngCore};
+use sha2::{Digest, Sha512};
+
+use crate::{Error, Result};
+
+/// Samples drawn and tested before any output; SP 800-90B asks for at least 1024
+pub const STARTUP_SAMPLES: usize = 1024;
+
+/// Repetition count cutoff for 8 bits of entropy per byte: 1 + ceil(20 / 8)
+pub const REPETITION_CUTOFF: usize = 4;
+
+/// Adaptive proportion window for non-binary samples
+pub const PROPORTION_WINDOW: usize = 512;
+
+/// Adaptive proportion cutoff for 8 bits of entropy per byte and a window of 512
+pub const PROPORTION_CUTOFF: usize = 13;
+
+/// A generator seeded from health-checked system randomness and caller entropy.
+///
+/// Build a new one for every key generation, so every generation runs the
+/// startup tests again.
+pub struct CheckedRng {
+    seed: [u8; 64],
+    counter: u64,
+    block: [u8; 64],
+    position: usize,
+}
+
+impl CheckedRng {
+    /// Test `STARTUP_SAMPLES` bytes of `rng` and seed from them and
+    /// `user_entropy` (which may be empty).
+    ///
+    /// Fails with [`Error::EntropyHealthCheck`] if the bytes fail either test
+    /// or `rng` reports an error.
+    pub fn new(rng: &mut impl CryptoRngCore, user_entropy: &[u8]) -> Result<Self> {
+        let mut samples = [0u8; STARTUP_SAMPLES];
+        rng.try_fill_bytes(&mut samples)
+            .map_err(|_| Error::EntropyHealthCheck)?;
+        if !repetition_count_ok(&samples) || !adaptive_proportion_ok(&samples) {
+            return Err(Error::EntropyHealthCheck);
+        }
+
+        Ok(Self {
+            seed: Sha512::new()
+                .chain_update(samples)
+                .chain_update((user_entropy.len() as u64).to_le_bytes())
+                .chain_update(user_entropy)
+                .finalize()
+                .into(),
+            counter: 0,
+            block: [0; 64],
+            position: 64,
+        })
+    }
+}
+
+impl RngCore for CheckedRng {
+    fn next_u32(&mut self) -> u32 {
+        rand_core::impls::next_u32_via_fill(self)
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        rand_core::impls::next_u64_via_fill(self)
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        for byte in dest {
+            if self.position == self.block.len() {
+                self.block = Sha512::new()
+                    .chain_update(self.seed)
+                    .chain_update(self.counter.to_le_bytes())
+                    .finalize()
+                    .into();
+                self.counter += 1;
+                self.position = 0;
+            }
+            *byte = self.block[self.position];
+            self.position += 1;
+        }
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> std::result::Result<(), rand_core::Error> {
+        self.fill_bytes(dest);
+        Ok(())
+    }
+}
+
+impl CryptoRng for CheckedRng {}
+
+fn repetition_count_ok(samples: &[u8]) -> bool {
+    samples
+        .chunk_by(|a, b| a == b)
+        .all(|run| run.len() < REPETITION_CUTOFF)
+}
+
+fn adaptive_proportion_ok(samples: &[u8]) -> bool {
+    samples.chunks(PROPORTION_WINDOW).all(|window| {
+        window.iter().filter(|&&sample| sample == window[0]).count() < PROPORTION_CUTOFF
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::StealthKeys;
+    use rand_core::OsRng;
+
+    /// Bytes 0, 1, 2, ... with a stride: passes both tests, and is predictable
+    struct Counter(u8, u8);
+
+    impl RngCore for Counter {
+        fn next_u32(&mut self) -> u32 {
+            rand_core::impls::next_u32_via_fill(self)
+        }
+
+        fn next_u64(&mut self) -> u64 {
+            rand_core::impls::next_u64_via_fill(self)
+        }
+
+        fn fill_bytes(&mut self, dest: &mut [u8]) {
+            for byte in dest {
+                *byte = self.0;
+                self.0 = self.0.wrapping_add(self.1);
+            }
+        }
+
+        fn try_fill_bytes(&mut self, dest: &mut [u8]) -> std::result::Result<(), rand_core::Error> {
+            self.fill_bytes(dest);
+            Ok(())
+        }
+    }
+
+    impl CryptoRng for Counter {}
+
+    #[test]
+    fn test_health_checks_reject_broken_sources() {
+        assert!(CheckedRng::new(&mut OsRng, &[]).is_ok());
+
+        // Stuck at one value
+        assert!(matches!(
+            CheckedRng::new(&mut Counter(7, 0), &[]),
+            Err(Error::EntropyHealthCheck)
+        ));
+        // Cycling through 16 values: no repeats, but each one 32 times a window
+        assert!(matches!(
+            CheckedRng::new(&mut Counter(0, 16), &[]),
+            Err(Error::EntropyHealthCheck)
+        ));
+    }
+
+    #[test]
+    fn test_user_entropy_is_mixed_in() {
+        let keys = |user_entropy: &[u8]| {
+            StealthKeys::generate_with_entropy(&mut Counter(0, 1), false, user_entropy)
+                .unwrap()
+                .meta_address()
+        };
+        assert_eq!(keys(b""), keys(b""));
+        assert_ne!(keys(b""), keys(b"4 6 1 3 3 5 2"));
+    }
+}
@@ -0,0 +1,72 @@
+use anchor_lang::prelude::Pubkey;
+use solana_rpc_client_api::client_error::Error as ClientError;
+use thiserror::Error;
+
+/// Errors returned by the client
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("invalid stealth meta-address")]
+    InvalidMetaAddress,
+
+    #[error("invalid key material")]
+    InvalidKey,
+
+    #[error("the entropy source failed its health checks")]
+    EntropyHealthCheck,
+
+    #[error("missing or malformed ML-KEM ciphertext")]
+    InvalidCiphertext,
+
+    #[error("memo exceeds the maximum memo size")]
+    InvalidMemo,
+
+    #[error("payroll roster is empty or pays zero to a payee")]
+    InvalidPayroll,
+
+    #[error("malformed or wrongly signed payment request")]
+    InvalidPaymentRequest,
+
+    #[error("payment request expired at {0}")]
+    PaymentRequestExpired(i64),
+
+    #[error("malformed or wrongly signed partial transaction")]
+    InvalidPartialTransaction,
+
+    #[error("misread fountain frame, or one from another payload")]
+    InvalidFrame,
+
+    #[error("transaction is missing {0} signatures")]
+    MissingSignatures(usize),
+
+    #[error("the payment flow takes {0} durable nonces, one per step")]
+    WrongNonceCount(usize),
+
+    #[error("derived stealth public key is not a valid point")]
+    InvalidPoint,
+
+    #[error("announcement {announcement_index} uses unsupported ML-KEM variant {kem_variant}")]
+    UnsupportedKemVariant {
+        announcement_index: u64,
+        kem_variant: u8,
+    },
+
+    #[error("account {0} could not be decoded")]
+    AccountDecode(Pubkey),
+
+    #[error("the program is not deployed on {0}")]
+    ProgramNotDeployed(String),
+
+    #[error("transaction {0} was not confirmed in time")]
+    Unconfirmed(String),
+
+    #[error("RPC request failed: {0}")]
+    Rpc(Box<ClientError>),
+}
+
+impl From<ClientError> for Error {
+    fn from(err: ClientError) -> Self {
+        Error::Rpc(Box::new(err))
+    }
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
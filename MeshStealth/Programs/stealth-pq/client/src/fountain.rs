@@ -0,0 +1,363 @@
+//! Fountain-coded frames, for moving a payload through an animated QR code.
+//!
+//! A [`PartialTransaction`](crate::PartialTransaction) for a hybrid payment is
+//! over 1.2 KB, more than a QR code a phone camera reads reliably. The
+//! [`FountainEncoder`] splits the payload into fragments and emits an endless
+//! sequence of frames: the first `fragment count` frames carry one fragment
+//! each, later ones the XOR of a pseudo-random set of fragments chosen from the
+//! frame number. The [`FountainDecoder`] takes frames in any order, ignores
+//! repeats, and finishes once it has seen about as many useful frames as there
+//! are fragments, whichever ones those were. Scanning can stop and pick up
+//! again with the same decoder. Frame layout, integers little-endian:
+//!
+//! ```text
+//! version (1) = 1
+//! sequence number (4), from 1
+//! fragment count (2)
+//! payload length (4)
+//! payload checksum (4), the first bytes of SHA-256 over the payload
+//! fragment (payload length / fragment count, rounded up; the last one zero-padded)
+//! frame checksum (4), the first bytes of SHA-256 over everything before it
+//! ```
+
+use sha2::{Digest, Sha256};
+
+use crate::{Error, Result};
+
+/// Frame version written by [`FountainEncoder::frame`]
+pub const FOUNTAIN_FRAME_VERSION: u8 = 1;
+
+/// Fragment size that keeps a frame within a QR code phones read at a glance
+pub const DEFAULT_FRAGMENT_SIZE: usize = 200;
+
+/// Bytes a frame adds to its fragment
+pub const FRAME_OVERHEAD: usize = 1 + 4 + 2 + 4 + 4 + 4;
+
+/// Splits a payload into an endless sequence of frames.
+pub struct FountainEncoder {
+    checksum: [u8; 4],
+    payload_len: usize,
+    fragments: Vec<Vec<u8>>,
+}
+
+impl FountainEncoder {
+    /// Split `payload` into fragments of at most `fragment_size` bytes.
+    ///
+    /// Fails with [`Error::InvalidFrame`] for an empty payload or one that
+    /// needs more than `u16::MAX` fragments.
+    pub fn new(payload: &[u8], fragment_size: usize) -> Result<Self> {
+        if payload.is_empty() || fragment_size == 0 || payload.len() > u32::MAX as usize {
+            return Err(Error::InvalidFrame);
+        }
+        let count = payload.len().div_ceil(fragment_size);
+        if count > u16::MAX as usize {
+            return Err(Error::InvalidFrame);
+        }
+        // Spread the payload evenly, so the padding stays under one byte per fragment
+        let fragment_size = payload.len().div_ceil(count);
+        let fragments = payload
+            .chunks(fragment_size)
+            .map(|chunk| {
+                let mut fragment = chunk.to_vec();
+                fragment.resize(fragment_size, 0);
+                fragment
+            })
+            .collect();
+
+        Ok(Self {
+            checksum: checksum(payload),
+            payload_len: payload.len(),
+            fragments,
+        })
+    }
+
+    /// Number of fragments; a receiver needs at least this many frames
+    pub fn fragment_count(&self) -> usize {
+        self.fragments.len()
+    }
+
+    /// Frame `seq`, counting from 1. Show frames in increasing order: each one
+    /// past `fragment_count` mixes a fresh set, so a receiver that missed some
+    /// catches up without waiting for a repeat.
+    pub fn frame(&self, seq: u32) -> Vec<u8> {
+        let mut fragment = vec![0u8; self.fragments[0].len()];
+        for index in mix(seq, self.fragments.len(), &self.checksum) {
+            xor_into(&mut fragment, &self.fragments[index]);
+        }
+
+        let mut frame = Vec::with_capacity(FRAME_OVERHEAD + fragment.len());
+        frame.push(FOUNTAIN_FRAME_VERSION);
+        frame.extend_from_slice(&seq.to_le_bytes());
+        frame.extend_from_slice(&(self.fragments.len() as u16).to_le_bytes());
+        frame.extend_from_slice(&(self.payload_len as u32).to_le_bytes());
+        frame.extend_from_slice(&self.checksum);
+        frame.extend_from_slice(&fragment);
+        let frame_checksum = checksum(&frame);
+        frame.extend_from_slice(&frame_checksum);
+        frame
+    }
+}
+
+/// Reassembles a payload from frames of a [`FountainEncoder`].
+#[derive(Clone, Debug, Default)]
+pub struct FountainDecoder {
+    /// Payload checksum, payload length and fragment size of the first frame
+    header: Option<([u8; 4], usize, usize)>,
+    fragments: Vec<Option<Vec<u8>>>,
+    /// Frames still mixing several unknown fragments
+    pending: Vec<(Vec<usize>, Vec<u8>)>,
+}
+
+impl FountainDecoder {
+    /// Take in a frame.
+    ///
+    /// Fails with [`Error::InvalidFrame`] for a misread frame or one from
+    /// another payload; the frames taken so far are kept either way.
+    pub fn receive(&mut self, frame: &[u8]) -> Result<()> {
+        if frame.len() <= FRAME_OVERHEAD || frame[0] != FOUNTAIN_FRAME_VERSION {
+            return Err(Error::InvalidFrame);
+        }
+        let (body, frame_checksum) = frame.split_at(frame.len() - 4);
+        if checksum(body) != frame_checksum {
+            return Err(Error::InvalidFrame);
+        }
+        let seq = u32::from_le_bytes(body[1..5].try_into().unwrap());
+        let count = u16::from_le_bytes(body[5..7].try_into().unwrap()) as usize;
+        let payload_len = u32::from_le_bytes(body[7..11].try_into().unwrap()) as usize;
+        let payload_checksum: [u8; 4] = body[11..15].try_into().unwrap();
+        let fragment = &body[15..];
+        if count == 0 || fragment.len() * count < payload_len {
+            return Err(Error::InvalidFrame);
+        }
+
+        let header = (payload_checksum, payload_len, fragment.len());
+        match self.header {
+            None => {
+                self.header = Some(header);
+                self.fragments = vec![None; count];
+            }
+            Some(known) => {
+                if known != header || count != self.fragments.len() {
+                    return Err(Error::InvalidFrame);
+                }
+            }
+        }
+
+        self.add(mix(seq, count, &payload_checksum), fragment.to_vec());
+        Ok(())
+    }
+
+    /// Fragments recovered and fragments in the payload, for a progress bar.
+    /// Both are 0 until the first frame arrives.
+    pub fn progress(&self) -> (usize, usize) {
+        let recovered = self.fragments.iter().filter(|f| f.is_some()).count();
+        (recovered, self.fragments.len())
+    }
+
+    /// Whether every fragment has been recovered
+    pub fn is_complete(&self) -> bool {
+        !self.fragments.is_empty() && self.fragments.iter().all(Option::is_some)
+    }
+
+    /// The payload, once complete.
+    ///
+    /// # Returns
+    /// `None` while fragments are missing. Fails with [`Error::InvalidFrame`] if
+    /// the reassembled payload doesn't match its checksum.
+    pub fn payload(&self) -> Result<Option<Vec<u8>>> {
+        let Some((payload_checksum, payload_len, _)) = self.header else {
+            return Ok(None);
+        };
+        if !self.is_complete() {
+            return Ok(None);
+        }
+        let mut payload: Vec<u8> = self.fragments.iter().flatten().flatten().copied().collect();
+        payload.truncate(payload_len);
+        if checksum(&payload) != payload_checksum {
+            return Err(Error::InvalidFrame);
+        }
+        Ok(Some(payload))
+    }
+
+    /// Peel known fragments out of a mixed frame, and every newly recovered
+    /// fragment out of the pending ones
+    fn add(&mut self, indexes: Vec<usize>, mixed: Vec<u8>) {
+        let mut queue = vec![(indexes, mixed)];
+        while let Some((mut indexes, mut mixed)) = queue.pop() {
+            indexes.retain(|&index| match &self.fragments[index] {
+                Some(fragment) => {
+                    xor_into(&mut mixed, fragment);
+                    false
+                }
+                None => true,
+            });
+
+            match indexes.as_slice() {
+                [] => {}
+                [index] => {
+                    self.fragments[*index] = Some(mixed);
+                    let index = *index;
+                    let (resolved, pending) = std::mem::take(&mut self.pending)
+                        .into_iter()
+                        .partition(|(indexes, _)| indexes.contains(&index));
+                    self.pending = pending;
+                    queue.extend(resolved);
+                }
+                _ => {
+                    if !self.pending.iter().any(|(known, _)| *known == indexes) {
+                        self.pending.push((indexes, mixed));
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Fragments mixed into frame `seq`: fragment `seq - 1` for the first `count`
+/// frames, then a set whose size follows the ideal soliton distribution (size
+/// d with probability about 1/d), seeded by the payload checksum and `seq`
+fn mix(seq: u32, count: usize, payload_checksum: &[u8; 4]) -> Vec<usize> {
+    if (1..=count as u64).contains(&(seq as u64)) {
+        return vec![seq as usize - 1];
+    }
+
+    let mut rng = HashStream::new(payload_checksum, seq);
+    let weights: Vec<f64> = (1..=count).map(|degree| 1.0 / degree as f64).collect();
+    let mut target = rng.next_unit() * weights.iter().sum::<f64>();
+    let mut degree = count;
+    for (index, weight) in weights.iter().enumerate() {
+        if target < *weight {
+            degree = index + 1;
+            break;
+        }
+        target -= weight;
+    }
+
+    // The first `degree` entries of a seeded shuffle
+    let mut indexes: Vec<usize> = (0..count).collect();
+    for position in 0..degree {
+        let pick = position + rng.next_u32() as usize % (count - position);
+        indexes.swap(position, pick);
+    }
+    indexes.truncate(degree);
+    indexes.sort_unstable();
+    indexes
+}
+
+/// SHA-256 in counter mode, so both sides draw the same numbers for a frame
+struct HashStream {
+    seed: [u8; 32],
+    counter: u32,
+    block: [u8; 32],
+    position: usize,
+}
+
+impl HashStream {
+    fn new(payload_checksum: &[u8; 4], seq: u32) -> Self {
+        Self {
+            seed: Sha256::new()
+                .chain_update(payload_checksum)
+                .chain_update(seq.to_le_bytes())
+                .finalize()
+                .into(),
+            counter: 0,
+            block: [0; 32],
+            position: 32,
+        }
+    }
+
+    fn next_u32(&mut self) -> u32 {
+        if self.position == 32 {
+            self.block = Sha256::new()
+                .chain_update(self.seed)
+                .chain_update(self.counter.to_le_bytes())
+                .finalize()
+                .into();
+            self.counter += 1;
+            self.position = 0;
+        }
+        let value = u32::from_le_bytes(
+            self.block[self.position..self.position + 4]
+                .try_into()
+                .unwrap(),
+        );
+        self.position += 4;
+        value
+    }
+
+    /// Uniform in [0, 1)
+    fn next_unit(&mut self) -> f64 {
+        self.next_u32() as f64 / (u32::MAX as f64 + 1.0)
+    }
+}
+
+fn checksum(data: &[u8]) -> [u8; 4] {
+    Sha256::digest(data)[..4].try_into().unwrap()
+}
+
+fn xor_into(target: &mut [u8], fragment: &[u8]) {
+    for (byte, other) in target.iter_mut().zip(fragment) {
+        *byte ^= other;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn payload() -> Vec<u8> {
+        (0..3_000u32).map(|i| (i * 7 + i / 256) as u8).collect()
+    }
+
+    #[test]
+    fn test_decoder_recovers_from_lost_frames() {
+        let payload = payload();
+        let encoder = FountainEncoder::new(&payload, DEFAULT_FRAGMENT_SIZE).unwrap();
+        let count = encoder.fragment_count();
+        assert_eq!(count, 15);
+        assert_eq!(
+            encoder.frame(1).len(),
+            FRAME_OVERHEAD + DEFAULT_FRAGMENT_SIZE
+        );
+
+        // Every third frame is missed and scanning starts partway through
+        let mut decoder = FountainDecoder::default();
+        let mut seq = 5;
+        while !decoder.is_complete() {
+            assert!((seq as usize) < 5 * count, "decoder stalled");
+            if seq % 3 != 0 {
+                decoder.receive(&encoder.frame(seq)).unwrap();
+                // Repeats change nothing
+                decoder.receive(&encoder.frame(seq)).unwrap();
+            }
+            seq += 1;
+        }
+        assert_eq!(decoder.progress(), (count, count));
+        assert_eq!(decoder.payload().unwrap(), Some(payload));
+    }
+
+    #[test]
+    fn test_invalid_frames_are_rejected() {
+        let encoder = FountainEncoder::new(&payload(), DEFAULT_FRAGMENT_SIZE).unwrap();
+        let mut decoder = FountainDecoder::default();
+        decoder.receive(&encoder.frame(1)).unwrap();
+        assert_eq!(decoder.payload().unwrap(), None);
+
+        let mut misread = encoder.frame(2);
+        misread[20] ^= 1;
+        assert!(matches!(
+            decoder.receive(&misread),
+            Err(Error::InvalidFrame)
+        ));
+
+        // A frame of another payload doesn't mix in
+        let other = FountainEncoder::new(&[1u8; 3_000], DEFAULT_FRAGMENT_SIZE).unwrap();
+        assert!(matches!(
+            decoder.receive(&other.frame(2)),
+            Err(Error::InvalidFrame)
+        ));
+        assert_eq!(decoder.progress(), (1, 15));
+
+        assert!(FountainEncoder::new(&[], DEFAULT_FRAGMENT_SIZE).is_err());
+    }
+}
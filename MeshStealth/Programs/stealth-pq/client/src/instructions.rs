@@ -0,0 +1,845 @@
+//! Instruction builders.
+//!
+//! A hybrid payment doesn't fit in one transaction, so it is sent as:
+//! 1. [`init_ciphertext`], which creates the CiphertextAccount with the first chunk
+//! 2. [`complete_ciphertext`], optionally [`set_memo`], and [`finalize_ciphertext`]
+//! 3. [`transfer_to_stealth`], or [`batch_transfer_to_stealth`] for several payments
+//!
+//! [`log_announcement`] then adds the payment to the announcement log, where the
+//! [`Scanner`](crate::Scanner) finds it. Recipients publish their meta-address
+//! with [`register_meta_address`], and can link an EVM stealth meta-address to
+//! it with [`attest_evm_meta_address`].
+//!
+//! The recipient later closes the account with [`reclaim_rent`], signed with the
+//! stealth address's [`SpendingKey`](crate::SpendingKey).
+//!
+//! Payments are always ML-KEM-768, the only parameter set a meta-address carries
+//! a key for; the builders reject ciphertexts of any other size. Announcements
+//! made with other parameter sets are reported by the
+//! [`Scanner`](crate::Scanner) as unsupported.
+
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::solana_program::instruction::{AccountMeta, Instruction};
+use anchor_lang::solana_program::sysvar::instructions as instructions_sysvar;
+use anchor_lang::{system_program, InstructionData, ToAccountMetas};
+use solana_sdk_ids::secp256k1_program;
+use stealth_pq::{
+    accounts, instruction, DataChunk, DEFAULT_APP_ID, EVM_META_ADDRESS_SIZE,
+    KEM_VARIANT_ML_KEM_768, MAX_CHUNK_SIZE, MAX_MEMO_SIZE,
+};
+
+use crate::{pda, Error, MetaAddress, Result, StealthPayment};
+
+/// `init_ciphertext` for a hybrid payment, storing the ephemeral key, view tag and
+/// first ciphertext chunk.
+///
+/// # Arguments
+/// * `sender` - Sender making the payment (signer, and rent payer if there's no other)
+/// * `rent_payer` - Sponsor paying rent for the new accounts instead of the sender (signer)
+/// * `payment` - Stealth address generated for the recipient
+/// * `app_id` - Integrator namespace (`DEFAULT_APP_ID` if none)
+/// * `expires_at` - Optional Unix timestamp after which the sender may refund the rent
+/// * `rent_payer_share_bps` - Share of the rent returned to the rent payer on close
+pub fn init_ciphertext(
+    sender: &Pubkey,
+    rent_payer: Option<Pubkey>,
+    payment: &StealthPayment,
+    app_id: u32,
+    expires_at: Option<i64>,
+    rent_payer_share_bps: u16,
+) -> Result<Instruction> {
+    let ciphertext = hybrid_ciphertext(payment)?;
+
+    Ok(Instruction {
+        program_id: stealth_pq::ID,
+        accounts: accounts::StealthTransfer {
+            sender: *sender,
+            rent_payer,
+            stealth_address: payment.stealth_address,
+            ciphertext_account: pda::ciphertext_account(&payment.stealth_address, app_id).0,
+            namespace_counter: pda::namespace_counter(app_id).0,
+            system_program: system_program::ID,
+            instructions_sysvar: None,
+        }
+        .to_account_metas(None),
+        data: instruction::InitCiphertext {
+            ephemeral_pubkey: payment.ephemeral_pubkey,
+            ciphertext_part1: chunk(&ciphertext[..MAX_CHUNK_SIZE]),
+            expires_at,
+            app_id,
+            rent_payer_share_bps,
+            view_tag: payment.view_tag,
+            kem_variant: KEM_VARIANT_ML_KEM_768,
+        }
+        .data(),
+    })
+}
+
+/// `complete_ciphertext` writing the rest of the payment's ciphertext.
+pub fn complete_ciphertext(
+    sender: &Pubkey,
+    payment: &StealthPayment,
+    app_id: u32,
+) -> Result<Instruction> {
+    let ciphertext = hybrid_ciphertext(payment)?;
+
+    Ok(Instruction {
+        program_id: stealth_pq::ID,
+        accounts: writer_accounts(sender, &payment.stealth_address, app_id),
+        data: instruction::CompleteCiphertext {
+            ciphertext_part2: chunk(&ciphertext[MAX_CHUNK_SIZE..]),
+            offset: MAX_CHUNK_SIZE as u16,
+        }
+        .data(),
+    })
+}
+
+/// `set_memo` attaching a memo encrypted to the payment's shared secret.
+///
+/// Must be sent before [`finalize_ciphertext`]. The memo is at most `MAX_MEMO_SIZE` bytes.
+pub fn set_memo(
+    sender: &Pubkey,
+    stealth_address: &Pubkey,
+    app_id: u32,
+    encrypted_memo: &[u8],
+) -> Result<Instruction> {
+    if encrypted_memo.len() > MAX_MEMO_SIZE {
+        return Err(Error::InvalidMemo);
+    }
+
+    let mut padded = [0u8; MAX_MEMO_SIZE];
+    padded[..encrypted_memo.len()].copy_from_slice(encrypted_memo);
+
+    Ok(Instruction {
+        program_id: stealth_pq::ID,
+        accounts: writer_accounts(sender, stealth_address, app_id),
+        data: instruction::SetMemo {
+            encrypted_memo: padded,
+            len: encrypted_memo.len() as u8,
+        }
+        .data(),
+    })
+}
+
+/// `finalize_ciphertext`, after which the account accepts transfers and no more writes.
+pub fn finalize_ciphertext(sender: &Pubkey, stealth_address: &Pubkey, app_id: u32) -> Instruction {
+    Instruction {
+        program_id: stealth_pq::ID,
+        accounts: writer_accounts(sender, stealth_address, app_id),
+        data: instruction::FinalizeCiphertext.data(),
+    }
+}
+
+/// `transfer_to_stealth` of `lamports` to a finalized stealth address.
+///
+/// Pass `count_in_stats` to include the transfer in the global statistics.
+pub fn transfer_to_stealth(
+    sender: &Pubkey,
+    stealth_address: &Pubkey,
+    app_id: u32,
+    lamports: u64,
+    count_in_stats: bool,
+) -> Instruction {
+    Instruction {
+        program_id: stealth_pq::ID,
+        accounts: accounts::TransferToStealth {
+            sender: *sender,
+            stealth_address: *stealth_address,
+            ciphertext_account: pda::ciphertext_account(stealth_address, app_id).0,
+            system_program: system_program::ID,
+            stats: count_in_stats.then(|| pda::stats().0),
+        }
+        .to_account_metas(None),
+        data: instruction::TransferToStealth { lamports }.data(),
+    }
+}
+
+/// `batch_transfer_to_stealth` paying several finalized stealth addresses at once.
+///
+/// `payments` holds `(stealth_address, lamports)` pairs, all announced in `app_id`.
+/// The batch is all-or-nothing; keep it small enough to fit in one transaction.
+pub fn batch_transfer_to_stealth(
+    sender: &Pubkey,
+    payments: &[(Pubkey, u64)],
+    app_id: u32,
+    count_in_stats: bool,
+) -> Instruction {
+    let mut accounts = accounts::BatchTransferToStealth {
+        sender: *sender,
+        system_program: system_program::ID,
+        stats: count_in_stats.then(|| pda::stats().0),
+    }
+    .to_account_metas(None);
+    for (stealth_address, _) in payments {
+        accounts.push(AccountMeta::new(*stealth_address, false));
+        accounts.push(AccountMeta::new_readonly(
+            pda::ciphertext_account(stealth_address, app_id).0,
+            false,
+        ));
+    }
+
+    Instruction {
+        program_id: stealth_pq::ID,
+        accounts,
+        data: instruction::BatchTransferToStealth {
+            amounts: payments.iter().map(|(_, lamports)| *lamports).collect(),
+        }
+        .data(),
+    }
+}
+
+/// `reclaim_rent`, closing the CiphertextAccount and returning its rent to the
+/// stealth address.
+///
+/// # Arguments
+/// * `stealth_address` - Stealth address (signer, with its spending key)
+/// * `app_id` - Namespace the payment was announced in
+/// * `rent_payer` - Rent payer to return its share to, if a share was set
+/// * `announcement` - Announcement log entry to close as well, if one was logged
+pub fn reclaim_rent(
+    stealth_address: &Pubkey,
+    app_id: u32,
+    rent_payer: Option<Pubkey>,
+    announcement: Option<Pubkey>,
+) -> Instruction {
+    Instruction {
+        program_id: stealth_pq::ID,
+        accounts: accounts::ReclaimRent {
+            stealth_signer: *stealth_address,
+            ciphertext_account: pda::ciphertext_account(stealth_address, app_id).0,
+            rent_payer,
+            announcement,
+        }
+        .to_account_metas(None),
+        data: instruction::ReclaimRent.data(),
+    }
+}
+
+/// `migrate_ciphertext`, rewriting a CiphertextAccount created in the legacy
+/// layout so that `reclaim_rent` and `refund_expired` can close it.
+///
+/// Legacy accounts were always in the default namespace.
+pub fn migrate_ciphertext(payer: &Pubkey, stealth_address: &Pubkey) -> Instruction {
+    Instruction {
+        program_id: stealth_pq::ID,
+        accounts: accounts::MigrateCiphertext {
+            payer: *payer,
+            ciphertext_account: pda::ciphertext_account(stealth_address, DEFAULT_APP_ID).0,
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: instruction::MigrateCiphertext.data(),
+    }
+}
+
+/// `init_announcement_log`, creating the global announcement log. Needed once per cluster.
+pub fn init_announcement_log(payer: &Pubkey) -> Instruction {
+    Instruction {
+        program_id: stealth_pq::ID,
+        accounts: accounts::InitAnnouncementLog {
+            payer: *payer,
+            announcement_log: pda::announcement_log().0,
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: instruction::InitAnnouncementLog.data(),
+    }
+}
+
+/// `log_announcement`, appending a finalized payment to the announcement log.
+///
+/// `index` is the log's current `count`, where the entry is created; if another
+/// announcement is logged first the instruction fails and must be rebuilt.
+pub fn log_announcement(
+    sender: &Pubkey,
+    stealth_address: &Pubkey,
+    app_id: u32,
+    index: u64,
+) -> Instruction {
+    Instruction {
+        program_id: stealth_pq::ID,
+        accounts: accounts::LogAnnouncement {
+            sender: *sender,
+            ciphertext_account: pda::ciphertext_account(stealth_address, app_id).0,
+            announcement_log: pda::announcement_log().0,
+            announcement: pda::announcement(index).0,
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: instruction::LogAnnouncement.data(),
+    }
+}
+
+/// `register_meta_address` publishing `meta_address` for `owner`, followed for a
+/// hybrid meta-address by the `write_meta_address_key` chunks of its ML-KEM key.
+///
+/// Send each instruction in its own transaction, in order. Senders resolving the
+/// entry before the last chunk lands see an incomplete key.
+pub fn register_meta_address(owner: &Pubkey, meta_address: &MetaAddress) -> Vec<Instruction> {
+    let mut instructions = vec![Instruction {
+        program_id: stealth_pq::ID,
+        accounts: accounts::RegisterMetaAddress {
+            owner: *owner,
+            meta_address: pda::meta_address(owner).0,
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: instruction::RegisterMetaAddress {
+            spending_pubkey: meta_address.spending_public_key,
+            viewing_pubkey: meta_address.viewing_public_key,
+        }
+        .data(),
+    }];
+    instructions.extend(write_meta_address_key(owner, meta_address));
+    instructions
+}
+
+/// `update_meta_address` replacing `owner`'s registered keys with
+/// `meta_address`, followed for a hybrid meta-address by the
+/// `write_meta_address_key` chunks of its ML-KEM key.
+///
+/// Send each instruction in its own transaction, in order. Keep scanning the
+/// replaced keys with [`Scanner::previous_keys`](crate::Scanner::previous_keys)
+/// until senders have picked up the new entry.
+pub fn update_meta_address(owner: &Pubkey, meta_address: &MetaAddress) -> Vec<Instruction> {
+    let mut instructions = vec![Instruction {
+        program_id: stealth_pq::ID,
+        accounts: accounts::UpdateMetaAddress {
+            owner: *owner,
+            meta_address: pda::meta_address(owner).0,
+        }
+        .to_account_metas(None),
+        data: instruction::UpdateMetaAddress {
+            spending_pubkey: meta_address.spending_public_key,
+            viewing_pubkey: meta_address.viewing_public_key,
+        }
+        .data(),
+    }];
+    instructions.extend(write_meta_address_key(owner, meta_address));
+    instructions
+}
+
+/// `close_meta_address` removing `owner`'s registry entry and returning its
+/// rent to `owner`
+pub fn close_meta_address(owner: &Pubkey) -> Instruction {
+    Instruction {
+        program_id: stealth_pq::ID,
+        accounts: accounts::CloseMetaAddress {
+            owner: *owner,
+            meta_address: pda::meta_address(owner).0,
+        }
+        .to_account_metas(None),
+        data: instruction::CloseMetaAddress.data(),
+    }
+}
+
+/// The `write_meta_address_key` chunks uploading `meta_address`'s ML-KEM key,
+/// none for a classical meta-address
+fn write_meta_address_key(owner: &Pubkey, meta_address: &MetaAddress) -> Vec<Instruction> {
+    let registry = pda::meta_address(owner).0;
+    let Some(mlkem_public_key) = &meta_address.mlkem_public_key else {
+        return Vec::new();
+    };
+    mlkem_public_key
+        .chunks(MAX_CHUNK_SIZE)
+        .enumerate()
+        .map(|(index, bytes)| Instruction {
+            program_id: stealth_pq::ID,
+            accounts: accounts::UpdateMetaAddress {
+                owner: *owner,
+                meta_address: registry,
+            }
+            .to_account_metas(None),
+            data: instruction::WriteMetaAddressKey {
+                chunk: chunk(bytes),
+                offset: (index * MAX_CHUNK_SIZE) as u16,
+            }
+            .data(),
+        })
+        .collect()
+}
+
+/// `attest_evm_meta_address` linking `evm_meta_address` to `owner`'s registered
+/// meta-address, preceded by the secp256k1 precompile instruction carrying the
+/// EVM account's signature.
+///
+/// Send both in one transaction, in order. `signature` is the 65-byte
+/// `personal_sign` result (r || s || v) over
+/// [`evm_attestation_message`](stealth_pq::evm_attestation_message).
+///
+/// # Arguments
+/// * `owner` - Owner of the registry entry (signer and rent payer)
+/// * `evm_address` - EVM account that produced `signature`
+/// * `evm_meta_address` - ERC-5564 stealth meta-address to link
+/// * `signature` - The EVM account's signature, with `v` as 27/28 or 0/1
+pub fn attest_evm_meta_address(
+    owner: &Pubkey,
+    evm_address: &[u8; 20],
+    evm_meta_address: &[u8; EVM_META_ADDRESS_SIZE],
+    signature: &[u8; 65],
+) -> [Instruction; 2] {
+    let message = stealth_pq::evm_attestation_message(owner, evm_meta_address);
+
+    // Offsets point into this instruction (index 0): address at 12, signature
+    // and recovery id at 32, message at 97
+    let mut data = vec![1u8];
+    data.extend_from_slice(&32u16.to_le_bytes());
+    data.push(0);
+    data.extend_from_slice(&12u16.to_le_bytes());
+    data.push(0);
+    data.extend_from_slice(&97u16.to_le_bytes());
+    data.extend_from_slice(&(message.len() as u16).to_le_bytes());
+    data.push(0);
+    data.extend_from_slice(evm_address);
+    data.extend_from_slice(&signature[..64]);
+    data.push(signature[64] % 27);
+    data.extend_from_slice(&message);
+
+    [
+        Instruction {
+            program_id: secp256k1_program::ID,
+            accounts: Vec::new(),
+            data,
+        },
+        Instruction {
+            program_id: stealth_pq::ID,
+            accounts: accounts::AttestEvmMetaAddress {
+                owner: *owner,
+                meta_address: pda::meta_address(owner).0,
+                attestation: pda::evm_attestation(owner).0,
+                instructions_sysvar: instructions_sysvar::ID,
+                system_program: system_program::ID,
+            }
+            .to_account_metas(None),
+            data: instruction::AttestEvmMetaAddress {
+                evm_address: *evm_address,
+                evm_meta_address: *evm_meta_address,
+            }
+            .data(),
+        },
+    ]
+}
+
+/// `revoke_evm_attestation`, closing `owner`'s attestation and returning its rent
+pub fn revoke_evm_attestation(owner: &Pubkey) -> Instruction {
+    Instruction {
+        program_id: stealth_pq::ID,
+        accounts: accounts::RevokeEvmAttestation {
+            owner: *owner,
+            attestation: pda::evm_attestation(owner).0,
+        }
+        .to_account_metas(None),
+        data: instruction::RevokeEvmAttestation.data(),
+    }
+}
+
+fn writer_accounts(sender: &Pubkey, stealth_address: &Pubkey, app_id: u32) -> Vec<AccountMeta> {
+    accounts::CompleteCiphertext {
+        sender: *sender,
+        ciphertext_account: pda::ciphertext_account(stealth_address, app_id).0,
+    }
+    .to_account_metas(None)
+}
+
+fn hybrid_ciphertext(payment: &StealthPayment) -> Result<&[u8]> {
+    payment
+        .mlkem_ciphertext
+        .as_deref()
+        .filter(|ciphertext| ciphertext.len() == stealth_pq::MLKEM_CIPHERTEXT_SIZE)
+        .ok_or(Error::InvalidCiphertext)
+}
+
+/// Zero-padded `DataChunk` holding `bytes` (at most `MAX_CHUNK_SIZE`)
+fn chunk(bytes: &[u8]) -> DataChunk {
+    let mut data = [0u8; MAX_CHUNK_SIZE];
+    data[..bytes.len()].copy_from_slice(bytes);
+    DataChunk {
+        len: bytes.len() as u16,
+        data,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::StealthKeys;
+    use rand_core::OsRng;
+    use sha2::{Digest, Sha256};
+
+    /// Anchor instruction discriminator: the first 8 bytes of SHA-256("global:<name>")
+    fn discriminator(name: &str) -> [u8; 8] {
+        Sha256::digest(format!("global:{name}"))[..8]
+            .try_into()
+            .unwrap()
+    }
+
+    fn hybrid_payment() -> StealthPayment {
+        let recipient = StealthKeys::generate(&mut OsRng, true);
+        StealthPayment::generate(&recipient.meta_address(), &mut OsRng).unwrap()
+    }
+
+    #[test]
+    fn test_init_ciphertext() {
+        let sender = Pubkey::new_unique();
+        let rent_payer = Pubkey::new_unique();
+        let payment = hybrid_payment();
+        let ix =
+            init_ciphertext(&sender, Some(rent_payer), &payment, 7, Some(1_000), 2500).unwrap();
+
+        assert_eq!(ix.program_id, stealth_pq::ID);
+        assert_eq!(ix.data[..8], discriminator("init_ciphertext"));
+        assert_eq!(
+            ix.accounts,
+            [
+                AccountMeta::new(sender, true),
+                AccountMeta::new(rent_payer, true),
+                AccountMeta::new(payment.stealth_address, false),
+                AccountMeta::new(
+                    pda::ciphertext_account(&payment.stealth_address, 7).0,
+                    false
+                ),
+                AccountMeta::new(pda::namespace_counter(7).0, false),
+                AccountMeta::new_readonly(system_program::ID, false),
+                // Omitted optional account
+                AccountMeta::new_readonly(stealth_pq::ID, false),
+            ]
+        );
+
+        // ephemeral key, then the first chunk (u16 length + data)
+        assert_eq!(ix.data[8..40], payment.ephemeral_pubkey);
+        let ciphertext = payment.mlkem_ciphertext.as_deref().unwrap();
+        assert_eq!(ix.data[40..42], (MAX_CHUNK_SIZE as u16).to_le_bytes());
+        assert_eq!(
+            ix.data[42..42 + MAX_CHUNK_SIZE],
+            ciphertext[..MAX_CHUNK_SIZE]
+        );
+        // ... and the trailing view tag and ML-KEM variant
+        assert_eq!(
+            ix.data[ix.data.len() - 2..],
+            [payment.view_tag, KEM_VARIANT_ML_KEM_768]
+        );
+
+        // Without a sponsor the sender pays the rent
+        let unsponsored = init_ciphertext(&sender, None, &payment, 7, None, 0).unwrap();
+        assert_eq!(
+            unsponsored.accounts[1],
+            AccountMeta::new_readonly(stealth_pq::ID, false)
+        );
+    }
+
+    #[test]
+    fn test_init_ciphertext_requires_hybrid_payment() {
+        let recipient = StealthKeys::generate(&mut OsRng, false);
+        let payment = StealthPayment::generate(&recipient.meta_address(), &mut OsRng).unwrap();
+        let sender = Pubkey::new_unique();
+
+        assert!(matches!(
+            init_ciphertext(&sender, None, &payment, 0, None, 0),
+            Err(Error::InvalidCiphertext)
+        ));
+        assert!(matches!(
+            complete_ciphertext(&sender, &payment, 0),
+            Err(Error::InvalidCiphertext)
+        ));
+    }
+
+    #[test]
+    fn test_writer_instructions() {
+        let sender = Pubkey::new_unique();
+        let payment = hybrid_payment();
+        let writer = [
+            AccountMeta::new(sender, true),
+            AccountMeta::new(
+                pda::ciphertext_account(&payment.stealth_address, 0).0,
+                false,
+            ),
+        ];
+
+        let complete = complete_ciphertext(&sender, &payment, 0).unwrap();
+        assert_eq!(complete.data[..8], discriminator("complete_ciphertext"));
+        assert_eq!(complete.accounts, writer);
+        let rest = &payment.mlkem_ciphertext.as_deref().unwrap()[MAX_CHUNK_SIZE..];
+        assert_eq!(complete.data[8..10], (rest.len() as u16).to_le_bytes());
+        assert_eq!(complete.data[10..10 + rest.len()], *rest);
+        assert_eq!(
+            complete.data[complete.data.len() - 2..],
+            (MAX_CHUNK_SIZE as u16).to_le_bytes()
+        );
+
+        let memo = set_memo(&sender, &payment.stealth_address, 0, b"order 42").unwrap();
+        assert_eq!(memo.data[..8], discriminator("set_memo"));
+        assert_eq!(memo.accounts, writer);
+        assert_eq!(memo.data.len(), 8 + MAX_MEMO_SIZE + 1);
+        assert_eq!(memo.data[8..16], *b"order 42");
+        assert_eq!(memo.data[8 + MAX_MEMO_SIZE], 8);
+        assert!(matches!(
+            set_memo(
+                &sender,
+                &payment.stealth_address,
+                0,
+                &[0u8; MAX_MEMO_SIZE + 1]
+            ),
+            Err(Error::InvalidMemo)
+        ));
+
+        let finalize = finalize_ciphertext(&sender, &payment.stealth_address, 0);
+        assert_eq!(finalize.data, discriminator("finalize_ciphertext"));
+        assert_eq!(finalize.accounts, writer);
+    }
+
+    #[test]
+    fn test_transfer_to_stealth() {
+        let sender = Pubkey::new_unique();
+        let stealth_address = Pubkey::new_unique();
+
+        let ix = transfer_to_stealth(&sender, &stealth_address, 0, 5_000, true);
+        assert_eq!(ix.data[..8], discriminator("transfer_to_stealth"));
+        assert_eq!(ix.data[8..], 5_000u64.to_le_bytes());
+        assert_eq!(
+            ix.accounts,
+            [
+                AccountMeta::new(sender, true),
+                AccountMeta::new(stealth_address, false),
+                AccountMeta::new_readonly(pda::ciphertext_account(&stealth_address, 0).0, false),
+                AccountMeta::new_readonly(system_program::ID, false),
+                AccountMeta::new(pda::stats().0, false),
+            ]
+        );
+
+        let unrecorded = transfer_to_stealth(&sender, &stealth_address, 0, 5_000, false);
+        assert_eq!(
+            unrecorded.accounts[4],
+            AccountMeta::new_readonly(stealth_pq::ID, false)
+        );
+    }
+
+    #[test]
+    fn test_batch_transfer_to_stealth() {
+        let sender = Pubkey::new_unique();
+        let (first, second) = (Pubkey::new_unique(), Pubkey::new_unique());
+
+        let ix = batch_transfer_to_stealth(&sender, &[(first, 1_000), (second, 2_000)], 3, false);
+        assert_eq!(ix.data[..8], discriminator("batch_transfer_to_stealth"));
+        // amounts: Vec<u64>
+        assert_eq!(ix.data[8..12], 2u32.to_le_bytes());
+        assert_eq!(ix.data[12..20], 1_000u64.to_le_bytes());
+        assert_eq!(ix.data[20..], 2_000u64.to_le_bytes());
+        assert_eq!(
+            ix.accounts,
+            [
+                AccountMeta::new(sender, true),
+                AccountMeta::new_readonly(system_program::ID, false),
+                AccountMeta::new_readonly(stealth_pq::ID, false),
+                AccountMeta::new(first, false),
+                AccountMeta::new_readonly(pda::ciphertext_account(&first, 3).0, false),
+                AccountMeta::new(second, false),
+                AccountMeta::new_readonly(pda::ciphertext_account(&second, 3).0, false),
+            ]
+        );
+
+        let recorded = batch_transfer_to_stealth(&sender, &[(first, 1_000)], 3, true);
+        assert_eq!(
+            recorded.accounts[2],
+            AccountMeta::new(pda::stats().0, false)
+        );
+    }
+
+    #[test]
+    fn test_reclaim_rent() {
+        let stealth_address = Pubkey::new_unique();
+        let rent_payer = Pubkey::new_unique();
+        let announcement = pda::announcement(3).0;
+
+        let ix = reclaim_rent(&stealth_address, 0, Some(rent_payer), Some(announcement));
+        assert_eq!(ix.data, discriminator("reclaim_rent"));
+        assert_eq!(
+            ix.accounts,
+            [
+                AccountMeta::new(stealth_address, true),
+                AccountMeta::new(pda::ciphertext_account(&stealth_address, 0).0, false),
+                AccountMeta::new(rent_payer, false),
+                AccountMeta::new(announcement, false),
+            ]
+        );
+
+        let bare = reclaim_rent(&stealth_address, 0, None, None);
+        let omitted = AccountMeta::new_readonly(stealth_pq::ID, false);
+        assert_eq!(bare.accounts[2..], [omitted.clone(), omitted]);
+    }
+
+    #[test]
+    fn test_migrate_ciphertext() {
+        let payer = Pubkey::new_unique();
+        let stealth_address = Pubkey::new_unique();
+
+        let ix = migrate_ciphertext(&payer, &stealth_address);
+        assert_eq!(ix.data, discriminator("migrate_ciphertext"));
+        assert_eq!(
+            ix.accounts,
+            [
+                AccountMeta::new(payer, true),
+                AccountMeta::new(
+                    pda::ciphertext_account(&stealth_address, DEFAULT_APP_ID).0,
+                    false
+                ),
+                AccountMeta::new_readonly(system_program::ID, false),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_log_announcement() {
+        let sender = Pubkey::new_unique();
+        let stealth_address = Pubkey::new_unique();
+
+        let ix = log_announcement(&sender, &stealth_address, 2, 41);
+        assert_eq!(ix.data, discriminator("log_announcement"));
+        assert_eq!(
+            ix.accounts,
+            [
+                AccountMeta::new(sender, true),
+                AccountMeta::new(pda::ciphertext_account(&stealth_address, 2).0, false),
+                AccountMeta::new(pda::announcement_log().0, false),
+                AccountMeta::new(pda::announcement(41).0, false),
+                AccountMeta::new_readonly(system_program::ID, false),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_register_meta_address() {
+        let owner = Pubkey::new_unique();
+        let meta_address = StealthKeys::generate(&mut OsRng, true).meta_address();
+        let registry = pda::meta_address(&owner).0;
+
+        let ixs = register_meta_address(&owner, &meta_address);
+        assert_eq!(ixs.len(), 4);
+        assert_eq!(ixs[0].data[..8], discriminator("register_meta_address"));
+        assert_eq!(ixs[0].data[8..40], meta_address.spending_public_key);
+        assert_eq!(ixs[0].data[40..], meta_address.viewing_public_key);
+        assert_eq!(
+            ixs[0].accounts,
+            [
+                AccountMeta::new(owner, true),
+                AccountMeta::new(registry, false),
+                AccountMeta::new_readonly(system_program::ID, false),
+            ]
+        );
+
+        // The key is written in chunks, each followed by its offset
+        let key = meta_address.mlkem_public_key.as_deref().unwrap();
+        let mut written = Vec::new();
+        for (index, ix) in ixs[1..].iter().enumerate() {
+            assert_eq!(ix.data[..8], discriminator("write_meta_address_key"));
+            assert_eq!(
+                ix.accounts,
+                [
+                    AccountMeta::new_readonly(owner, true),
+                    AccountMeta::new(registry, false),
+                ]
+            );
+            let len = u16::from_le_bytes(ix.data[8..10].try_into().unwrap()) as usize;
+            written.extend_from_slice(&ix.data[10..10 + len]);
+            assert_eq!(
+                ix.data[10 + MAX_CHUNK_SIZE..],
+                ((index * MAX_CHUNK_SIZE) as u16).to_le_bytes()
+            );
+        }
+        assert_eq!(written, key);
+
+        let classical = StealthKeys::generate(&mut OsRng, false).meta_address();
+        assert_eq!(register_meta_address(&owner, &classical).len(), 1);
+    }
+
+    #[test]
+    fn test_update_and_close_meta_address() {
+        let owner = Pubkey::new_unique();
+        let meta_address = StealthKeys::generate(&mut OsRng, true).meta_address();
+        let registry = pda::meta_address(&owner).0;
+
+        let ixs = update_meta_address(&owner, &meta_address);
+        assert_eq!(ixs.len(), 4);
+        assert_eq!(ixs[0].data[..8], discriminator("update_meta_address"));
+        assert_eq!(ixs[0].data[8..40], meta_address.spending_public_key);
+        assert_eq!(ixs[0].data[40..], meta_address.viewing_public_key);
+        assert_eq!(
+            ixs[0].accounts,
+            [
+                AccountMeta::new_readonly(owner, true),
+                AccountMeta::new(registry, false),
+            ]
+        );
+        // The new key is uploaded with the same chunks as at registration
+        assert_eq!(ixs[1..], register_meta_address(&owner, &meta_address)[1..]);
+
+        let ix = close_meta_address(&owner);
+        assert_eq!(ix.data, discriminator("close_meta_address"));
+        assert_eq!(
+            ix.accounts,
+            [
+                AccountMeta::new(owner, true),
+                AccountMeta::new(registry, false),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_attest_evm_meta_address() {
+        use sha3::Keccak256;
+
+        let owner = Pubkey::new_unique();
+        let evm_key = libsecp256k1::SecretKey::parse(&[7u8; 32]).unwrap();
+        let evm_public_key = libsecp256k1::PublicKey::from_secret_key(&evm_key);
+        let evm_address: [u8; 20] = Keccak256::digest(&evm_public_key.serialize()[1..])[12..]
+            .try_into()
+            .unwrap();
+        let evm_meta_address = [3u8; EVM_META_ADDRESS_SIZE];
+
+        // What an EVM wallet's personal_sign returns, with a fixed nonce
+        let message = stealth_pq::evm_attestation_message(&owner, &evm_meta_address);
+        let digest = libsecp256k1::Message::parse(&Keccak256::digest(&message).into());
+        let mut nonce = libsecp256k1::curve::Scalar::default();
+        let _ = nonce.set_b32(&[5u8; 32]);
+        let (r, s, recovery_id) = libsecp256k1::ECMULT_GEN_CONTEXT
+            .sign_raw(&evm_key.into(), &digest.0, &nonce)
+            .unwrap();
+        let mut personal_sign = [0u8; 65];
+        personal_sign[..32].copy_from_slice(&r.b32());
+        personal_sign[32..64].copy_from_slice(&s.b32());
+        personal_sign[64] = 27 + recovery_id;
+
+        let [secp, attest] =
+            attest_evm_meta_address(&owner, &evm_address, &evm_meta_address, &personal_sign);
+
+        // The precompile recovers the signer from the data at the offsets
+        assert_eq!(secp.program_id, secp256k1_program::ID);
+        assert_eq!(secp.data[..2], [1, 32]);
+        assert_eq!(secp.data[12..32], evm_address);
+        assert_eq!(secp.data[97..], message);
+        let recovered = libsecp256k1::recover(
+            &digest,
+            &libsecp256k1::Signature::parse_standard_slice(&secp.data[32..96]).unwrap(),
+            &libsecp256k1::RecoveryId::parse(secp.data[96]).unwrap(),
+        )
+        .unwrap();
+        assert_eq!(recovered, evm_public_key);
+
+        assert_eq!(attest.data[..8], discriminator("attest_evm_meta_address"));
+        assert_eq!(attest.data[8..28], evm_address);
+        assert_eq!(attest.data[28..], evm_meta_address);
+        assert_eq!(
+            attest.accounts,
+            [
+                AccountMeta::new(owner, true),
+                AccountMeta::new_readonly(pda::meta_address(&owner).0, false),
+                AccountMeta::new(pda::evm_attestation(&owner).0, false),
+                AccountMeta::new_readonly(instructions_sysvar::ID, false),
+                AccountMeta::new_readonly(system_program::ID, false),
+            ]
+        );
+
+        let revoke = revoke_evm_attestation(&owner);
+        assert_eq!(revoke.data, discriminator("revoke_evm_attestation"));
+        assert_eq!(revoke.accounts[1].pubkey, pda::evm_attestation(&owner).0);
+    }
+}
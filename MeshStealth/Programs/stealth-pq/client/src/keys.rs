@@ -0,0 +1,423 @@
+//! Meta-addresses, recipient keys and stealth spending keys.
+
+use anchor_lang::prelude::Pubkey;
+use curve25519_dalek::edwards::{CompressedEdwardsY, EdwardsPoint};
+use curve25519_dalek::scalar::Scalar;
+use ml_kem::{EncodedSizeUser, KemCore, MlKem768, B32};
+use rand_core::CryptoRngCore;
+use sha2::{Digest, Sha512};
+use sha3::Sha3_256;
+use stealth_pq::MetaAddressRegistry;
+use x25519_dalek::{PublicKey as X25519PublicKey, StaticSecret};
+
+use crate::entropy::CheckedRng;
+use crate::{Error, Result};
+
+pub(crate) type EncapsulationKey = <MlKem768 as KemCore>::EncapsulationKey;
+pub(crate) type DecapsulationKey = <MlKem768 as KemCore>::DecapsulationKey;
+
+/// Size of the ML-KEM-768 encapsulation key in a hybrid meta-address
+pub const MLKEM_PUBLIC_KEY_SIZE: usize = stealth_pq::MLKEM_ENCAPSULATION_KEY_SIZE;
+
+/// Size of an ML-KEM-768 private key seed (d || z)
+pub const MLKEM_SEED_SIZE: usize = 64;
+
+/// Size of a classical meta-address: M (32) || V (32)
+pub const CLASSICAL_META_ADDRESS_SIZE: usize = 64;
+
+/// Size of a hybrid meta-address: M (32) || V (32) || K (1184)
+pub const HYBRID_META_ADDRESS_SIZE: usize = CLASSICAL_META_ADDRESS_SIZE + MLKEM_PUBLIC_KEY_SIZE;
+
+/// A recipient's published stealth meta-address.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MetaAddress {
+    /// Ed25519 spending public key M
+    pub spending_public_key: [u8; 32],
+
+    /// X25519 viewing public key V
+    pub viewing_public_key: [u8; 32],
+
+    /// ML-KEM-768 encapsulation key K; `None` for a classical meta-address
+    pub mlkem_public_key: Option<Vec<u8>>,
+}
+
+impl MetaAddress {
+    /// Parse a 64-byte classical or 1248-byte hybrid meta-address
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        let mlkem_public_key = match bytes.len() {
+            CLASSICAL_META_ADDRESS_SIZE => None,
+            HYBRID_META_ADDRESS_SIZE => Some(bytes[CLASSICAL_META_ADDRESS_SIZE..].to_vec()),
+            _ => return Err(Error::InvalidMetaAddress),
+        };
+
+        Ok(Self {
+            spending_public_key: bytes[..32].try_into().unwrap(),
+            viewing_public_key: bytes[32..64].try_into().unwrap(),
+            mlkem_public_key,
+        })
+    }
+
+    /// Parse a base58-encoded meta-address, as shared by StealthCore
+    pub fn from_base58(encoded: &str) -> Result<Self> {
+        let bytes = bs58::decode(encoded)
+            .into_vec()
+            .map_err(|_| Error::InvalidMetaAddress)?;
+        Self::from_bytes(&bytes)
+    }
+
+    /// Serialized meta-address: M || V, followed by K for a hybrid meta-address
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(HYBRID_META_ADDRESS_SIZE);
+        bytes.extend_from_slice(&self.spending_public_key);
+        bytes.extend_from_slice(&self.viewing_public_key);
+        if let Some(mlkem_public_key) = &self.mlkem_public_key {
+            bytes.extend_from_slice(mlkem_public_key);
+        }
+        bytes
+    }
+
+    /// Base58-encoded meta-address
+    pub fn to_base58(&self) -> String {
+        bs58::encode(self.to_bytes()).into_string()
+    }
+
+    /// Whether payments to this meta-address use the hybrid scheme
+    pub fn is_hybrid(&self) -> bool {
+        self.mlkem_public_key.is_some()
+    }
+}
+
+impl From<&MetaAddressRegistry> for MetaAddress {
+    /// Meta-address of a registry entry; classical until its ML-KEM key is uploaded
+    fn from(entry: &MetaAddressRegistry) -> Self {
+        let key = &entry.mlkem_encapsulation_key;
+        Self {
+            spending_public_key: entry.spending_pubkey,
+            viewing_public_key: entry.viewing_pubkey,
+            mlkem_public_key: key.iter().any(|&byte| byte != 0).then(|| key.to_vec()),
+        }
+    }
+}
+
+/// A recipient's private keys: spending scalar m, viewing key v and, for hybrid
+/// mode, the ML-KEM-768 key pair.
+///
+/// Keys are never mutated after construction and detection takes `&self`, so a
+/// server can share one instance between request handlers (e.g. in an `Arc`)
+/// without locking. The same holds for the `RpcClient` a [`Scanner`](crate::Scanner)
+/// borrows next to it.
+pub struct StealthKeys {
+    spending_scalar: Scalar,
+    spending_public_key: [u8; 32],
+    viewing_secret: StaticSecret,
+    mlkem: Option<MlKemKeys>,
+}
+
+struct MlKemKeys {
+    seed: [u8; MLKEM_SEED_SIZE],
+    decapsulation_key: DecapsulationKey,
+    encapsulation_key: EncapsulationKey,
+}
+
+impl MlKemKeys {
+    fn from_seed(seed: [u8; MLKEM_SEED_SIZE]) -> Self {
+        let d = B32::try_from(&seed[..32]).unwrap();
+        let z = B32::try_from(&seed[32..]).unwrap();
+        let (decapsulation_key, encapsulation_key) = MlKem768::generate_deterministic(&d, &z);
+        Self {
+            seed,
+            decapsulation_key,
+            encapsulation_key,
+        }
+    }
+}
+
+impl StealthKeys {
+    /// Generate new keys, with an ML-KEM-768 key pair if `post_quantum` is set
+    pub fn generate(rng: &mut impl CryptoRngCore, post_quantum: bool) -> Self {
+        let mut wide = [0u8; 64];
+        rng.fill_bytes(&mut wide);
+        let spending_scalar = Scalar::from_bytes_mod_order_wide(&wide);
+        let viewing_secret = StaticSecret::random_from_rng(&mut *rng);
+
+        let mlkem = post_quantum.then(|| {
+            let mut seed = [0u8; MLKEM_SEED_SIZE];
+            rng.fill_bytes(&mut seed);
+            MlKemKeys::from_seed(seed)
+        });
+
+        Self::with_parts(spending_scalar, viewing_secret, mlkem)
+    }
+
+    /// Generate new keys from a [`CheckedRng`] over `rng` and `user_entropy`.
+    ///
+    /// Fails with [`Error::EntropyHealthCheck`] if `rng` fails the health checks.
+    pub fn generate_with_entropy(
+        rng: &mut impl CryptoRngCore,
+        post_quantum: bool,
+        user_entropy: &[u8],
+    ) -> Result<Self> {
+        Ok(Self::generate(
+            &mut CheckedRng::new(rng, user_entropy)?,
+            post_quantum,
+        ))
+    }
+
+    /// Restore keys exported from StealthCore.
+    ///
+    /// * `spending_scalar` - Raw spending scalar m (`rawSpendingScalar`)
+    /// * `viewing_private_key` - X25519 viewing private key v (`rawViewingPrivateKey`)
+    /// * `mlkem_private_key` - The 64-byte ML-KEM seed, or CryptoKit's 96-byte
+    ///   integrity-checked representation (the seed followed by SHA3-256 of the
+    ///   encapsulation key), which is checked against the derived key
+    pub fn from_bytes(
+        spending_scalar: &[u8; 32],
+        viewing_private_key: &[u8; 32],
+        mlkem_private_key: Option<&[u8]>,
+    ) -> Result<Self> {
+        let mlkem = match mlkem_private_key {
+            None => None,
+            Some(bytes)
+                if bytes.len() == MLKEM_SEED_SIZE || bytes.len() == MLKEM_SEED_SIZE + 32 =>
+            {
+                let keys = MlKemKeys::from_seed(bytes[..MLKEM_SEED_SIZE].try_into().unwrap());
+                let hash = &bytes[MLKEM_SEED_SIZE..];
+                if !hash.is_empty()
+                    && Sha3_256::digest(keys.encapsulation_key.as_bytes()).as_slice() != hash
+                {
+                    return Err(Error::InvalidKey);
+                }
+                Some(keys)
+            }
+            Some(_) => return Err(Error::InvalidKey),
+        };
+
+        Ok(Self::with_parts(
+            Scalar::from_bytes_mod_order(*spending_scalar),
+            StaticSecret::from(*viewing_private_key),
+            mlkem,
+        ))
+    }
+
+    fn with_parts(
+        spending_scalar: Scalar,
+        viewing_secret: StaticSecret,
+        mlkem: Option<MlKemKeys>,
+    ) -> Self {
+        Self {
+            spending_public_key: EdwardsPoint::mul_base(&spending_scalar)
+                .compress()
+                .to_bytes(),
+            spending_scalar,
+            viewing_secret,
+            mlkem,
+        }
+    }
+
+    /// The meta-address to publish for these keys
+    pub fn meta_address(&self) -> MetaAddress {
+        MetaAddress {
+            spending_public_key: self.spending_public_key,
+            viewing_public_key: X25519PublicKey::from(&self.viewing_secret).to_bytes(),
+            mlkem_public_key: self
+                .mlkem
+                .as_ref()
+                .map(|keys| keys.encapsulation_key.as_bytes().to_vec()),
+        }
+    }
+
+    /// Whether these keys include an ML-KEM-768 key pair
+    pub fn has_post_quantum(&self) -> bool {
+        self.mlkem.is_some()
+    }
+
+    /// The raw spending scalar m, for secure storage
+    pub fn spending_scalar(&self) -> [u8; 32] {
+        self.spending_scalar.to_bytes()
+    }
+
+    /// The X25519 viewing private key v, for secure storage
+    pub fn viewing_private_key(&self) -> [u8; 32] {
+        self.viewing_secret.to_bytes()
+    }
+
+    /// The 64-byte ML-KEM-768 seed, for secure storage
+    pub fn mlkem_seed(&self) -> Option<[u8; MLKEM_SEED_SIZE]> {
+        self.mlkem.as_ref().map(|keys| keys.seed)
+    }
+
+    /// X25519 shared secret with a sender's ephemeral key: S = X25519(v, R)
+    pub(crate) fn classical_secret(&self, ephemeral_pubkey: &[u8; 32]) -> [u8; 32] {
+        self.viewing_secret
+            .diffie_hellman(&X25519PublicKey::from(*ephemeral_pubkey))
+            .to_bytes()
+    }
+
+    pub(crate) fn decapsulation_key(&self) -> Option<&DecapsulationKey> {
+        self.mlkem.as_ref().map(|keys| &keys.decapsulation_key)
+    }
+
+    pub(crate) fn spending_public_key(&self) -> &[u8; 32] {
+        &self.spending_public_key
+    }
+
+    /// Spending key of the stealth address P = M + h*G: p = m + h (mod L)
+    pub(crate) fn stealth_spending_key(&self, hash: &Scalar) -> SpendingKey {
+        SpendingKey::from_scalar(self.spending_scalar + hash)
+    }
+}
+
+/// Private key of a stealth address.
+///
+/// A raw scalar rather than an Ed25519 seed, so it signs with [`SpendingKey::sign`]
+/// instead of going through the standard Ed25519 key expansion.
+#[derive(Clone)]
+pub struct SpendingKey {
+    scalar: Scalar,
+    public_key: [u8; 32],
+}
+
+impl SpendingKey {
+    /// Build a spending key from a raw scalar (as stored by StealthCore)
+    pub fn from_bytes(scalar: &[u8; 32]) -> Self {
+        Self::from_scalar(Scalar::from_bytes_mod_order(*scalar))
+    }
+
+    fn from_scalar(scalar: Scalar) -> Self {
+        Self {
+            public_key: EdwardsPoint::mul_base(&scalar).compress().to_bytes(),
+            scalar,
+        }
+    }
+
+    /// The raw scalar, for secure storage
+    pub fn to_bytes(&self) -> [u8; 32] {
+        self.scalar.to_bytes()
+    }
+
+    /// The stealth address controlled by this key
+    pub fn pubkey(&self) -> Pubkey {
+        Pubkey::new_from_array(self.public_key)
+    }
+
+    /// Sign a message (e.g. a serialized transaction message) for the stealth address.
+    ///
+    /// Produces a standard Ed25519 signature, with the same deterministic nonce
+    /// as StealthCore's `signWithScalar`: r = SHA512(p || message) mod L.
+    pub fn sign(&self, message: &[u8]) -> [u8; 64] {
+        let r = hash_to_scalar(&[self.scalar.as_bytes(), message]);
+        let big_r = EdwardsPoint::mul_base(&r).compress();
+        let k = hash_to_scalar(&[big_r.as_bytes(), &self.public_key, message]);
+        let s = r + k * self.scalar;
+
+        let mut signature = [0u8; 64];
+        signature[..32].copy_from_slice(big_r.as_bytes());
+        signature[32..].copy_from_slice(s.as_bytes());
+        signature
+    }
+}
+
+/// Verify an Ed25519 signature, such as one made by [`SpendingKey::sign`] or a
+/// wallet.
+pub fn verify_signature(public_key: &Pubkey, message: &[u8], signature: &[u8; 64]) -> bool {
+    let Some(a) = CompressedEdwardsY(public_key.to_bytes()).decompress() else {
+        return false;
+    };
+    let Some(s) = Option::<Scalar>::from(Scalar::from_canonical_bytes(
+        signature[32..].try_into().unwrap(),
+    )) else {
+        return false;
+    };
+
+    // s*G == R + k*A, compared compressed so a non-canonical R fails
+    let k = hash_to_scalar(&[&signature[..32], public_key.as_ref(), message]);
+    let big_r = EdwardsPoint::vartime_double_scalar_mul_basepoint(&k, &-a, &s);
+    big_r.compress().as_bytes() == &signature[..32]
+}
+
+/// SHA-512 over the concatenated parts, reduced mod L
+fn hash_to_scalar(parts: &[&[u8]]) -> Scalar {
+    let mut hasher = Sha512::new();
+    for part in parts {
+        hasher.update(part);
+    }
+    let mut wide = [0u8; 64];
+    wide.copy_from_slice(&hasher.finalize());
+    Scalar::from_bytes_mod_order_wide(&wide)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand_core::OsRng;
+
+    #[test]
+    fn test_meta_address_round_trip() {
+        let keys = StealthKeys::generate(&mut OsRng, true);
+        let meta = keys.meta_address();
+        assert!(meta.is_hybrid());
+        assert_eq!(meta.to_bytes().len(), HYBRID_META_ADDRESS_SIZE);
+        assert_eq!(MetaAddress::from_base58(&meta.to_base58()).unwrap(), meta);
+
+        let restored = StealthKeys::from_bytes(
+            &keys.spending_scalar(),
+            &keys.viewing_private_key(),
+            Some(&keys.mlkem_seed().unwrap()),
+        )
+        .unwrap();
+        assert_eq!(restored.meta_address(), meta);
+
+        assert!(MetaAddress::from_bytes(&[0u8; 65]).is_err());
+    }
+
+    #[test]
+    fn test_meta_address_from_registry() {
+        let meta = StealthKeys::generate(&mut OsRng, true).meta_address();
+        let mut entry = MetaAddressRegistry {
+            spending_pubkey: meta.spending_public_key,
+            viewing_pubkey: meta.viewing_public_key,
+            ..Default::default()
+        };
+
+        // Registered, ML-KEM key not uploaded yet
+        let classical = MetaAddress::from(&entry);
+        assert!(!classical.is_hybrid());
+        assert_eq!(
+            classical.to_bytes(),
+            meta.to_bytes()[..CLASSICAL_META_ADDRESS_SIZE]
+        );
+
+        entry
+            .mlkem_encapsulation_key
+            .copy_from_slice(meta.mlkem_public_key.as_deref().unwrap());
+        assert_eq!(MetaAddress::from(&entry), meta);
+    }
+
+    #[test]
+    fn test_sign_verifies() {
+        let key = SpendingKey::from_bytes(&[7u8; 32]);
+        let message = b"stealth transaction message";
+        let signature = key.sign(message);
+
+        // s*G == R + k*A
+        let big_r = CompressedEdwardsY(signature[..32].try_into().unwrap())
+            .decompress()
+            .unwrap();
+        let a = CompressedEdwardsY(key.public_key).decompress().unwrap();
+        let s = Scalar::from_canonical_bytes(signature[32..].try_into().unwrap()).unwrap();
+        let k = hash_to_scalar(&[&signature[..32], &key.public_key, message]);
+        assert_eq!(EdwardsPoint::mul_base(&s), big_r + k * a);
+
+        assert!(verify_signature(&key.pubkey(), message, &signature));
+        assert!(!verify_signature(
+            &key.pubkey(),
+            b"another message",
+            &signature
+        ));
+        assert!(!verify_signature(
+            &Pubkey::new_unique(),
+            message,
+            &signature
+        ));
+    }
+}
@@ -0,0 +1,54 @@
+//! Off-chain client for the stealth-pq program.
+//!
+//! Implements both sides of the hybrid X25519 + ML-KEM-768 stealth address
+//! scheme with the same derivation as the StealthCore Swift package, so
+//! payments made with one can be detected with the other. Account layouts,
+//! PDA seeds and instruction data come from the `stealth-pq` program crate
+//! itself rather than being redefined here.
+//!
+//! - [`cluster`]: RPC endpoints of the clusters the program is deployed to
+//! - [`entropy`]: health-checked randomness for key generation
+//! - [`keys`]: meta-addresses, recipient keys and stealth spending keys
+//! - [`stealth`]: sender-side derivation and recipient-side detection
+//! - [`pda`]: program-derived addresses
+//! - [`instructions`]: builders for the announce, transfer, reclaim and registry instructions
+//! - [`registry`]: resolving wallets to their published meta-addresses
+//! - [`request`]: signed payment requests for QR codes and NFC tags
+//! - [`scanner`]: async scanning of the announcement log over RPC
+//! - [`watch`]: alerts when watched stealth addresses are spent from or closed
+//! - [`preflight`]: checking a payment's rent, fees and first steps before sending it
+//! - [`payroll`]: paying a roster of meta-addresses in batched transfers
+//! - [`offline`]: partially signed transactions, for spend keys kept offline
+//! - [`fountain`]: fountain-coded frames for passing them through animated QR codes
+
+pub mod cluster;
+pub mod entropy;
+pub mod error;
+pub mod fountain;
+pub mod instructions;
+pub mod keys;
+pub mod offline;
+pub mod payroll;
+pub mod pda;
+pub mod preflight;
+pub mod registry;
+pub mod request;
+pub mod scanner;
+pub mod stealth;
+pub mod watch;
+
+pub use cluster::Cluster;
+pub use error::{Error, Result};
+pub use fountain::{FountainDecoder, FountainEncoder};
+pub use keys::{MetaAddress, SpendingKey, StealthKeys};
+pub use offline::{PartialTransaction, StealthInput};
+pub use payroll::{Payee, Payroll, PayrollReport};
+pub use preflight::{DurableNonce, PaymentFlow, Preflight};
+pub use registry::{fetch_meta_address, RegistryCache};
+pub use request::{PaymentRequest, RequestTarget};
+pub use scanner::{
+    DetectedPayment, ScanControl, ScanPage, ScanProgress, Scanner, UnsupportedAnnouncement,
+};
+pub use stealth::StealthPayment;
+pub use stealth_pq::{DEFAULT_APP_ID, ID as PROGRAM_ID};
+pub use watch::Watchtower;
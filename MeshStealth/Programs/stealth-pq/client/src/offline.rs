@@ -0,0 +1,517 @@
+//! Partially signed transactions, for spending from an offline machine.
+//!
+//! An online machine builds the transaction (e.g. a [`sweep`]) and exports it
+//! with [`PartialTransaction::to_bytes`]. The offline machine, holding the
+//! recipient's [`StealthKeys`], derives each stealth address's spending key from
+//! the announcement data the transaction carries and signs
+//! ([`PartialTransaction::sign_stealth`]); other signers such as the fee payer
+//! sign with [`PartialTransaction::sign`]. Copies signed on different machines
+//! are merged with [`PartialTransaction::combine`], and the online machine sends
+//! the result of [`PartialTransaction::into_transaction`]. Payload layout,
+//! integers little-endian:
+//!
+//! ```text
+//! version (1) = 1
+//! message length (2) || message (legacy wire format)
+//! signature count (1), the message's required signatures
+//! per signer, in account order: 0, or 1 || Ed25519 signature (64)
+//! input count (1)
+//! per stealth input: stealth address (32) || ephemeral key (32)
+//!                    || ciphertext length (2) || ML-KEM ciphertext (0 or 1088)
+//! ```
+//!
+//! Decoding checks every signature present against the message and that every
+//! stealth input is one of its signers, so a signer only has to check what the
+//! message does before adding its own signature. The message commits to a recent
+//! blockhash: the round trip has to finish, and the transaction land, before it
+//! expires.
+//!
+//! Transactions sent in sequence, such as the steps of a
+//! [`PaymentFlow`](crate::PaymentFlow), travel together as a bundle
+//! ([`encode_bundle`]): version (1) = 1, transaction count (1), then each
+//! transaction's payload prefixed with its length (4).
+
+use anchor_lang::prelude::Pubkey;
+use solana_message::Message;
+use solana_rpc_client::nonblocking::rpc_client::RpcClient;
+use solana_signature::Signature;
+use solana_system_interface::instruction::transfer;
+use solana_transaction::Transaction;
+use stealth_pq::{Announcement, CiphertextAccount, KEM_VARIANT_ML_KEM_768, MLKEM_CIPHERTEXT_SIZE};
+
+use crate::keys::verify_signature;
+use crate::scanner::decode;
+use crate::{instructions, Error, Result, SpendingKey, StealthKeys};
+
+/// Payload version written by [`PartialTransaction::to_bytes`]
+pub const PARTIAL_TRANSACTION_VERSION: u8 = 1;
+
+/// Payload version written by [`encode_bundle`]
+pub const BUNDLE_VERSION: u8 = 1;
+
+/// What an offline signer needs to derive a stealth address's spending key
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StealthInput {
+    /// The stealth address that signs
+    pub stealth_address: Pubkey,
+
+    /// Ephemeral X25519 public key R of its payment
+    pub ephemeral_pubkey: [u8; 32],
+
+    /// ML-KEM-768 ciphertext of its payment, empty for a classical payment
+    pub mlkem_ciphertext: Vec<u8>,
+}
+
+/// A transaction message with the signatures collected for it so far.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PartialTransaction {
+    message: Message,
+    signatures: Vec<Option<[u8; 64]>>,
+    inputs: Vec<StealthInput>,
+}
+
+impl PartialTransaction {
+    /// An unsigned transaction for `message`, whose `inputs` are signed with
+    /// [`sign_stealth`](Self::sign_stealth).
+    ///
+    /// Fails with [`Error::InvalidPartialTransaction`] if an input isn't one of
+    /// the message's signers.
+    pub fn new(message: Message, inputs: Vec<StealthInput>) -> Result<Self> {
+        let transaction = Self {
+            signatures: vec![None; message.header.num_required_signatures as usize],
+            message,
+            inputs,
+        };
+        transaction.check_inputs()?;
+        Ok(transaction)
+    }
+
+    /// The message being signed
+    pub fn message(&self) -> &Message {
+        &self.message
+    }
+
+    /// The stealth addresses among the signers, with their announcement data
+    pub fn inputs(&self) -> &[StealthInput] {
+        &self.inputs
+    }
+
+    /// Signers that haven't signed yet, in account order
+    pub fn missing_signers(&self) -> Vec<Pubkey> {
+        self.signer_keys()
+            .zip(&self.signatures)
+            .filter(|(_, signature)| signature.is_none())
+            .map(|(signer, _)| *signer)
+            .collect()
+    }
+
+    /// Sign with `key` if it is one of the message's signers.
+    ///
+    /// # Returns
+    /// Whether `key` is a signer
+    pub fn sign(&mut self, key: &SpendingKey) -> bool {
+        let Some(position) = self
+            .signer_keys()
+            .position(|signer| *signer == key.pubkey())
+        else {
+            return false;
+        };
+        self.signatures[position] = Some(key.sign(&self.message.serialize()));
+        true
+    }
+
+    /// Sign for every unsigned stealth input that is a payment to `keys`.
+    ///
+    /// # Returns
+    /// How many inputs were signed
+    pub fn sign_stealth(&mut self, keys: &StealthKeys) -> Result<usize> {
+        let mut signed = 0;
+        for input in self.inputs.clone() {
+            if !self.missing_signers().contains(&input.stealth_address) {
+                continue;
+            }
+            let ciphertext =
+                (!input.mlkem_ciphertext.is_empty()).then_some(input.mlkem_ciphertext.as_slice());
+            if let Some(spending_key) =
+                keys.detect(&input.stealth_address, &input.ephemeral_pubkey, ciphertext)?
+            {
+                self.sign(&spending_key);
+                signed += 1;
+            }
+        }
+        Ok(signed)
+    }
+
+    /// Add the signatures of `other`, a copy of this transaction signed
+    /// elsewhere.
+    ///
+    /// Fails with [`Error::InvalidPartialTransaction`] if `other` is for a
+    /// different message.
+    pub fn combine(&mut self, other: &PartialTransaction) -> Result<()> {
+        if other.message != self.message {
+            return Err(Error::InvalidPartialTransaction);
+        }
+        for (signature, other) in self.signatures.iter_mut().zip(&other.signatures) {
+            if signature.is_none() {
+                *signature = *other;
+            }
+        }
+        Ok(())
+    }
+
+    /// The signed transaction, ready to send.
+    ///
+    /// Fails with [`Error::MissingSignatures`] until every signer has signed.
+    pub fn into_transaction(self) -> Result<Transaction> {
+        let missing = self.missing_signers().len();
+        if missing > 0 {
+            return Err(Error::MissingSignatures(missing));
+        }
+        Ok(Transaction {
+            signatures: self
+                .signatures
+                .into_iter()
+                .map(|signature| Signature::from(signature.unwrap()))
+                .collect(),
+            message: self.message,
+        })
+    }
+
+    /// Encode the transaction for the other machine
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let message = self.message.serialize();
+        let mut payload = Vec::with_capacity(message.len() + 2048);
+        payload.push(PARTIAL_TRANSACTION_VERSION);
+        payload.extend_from_slice(&(message.len() as u16).to_le_bytes());
+        payload.extend_from_slice(&message);
+
+        payload.push(self.signatures.len() as u8);
+        for signature in &self.signatures {
+            match signature {
+                Some(signature) => {
+                    payload.push(1);
+                    payload.extend_from_slice(signature);
+                }
+                None => payload.push(0),
+            }
+        }
+
+        payload.push(self.inputs.len() as u8);
+        for input in &self.inputs {
+            payload.extend_from_slice(input.stealth_address.as_ref());
+            payload.extend_from_slice(&input.ephemeral_pubkey);
+            payload.extend_from_slice(&(input.mlkem_ciphertext.len() as u16).to_le_bytes());
+            payload.extend_from_slice(&input.mlkem_ciphertext);
+        }
+        payload
+    }
+
+    /// Decode a payload written by [`to_bytes`](Self::to_bytes), checking every
+    /// signature it carries.
+    pub fn from_bytes(payload: &[u8]) -> Result<Self> {
+        let mut reader = Reader(payload);
+        if reader.take(1)?[0] != PARTIAL_TRANSACTION_VERSION {
+            return Err(Error::InvalidPartialTransaction);
+        }
+        let message_len = u16::from_le_bytes(reader.array()?) as usize;
+        let message_bytes = reader.take(message_len)?;
+        let message: Message =
+            bincode::deserialize(message_bytes).map_err(|_| Error::InvalidPartialTransaction)?;
+        // Signatures are checked over the re-serialized message, so it must be
+        // the same bytes
+        let num_signers = message.header.num_required_signatures as usize;
+        if message.serialize() != message_bytes || num_signers > message.account_keys.len() {
+            return Err(Error::InvalidPartialTransaction);
+        }
+
+        if reader.take(1)?[0] as usize != num_signers {
+            return Err(Error::InvalidPartialTransaction);
+        }
+        let mut signatures = Vec::with_capacity(num_signers);
+        for signer in &message.account_keys[..num_signers] {
+            let signature = match reader.take(1)?[0] {
+                0 => None,
+                1 => {
+                    let signature: [u8; 64] = reader.array()?;
+                    if !verify_signature(signer, message_bytes, &signature) {
+                        return Err(Error::InvalidPartialTransaction);
+                    }
+                    Some(signature)
+                }
+                _ => return Err(Error::InvalidPartialTransaction),
+            };
+            signatures.push(signature);
+        }
+
+        let input_count = reader.take(1)?[0];
+        let mut inputs = Vec::with_capacity(input_count as usize);
+        for _ in 0..input_count {
+            let stealth_address = Pubkey::new_from_array(reader.array()?);
+            let ephemeral_pubkey = reader.array()?;
+            let ciphertext_len = u16::from_le_bytes(reader.array()?) as usize;
+            if ciphertext_len != 0 && ciphertext_len != MLKEM_CIPHERTEXT_SIZE {
+                return Err(Error::InvalidPartialTransaction);
+            }
+            inputs.push(StealthInput {
+                stealth_address,
+                ephemeral_pubkey,
+                mlkem_ciphertext: reader.take(ciphertext_len)?.to_vec(),
+            });
+        }
+        if !reader.0.is_empty() {
+            return Err(Error::InvalidPartialTransaction);
+        }
+
+        let transaction = Self {
+            message,
+            signatures,
+            inputs,
+        };
+        transaction.check_inputs()?;
+        Ok(transaction)
+    }
+
+    fn signer_keys(&self) -> impl Iterator<Item = &Pubkey> {
+        self.message.account_keys[..self.signatures.len()].iter()
+    }
+
+    fn check_inputs(&self) -> Result<()> {
+        if self.signatures.len() > self.message.account_keys.len() {
+            return Err(Error::InvalidPartialTransaction);
+        }
+        let signers: Vec<&Pubkey> = self.signer_keys().collect();
+        if self
+            .inputs
+            .iter()
+            .any(|input| !signers.contains(&&input.stealth_address))
+        {
+            return Err(Error::InvalidPartialTransaction);
+        }
+        Ok(())
+    }
+}
+
+/// Encode transactions to be signed and sent in order, at most 255
+pub fn encode_bundle(transactions: &[PartialTransaction]) -> Result<Vec<u8>> {
+    let count = u8::try_from(transactions.len()).map_err(|_| Error::InvalidPartialTransaction)?;
+    let mut payload = vec![BUNDLE_VERSION, count];
+    for transaction in transactions {
+        let bytes = transaction.to_bytes();
+        payload.extend_from_slice(&(bytes.len() as u32).to_le_bytes());
+        payload.extend_from_slice(&bytes);
+    }
+    Ok(payload)
+}
+
+/// Decode a bundle written by [`encode_bundle`], checking each transaction as
+/// [`PartialTransaction::from_bytes`] does.
+pub fn decode_bundle(payload: &[u8]) -> Result<Vec<PartialTransaction>> {
+    let mut reader = Reader(payload);
+    if reader.take(1)?[0] != BUNDLE_VERSION {
+        return Err(Error::InvalidPartialTransaction);
+    }
+    let count = reader.take(1)?[0];
+    let mut transactions = Vec::with_capacity(count as usize);
+    for _ in 0..count {
+        let len = u32::from_le_bytes(reader.array()?) as usize;
+        transactions.push(PartialTransaction::from_bytes(reader.take(len)?)?);
+    }
+    if !reader.0.is_empty() {
+        return Err(Error::InvalidPartialTransaction);
+    }
+    Ok(transactions)
+}
+
+/// The transaction sweeping the payment announced at `announcement` into
+/// `destination`, for signing offline.
+///
+/// It closes the payment's CiphertextAccount and announcement log entry with
+/// `reclaim_rent` and moves everything the stealth address then holds to
+/// `destination`. `fee_payer` pays the fee, so nothing is left behind; it signs
+/// with [`PartialTransaction::sign`] and the stealth address with
+/// [`PartialTransaction::sign_stealth`].
+pub async fn sweep(
+    rpc: &RpcClient,
+    announcement: &Pubkey,
+    destination: &Pubkey,
+    fee_payer: &Pubkey,
+) -> Result<PartialTransaction> {
+    let entry = rpc.get_account(announcement).await?;
+    let entry_data: Announcement = decode(announcement, &entry.data)?;
+    let stealth_address = entry_data.stealth_pubkey;
+
+    let accounts = rpc
+        .get_multiple_accounts(&[entry_data.ciphertext_account, stealth_address])
+        .await?;
+    let Some(ciphertext_account) = &accounts[0] else {
+        return Err(Error::AccountDecode(entry_data.ciphertext_account));
+    };
+    let ciphertext: CiphertextAccount =
+        decode(&entry_data.ciphertext_account, &ciphertext_account.data)?;
+    if ciphertext.kem_variant != KEM_VARIANT_ML_KEM_768 {
+        return Err(Error::UnsupportedKemVariant {
+            announcement_index: entry_data.index,
+            kem_variant: ciphertext.kem_variant,
+        });
+    }
+
+    // What reclaim_rent leaves in the stealth address
+    let balance = accounts[1].as_ref().map_or(0, |account| account.lamports);
+    let lamports = balance + entry.lamports + ciphertext_account.lamports
+        - ciphertext.rent_payer_share(ciphertext_account.lamports);
+
+    let message = Message::new_with_blockhash(
+        &[
+            instructions::reclaim_rent(
+                &stealth_address,
+                entry_data.app_id,
+                Some(ciphertext.rent_payer),
+                Some(*announcement),
+            ),
+            transfer(&stealth_address, destination, lamports),
+        ],
+        Some(fee_payer),
+        &rpc.get_latest_blockhash().await?,
+    );
+    PartialTransaction::new(
+        message,
+        vec![StealthInput {
+            stealth_address,
+            ephemeral_pubkey: ciphertext.ephemeral_pubkey,
+            mlkem_ciphertext: ciphertext.mlkem_ciphertext,
+        }],
+    )
+}
+
+struct Reader<'a>(&'a [u8]);
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8]> {
+        if self.0.len() < len {
+            return Err(Error::InvalidPartialTransaction);
+        }
+        let (head, rest) = self.0.split_at(len);
+        self.0 = rest;
+        Ok(head)
+    }
+
+    fn array<const N: usize>(&mut self) -> Result<[u8; N]> {
+        Ok(self.take(N)?.try_into().unwrap())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::StealthPayment;
+    use rand_core::OsRng;
+
+    /// A transfer out of a payment to `recipient`, paid for by a wallet
+    fn unsigned(recipient: &StealthKeys) -> (PartialTransaction, SpendingKey) {
+        let payment = StealthPayment::generate(&recipient.meta_address(), &mut OsRng).unwrap();
+        let fee_payer = SpendingKey::from_bytes(&[7u8; 32]);
+        let message = Message::new_with_blockhash(
+            &[transfer(
+                &payment.stealth_address,
+                &fee_payer.pubkey(),
+                1_000,
+            )],
+            Some(&fee_payer.pubkey()),
+            &Default::default(),
+        );
+        let transaction = PartialTransaction::new(
+            message,
+            vec![StealthInput {
+                stealth_address: payment.stealth_address,
+                ephemeral_pubkey: payment.ephemeral_pubkey,
+                mlkem_ciphertext: payment.mlkem_ciphertext.unwrap(),
+            }],
+        )
+        .unwrap();
+        (transaction, fee_payer)
+    }
+
+    #[test]
+    fn test_offline_signing_round_trip() {
+        let recipient = StealthKeys::generate(&mut OsRng, true);
+        let (online, fee_payer) = unsigned(&recipient);
+        let stealth_address = online.inputs()[0].stealth_address;
+        assert_eq!(
+            online.missing_signers(),
+            [fee_payer.pubkey(), stealth_address]
+        );
+
+        // Someone else's keys don't match the input
+        let mut offline = PartialTransaction::from_bytes(&online.to_bytes()).unwrap();
+        let stranger = StealthKeys::generate(&mut OsRng, true);
+        assert_eq!(offline.sign_stealth(&stranger).unwrap(), 0);
+        assert_eq!(offline.sign_stealth(&recipient).unwrap(), 1);
+        assert_eq!(offline.missing_signers(), [fee_payer.pubkey()]);
+
+        let mut online = online;
+        assert!(online.sign(&fee_payer));
+        assert!(!online.sign(&SpendingKey::from_bytes(&[8u8; 32])));
+        assert!(matches!(
+            online.clone().into_transaction(),
+            Err(Error::MissingSignatures(1))
+        ));
+
+        let signed = PartialTransaction::from_bytes(&offline.to_bytes()).unwrap();
+        online.combine(&signed).unwrap();
+        let transaction = online.into_transaction().unwrap();
+        let message = transaction.message.serialize();
+        for (signature, signer) in transaction
+            .signatures
+            .iter()
+            .zip(&transaction.message.account_keys)
+        {
+            assert!(verify_signature(signer, &message, signature.as_array()));
+        }
+    }
+
+    #[test]
+    fn test_invalid_partial_transactions_are_rejected() {
+        let recipient = StealthKeys::generate(&mut OsRng, true);
+        let (mut transaction, fee_payer) = unsigned(&recipient);
+        transaction.sign(&fee_payer);
+        let payload = transaction.to_bytes();
+
+        // Changing the amount breaks the fee payer's signature
+        let mut tampered = payload.clone();
+        let message_len = u16::from_le_bytes([payload[1], payload[2]]) as usize;
+        tampered[3 + message_len - 8] ^= 1;
+        assert!(matches!(
+            PartialTransaction::from_bytes(&tampered),
+            Err(Error::InvalidPartialTransaction)
+        ));
+        assert!(PartialTransaction::from_bytes(&payload[..payload.len() - 1]).is_err());
+
+        // An input the message doesn't need a signature from
+        let mut extra = transaction.inputs()[0].clone();
+        extra.stealth_address = Pubkey::new_unique();
+        assert!(matches!(
+            PartialTransaction::new(transaction.message().clone(), vec![extra]),
+            Err(Error::InvalidPartialTransaction)
+        ));
+
+        // Signatures for another message can't be combined in
+        let (other, _) = unsigned(&recipient);
+        assert!(matches!(
+            transaction.combine(&other),
+            Err(Error::InvalidPartialTransaction)
+        ));
+    }
+
+    #[test]
+    fn test_bundle_round_trip() {
+        let recipient = StealthKeys::generate(&mut OsRng, true);
+        let (mut first, fee_payer) = unsigned(&recipient);
+        first.sign(&fee_payer);
+        let (second, _) = unsigned(&recipient);
+
+        let payload = encode_bundle(&[first.clone(), second.clone()]).unwrap();
+        assert_eq!(decode_bundle(&payload).unwrap(), [first, second]);
+        assert!(decode_bundle(&payload[..payload.len() - 1]).is_err());
+        assert!(decode_bundle(&[payload.as_slice(), &[0]].concat()).is_err());
+    }
+}
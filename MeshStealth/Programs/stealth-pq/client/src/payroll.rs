@@ -0,0 +1,442 @@
+//! Paying a roster of recipients in one run.
+//!
+//! [`Payroll::new`] generates a stealth address for every entry of the roster.
+//! Each payment is then announced on its own ([`Payout::announce`]), and the
+//! announced addresses are paid together by [`Payroll::transfers`], one
+//! `batch_transfer_to_stealth` per batch. Afterwards [`Payroll::fetch_report`]
+//! checks the stealth address balances against the roster.
+//!
+//! Every batch is its own transaction and succeeds or fails on its own, so a
+//! report can show some payees paid and others not; resend the transfers of
+//! the unpaid batches.
+//!
+//! [`Payroll::with_denominations`] pays each payee in several outputs of
+//! standard amounts instead (see [`split_amount`]), each to its own stealth
+//! address, so announcements can't be matched across payments by an unusual
+//! amount. Outputs paid in the same batch are still visibly from one sender.
+
+use std::collections::HashMap;
+
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::solana_program::instruction::Instruction;
+use rand_core::CryptoRngCore;
+use solana_rpc_client::nonblocking::rpc_client::RpcClient;
+use stealth_pq::DEFAULT_APP_ID;
+
+use crate::scanner::DEFAULT_PAGE_SIZE;
+use crate::{instructions, Error, MetaAddress, Result, StealthPayment};
+
+/// Payees per `batch_transfer_to_stealth`: fits a legacy transaction with room
+/// for a compute-budget instruction
+pub const DEFAULT_BATCH_SIZE: usize = 12;
+
+/// Standard denominations for [`Payroll::with_denominations`], in lamports:
+/// 10, 1, 0.1, 0.01 and 0.001 SOL
+pub const SOL_DENOMINATIONS: [u64; 5] = [
+    10_000_000_000,
+    1_000_000_000,
+    100_000_000,
+    10_000_000,
+    1_000_000,
+];
+
+/// Split `lamports` into amounts from `denominations`, largest first, followed
+/// by whatever is left below the smallest one.
+///
+/// Each denomination is used as often as it fits, so 2.3 SOL over
+/// [`SOL_DENOMINATIONS`] is two 1 SOL and three 0.1 SOL outputs. Without
+/// denominations the amount stays whole.
+pub fn split_amount(lamports: u64, denominations: &[u64]) -> Vec<u64> {
+    let mut denominations: Vec<u64> = denominations
+        .iter()
+        .copied()
+        .filter(|denomination| *denomination > 0)
+        .collect();
+    denominations.sort_unstable_by(|a, b| b.cmp(a));
+
+    let mut amounts = Vec::new();
+    let mut left = lamports;
+    for denomination in denominations {
+        let count = left / denomination;
+        amounts.extend(std::iter::repeat_n(denomination, count as usize));
+        left -= count * denomination;
+    }
+    if left > 0 {
+        amounts.push(left);
+    }
+    amounts
+}
+
+/// One entry of the roster.
+#[derive(Clone, Debug)]
+pub struct Payee {
+    /// The recipient's meta-address (must carry an ML-KEM key)
+    pub meta_address: MetaAddress,
+
+    /// Lamports to pay
+    pub lamports: u64,
+}
+
+/// A roster entry, or one output of it, with the stealth address generated for it.
+#[derive(Clone, Debug)]
+pub struct Payout {
+    /// Position of the payee in the roster
+    pub payee: usize,
+
+    /// Stealth address generated for the payee
+    pub payment: StealthPayment,
+
+    /// Lamports to pay
+    pub lamports: u64,
+}
+
+impl Payout {
+    /// Instructions announcing the payment: `init_ciphertext`,
+    /// `complete_ciphertext` and `finalize_ciphertext`.
+    ///
+    /// The ciphertext chunks don't fit in one transaction together, so send each
+    /// instruction in its own transaction, in order.
+    pub fn announce(
+        &self,
+        sender: &Pubkey,
+        rent_payer: Option<Pubkey>,
+        app_id: u32,
+    ) -> Result<Vec<Instruction>> {
+        Ok(vec![
+            instructions::init_ciphertext(sender, rent_payer, &self.payment, app_id, None, 0)?,
+            instructions::complete_ciphertext(sender, &self.payment, app_id)?,
+            instructions::finalize_ciphertext(sender, &self.payment.stealth_address, app_id),
+        ])
+    }
+}
+
+/// A payroll run.
+pub struct Payroll {
+    sender: Pubkey,
+    app_id: u32,
+    batch_size: usize,
+    payouts: Vec<Payout>,
+}
+
+impl Payroll {
+    /// Generate stealth addresses for a roster paid by `sender`.
+    ///
+    /// Fails with [`Error::InvalidPayroll`] if the roster is empty or pays zero to
+    /// anyone, both of which the program rejects.
+    pub fn new(sender: Pubkey, roster: &[Payee], rng: &mut impl CryptoRngCore) -> Result<Self> {
+        Self::with_denominations(sender, roster, &[], rng)
+    }
+
+    /// Like [`new`](Self::new), but paying each payee in the outputs
+    /// [`split_amount`] splits its amount into, each to a fresh stealth address.
+    ///
+    /// Every output is announced and reconciled on its own, so payouts and
+    /// report lines are per output, in roster order.
+    pub fn with_denominations(
+        sender: Pubkey,
+        roster: &[Payee],
+        denominations: &[u64],
+        rng: &mut impl CryptoRngCore,
+    ) -> Result<Self> {
+        if roster.is_empty() || roster.iter().any(|payee| payee.lamports == 0) {
+            return Err(Error::InvalidPayroll);
+        }
+
+        let mut payouts = Vec::with_capacity(roster.len());
+        for (index, payee) in roster.iter().enumerate() {
+            for lamports in split_amount(payee.lamports, denominations) {
+                payouts.push(Payout {
+                    payee: index,
+                    payment: StealthPayment::generate(&payee.meta_address, rng)?,
+                    lamports,
+                });
+            }
+        }
+
+        Ok(Self {
+            sender,
+            app_id: DEFAULT_APP_ID,
+            batch_size: DEFAULT_BATCH_SIZE,
+            payouts,
+        })
+    }
+
+    /// Announce the payments in `app_id`
+    pub fn app_id(mut self, app_id: u32) -> Self {
+        self.app_id = app_id;
+        self
+    }
+
+    /// Number of payees per transfer transaction (1 to `DEFAULT_BATCH_SIZE`)
+    pub fn batch_size(mut self, batch_size: usize) -> Self {
+        self.batch_size = batch_size.clamp(1, DEFAULT_BATCH_SIZE);
+        self
+    }
+
+    /// Payouts in roster order
+    pub fn payouts(&self) -> &[Payout] {
+        &self.payouts
+    }
+
+    /// Instructions announcing every payout, one per transaction in order;
+    /// see [`Payout::announce`]
+    pub fn announcements(&self, rent_payer: Option<Pubkey>) -> Result<Vec<Instruction>> {
+        let mut announcements = Vec::with_capacity(self.payouts.len() * 3);
+        for payout in &self.payouts {
+            announcements.extend(payout.announce(&self.sender, rent_payer, self.app_id)?);
+        }
+        Ok(announcements)
+    }
+
+    /// One `batch_transfer_to_stealth` per batch of payouts, each for its own
+    /// transaction. Send them once all announcements are confirmed.
+    pub fn transfers(&self, count_in_stats: bool) -> Vec<Instruction> {
+        self.payouts
+            .chunks(self.batch_size)
+            .map(|batch| {
+                let payments: Vec<(Pubkey, u64)> = batch
+                    .iter()
+                    .map(|payout| (payout.payment.stealth_address, payout.lamports))
+                    .collect();
+                instructions::batch_transfer_to_stealth(
+                    &self.sender,
+                    &payments,
+                    self.app_id,
+                    count_in_stats,
+                )
+            })
+            .collect()
+    }
+
+    /// Compare the roster with stealth address balances.
+    ///
+    /// Addresses missing from `balances` count as holding nothing.
+    pub fn reconcile(&self, balances: &HashMap<Pubkey, u64>) -> PayrollReport {
+        PayrollReport {
+            lines: self
+                .payouts
+                .iter()
+                .enumerate()
+                .map(|(index, payout)| PayrollLine {
+                    payee: payout.payee,
+                    batch: index / self.batch_size,
+                    stealth_address: payout.payment.stealth_address,
+                    expected: payout.lamports,
+                    received: balances
+                        .get(&payout.payment.stealth_address)
+                        .copied()
+                        .unwrap_or(0),
+                })
+                .collect(),
+        }
+    }
+
+    /// Fetch the stealth address balances and [`reconcile`](Self::reconcile) them.
+    pub async fn fetch_report(&self, rpc: &RpcClient) -> Result<PayrollReport> {
+        let addresses: Vec<Pubkey> = self
+            .payouts
+            .iter()
+            .map(|payout| payout.payment.stealth_address)
+            .collect();
+
+        let mut balances = HashMap::with_capacity(addresses.len());
+        for page in addresses.chunks(DEFAULT_PAGE_SIZE as usize) {
+            for (address, account) in page.iter().zip(rpc.get_multiple_accounts(page).await?) {
+                if let Some(account) = account {
+                    balances.insert(*address, account.lamports);
+                }
+            }
+        }
+
+        Ok(self.reconcile(&balances))
+    }
+}
+
+/// One payout's line in a [`PayrollReport`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PayrollLine {
+    /// Position of the payee in the roster
+    pub payee: usize,
+
+    /// Index of the transfer instruction that pays this payee
+    pub batch: usize,
+
+    /// Stealth address generated for the payee
+    pub stealth_address: Pubkey,
+
+    /// Lamports the roster pays
+    pub expected: u64,
+
+    /// Lamports the stealth address holds
+    pub received: u64,
+}
+
+impl PayrollLine {
+    /// Whether the stealth address holds at least the roster amount
+    pub fn is_paid(&self) -> bool {
+        self.received >= self.expected
+    }
+}
+
+/// Roster amounts against what arrived, in roster order.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct PayrollReport {
+    /// One line per payout
+    pub lines: Vec<PayrollLine>,
+}
+
+impl PayrollReport {
+    /// Total lamports the roster pays
+    pub fn total_expected(&self) -> u64 {
+        self.lines.iter().map(|line| line.expected).sum()
+    }
+
+    /// Lines whose stealth address holds less than the roster amount
+    pub fn unpaid(&self) -> impl Iterator<Item = &PayrollLine> {
+        self.lines.iter().filter(|line| !line.is_paid())
+    }
+
+    /// Transfer instructions (by index into [`Payroll::transfers`]) with an unpaid line
+    pub fn unpaid_batches(&self) -> Vec<usize> {
+        let mut batches: Vec<usize> = self.unpaid().map(|line| line.batch).collect();
+        batches.dedup();
+        batches
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::StealthKeys;
+    use rand_core::OsRng;
+
+    fn roster(len: usize) -> Vec<Payee> {
+        (0..len)
+            .map(|index| Payee {
+                meta_address: StealthKeys::generate(&mut OsRng, true).meta_address(),
+                lamports: 1_000 * (index as u64 + 1),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_payroll_batches_transfers() {
+        let sender = Pubkey::new_unique();
+        let payroll = Payroll::new(sender, &roster(25), &mut OsRng)
+            .unwrap()
+            .app_id(4);
+
+        assert_eq!(payroll.announcements(None).unwrap().len(), 25 * 3);
+
+        let transfers = payroll.transfers(false);
+        assert_eq!(transfers.len(), 3);
+        // sender, system program, stats placeholder, then two accounts per payee
+        let pairs: Vec<usize> = transfers
+            .iter()
+            .map(|ix| (ix.accounts.len() - 3) / 2)
+            .collect();
+        assert_eq!(pairs, [12, 12, 1]);
+
+        let last = &payroll.payouts()[24];
+        assert_eq!(
+            transfers[2].accounts[3].pubkey,
+            last.payment.stealth_address
+        );
+        assert_eq!(
+            transfers[2].accounts[4].pubkey,
+            crate::pda::ciphertext_account(&last.payment.stealth_address, 4).0
+        );
+        assert_eq!(transfers[2].data[12..], 25_000u64.to_le_bytes());
+    }
+
+    #[test]
+    fn test_payroll_rejects_invalid_roster() {
+        let sender = Pubkey::new_unique();
+        let mut unpaid = roster(2);
+        unpaid[1].lamports = 0;
+
+        assert!(matches!(
+            Payroll::new(sender, &[], &mut OsRng),
+            Err(Error::InvalidPayroll)
+        ));
+        assert!(matches!(
+            Payroll::new(sender, &unpaid, &mut OsRng),
+            Err(Error::InvalidPayroll)
+        ));
+    }
+
+    #[test]
+    fn test_payroll_reconcile() {
+        let payroll = Payroll::new(Pubkey::new_unique(), &roster(5), &mut OsRng)
+            .unwrap()
+            .batch_size(2);
+        let address = |index: usize| payroll.payouts()[index].payment.stealth_address;
+
+        // The second batch (payees 2 and 3) never landed
+        let balances = HashMap::from([
+            (address(0), 1_000),
+            (address(1), 2_000),
+            (address(4), 5_000),
+        ]);
+        let report = payroll.reconcile(&balances);
+
+        assert_eq!(report.total_expected(), 15_000);
+        assert_eq!(
+            report.unpaid().map(|line| line.payee).collect::<Vec<_>>(),
+            [2, 3]
+        );
+        assert_eq!(report.unpaid_batches(), [1]);
+        assert_eq!(report.lines[4].batch, 2);
+        assert!(report.lines[4].is_paid());
+    }
+
+    #[test]
+    fn test_split_amount() {
+        const SOL: u64 = 1_000_000_000;
+
+        assert_eq!(
+            split_amount(2 * SOL + 340_000_500, &SOL_DENOMINATIONS),
+            [
+                SOL,
+                SOL,
+                SOL / 10,
+                SOL / 10,
+                SOL / 10,
+                SOL / 100,
+                SOL / 100,
+                SOL / 100,
+                SOL / 100,
+                500
+            ]
+        );
+        assert_eq!(split_amount(SOL, &SOL_DENOMINATIONS), [SOL]);
+        // Order and zeros in the denominations don't matter
+        assert_eq!(split_amount(25, &[0, 2, 10]), [10, 10, 2, 2, 1]);
+        assert_eq!(split_amount(25, &[]), [25]);
+    }
+
+    #[test]
+    fn test_payroll_with_denominations() {
+        let roster = roster(2);
+        let payroll =
+            Payroll::with_denominations(Pubkey::new_unique(), &roster, &[1_000], &mut OsRng)
+                .unwrap();
+
+        // 1,000 and 2,000 lamports: one output for the first payee, two for the second
+        let payouts = payroll.payouts();
+        assert_eq!(
+            payouts
+                .iter()
+                .map(|payout| payout.payee)
+                .collect::<Vec<_>>(),
+            [0, 1, 1]
+        );
+        assert!(payouts.iter().all(|payout| payout.lamports == 1_000));
+        assert_ne!(
+            payouts[1].payment.stealth_address,
+            payouts[2].payment.stealth_address
+        );
+        assert_eq!(payroll.announcements(None).unwrap().len(), 3 * 3);
+        assert_eq!(payroll.reconcile(&HashMap::new()).total_expected(), 3_000);
+    }
+}
@@ -0,0 +1,48 @@
+//! Program-derived addresses, using the same seeds as the program's account constraints.
+
+use anchor_lang::prelude::Pubkey;
+use stealth_pq::CiphertextAccount;
+
+/// CiphertextAccount for a stealth address:
+/// ["ciphertext", stealth_address, app_id (u32 LE, omitted for DEFAULT_APP_ID)]
+pub fn ciphertext_account(stealth_address: &Pubkey, app_id: u32) -> (Pubkey, u8) {
+    let app_id = app_id.to_le_bytes();
+    Pubkey::find_program_address(
+        &[
+            b"ciphertext",
+            stealth_address.as_ref(),
+            CiphertextAccount::app_id_seed(&app_id),
+        ],
+        &stealth_pq::ID,
+    )
+}
+
+/// Per-app announcement counter: ["namespace", app_id (u32 LE)]
+pub fn namespace_counter(app_id: u32) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"namespace", &app_id.to_le_bytes()], &stealth_pq::ID)
+}
+
+/// Global announcement log: ["announcement_log"]
+pub fn announcement_log() -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"announcement_log"], &stealth_pq::ID)
+}
+
+/// Announcement log entry: ["announcement", index (u64 LE)]
+pub fn announcement(index: u64) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"announcement", &index.to_le_bytes()], &stealth_pq::ID)
+}
+
+/// Global stats account: ["stats"]
+pub fn stats() -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"stats"], &stealth_pq::ID)
+}
+
+/// Meta-address registry entry of a wallet: ["meta", owner]
+pub fn meta_address(owner: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"meta", owner.as_ref()], &stealth_pq::ID)
+}
+
+/// EVM attestation of a wallet: ["evm", owner]
+pub fn evm_attestation(owner: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"evm", owner.as_ref()], &stealth_pq::ID)
+}
@@ -0,0 +1,480 @@
+//! Checking a whole payment against current state before sending any of it.
+//!
+//! A payment takes several transactions ([`Step`]). One that fails halfway
+//! leaves rent locked in a ciphertext account nobody will pay into, so
+//! [`PaymentFlow::preflight`] checks the flow up front: the stealth address is
+//! unused, the sender can cover rent, fees and the amount, and the steps that
+//! only depend on current state simulate cleanly. The later steps read accounts
+//! the earlier ones create and can't be simulated before those land.
+//!
+//! [`PaymentFlow::unsigned_transactions`] builds the same steps as transactions
+//! for a sender that signs offline. When signing and sending are further apart
+//! than a blockhash lives, [`PaymentFlow::nonce_transactions`] builds them on
+//! durable nonces instead.
+
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::solana_program::instruction::Instruction;
+use solana_hash::Hash;
+use solana_message::Message;
+use solana_rpc_client::nonblocking::rpc_client::RpcClient;
+use solana_rpc_client_api::config::RpcSimulateTransactionConfig;
+use solana_transaction::Transaction;
+use stealth_pq::{Announcement, AnnouncementLog, CiphertextAccount, NamespaceCounter};
+
+use crate::scanner::decode;
+use crate::{instructions, pda, Error, PartialTransaction, Result, StealthPayment};
+
+/// Size of an initialized nonce account: version (4), state (4), authority
+/// (32), nonce (32), fee calculator (8)
+const NONCE_ACCOUNT_SIZE: usize = 80;
+
+/// One transaction of a payment, in the order they are sent.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Step {
+    /// `init_announcement_log`, only on a cluster without a log yet
+    InitAnnouncementLog,
+    /// `init_ciphertext` with the first ciphertext chunk
+    InitCiphertext,
+    /// `complete_ciphertext` with the rest of the ciphertext
+    CompleteCiphertext,
+    /// `finalize_ciphertext`
+    FinalizeCiphertext,
+    /// `log_announcement`, so scanners find the payment
+    LogAnnouncement,
+    /// `transfer_to_stealth`
+    Transfer,
+}
+
+/// Something that would make the payment fail partway.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Problem {
+    /// The stealth address already has a ciphertext account
+    AddressTaken(Pubkey),
+
+    /// The sender can't cover rent, fees and the amount
+    InsufficientBalance { needed: u64, available: u64 },
+
+    /// A step failed in simulation
+    SimulationFailed {
+        step: Step,
+        error: String,
+        logs: Vec<String>,
+    },
+}
+
+/// Outcome of [`PaymentFlow::preflight`].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Preflight {
+    /// Rent for the accounts the flow creates
+    pub rent: u64,
+
+    /// Fees for all of the flow's transactions
+    pub fees: u64,
+
+    /// Lamports paid to the stealth address
+    pub lamports: u64,
+
+    /// The sender's current balance
+    pub balance: u64,
+
+    /// Empty if the flow is expected to go through
+    pub problems: Vec<Problem>,
+}
+
+impl Preflight {
+    /// Lamports the flow costs the sender in total
+    pub fn total(&self) -> u64 {
+        self.rent + self.fees + self.lamports
+    }
+
+    /// Whether no problem was found
+    pub fn is_ok(&self) -> bool {
+        self.problems.is_empty()
+    }
+}
+
+/// A durable nonce account and the nonce it holds, standing in for a recent
+/// blockhash in a transaction that is sent long after it is signed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DurableNonce {
+    /// The nonce account
+    pub account: Pubkey,
+
+    /// The account allowed to advance it, which signs the transaction
+    pub authority: Pubkey,
+
+    /// The nonce the account holds now
+    pub nonce: Hash,
+}
+
+impl DurableNonce {
+    /// Read the nonce `account` holds
+    pub async fn fetch(rpc: &RpcClient, account: &Pubkey) -> Result<Self> {
+        Self::decode(account, &rpc.get_account_data(account).await?)
+    }
+
+    /// Decode the data of an initialized nonce account
+    pub fn decode(account: &Pubkey, data: &[u8]) -> Result<Self> {
+        // Version 0 (legacy) or 1 (current), then state 1 (initialized)
+        if data.len() != NONCE_ACCOUNT_SIZE
+            || u32::from_le_bytes(data[..4].try_into().unwrap()) > 1
+            || u32::from_le_bytes(data[4..8].try_into().unwrap()) != 1
+        {
+            return Err(Error::AccountDecode(*account));
+        }
+        Ok(Self {
+            account: *account,
+            authority: Pubkey::new_from_array(data[8..40].try_into().unwrap()),
+            nonce: Hash::new_from_array(data[40..72].try_into().unwrap()),
+        })
+    }
+}
+
+/// The transactions of a hybrid payment, paid for and signed by the sender.
+pub struct PaymentFlow {
+    sender: Pubkey,
+    stealth_address: Pubkey,
+    app_id: u32,
+    lamports: u64,
+    announcement_index: u64,
+    steps: Vec<(Step, Instruction)>,
+}
+
+impl PaymentFlow {
+    /// Build the flow for `payment`, logging it at the next free index of the
+    /// announcement log (and creating the log if there is none).
+    pub async fn new(
+        rpc: &RpcClient,
+        sender: &Pubkey,
+        payment: &StealthPayment,
+        app_id: u32,
+        lamports: u64,
+    ) -> Result<Self> {
+        let log_address = pda::announcement_log().0;
+        let log = fetch(rpc, &log_address).await?;
+        let announcement_index = match &log {
+            Some(data) => decode::<AnnouncementLog>(&log_address, data)?.count,
+            None => 0,
+        };
+
+        let mut steps = Vec::with_capacity(6);
+        if log.is_none() {
+            steps.push((
+                Step::InitAnnouncementLog,
+                instructions::init_announcement_log(sender),
+            ));
+        }
+        steps.extend([
+            (
+                Step::InitCiphertext,
+                instructions::init_ciphertext(sender, None, payment, app_id, None, 0)?,
+            ),
+            (
+                Step::CompleteCiphertext,
+                instructions::complete_ciphertext(sender, payment, app_id)?,
+            ),
+            (
+                Step::FinalizeCiphertext,
+                instructions::finalize_ciphertext(sender, &payment.stealth_address, app_id),
+            ),
+            (
+                Step::LogAnnouncement,
+                instructions::log_announcement(
+                    sender,
+                    &payment.stealth_address,
+                    app_id,
+                    announcement_index,
+                ),
+            ),
+            (
+                Step::Transfer,
+                instructions::transfer_to_stealth(
+                    sender,
+                    &payment.stealth_address,
+                    app_id,
+                    lamports,
+                    false,
+                ),
+            ),
+        ]);
+
+        Ok(Self {
+            sender: *sender,
+            stealth_address: payment.stealth_address,
+            app_id,
+            lamports,
+            announcement_index,
+            steps,
+        })
+    }
+
+    /// Steps in order, one transaction each; send each once the previous one
+    /// is confirmed
+    pub fn steps(&self) -> &[(Step, Instruction)] {
+        &self.steps
+    }
+
+    /// Index of the announcement log entry the flow writes
+    pub fn announcement_index(&self) -> u64 {
+        self.announcement_index
+    }
+
+    /// The steps as unsigned transactions committing to `blockhash`, in order,
+    /// for the sender to sign offline.
+    ///
+    /// Export them with [`offline::encode_bundle`](crate::offline::encode_bundle).
+    /// Send each once the previous one is confirmed, before `blockhash`
+    /// expires.
+    pub fn unsigned_transactions(&self, blockhash: &Hash) -> Result<Vec<PartialTransaction>> {
+        self.steps
+            .iter()
+            .map(|(_, instruction)| {
+                let message = Message::new_with_blockhash(
+                    std::slice::from_ref(instruction),
+                    Some(&self.sender),
+                    blockhash,
+                );
+                PartialTransaction::new(message, Vec::new())
+            })
+            .collect()
+    }
+
+    /// The steps as unsigned transactions on durable nonces, one nonce per step,
+    /// for a sender that signs long before the flow is sent.
+    ///
+    /// Each transaction first advances its own nonce, so the transactions stay
+    /// valid until sent and each can land only once. A nonce changes when its
+    /// transaction lands, and the following step can't know the new value in
+    /// advance, which is why no two steps share a nonce account. Send each
+    /// after the previous one is confirmed, and [`DurableNonce::fetch`] the
+    /// accounts again before reusing them. Each nonce's authority signs along
+    /// with the sender.
+    ///
+    /// Fails with [`Error::WrongNonceCount`] unless there is exactly one nonce
+    /// per step.
+    pub fn nonce_transactions(&self, nonces: &[DurableNonce]) -> Result<Vec<PartialTransaction>> {
+        if nonces.len() != self.steps.len() {
+            return Err(Error::WrongNonceCount(self.steps.len()));
+        }
+        self.steps
+            .iter()
+            .zip(nonces)
+            .map(|((_, instruction), nonce)| {
+                let mut message = Message::new_with_nonce(
+                    vec![instruction.clone()],
+                    Some(&self.sender),
+                    &nonce.account,
+                    &nonce.authority,
+                );
+                message.recent_blockhash = nonce.nonce;
+                PartialTransaction::new(message, Vec::new())
+            })
+            .collect()
+    }
+
+    /// Check the flow against current state without sending anything.
+    pub async fn preflight(&self, rpc: &RpcClient) -> Result<Preflight> {
+        let mut problems = Vec::new();
+
+        let ciphertext_account = pda::ciphertext_account(&self.stealth_address, self.app_id).0;
+        if fetch(rpc, &ciphertext_account).await?.is_some() {
+            problems.push(Problem::AddressTaken(self.stealth_address));
+        }
+
+        let mut sizes = vec![
+            CiphertextAccount::init_space(stealth_pq::KEM_VARIANT_ML_KEM_768),
+            8 + Announcement::SIZE,
+        ];
+        if self.creates_log() {
+            sizes.push(8 + AnnouncementLog::SIZE);
+        }
+        if fetch(rpc, &pda::namespace_counter(self.app_id).0)
+            .await?
+            .is_none()
+        {
+            sizes.push(8 + NamespaceCounter::SIZE);
+        }
+        let mut rent = 0;
+        for size in sizes {
+            rent += rpc.get_minimum_balance_for_rent_exemption(size).await?;
+        }
+
+        let blockhash = rpc.get_latest_blockhash().await?;
+        let mut fees = 0;
+        for (step, instruction) in &self.steps {
+            let message = Message::new_with_blockhash(
+                std::slice::from_ref(instruction),
+                Some(&self.sender),
+                &blockhash,
+            );
+            fees += rpc.get_fee_for_message(&message).await?;
+
+            // Steps after init_ciphertext read the accounts it creates
+            if matches!(step, Step::InitAnnouncementLog | Step::InitCiphertext) {
+                if let Some(problem) = simulate(rpc, *step, message).await? {
+                    problems.push(problem);
+                }
+            }
+        }
+
+        let balance = rpc.get_balance(&self.sender).await?;
+        let needed = rent + fees + self.lamports;
+        if balance < needed {
+            problems.push(Problem::InsufficientBalance {
+                needed,
+                available: balance,
+            });
+        }
+
+        Ok(Preflight {
+            rent,
+            fees,
+            lamports: self.lamports,
+            balance,
+            problems,
+        })
+    }
+
+    fn creates_log(&self) -> bool {
+        self.steps
+            .iter()
+            .any(|(step, _)| *step == Step::InitAnnouncementLog)
+    }
+}
+
+async fn fetch(rpc: &RpcClient, address: &Pubkey) -> Result<Option<Vec<u8>>> {
+    Ok(rpc
+        .get_account_with_commitment(address, rpc.commitment())
+        .await?
+        .value
+        .map(|account| account.data))
+}
+
+async fn simulate(rpc: &RpcClient, step: Step, message: Message) -> Result<Option<Problem>> {
+    let result = rpc
+        .simulate_transaction_with_config(
+            &Transaction::new_unsigned(message),
+            RpcSimulateTransactionConfig {
+                sig_verify: false,
+                replace_recent_blockhash: true,
+                commitment: Some(rpc.commitment()),
+                ..RpcSimulateTransactionConfig::default()
+            },
+        )
+        .await?
+        .value;
+
+    Ok(result.err.map(|error| Problem::SimulationFailed {
+        step,
+        error: error.to_string(),
+        logs: result.logs.unwrap_or_default(),
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::StealthKeys;
+    use futures::executor::block_on;
+    use rand_core::OsRng;
+
+    #[test]
+    fn test_preflight_reports_shortfall() {
+        // The mock cluster has no accounts, 20 lamports of rent per account, free
+        // transactions and a 50-lamport balance
+        let rpc = RpcClient::new_mock("succeeds".to_string());
+        let sender = Pubkey::new_unique();
+        let payment = StealthPayment::generate(
+            &StealthKeys::generate(&mut OsRng, true).meta_address(),
+            &mut OsRng,
+        )
+        .unwrap();
+
+        let flow = block_on(PaymentFlow::new(&rpc, &sender, &payment, 3, 1_000)).unwrap();
+        assert_eq!(flow.announcement_index(), 0);
+        assert_eq!(
+            flow.steps()
+                .iter()
+                .map(|(step, _)| *step)
+                .collect::<Vec<_>>(),
+            [
+                Step::InitAnnouncementLog,
+                Step::InitCiphertext,
+                Step::CompleteCiphertext,
+                Step::FinalizeCiphertext,
+                Step::LogAnnouncement,
+                Step::Transfer,
+            ]
+        );
+
+        let preflight = block_on(flow.preflight(&rpc)).unwrap();
+        // Ciphertext account, log entry, log and namespace counter
+        assert_eq!(preflight.rent, 4 * 20);
+        assert_eq!(preflight.total(), 1_080);
+        assert_eq!(
+            preflight.problems,
+            [Problem::InsufficientBalance {
+                needed: 1_080,
+                available: 50,
+            }]
+        );
+
+        let transactions = flow.unsigned_transactions(&Hash::new_unique()).unwrap();
+        assert_eq!(transactions.len(), flow.steps().len());
+        for (transaction, (_, instruction)) in transactions.iter().zip(flow.steps()) {
+            assert_eq!(transaction.missing_signers(), [sender]);
+            assert_eq!(transaction.message().instructions[0].data, instruction.data);
+        }
+
+        // On nonces, each step advances its own nonce first
+        let authority = Pubkey::new_unique();
+        let nonces: Vec<DurableNonce> = (0..flow.steps().len())
+            .map(|_| DurableNonce {
+                account: Pubkey::new_unique(),
+                authority,
+                nonce: Hash::new_unique(),
+            })
+            .collect();
+        let transactions = flow.nonce_transactions(&nonces).unwrap();
+        for ((transaction, (_, instruction)), nonce) in
+            transactions.iter().zip(flow.steps()).zip(&nonces)
+        {
+            let message = transaction.message();
+            assert_eq!(message.recent_blockhash, nonce.nonce);
+            assert_eq!(message.instructions[0].data, 4u32.to_le_bytes());
+            assert_eq!(message.instructions[1].data, instruction.data);
+            assert_eq!(transaction.missing_signers(), [sender, authority]);
+        }
+        assert!(matches!(
+            flow.nonce_transactions(&nonces[1..]),
+            Err(Error::WrongNonceCount(6))
+        ));
+    }
+
+    #[test]
+    fn test_durable_nonce_decode() {
+        let account = Pubkey::new_unique();
+        let authority = Pubkey::new_unique();
+        let nonce = Hash::new_unique();
+        let mut data = [1u32.to_le_bytes(), 1u32.to_le_bytes()].concat();
+        data.extend_from_slice(authority.as_ref());
+        data.extend_from_slice(nonce.as_ref());
+        data.extend_from_slice(&5_000u64.to_le_bytes());
+
+        assert_eq!(
+            DurableNonce::decode(&account, &data).unwrap(),
+            DurableNonce {
+                account,
+                authority,
+                nonce,
+            }
+        );
+
+        // An uninitialized nonce account holds no nonce
+        data[4] = 0;
+        assert!(matches!(
+            DurableNonce::decode(&account, &data),
+            Err(Error::AccountDecode(_))
+        ));
+    }
+}
@@ -0,0 +1,120 @@
+//! Resolving wallets to meta-addresses through the on-chain registry.
+//!
+//! [`fetch_meta_address`] reads the registry on every call. [`RegistryCache`]
+//! keeps entries for a while instead, for senders paying the same wallets
+//! repeatedly.
+
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use anchor_lang::prelude::Pubkey;
+use solana_rpc_client::nonblocking::rpc_client::RpcClient;
+use stealth_pq::{MetaAddressEvent, MetaAddressRegistry};
+
+use crate::scanner::decode;
+use crate::{pda, MetaAddress, Result};
+
+/// Fetch the meta-address `owner` published, or `None` if there is none.
+///
+/// The ML-KEM key is uploaded after registration (see
+/// [`register_meta_address`](crate::instructions::register_meta_address)); until
+/// then the entry reads as a classical meta-address.
+pub async fn fetch_meta_address(rpc: &RpcClient, owner: &Pubkey) -> Result<Option<MetaAddress>> {
+    let address = pda::meta_address(owner).0;
+    let Some(account) = rpc
+        .get_account_with_commitment(&address, rpc.commitment())
+        .await?
+        .value
+    else {
+        return Ok(None);
+    };
+
+    let entry: MetaAddressRegistry = decode(&address, &account.data)?;
+    Ok(Some(MetaAddress::from(&entry)))
+}
+
+/// Registry entries fetched within the last `ttl`, by owner.
+///
+/// Wallets with no entry are cached too, as `None`. Entries change only through
+/// the owner's `update_meta_address` and `close_meta_address`, so an entry can
+/// be stale for up to `ttl`; feed [`on_event`](Self::on_event) with
+/// `MetaAddressEvent`s to drop updated entries as soon as they change.
+pub struct RegistryCache {
+    ttl: Duration,
+    entries: HashMap<Pubkey, (Instant, Option<MetaAddress>)>,
+}
+
+impl RegistryCache {
+    /// Empty cache keeping entries for `ttl`
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            entries: HashMap::new(),
+        }
+    }
+
+    /// The meta-address `owner` published, from the cache if fetched within the
+    /// TTL, otherwise through [`fetch_meta_address`]
+    pub async fn get(&mut self, rpc: &RpcClient, owner: &Pubkey) -> Result<Option<MetaAddress>> {
+        if let Some((fetched_at, meta_address)) = self.entries.get(owner) {
+            if fetched_at.elapsed() < self.ttl {
+                return Ok(meta_address.clone());
+            }
+        }
+
+        let meta_address = fetch_meta_address(rpc, owner).await?;
+        self.entries
+            .insert(*owner, (Instant::now(), meta_address.clone()));
+        Ok(meta_address)
+    }
+
+    /// Drop the entry of `owner`, e.g. after a payment to it failed
+    pub fn invalidate(&mut self, owner: &Pubkey) {
+        self.entries.remove(owner);
+    }
+
+    /// Drop the entry a registry event replaced
+    pub fn on_event(&mut self, event: &MetaAddressEvent) {
+        self.invalidate(&event.owner);
+    }
+
+    /// Drop every entry
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::StealthKeys;
+    use futures::executor::block_on;
+    use rand_core::OsRng;
+
+    #[test]
+    fn test_registry_cache_ttl_and_invalidation() {
+        // The mock cluster has no registry entries
+        let rpc = RpcClient::new_mock("succeeds".to_string());
+        let owner = Pubkey::new_unique();
+        let cached = StealthKeys::generate(&mut OsRng, true).meta_address();
+
+        let mut cache = RegistryCache::new(Duration::from_secs(60));
+        cache
+            .entries
+            .insert(owner, (Instant::now(), Some(cached.clone())));
+        assert_eq!(
+            block_on(cache.get(&rpc, &owner))
+                .unwrap()
+                .map(|m| m.to_bytes()),
+            Some(cached.to_bytes())
+        );
+
+        cache.on_event(&MetaAddressEvent { owner, epoch: 2 });
+        assert!(block_on(cache.get(&rpc, &owner)).unwrap().is_none());
+
+        // Expired entries are fetched again
+        let mut cache = RegistryCache::new(Duration::ZERO);
+        cache.entries.insert(owner, (Instant::now(), Some(cached)));
+        assert!(block_on(cache.get(&rpc, &owner)).unwrap().is_none());
+    }
+}
@@ -0,0 +1,253 @@
+//! Signed payment requests for QR codes and NFC tags.
+//!
+//! A merchant encodes what to pay and signs it with its wallet key
+//! ([`PaymentRequest::sign`]); the sender checks the signature and expiry before
+//! paying ([`PaymentRequest::verify`]). Payload layout, integers little-endian:
+//!
+//! ```text
+//! version (1) = 1
+//! target  (1) = 0: wallet (32), the meta-address is read from the registry
+//!               1: meta-address length (2) || meta-address (64 or 1248)
+//! lamports (8)
+//! expires_at (8), Unix timestamp, 0 for none
+//! reference length (1) || reference (up to 32)
+//! merchant wallet (32)
+//! signature (64), Ed25519 by the merchant wallet over everything before it
+//! ```
+//!
+//! A wallet target keeps the payload under 180 bytes, which fits an NTAG215
+//! tag; an inline hybrid meta-address (over 1.3 KB) needs a QR code. The merchant
+//! wallet must sign for its own wallet target, so a request can't redirect
+//! payments meant for another wallet's registry entry.
+
+use anchor_lang::prelude::Pubkey;
+use solana_rpc_client::nonblocking::rpc_client::RpcClient;
+
+use crate::keys::verify_signature;
+use crate::{fetch_meta_address, Error, MetaAddress, Result, SpendingKey};
+
+/// Payload version written by [`PaymentRequest::sign`]
+pub const PAYMENT_REQUEST_VERSION: u8 = 1;
+
+/// Longest reference (e.g. an order number) a request can carry
+pub const MAX_REFERENCE_SIZE: usize = 32;
+
+const TARGET_WALLET: u8 = 0;
+const TARGET_META_ADDRESS: u8 = 1;
+
+/// Where a [`PaymentRequest`] asks to be paid.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum RequestTarget {
+    /// The meta-address this wallet published in the registry
+    Wallet(Pubkey),
+    /// A meta-address carried in the request
+    MetaAddress(MetaAddress),
+}
+
+/// What a merchant asks a sender to pay.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PaymentRequest {
+    /// Where to pay
+    pub target: RequestTarget,
+
+    /// Lamports to pay
+    pub lamports: u64,
+
+    /// Unix timestamp after which the request must not be paid
+    pub expires_at: Option<i64>,
+
+    /// Merchant reference, at most `MAX_REFERENCE_SIZE` bytes
+    pub reference: Vec<u8>,
+}
+
+impl PaymentRequest {
+    /// Encode and sign the request with the merchant's wallet key.
+    ///
+    /// Fails with [`Error::InvalidPaymentRequest`] for an oversized reference, a
+    /// zero amount, or a wallet target other than `merchant`'s own wallet.
+    pub fn sign(&self, merchant: &SpendingKey) -> Result<Vec<u8>> {
+        if self.reference.len() > MAX_REFERENCE_SIZE || self.lamports == 0 {
+            return Err(Error::InvalidPaymentRequest);
+        }
+
+        let mut payload = Vec::with_capacity(256);
+        payload.push(PAYMENT_REQUEST_VERSION);
+        match &self.target {
+            RequestTarget::Wallet(wallet) => {
+                if *wallet != merchant.pubkey() {
+                    return Err(Error::InvalidPaymentRequest);
+                }
+                payload.push(TARGET_WALLET);
+                payload.extend_from_slice(wallet.as_ref());
+            }
+            RequestTarget::MetaAddress(meta_address) => {
+                let bytes = meta_address.to_bytes();
+                payload.push(TARGET_META_ADDRESS);
+                payload.extend_from_slice(&(bytes.len() as u16).to_le_bytes());
+                payload.extend_from_slice(&bytes);
+            }
+        }
+        payload.extend_from_slice(&self.lamports.to_le_bytes());
+        payload.extend_from_slice(&self.expires_at.unwrap_or(0).to_le_bytes());
+        payload.push(self.reference.len() as u8);
+        payload.extend_from_slice(&self.reference);
+        payload.extend_from_slice(merchant.pubkey().as_ref());
+
+        let signature = merchant.sign(&payload);
+        payload.extend_from_slice(&signature);
+        Ok(payload)
+    }
+
+    /// Decode a payload and check its signature and expiry against `now`.
+    ///
+    /// # Returns
+    /// The request and the merchant wallet that signed it. For a meta-address
+    /// target, the sender decides whether it trusts that wallet.
+    pub fn verify(payload: &[u8], now: i64) -> Result<(Self, Pubkey)> {
+        let mut reader = Reader(payload);
+        if reader.take(1)?[0] != PAYMENT_REQUEST_VERSION {
+            return Err(Error::InvalidPaymentRequest);
+        }
+        let target = match reader.take(1)?[0] {
+            TARGET_WALLET => RequestTarget::Wallet(reader.pubkey()?),
+            TARGET_META_ADDRESS => {
+                let len = u16::from_le_bytes(reader.array()?) as usize;
+                RequestTarget::MetaAddress(
+                    MetaAddress::from_bytes(reader.take(len)?)
+                        .map_err(|_| Error::InvalidPaymentRequest)?,
+                )
+            }
+            _ => return Err(Error::InvalidPaymentRequest),
+        };
+        let lamports = u64::from_le_bytes(reader.array()?);
+        let expires_at = i64::from_le_bytes(reader.array()?);
+        let reference_len = reader.take(1)?[0] as usize;
+        if reference_len > MAX_REFERENCE_SIZE {
+            return Err(Error::InvalidPaymentRequest);
+        }
+        let reference = reader.take(reference_len)?.to_vec();
+        let merchant = reader.pubkey()?;
+
+        let signed = &payload[..payload.len() - reader.0.len()];
+        let signature: [u8; 64] = reader.array()?;
+        if !reader.0.is_empty() || !verify_signature(&merchant, signed, &signature) {
+            return Err(Error::InvalidPaymentRequest);
+        }
+        if matches!(&target, RequestTarget::Wallet(wallet) if *wallet != merchant) {
+            return Err(Error::InvalidPaymentRequest);
+        }
+        if expires_at != 0 && now > expires_at {
+            return Err(Error::PaymentRequestExpired(expires_at));
+        }
+
+        let request = Self {
+            target,
+            lamports,
+            expires_at: (expires_at != 0).then_some(expires_at),
+            reference,
+        };
+        Ok((request, merchant))
+    }
+
+    /// The meta-address to pay, reading the registry for a wallet target
+    /// (`None` if the wallet has no entry)
+    pub async fn meta_address(&self, rpc: &RpcClient) -> Result<Option<MetaAddress>> {
+        match &self.target {
+            RequestTarget::Wallet(wallet) => fetch_meta_address(rpc, wallet).await,
+            RequestTarget::MetaAddress(meta_address) => Ok(Some(meta_address.clone())),
+        }
+    }
+}
+
+struct Reader<'a>(&'a [u8]);
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8]> {
+        if self.0.len() < len {
+            return Err(Error::InvalidPaymentRequest);
+        }
+        let (head, rest) = self.0.split_at(len);
+        self.0 = rest;
+        Ok(head)
+    }
+
+    fn array<const N: usize>(&mut self) -> Result<[u8; N]> {
+        Ok(self.take(N)?.try_into().unwrap())
+    }
+
+    fn pubkey(&mut self) -> Result<Pubkey> {
+        Ok(Pubkey::new_from_array(self.array()?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::StealthKeys;
+    use rand_core::OsRng;
+
+    fn merchant() -> SpendingKey {
+        SpendingKey::from_bytes(&[9u8; 32])
+    }
+
+    #[test]
+    fn test_wallet_request_round_trip() {
+        let merchant = merchant();
+        let request = PaymentRequest {
+            target: RequestTarget::Wallet(merchant.pubkey()),
+            lamports: 25_000_000,
+            expires_at: Some(1_800_000_000),
+            reference: b"order 42".to_vec(),
+        };
+        let payload = request.sign(&merchant).unwrap();
+        assert_eq!(payload.len(), 1 + 1 + 32 + 8 + 8 + 1 + 8 + 32 + 64);
+
+        let (verified, signer) = PaymentRequest::verify(&payload, 1_700_000_000).unwrap();
+        assert_eq!(verified, request);
+        assert_eq!(signer, merchant.pubkey());
+
+        assert!(matches!(
+            PaymentRequest::verify(&payload, 1_800_000_001),
+            Err(Error::PaymentRequestExpired(1_800_000_000))
+        ));
+
+        // Raising the amount breaks the signature
+        let mut tampered = payload.clone();
+        tampered[34] ^= 1;
+        assert!(matches!(
+            PaymentRequest::verify(&tampered, 0),
+            Err(Error::InvalidPaymentRequest)
+        ));
+        assert!(PaymentRequest::verify(&payload[..payload.len() - 1], 0).is_err());
+    }
+
+    #[test]
+    fn test_meta_address_request() {
+        let meta_address = StealthKeys::generate(&mut OsRng, true).meta_address();
+        let request = PaymentRequest {
+            target: RequestTarget::MetaAddress(meta_address.clone()),
+            lamports: 1,
+            expires_at: None,
+            reference: Vec::new(),
+        };
+        let payload = request.sign(&merchant()).unwrap();
+
+        let (verified, _) = PaymentRequest::verify(&payload, i64::MAX).unwrap();
+        assert_eq!(verified.target, RequestTarget::MetaAddress(meta_address));
+        assert_eq!(verified.expires_at, None);
+    }
+
+    #[test]
+    fn test_request_for_another_wallet_is_rejected() {
+        let request = PaymentRequest {
+            target: RequestTarget::Wallet(Pubkey::new_unique()),
+            lamports: 1,
+            expires_at: None,
+            reference: Vec::new(),
+        };
+        assert!(matches!(
+            request.sign(&merchant()),
+            Err(Error::InvalidPaymentRequest)
+        ));
+    }
+}
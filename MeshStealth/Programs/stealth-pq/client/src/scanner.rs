@@ -0,0 +1,560 @@
+//! Scanning the announcement log over RPC.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::AccountDeserialize;
+use futures::stream::{self, Stream, TryStreamExt};
+use solana_rpc_client::nonblocking::rpc_client::RpcClient;
+use stealth_pq::{Announcement, AnnouncementLog, CiphertextAccount, KEM_VARIANT_ML_KEM_768};
+use tokio::sync::Notify;
+
+use crate::{pda, Error, Result, SpendingKey, StealthKeys};
+
+/// Default number of log entries fetched per page (the `getMultipleAccounts` limit)
+pub const DEFAULT_PAGE_SIZE: u64 = 100;
+
+/// A payment addressed to the scanning keys.
+#[derive(Clone)]
+pub struct DetectedPayment {
+    /// Index of the announcement in the log
+    pub announcement_index: u64,
+
+    /// Address of the announcement log entry (pass it to `reclaim_rent` to close it)
+    pub announcement: Pubkey,
+
+    /// App namespace the payment was announced in
+    pub app_id: u32,
+
+    /// The stealth address that was paid
+    pub stealth_address: Pubkey,
+
+    /// The payment's CiphertextAccount
+    pub ciphertext_account: Pubkey,
+
+    /// Rent payer recorded in the CiphertextAccount
+    pub rent_payer: Pubkey,
+
+    /// Memo the sender encrypted to the payment's shared secret, if any
+    pub encrypted_memo: Option<Vec<u8>>,
+
+    /// Registry epoch the sender recorded for the meta-address it paid, if any
+    pub registry_epoch: Option<u32>,
+
+    /// Whether the payment was made to one of the scanner's previous key sets,
+    /// i.e. by a sender still using a replaced registry entry
+    pub previous_keys: bool,
+
+    /// Spending key for the stealth address
+    pub spending_key: SpendingKey,
+}
+
+/// An announcement whose view tag matches but whose ciphertext uses an ML-KEM
+/// parameter set these keys can't decapsulate.
+#[derive(Clone, Debug)]
+pub struct UnsupportedAnnouncement {
+    /// Index of the announcement in the log
+    pub announcement_index: u64,
+
+    /// Address of the announcement log entry
+    pub announcement: Pubkey,
+
+    /// The payment's CiphertextAccount
+    pub ciphertext_account: Pubkey,
+
+    /// ML-KEM parameter set recorded in the CiphertextAccount
+    pub kem_variant: u8,
+}
+
+/// One page of scan results.
+#[derive(Clone, Default)]
+pub struct ScanPage {
+    /// Payments addressed to the scanning keys
+    pub payments: Vec<DetectedPayment>,
+
+    /// Candidates that couldn't be checked because of their ML-KEM parameter set
+    pub unsupported: Vec<UnsupportedAnnouncement>,
+}
+
+/// How far a [`Scanner`] has got, reported after every page.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ScanProgress {
+    /// Log index the next page starts at
+    pub next_index: u64,
+
+    /// Entries in the log when the page was read
+    pub log_count: u64,
+}
+
+/// Pauses and resumes a [`Scanner`] from elsewhere, e.g. an app lifecycle hook.
+///
+/// Clones control the same scanner. A page already being fetched finishes; the
+/// next one waits until [`resume`](Self::resume).
+#[derive(Clone, Default)]
+pub struct ScanControl(Arc<ControlState>);
+
+#[derive(Default)]
+struct ControlState {
+    paused: AtomicBool,
+    resumed: Notify,
+}
+
+impl ScanControl {
+    /// Hold the scanner before its next page
+    pub fn pause(&self) {
+        self.0.paused.store(true, Ordering::SeqCst);
+    }
+
+    /// Let a paused scanner carry on
+    pub fn resume(&self) {
+        self.0.paused.store(false, Ordering::SeqCst);
+        self.0.resumed.notify_waiters();
+    }
+
+    /// Whether the scanner is held
+    pub fn is_paused(&self) -> bool {
+        self.0.paused.load(Ordering::SeqCst)
+    }
+
+    async fn wait_while_paused(&self) {
+        loop {
+            let resumed = self.0.resumed.notified();
+            tokio::pin!(resumed);
+            // Registered before checking, so a resume in between isn't missed
+            resumed.as_mut().enable();
+            if !self.is_paused() {
+                return;
+            }
+            resumed.await;
+        }
+    }
+}
+
+/// Walks the announcement log in index order, yielding payments for one set of keys.
+///
+/// Each page costs one `getMultipleAccounts` call for the log entries and, for
+/// entries whose view tag matches, one more for their CiphertextAccounts. Entries
+/// closed by `reclaim_rent` are skipped. Accounts that fail to decode or detect
+/// are logged and skipped rather than failing the page.
+///
+/// Meta-addresses only carry an ML-KEM-768 key, so candidates whose ciphertext
+/// uses another parameter set can't be decapsulated. They are reported in
+/// [`ScanPage::unsupported`] (and as [`Error::UnsupportedKemVariant`] items by
+/// [`Scanner::payments`]) rather than dropped.
+///
+/// Persist [`Scanner::next_index`] between runs and pass it to
+/// [`Scanner::start_at`] to resume instead of rescanning from the start.
+///
+/// After rotating keys with `update_meta_address`, pass the replaced key sets to
+/// [`Scanner::previous_keys`] for a transition window: senders that read the
+/// registry before the update still pay them.
+///
+/// For background scanning, [`Scanner::control`] lets the app pause and resume
+/// the scan, [`Scanner::min_page_interval`] spaces out requests on a metered
+/// network, and [`Scanner::on_progress`] reports each page.
+pub struct Scanner<'a> {
+    rpc: &'a RpcClient,
+    keys: &'a StealthKeys,
+    previous_keys: &'a [StealthKeys],
+    app_id: Option<u32>,
+    next_index: u64,
+    page_size: u64,
+    control: Option<ScanControl>,
+    min_page_interval: Option<Duration>,
+    last_page_at: Option<Instant>,
+    on_progress: Option<Box<dyn FnMut(ScanProgress) + Send + 'a>>,
+}
+
+impl<'a> Scanner<'a> {
+    /// Scanner over the whole log, starting at index 0
+    pub fn new(rpc: &'a RpcClient, keys: &'a StealthKeys) -> Self {
+        Self {
+            rpc,
+            keys,
+            previous_keys: &[],
+            app_id: None,
+            next_index: 0,
+            page_size: DEFAULT_PAGE_SIZE,
+            control: None,
+            min_page_interval: None,
+            last_page_at: None,
+            on_progress: None,
+        }
+    }
+
+    /// Resume at log index `index`
+    pub fn start_at(mut self, index: u64) -> Self {
+        self.next_index = index;
+        self
+    }
+
+    /// Also detect payments to key sets the meta-address was rotated away from.
+    ///
+    /// Payments found with them have [`DetectedPayment::previous_keys`] set, so
+    /// the wallet can tell their senders to refresh the registry entry. Each
+    /// key set adds one view tag check per log entry.
+    pub fn previous_keys(mut self, keys: &'a [StealthKeys]) -> Self {
+        self.previous_keys = keys;
+        self
+    }
+
+    /// Only report payments announced in `app_id`
+    pub fn app_id(mut self, app_id: u32) -> Self {
+        self.app_id = Some(app_id);
+        self
+    }
+
+    /// Number of log entries fetched per page (1 to `DEFAULT_PAGE_SIZE`)
+    pub fn page_size(mut self, page_size: u64) -> Self {
+        self.page_size = page_size.clamp(1, DEFAULT_PAGE_SIZE);
+        self
+    }
+
+    /// Pause and resume the scan through `control`
+    pub fn control(mut self, control: ScanControl) -> Self {
+        self.control = Some(control);
+        self
+    }
+
+    /// Start pages at least `interval` apart, to limit bandwidth
+    pub fn min_page_interval(mut self, interval: Duration) -> Self {
+        self.min_page_interval = Some(interval);
+        self
+    }
+
+    /// Call `callback` after every page, including one that finds the scanner
+    /// caught up
+    pub fn on_progress(mut self, callback: impl FnMut(ScanProgress) + Send + 'a) -> Self {
+        self.on_progress = Some(Box::new(callback));
+        self
+    }
+
+    /// Log index the next page starts at
+    pub fn next_index(&self) -> u64 {
+        self.next_index
+    }
+
+    /// Scan the next page of the log.
+    ///
+    /// # Returns
+    /// The payments and unsupported candidates found in the page, or `None` once
+    /// the scanner has caught up with the log.
+    pub async fn next_page(&mut self) -> Result<Option<ScanPage>> {
+        if let Some(control) = &self.control {
+            control.wait_while_paused().await;
+        }
+        if let (Some(interval), Some(last)) = (self.min_page_interval, self.last_page_at) {
+            tokio::time::sleep(interval.saturating_sub(last.elapsed())).await;
+        }
+        self.last_page_at = Some(Instant::now());
+
+        let log_address = pda::announcement_log().0;
+        let log: AnnouncementLog = decode(
+            &log_address,
+            &self.rpc.get_account_data(&log_address).await?,
+        )?;
+        if self.next_index >= log.count {
+            self.report_progress(log.count);
+            return Ok(None);
+        }
+
+        let end = log.count.min(self.next_index + self.page_size);
+        let addresses: Vec<Pubkey> = (self.next_index..end)
+            .map(|index| pda::announcement(index).0)
+            .collect();
+
+        let mut candidates = Vec::new();
+        for (address, account) in addresses
+            .iter()
+            .zip(self.rpc.get_multiple_accounts(&addresses).await?)
+        {
+            let Some(account) = account else {
+                continue;
+            };
+            let announcement: Announcement = match decode(address, &account.data) {
+                Ok(announcement) => announcement,
+                Err(err) => {
+                    log::warn!("skipping log entry: {err}");
+                    continue;
+                }
+            };
+            if self
+                .app_id
+                .is_some_and(|app_id| app_id != announcement.app_id)
+            {
+                continue;
+            }
+            let matching: Vec<usize> = self
+                .key_sets()
+                .enumerate()
+                .filter(|(_, keys)| {
+                    keys.view_tag(&announcement.ephemeral_pubkey) == announcement.view_tag
+                })
+                .map(|(position, _)| position)
+                .collect();
+            if !matching.is_empty() {
+                candidates.push((*address, announcement, matching));
+            }
+        }
+
+        let mut page = ScanPage::default();
+        if !candidates.is_empty() {
+            let ciphertext_addresses: Vec<Pubkey> = candidates
+                .iter()
+                .map(|(_, announcement, _)| announcement.ciphertext_account)
+                .collect();
+            let ciphertext_accounts = self
+                .rpc
+                .get_multiple_accounts(&ciphertext_addresses)
+                .await?;
+
+            for ((address, announcement, matching), account) in
+                candidates.into_iter().zip(ciphertext_accounts)
+            {
+                let Some(account) = account else {
+                    continue;
+                };
+                let ciphertext: CiphertextAccount =
+                    match decode(&announcement.ciphertext_account, &account.data) {
+                        Ok(ciphertext) => ciphertext,
+                        Err(err) => {
+                            log::warn!("skipping log entry {}: {err}", announcement.index);
+                            continue;
+                        }
+                    };
+                if ciphertext.kem_variant != KEM_VARIANT_ML_KEM_768 {
+                    page.unsupported.push(UnsupportedAnnouncement {
+                        announcement_index: announcement.index,
+                        announcement: address,
+                        ciphertext_account: announcement.ciphertext_account,
+                        kem_variant: ciphertext.kem_variant,
+                    });
+                    continue;
+                }
+                let mut detected = None;
+                for position in matching {
+                    let keys = self
+                        .key_sets()
+                        .nth(position)
+                        .expect("position of a key set");
+                    match keys.detect(
+                        &announcement.stealth_pubkey,
+                        &announcement.ephemeral_pubkey,
+                        Some(ciphertext.mlkem_ciphertext.as_slice()),
+                    ) {
+                        Ok(Some(spending_key)) => {
+                            detected = Some((spending_key, position > 0));
+                            break;
+                        }
+                        Ok(None) => {}
+                        Err(err) => {
+                            log::warn!("skipping log entry {}: {err}", announcement.index)
+                        }
+                    }
+                }
+
+                if let Some((spending_key, previous_keys)) = detected {
+                    page.payments.push(DetectedPayment {
+                        announcement_index: announcement.index,
+                        announcement: address,
+                        app_id: announcement.app_id,
+                        stealth_address: announcement.stealth_pubkey,
+                        ciphertext_account: announcement.ciphertext_account,
+                        rent_payer: ciphertext.rent_payer,
+                        encrypted_memo: (!ciphertext.memo().is_empty())
+                            .then(|| ciphertext.memo().to_vec()),
+                        registry_epoch: ciphertext.registry_epoch().ok().flatten(),
+                        previous_keys,
+                        spending_key,
+                    });
+                }
+            }
+        }
+
+        self.next_index = end;
+        self.report_progress(log.count);
+        Ok(Some(page))
+    }
+
+    fn report_progress(&mut self, log_count: u64) {
+        if let Some(callback) = &mut self.on_progress {
+            callback(ScanProgress {
+                next_index: self.next_index,
+                log_count,
+            });
+        }
+    }
+
+    /// Current keys first, then the previous ones
+    fn key_sets(&self) -> impl Iterator<Item = &'a StealthKeys> {
+        std::iter::once(self.keys).chain(self.previous_keys)
+    }
+
+    /// Stream detected payments until the scanner catches up with the log.
+    ///
+    /// Unsupported candidates are yielded as [`Error::UnsupportedKemVariant`]
+    /// after the payments of their page; the stream carries on past them.
+    pub fn payments(self) -> impl Stream<Item = Result<DetectedPayment>> + 'a {
+        page_stream(self)
+    }
+}
+
+/// Source of scan pages, so the paging stream can run without an RPC endpoint
+trait PageSource {
+    async fn next_page(&mut self) -> Result<Option<ScanPage>>;
+}
+
+impl PageSource for Scanner<'_> {
+    async fn next_page(&mut self) -> Result<Option<ScanPage>> {
+        Scanner::next_page(self).await
+    }
+}
+
+/// Flatten the pages of `source` into one stream of payments and unsupported
+/// candidates, ending when the source runs out of pages
+fn page_stream<S: PageSource>(source: S) -> impl Stream<Item = Result<DetectedPayment>> {
+    stream::try_unfold(source, |mut source| async move {
+        let page = source.next_page().await?;
+        Ok::<_, Error>(page.map(|page| (page, source)))
+    })
+    .map_ok(|page| {
+        let unsupported = page.unsupported.into_iter().map(|candidate| {
+            Err(Error::UnsupportedKemVariant {
+                announcement_index: candidate.announcement_index,
+                kem_variant: candidate.kem_variant,
+            })
+        });
+        stream::iter(page.payments.into_iter().map(Ok).chain(unsupported))
+    })
+    .try_flatten()
+}
+
+pub(crate) fn decode<T: AccountDeserialize>(address: &Pubkey, mut data: &[u8]) -> Result<T> {
+    T::try_deserialize(&mut data).map_err(|_| Error::AccountDecode(*address))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::executor::block_on;
+    use futures::StreamExt;
+    use std::collections::VecDeque;
+
+    struct StubSource(VecDeque<Result<Option<ScanPage>>>);
+
+    impl PageSource for StubSource {
+        async fn next_page(&mut self) -> Result<Option<ScanPage>> {
+            self.0.pop_front().unwrap_or(Ok(None))
+        }
+    }
+
+    fn payment(announcement_index: u64) -> DetectedPayment {
+        let spending_key = SpendingKey::from_bytes(&[announcement_index as u8 + 1; 32]);
+        DetectedPayment {
+            announcement_index,
+            announcement: pda::announcement(announcement_index).0,
+            app_id: stealth_pq::DEFAULT_APP_ID,
+            stealth_address: spending_key.pubkey(),
+            ciphertext_account: Pubkey::new_unique(),
+            rent_payer: Pubkey::new_unique(),
+            encrypted_memo: None,
+            registry_epoch: None,
+            previous_keys: false,
+            spending_key,
+        }
+    }
+
+    fn unsupported(announcement_index: u64) -> UnsupportedAnnouncement {
+        UnsupportedAnnouncement {
+            announcement_index,
+            announcement: pda::announcement(announcement_index).0,
+            ciphertext_account: Pubkey::new_unique(),
+            kem_variant: stealth_pq::KEM_VARIANT_ML_KEM_1024,
+        }
+    }
+
+    /// Announcement index of a payment, or of the candidate an error reports
+    fn index_of(item: &Result<DetectedPayment>) -> u64 {
+        match item {
+            Ok(payment) => payment.announcement_index,
+            Err(Error::UnsupportedKemVariant {
+                announcement_index, ..
+            }) => *announcement_index,
+            Err(err) => panic!("unexpected error: {err}"),
+        }
+    }
+
+    #[test]
+    fn test_page_stream_flattens_pages() {
+        let source = StubSource(VecDeque::from([
+            Ok(Some(ScanPage {
+                payments: vec![payment(0), payment(2)],
+                unsupported: vec![unsupported(1)],
+            })),
+            Ok(Some(ScanPage::default())),
+            Ok(Some(ScanPage {
+                payments: vec![payment(5)],
+                unsupported: vec![],
+            })),
+            Ok(None),
+            Ok(Some(ScanPage {
+                payments: vec![payment(9)],
+                unsupported: vec![],
+            })),
+        ]));
+
+        let items: Vec<_> = block_on(page_stream(source).collect());
+
+        // Unsupported candidates follow the payments of their page, and the
+        // stream ends at the first `None`
+        assert_eq!(items.iter().map(index_of).collect::<Vec<_>>(), [0, 2, 1, 5]);
+        assert!(matches!(
+            items[2],
+            Err(Error::UnsupportedKemVariant {
+                kem_variant: stealth_pq::KEM_VARIANT_ML_KEM_1024,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn test_page_stream_ends_on_page_error() {
+        let source = StubSource(VecDeque::from([
+            Ok(Some(ScanPage {
+                payments: vec![payment(0)],
+                unsupported: vec![],
+            })),
+            Err(Error::AccountDecode(pda::announcement_log().0)),
+            Ok(Some(ScanPage {
+                payments: vec![payment(1)],
+                unsupported: vec![],
+            })),
+        ]));
+
+        let items: Vec<_> = block_on(page_stream(source).collect());
+
+        assert_eq!(items.len(), 2);
+        assert_eq!(index_of(&items[0]), 0);
+        assert!(matches!(items[1], Err(Error::AccountDecode(_))));
+    }
+
+    #[test]
+    fn test_keys_and_scans_are_shareable_between_threads() {
+        fn send_sync<T: Send + Sync>(_: &T) {}
+        fn send<T: Send>(_: &T) {}
+
+        let rpc = RpcClient::new_mock("succeeds".to_string());
+        let keys = StealthKeys::generate(&mut rand_core::OsRng, true);
+        send_sync(&rpc);
+        send_sync(&keys);
+
+        // The futures can be spawned on a multi-threaded runtime
+        let mut scanner = Scanner::new(&rpc, &keys);
+        send(&scanner.next_page());
+        send(&Scanner::new(&rpc, &keys).payments());
+        let address = Pubkey::new_unique();
+        send(&crate::offline::sweep(&rpc, &address, &address, &address));
+    }
+}
@@ -0,0 +1,228 @@
+//! Stealth address derivation.
+//!
+//! With the recipient's meta-address (M, V, K), the sender:
+//! 1. Generates an ephemeral X25519 key pair (r, R) and computes S_classical = X25519(r, V)
+//! 2. Encapsulates to K: (ciphertext, S_kyber) = ML-KEM-768.Encaps(K)
+//! 3. Combines the secrets: S = SHA256(S_classical || S_kyber), h = SHA256(S) mod L
+//! 4. Derives the stealth address P = M + h*G
+//!
+//! The recipient recomputes S from v and the ML-KEM decapsulation key, and spends
+//! with p = m + h. Classical meta-addresses skip step 2 and use S = S_classical.
+//!
+//! The view tag stored in the CiphertextAccount is SHA256(S_classical)[0] in both
+//! modes, so scanners can rule out most announcements before decapsulating.
+
+use anchor_lang::prelude::Pubkey;
+use curve25519_dalek::edwards::{CompressedEdwardsY, EdwardsPoint};
+use curve25519_dalek::scalar::Scalar;
+use ml_kem::kem::{Decapsulate, Encapsulate};
+use ml_kem::{Ciphertext, Encoded, EncodedSizeUser, MlKem768};
+use rand_core::CryptoRngCore;
+use sha2::{Digest, Sha256};
+use stealth_pq::MLKEM_CIPHERTEXT_SIZE;
+use x25519_dalek::{EphemeralSecret, PublicKey as X25519PublicKey};
+
+use crate::keys::{EncapsulationKey, MetaAddress, SpendingKey, StealthKeys};
+use crate::{Error, Result};
+
+/// A one-time stealth address generated for a payment.
+#[derive(Clone, Debug)]
+pub struct StealthPayment {
+    /// The stealth address P to pay
+    pub stealth_address: Pubkey,
+
+    /// Ephemeral X25519 public key R
+    pub ephemeral_pubkey: [u8; 32],
+
+    /// ML-KEM-768 ciphertext (1088 bytes); `None` for a classical meta-address
+    pub mlkem_ciphertext: Option<Vec<u8>>,
+
+    /// View tag over the X25519 secret, stored in the CiphertextAccount
+    pub view_tag: u8,
+}
+
+impl StealthPayment {
+    /// Generate a stealth address for a recipient.
+    ///
+    /// Uses the hybrid scheme if the meta-address carries an ML-KEM key.
+    pub fn generate(meta_address: &MetaAddress, rng: &mut impl CryptoRngCore) -> Result<Self> {
+        let ephemeral_secret = EphemeralSecret::random_from_rng(&mut *rng);
+        let ephemeral_pubkey = X25519PublicKey::from(&ephemeral_secret).to_bytes();
+        let classical_secret = ephemeral_secret
+            .diffie_hellman(&X25519PublicKey::from(meta_address.viewing_public_key))
+            .to_bytes();
+
+        let (secret, mlkem_ciphertext) = match &meta_address.mlkem_public_key {
+            Some(mlkem_public_key) => {
+                let encoded = Encoded::<EncapsulationKey>::try_from(mlkem_public_key.as_slice())
+                    .map_err(|_| Error::InvalidMetaAddress)?;
+                let (ciphertext, kyber_secret) = EncapsulationKey::from_bytes(&encoded)
+                    .encapsulate(rng)
+                    .map_err(|_| Error::InvalidMetaAddress)?;
+                (
+                    combine_secrets(&classical_secret, &kyber_secret),
+                    Some(ciphertext.to_vec()),
+                )
+            }
+            None => (classical_secret, None),
+        };
+
+        let (stealth_pubkey, _) =
+            derive_stealth_pubkey(&meta_address.spending_public_key, &secret)?;
+
+        Ok(Self {
+            stealth_address: Pubkey::new_from_array(stealth_pubkey),
+            ephemeral_pubkey,
+            mlkem_ciphertext,
+            view_tag: view_tag(&classical_secret),
+        })
+    }
+}
+
+impl StealthKeys {
+    /// View tag expected for an announcement with this ephemeral key.
+    ///
+    /// Compare it with the announcement's stored view tag before calling
+    /// [`StealthKeys::detect`]; a mismatch means the payment isn't ours.
+    pub fn view_tag(&self, ephemeral_pubkey: &[u8; 32]) -> u8 {
+        view_tag(&self.classical_secret(ephemeral_pubkey))
+    }
+
+    /// Check whether an announcement is addressed to these keys.
+    ///
+    /// Uses the hybrid scheme if a ciphertext is given and these keys have an
+    /// ML-KEM key pair, otherwise the classical one.
+    ///
+    /// # Returns
+    /// The stealth address's spending key if it matches, `None` otherwise
+    pub fn detect(
+        &self,
+        stealth_address: &Pubkey,
+        ephemeral_pubkey: &[u8; 32],
+        mlkem_ciphertext: Option<&[u8]>,
+    ) -> Result<Option<SpendingKey>> {
+        let classical_secret = self.classical_secret(ephemeral_pubkey);
+
+        let secret = match (mlkem_ciphertext, self.decapsulation_key()) {
+            (Some(ciphertext), Some(decapsulation_key)) => {
+                if ciphertext.len() != MLKEM_CIPHERTEXT_SIZE {
+                    return Err(Error::InvalidCiphertext);
+                }
+                let ciphertext = Ciphertext::<MlKem768>::try_from(ciphertext)
+                    .map_err(|_| Error::InvalidCiphertext)?;
+                let kyber_secret = decapsulation_key
+                    .decapsulate(&ciphertext)
+                    .map_err(|_| Error::InvalidCiphertext)?;
+                combine_secrets(&classical_secret, &kyber_secret)
+            }
+            _ => classical_secret,
+        };
+
+        let (expected, hash) = derive_stealth_pubkey(self.spending_public_key(), &secret)?;
+        if expected != stealth_address.to_bytes() {
+            return Ok(None);
+        }
+
+        Ok(Some(self.stealth_spending_key(&hash)))
+    }
+}
+
+/// View tag: first byte of SHA-256 over the X25519 shared secret
+pub fn view_tag(classical_secret: &[u8; 32]) -> u8 {
+    Sha256::digest(classical_secret)[0]
+}
+
+/// Hybrid shared secret: S = SHA256(S_classical || S_kyber)
+fn combine_secrets(classical_secret: &[u8; 32], kyber_secret: &[u8]) -> [u8; 32] {
+    Sha256::new()
+        .chain_update(classical_secret)
+        .chain_update(kyber_secret)
+        .finalize()
+        .into()
+}
+
+/// Stealth public key P = M + h*G with h = SHA256(S) mod L.
+///
+/// # Returns
+/// P and h
+fn derive_stealth_pubkey(
+    spending_public_key: &[u8; 32],
+    secret: &[u8; 32],
+) -> Result<([u8; 32], Scalar)> {
+    let hash = Scalar::from_bytes_mod_order(Sha256::digest(secret).into());
+
+    let spending_point = CompressedEdwardsY(*spending_public_key)
+        .decompress()
+        .ok_or(Error::InvalidMetaAddress)?;
+    let stealth_point: EdwardsPoint = spending_point + EdwardsPoint::mul_base(&hash);
+
+    // Same check as libsodium's crypto_core_ed25519_is_valid_point
+    if stealth_point.is_small_order() || !stealth_point.is_torsion_free() {
+        return Err(Error::InvalidPoint);
+    }
+
+    Ok((stealth_point.compress().to_bytes(), hash))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand_core::OsRng;
+
+    #[test]
+    fn test_hybrid_payment_detected_by_recipient() {
+        let recipient = StealthKeys::generate(&mut OsRng, true);
+        let payment = StealthPayment::generate(&recipient.meta_address(), &mut OsRng).unwrap();
+        let ciphertext = payment.mlkem_ciphertext.as_deref().unwrap();
+        assert_eq!(ciphertext.len(), MLKEM_CIPHERTEXT_SIZE);
+        assert_eq!(
+            recipient.view_tag(&payment.ephemeral_pubkey),
+            payment.view_tag
+        );
+
+        let spending_key = recipient
+            .detect(
+                &payment.stealth_address,
+                &payment.ephemeral_pubkey,
+                Some(ciphertext),
+            )
+            .unwrap()
+            .expect("payment should be detected");
+        assert_eq!(spending_key.pubkey(), payment.stealth_address);
+
+        // The classical secret alone doesn't derive the hybrid address
+        assert!(recipient
+            .detect(&payment.stealth_address, &payment.ephemeral_pubkey, None)
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn test_classical_payment_detected_by_recipient() {
+        let recipient = StealthKeys::generate(&mut OsRng, false);
+        let payment = StealthPayment::generate(&recipient.meta_address(), &mut OsRng).unwrap();
+        assert!(payment.mlkem_ciphertext.is_none());
+
+        let spending_key = recipient
+            .detect(&payment.stealth_address, &payment.ephemeral_pubkey, None)
+            .unwrap()
+            .unwrap();
+        assert_eq!(spending_key.pubkey(), payment.stealth_address);
+    }
+
+    #[test]
+    fn test_payment_not_detected_by_other_keys() {
+        let recipient = StealthKeys::generate(&mut OsRng, true);
+        let other = StealthKeys::generate(&mut OsRng, true);
+        let payment = StealthPayment::generate(&recipient.meta_address(), &mut OsRng).unwrap();
+
+        assert!(other
+            .detect(
+                &payment.stealth_address,
+                &payment.ephemeral_pubkey,
+                payment.mlkem_ciphertext.as_deref(),
+            )
+            .unwrap()
+            .is_none());
+    }
+}
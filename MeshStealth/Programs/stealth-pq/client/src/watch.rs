@@ -0,0 +1,189 @@
+//! Watching stealth addresses for tampering, from public data only.
+//!
+//! A custodian holding stealth funds offline wants to know when something
+//! happens to them without bringing the keys online. A [`Watchtower`] is given
+//! the stealth addresses (and their namespaces) and, on every
+//! [`check`](Watchtower::check), compares them with the previous check:
+//!
+//! - a balance that went down means funds moved ([`Alert::FundsMoved`])
+//! - a CiphertextAccount that disappeared was closed with the stealth key or
+//!   refunded by the sender ([`Alert::CiphertextClosed`])
+//! - a new announcement that reuses the ephemeral key of a watched payment
+//!   points at a broken or malicious sender ([`Alert::EphemeralKeyReused`])
+//!
+//! The first check only records the starting state. Run checks on a timer;
+//! the watchtower keeps no state beyond the process.
+
+use std::collections::HashMap;
+
+use anchor_lang::prelude::Pubkey;
+use solana_rpc_client::nonblocking::rpc_client::RpcClient;
+use stealth_pq::{Announcement, AnnouncementLog, CiphertextAccount};
+
+use crate::scanner::{decode, DEFAULT_PAGE_SIZE};
+use crate::{pda, Result};
+
+/// Accounts fetched per `getMultipleAccounts` call
+const ACCOUNTS_PER_REQUEST: usize = 100;
+
+/// Something that happened to a watched stealth address.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Alert {
+    /// The stealth address's balance went down
+    FundsMoved {
+        stealth_address: Pubkey,
+        before: u64,
+        after: u64,
+    },
+
+    /// The stealth address's CiphertextAccount was closed
+    CiphertextClosed {
+        stealth_address: Pubkey,
+        ciphertext_account: Pubkey,
+    },
+
+    /// An announcement for another address reuses a watched payment's
+    /// ephemeral key
+    EphemeralKeyReused {
+        stealth_address: Pubkey,
+        announcement_index: u64,
+        other_address: Pubkey,
+    },
+}
+
+struct Watched {
+    stealth_address: Pubkey,
+    ciphertext_account: Pubkey,
+    /// Balance and whether the CiphertextAccount existed, as of the last check
+    last: Option<(u64, bool)>,
+}
+
+/// Watches stealth addresses through RPC.
+pub struct Watchtower<'a> {
+    rpc: &'a RpcClient,
+    watched: Vec<Watched>,
+    ephemeral_keys: HashMap<[u8; 32], Pubkey>,
+    next_index: u64,
+}
+
+impl<'a> Watchtower<'a> {
+    /// A watchtower with nothing to watch, reading the announcement log from
+    /// the start
+    pub fn new(rpc: &'a RpcClient) -> Self {
+        Self {
+            rpc,
+            watched: Vec::new(),
+            ephemeral_keys: HashMap::new(),
+            next_index: 0,
+        }
+    }
+
+    /// Start reading the announcement log at `index`, e.g. where a previous
+    /// run stopped
+    pub fn start_at(mut self, index: u64) -> Self {
+        self.next_index = index;
+        self
+    }
+
+    /// Watch a stealth address paid in namespace `app_id`
+    pub fn watch(&mut self, stealth_address: Pubkey, app_id: u32) {
+        self.watched.push(Watched {
+            stealth_address,
+            ciphertext_account: pda::ciphertext_account(&stealth_address, app_id).0,
+            last: None,
+        });
+    }
+
+    /// Index of the next announcement log entry to read
+    pub fn next_index(&self) -> u64 {
+        self.next_index
+    }
+
+    /// Compare the watched addresses with the previous check, and read the
+    /// announcements logged since.
+    pub async fn check(&mut self) -> Result<Vec<Alert>> {
+        let mut alerts = Vec::new();
+
+        let addresses: Vec<Pubkey> = self
+            .watched
+            .iter()
+            .flat_map(|watched| [watched.stealth_address, watched.ciphertext_account])
+            .collect();
+        let mut accounts = Vec::with_capacity(addresses.len());
+        for chunk in addresses.chunks(ACCOUNTS_PER_REQUEST) {
+            accounts.extend(self.rpc.get_multiple_accounts(chunk).await?);
+        }
+
+        for (watched, pair) in self.watched.iter_mut().zip(accounts.chunks(2)) {
+            let balance = pair[0].as_ref().map_or(0, |account| account.lamports);
+            let ciphertext = pair[1].as_ref();
+            if let Some(account) = ciphertext {
+                // Keep the ephemeral key to spot reuse; a legacy layout that
+                // doesn't decode has nothing to compare
+                if let Ok(ciphertext) =
+                    decode::<CiphertextAccount>(&watched.ciphertext_account, &account.data)
+                {
+                    self.ephemeral_keys
+                        .insert(ciphertext.ephemeral_pubkey, watched.stealth_address);
+                }
+            }
+
+            if let Some((before, had_ciphertext)) = watched.last {
+                if balance < before {
+                    alerts.push(Alert::FundsMoved {
+                        stealth_address: watched.stealth_address,
+                        before,
+                        after: balance,
+                    });
+                }
+                if had_ciphertext && ciphertext.is_none() {
+                    alerts.push(Alert::CiphertextClosed {
+                        stealth_address: watched.stealth_address,
+                        ciphertext_account: watched.ciphertext_account,
+                    });
+                }
+            }
+            watched.last = Some((balance, ciphertext.is_some()));
+        }
+
+        let log_address = pda::announcement_log().0;
+        let Some(log) = self
+            .rpc
+            .get_account_with_commitment(&log_address, self.rpc.commitment())
+            .await?
+            .value
+        else {
+            return Ok(alerts);
+        };
+        let count = decode::<AnnouncementLog>(&log_address, &log.data)?.count;
+        while self.next_index < count {
+            let end = count.min(self.next_index + DEFAULT_PAGE_SIZE);
+            let entries: Vec<Pubkey> = (self.next_index..end)
+                .map(|index| pda::announcement(index).0)
+                .collect();
+            for (address, account) in entries
+                .iter()
+                .zip(self.rpc.get_multiple_accounts(&entries).await?)
+            {
+                let Some(announcement) =
+                    account.and_then(|account| decode::<Announcement>(address, &account.data).ok())
+                else {
+                    continue;
+                };
+                match self.ephemeral_keys.get(&announcement.ephemeral_pubkey) {
+                    Some(watched) if *watched != announcement.stealth_pubkey => {
+                        alerts.push(Alert::EphemeralKeyReused {
+                            stealth_address: *watched,
+                            announcement_index: announcement.index,
+                            other_address: announcement.stealth_pubkey,
+                        });
+                    }
+                    _ => {}
+                }
+            }
+            self.next_index = end;
+        }
+
+        Ok(alerts)
+    }
+}
@@ -0,0 +1,115 @@
+//! Generate recipient keys on an offline machine.
+//!
+//! ```text
+//! ceremony <keys file> <meta-address file>
+//! ```
+//!
+//! The operator types in extra entropy (dice rolls, shuffled cards), the keys are
+//! generated through the RNG health checks and written to the keys file, and the
+//! operator copies the printed backup onto paper. Three randomly chosen groups of
+//! the backup are then asked back; only if they match is the meta-address
+//! written out. Carry the meta-address file, never the keys file, to an online
+//! machine and publish it with `recipient publish`. The backup is the keys file
+//! in hex, so `xxd -r -p` turns a typed-in copy back into one.
+//!
+//! Each step is logged to stderr with a timestamp, and the log names no secret,
+//! so it can be kept as the ceremony record.
+
+use std::io::{BufRead, Write};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use anyhow::{bail, Context, Result};
+use rand_core::{OsRng, RngCore};
+use sha2::{Digest, Sha256};
+use stealth_pq_client::StealthKeys;
+use stealth_pq_examples::{load_keys, save_keys, to_hex};
+
+/// Fewest characters of operator entropy accepted, about 100 bits as dice rolls
+const MIN_ENTROPY_CHARS: usize = 40;
+
+/// Hex characters per backup group
+const GROUP_SIZE: usize = 4;
+
+/// Groups asked back to check the paper copy
+const CHALLENGES: usize = 3;
+
+fn main() -> Result<()> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let [keys_path, meta_address_path] = args.as_slice() else {
+        bail!("usage: ceremony <keys file> <meta-address file>");
+    };
+    if std::path::Path::new(keys_path).exists() {
+        bail!("{keys_path} already exists; payments to its keys would be lost");
+    }
+    let mut input = std::io::stdin().lock();
+
+    record("ceremony started");
+    println!(
+        "Type at least {MIN_ENTROPY_CHARS} dice rolls or other random characters, then Enter:"
+    );
+    let entropy = read_line(&mut input)?;
+    if entropy.chars().filter(|c| !c.is_whitespace()).count() < MIN_ENTROPY_CHARS {
+        bail!("not enough entropy typed in");
+    }
+    record("operator entropy collected");
+
+    let keys = StealthKeys::generate_with_entropy(&mut OsRng, true, entropy.as_bytes())?;
+    record("keys generated; RNG health checks passed");
+
+    save_keys(keys_path, &keys)?;
+    let meta_address = keys.meta_address();
+    if load_keys(keys_path)?.meta_address() != meta_address {
+        bail!("{keys_path} doesn't read back as the generated keys");
+    }
+    record(&format!("keys written to {keys_path} and read back"));
+
+    let backup = to_hex(&std::fs::read(keys_path)?);
+    let groups: Vec<&str> = backup
+        .as_bytes()
+        .chunks(GROUP_SIZE)
+        .map(|group| std::str::from_utf8(group).unwrap())
+        .collect();
+    println!("\nCopy this backup onto paper:\n");
+    for (row, line) in groups.chunks(8).enumerate() {
+        println!("{:3}: {}", row * 8 + 1, line.join(" "));
+    }
+    println!("\nPress Enter once it is written down.");
+    read_line(&mut input)?;
+    // Push the backup off the screen before asking for it back
+    print!("{}", "\n".repeat(100));
+
+    for _ in 0..CHALLENGES {
+        let index = OsRng.next_u32() as usize % groups.len();
+        print!("Group {} of the backup: ", index + 1);
+        std::io::stdout().flush()?;
+        if read_line(&mut input)?.trim().to_lowercase() != groups[index] {
+            std::fs::remove_file(keys_path)?;
+            record("backup check failed; keys deleted");
+            bail!("that doesn't match the backup; run the ceremony again");
+        }
+    }
+    record("paper backup checked");
+
+    std::fs::write(meta_address_path, meta_address.to_base58())?;
+    record(&format!(
+        "meta-address written to {meta_address_path}, SHA-256 {}",
+        to_hex(&Sha256::digest(meta_address.to_bytes()))
+    ));
+    Ok(())
+}
+
+fn read_line(input: &mut impl BufRead) -> Result<String> {
+    let mut line = String::new();
+    if input.read_line(&mut line).context("reading stdin")? == 0 {
+        bail!("stdin closed");
+    }
+    Ok(line)
+}
+
+/// Log a step of the ceremony
+fn record(step: &str) {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs());
+    eprintln!("[{now}] {step}");
+}
@@ -0,0 +1,54 @@
+//! Create signed payment requests.
+//!
+//! ```text
+//! merchant invoice <wallet.json> <lamports> <reference> [seconds to expiry]
+//! ```
+//!
+//! Prints the request as hex, for a QR code or NFC tag, and `sender` pays it with
+//! `sender <wallet.json> <request>`. The request names the wallet, so the sender
+//! pays the meta-address it registered; the reference (an order number, at most
+//! 32 bytes) is for the merchant's own records.
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use anyhow::{bail, Context, Result};
+use stealth_pq_client::{PaymentRequest, RequestTarget};
+use stealth_pq_examples::{load_wallet, to_hex};
+
+const USAGE: &str =
+    "usage: merchant invoice <wallet.json> <lamports> <reference> [seconds to expiry]";
+
+fn main() -> Result<()> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let (wallet, lamports, reference, expires_in) = match args
+        .iter()
+        .map(String::as_str)
+        .collect::<Vec<_>>()
+        .as_slice()
+    {
+        ["invoice", wallet, lamports, reference] => (*wallet, *lamports, *reference, None),
+        ["invoice", wallet, lamports, reference, expires_in] => {
+            (*wallet, *lamports, *reference, Some(*expires_in))
+        }
+        _ => bail!(USAGE),
+    };
+
+    let wallet = load_wallet(wallet)?;
+    let expires_at = match expires_in {
+        Some(seconds) => {
+            let seconds: i64 = seconds.parse().context("invalid expiry")?;
+            let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs() as i64;
+            Some(now + seconds)
+        }
+        None => None,
+    };
+    let request = PaymentRequest {
+        target: RequestTarget::Wallet(wallet.pubkey()),
+        lamports: lamports.parse().context("invalid amount")?,
+        expires_at,
+        reference: reference.as_bytes().to_vec(),
+    };
+
+    println!("{}", to_hex(&request.sign(&wallet)?));
+    Ok(())
+}
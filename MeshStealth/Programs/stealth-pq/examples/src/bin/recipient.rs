@@ -0,0 +1,340 @@
+//! Receive stealth payments.
+//!
+//! ```text
+//! recipient register <wallet.json> <keys file>   generate keys and publish the meta-address
+//! recipient publish <wallet.json> <meta-address file>
+//!                                                publish a meta-address from `ceremony`
+//! recipient rotate <wallet.json> <keys file>     replace the keys and update the meta-address
+//! recipient revoke <wallet.json>                 close the meta-address
+//! recipient scan <keys file>                     list payments to the keys
+//! recipient sweep <wallet.json> <keys file>      reclaim each payment's rent and move it to the wallet
+//! recipient migrate <keys file>                  re-pay payments to the replaced keys to the current ones
+//! recipient rescan <keys file> <checkpoint file> [from index] [to index]
+//!                                                list payments in part of the log, resumably
+//! ```
+//!
+//! `rotate` keeps the replaced keys next to the keys file, with `.previous`
+//! appended, and `scan` and `sweep` keep finding payments to them. `migrate`
+//! moves each payment to the replaced keys into a new payment to the current
+//! keys, paid for out of the payment itself. Sweep or migrate, then delete that
+//! file before rotating again. `rotate` and `revoke` ask for
+//! confirmation first.
+//!
+//! `rescan` is for recovery, e.g. keys restored from a backup. It reads the
+//! log at most one page per `RESCAN_PAGE_INTERVAL`, shows its progress on
+//! stderr, and writes the next index to the checkpoint file after every page.
+//! Run it again without `from index` to pick up where it stopped.
+
+use std::io::Write;
+use std::pin::pin;
+use std::time::Duration;
+
+use anyhow::{bail, Result};
+use futures::StreamExt;
+use rand_core::OsRng;
+use solana_rpc_client::nonblocking::rpc_client::RpcClient;
+use solana_system_interface::instruction::transfer;
+use stealth_pq_client::preflight::Problem;
+use stealth_pq_client::{
+    instructions, DetectedPayment, Error, MetaAddress, PaymentFlow, Scanner, SpendingKey,
+    StealthKeys, StealthPayment,
+};
+use stealth_pq_examples::{fee, load_keys, load_wallet, rpc, save_keys, send};
+
+const USAGE: &str = "usage: recipient register <wallet.json> <keys file>
+       recipient publish <wallet.json> <meta-address file>
+       recipient rotate <wallet.json> <keys file>
+       recipient revoke <wallet.json>
+       recipient scan <keys file>
+       recipient sweep <wallet.json> <keys file>
+       recipient migrate <keys file>
+       recipient rescan <keys file> <checkpoint file> [from index] [to index]";
+
+/// Shortest time between two pages of a rescan, to go easy on the RPC node
+const RESCAN_PAGE_INTERVAL: Duration = Duration::from_millis(250);
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let rpc = rpc();
+
+    match args
+        .iter()
+        .map(String::as_str)
+        .collect::<Vec<_>>()
+        .as_slice()
+    {
+        ["register", wallet, keys] => {
+            if std::path::Path::new(keys).exists() {
+                bail!("{keys} already exists; payments to its keys would be lost");
+            }
+            let wallet = load_wallet(wallet)?;
+            let stealth_keys = StealthKeys::generate_with_entropy(&mut OsRng, true, &[])?;
+            save_keys(keys, &stealth_keys)?;
+            publish(&rpc, &wallet, &stealth_keys.meta_address()).await?;
+        }
+        ["publish", wallet, meta_address] => {
+            let meta_address =
+                MetaAddress::from_base58(std::fs::read_to_string(meta_address)?.trim())?;
+            publish(&rpc, &load_wallet(wallet)?, &meta_address).await?;
+        }
+        ["rotate", wallet, keys] => {
+            let previous = format!("{keys}.previous");
+            if std::path::Path::new(&previous).exists() {
+                bail!("{previous} already exists; sweep its payments and delete it first");
+            }
+            let wallet = load_wallet(wallet)?;
+            let old_keys = load_keys(keys)?;
+            confirm(&format!(
+                "Replace the meta-address of {} with new keys?",
+                wallet.pubkey()
+            ))?;
+
+            // Keep the old keys before anything replaces them
+            save_keys(&previous, &old_keys)?;
+            let stealth_keys = StealthKeys::generate_with_entropy(&mut OsRng, true, &[])?;
+            save_keys(keys, &stealth_keys)?;
+            for instruction in
+                instructions::update_meta_address(&wallet.pubkey(), &stealth_keys.meta_address())
+            {
+                send(&rpc, &wallet, &[instruction]).await?;
+            }
+            println!(
+                "Updated the meta-address for {}; the old keys are in {previous}",
+                wallet.pubkey()
+            );
+        }
+        ["revoke", wallet] => {
+            let wallet = load_wallet(wallet)?;
+            confirm(&format!(
+                "Close the meta-address of {}? Senders can no longer resolve it.",
+                wallet.pubkey()
+            ))?;
+            let signature = send(
+                &rpc,
+                &wallet,
+                &[instructions::close_meta_address(&wallet.pubkey())],
+            )
+            .await?;
+            println!(
+                "Closed the meta-address for {}: {signature}",
+                wallet.pubkey()
+            );
+        }
+        ["scan", keys] => {
+            for payment in scan(&rpc, keys).await? {
+                let balance = rpc.get_balance(&payment.stealth_address).await?;
+                println!(
+                    "{} (announcement {}): {balance} lamports",
+                    payment.stealth_address, payment.announcement_index
+                );
+            }
+        }
+        ["sweep", wallet, keys] => {
+            let wallet = load_wallet(wallet)?.pubkey();
+            for payment in scan(&rpc, keys).await? {
+                let stealth_key = &payment.spending_key;
+                let stealth_address = payment.stealth_address;
+
+                reclaim(&rpc, &payment).await?;
+
+                // Then move everything left, after the fee, to the wallet
+                let balance = rpc.get_balance(&stealth_address).await?;
+                let fee = fee(
+                    &rpc,
+                    &stealth_address,
+                    &[transfer(&stealth_address, &wallet, balance)],
+                )
+                .await?;
+                if balance <= fee {
+                    println!("{stealth_address}: nothing left to sweep");
+                    continue;
+                }
+                let signature = send(
+                    &rpc,
+                    stealth_key,
+                    &[transfer(&stealth_address, &wallet, balance - fee)],
+                )
+                .await?;
+                println!(
+                    "{stealth_address}: swept {} lamports: {signature}",
+                    balance - fee
+                );
+            }
+        }
+        ["migrate", keys] => {
+            let (current, previous_keys) = load_key_sets(keys)?;
+            if previous_keys.is_empty() {
+                bail!("{keys}.previous doesn't exist; there are no replaced keys to migrate from");
+            }
+            let payments = scan(&rpc, keys).await?;
+            for payment in payments.iter().filter(|payment| payment.previous_keys) {
+                let stealth_address = payment.stealth_address;
+                reclaim(&rpc, payment).await?;
+
+                // Price the flow with the whole balance, then pay what rent and
+                // fees leave over
+                let balance = rpc.get_balance(&stealth_address).await?;
+                let repayment = StealthPayment::generate(&current.meta_address(), &mut OsRng)?;
+                let flow =
+                    PaymentFlow::new(&rpc, &stealth_address, &repayment, payment.app_id, balance)
+                        .await?;
+                let preflight = flow.preflight(&rpc).await?;
+                if let Some(problem) = preflight
+                    .problems
+                    .iter()
+                    .find(|problem| !matches!(problem, Problem::InsufficientBalance { .. }))
+                {
+                    bail!("{stealth_address}: migration would fail: {problem:?}");
+                }
+                let lamports = balance.saturating_sub(preflight.rent + preflight.fees);
+                if lamports == 0 {
+                    println!("{stealth_address}: too little left to migrate");
+                    continue;
+                }
+
+                let flow =
+                    PaymentFlow::new(&rpc, &stealth_address, &repayment, payment.app_id, lamports)
+                        .await?;
+                for (_, instruction) in flow.steps() {
+                    send(
+                        &rpc,
+                        &payment.spending_key,
+                        std::slice::from_ref(instruction),
+                    )
+                    .await?;
+                }
+                println!(
+                    "{stealth_address}: moved {lamports} lamports to {} (announcement {})",
+                    repayment.stealth_address,
+                    flow.announcement_index()
+                );
+            }
+        }
+        ["rescan", keys, checkpoint, range @ ..] if range.len() <= 2 => {
+            let from = match range.first() {
+                Some(from) => from.parse()?,
+                None => std::fs::read_to_string(checkpoint)
+                    .map_or(Ok(0), |index| index.trim().parse())?,
+            };
+            let to = range.get(1).map(|to| to.parse()).transpose()?;
+            rescan(&rpc, keys, checkpoint, from, to).await?;
+        }
+        _ => bail!(USAGE),
+    }
+
+    Ok(())
+}
+
+/// Register `meta_address` for `wallet`: the registration, then the ML-KEM key
+/// in chunks
+async fn publish(rpc: &RpcClient, wallet: &SpendingKey, meta_address: &MetaAddress) -> Result<()> {
+    for instruction in instructions::register_meta_address(&wallet.pubkey(), meta_address) {
+        send(rpc, wallet, &[instruction]).await?;
+    }
+    println!("Registered a meta-address for {}", wallet.pubkey());
+    Ok(())
+}
+
+/// Close `payment`'s CiphertextAccount and announcement, returning their rent to
+/// the stealth address
+async fn reclaim(rpc: &RpcClient, payment: &DetectedPayment) -> Result<()> {
+    send(
+        rpc,
+        &payment.spending_key,
+        &[instructions::reclaim_rent(
+            &payment.stealth_address,
+            payment.app_id,
+            Some(payment.rent_payer),
+            Some(payment.announcement),
+        )],
+    )
+    .await?;
+    Ok(())
+}
+
+/// Ask before a change to the registry, failing unless the answer is yes
+fn confirm(question: &str) -> Result<()> {
+    print!("{question} [y/N] ");
+    std::io::stdout().flush()?;
+    let mut answer = String::new();
+    std::io::stdin().read_line(&mut answer)?;
+    if !matches!(answer.trim(), "y" | "Y" | "yes") {
+        bail!("cancelled");
+    }
+    Ok(())
+}
+
+/// The keys in `path`, and the keys they replaced if `rotate` kept them
+fn load_key_sets(path: &str) -> Result<(StealthKeys, Vec<StealthKeys>)> {
+    let previous = format!("{path}.previous");
+    let previous_keys = if std::path::Path::new(&previous).exists() {
+        vec![load_keys(&previous)?]
+    } else {
+        Vec::new()
+    };
+    Ok((load_keys(path)?, previous_keys))
+}
+
+/// Every payment in the announcement log addressed to the keys in `path`, or
+/// to the keys they replaced
+async fn scan(rpc: &RpcClient, path: &str) -> Result<Vec<DetectedPayment>> {
+    let (keys, previous_keys) = load_key_sets(path)?;
+
+    let mut payments = Vec::new();
+    let mut stream = pin!(Scanner::new(rpc, &keys)
+        .previous_keys(&previous_keys)
+        .payments());
+    while let Some(payment) = stream.next().await {
+        match payment {
+            Ok(payment) => payments.push(payment),
+            Err(err @ Error::UnsupportedKemVariant { .. }) => eprintln!("skipping: {err}"),
+            Err(err) => return Err(err.into()),
+        }
+    }
+    Ok(payments)
+}
+
+/// List the payments to the keys in `path` announced from index `from` up to
+/// `to` (or the end of the log), checkpointing after every page
+async fn rescan(
+    rpc: &RpcClient,
+    path: &str,
+    checkpoint: &str,
+    from: u64,
+    to: Option<u64>,
+) -> Result<()> {
+    let (keys, previous_keys) = load_key_sets(path)?;
+    let mut scanner = Scanner::new(rpc, &keys)
+        .previous_keys(&previous_keys)
+        .start_at(from)
+        .min_page_interval(RESCAN_PAGE_INTERVAL)
+        .on_progress(|progress| {
+            let end = to.map_or(progress.log_count, |to| to.min(progress.log_count));
+            eprint!("\rscanned {} of {end}", progress.next_index.min(end));
+        });
+
+    while to.is_none_or(|to| scanner.next_index() < to) {
+        let Some(page) = scanner.next_page().await? else {
+            break;
+        };
+        for payment in page.payments {
+            if to.is_none_or(|to| payment.announcement_index < to) {
+                eprintln!();
+                println!(
+                    "{} (announcement {})",
+                    payment.stealth_address, payment.announcement_index
+                );
+            }
+        }
+        for unsupported in page.unsupported {
+            eprintln!(
+                "\rskipping announcement {}: unsupported ML-KEM parameter set",
+                unsupported.announcement_index
+            );
+        }
+        std::fs::write(checkpoint, scanner.next_index().to_string())?;
+    }
+    eprintln!();
+    Ok(())
+}
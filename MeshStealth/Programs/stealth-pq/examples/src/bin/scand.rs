@@ -0,0 +1,305 @@
+//! Scan for payments as a long-running service.
+//!
+//! ```text
+//! scand <config.toml>
+//! ```
+//!
+//! The config names the keys to scan for and a state directory:
+//!
+//! ```toml
+//! keys = "/etc/smesh/recipient.keys"
+//! previous_keys = ["/etc/smesh/recipient.keys.previous"]  # optional
+//! state_dir = "/var/lib/smesh"
+//! cluster = "devnet"              # optional, as `CLUSTER`
+//! poll_interval_secs = 30         # optional
+//! health_addr = "127.0.0.1:8080"  # optional
+//!
+//! [[notify]]                      # optional, any number
+//! kind = "slack"                  # slack, telegram or webhook
+//! url = "https://hooks.slack.com/services/..."
+//! template = "Payment received (announcement {index})"  # optional
+//!
+//! [[notify]]
+//! kind = "telegram"
+//! url = "https://api.telegram.org/bot<token>/sendMessage"
+//! chat_id = "12345"
+//! ```
+//!
+//! Every poll scans the announcement log from where the last one stopped and
+//! appends the payments found to `payments.log` in the state directory, one
+//! `<unix time> <announcement index> <stealth address>` line each. The next log
+//! index is written to `next_index` there after every page, so a restart
+//! resumes. `payments.log` is opened for each write, so rotating it only takes
+//! moving it away.
+//!
+//! SIGTERM or Ctrl-C stops the daemon; a page being scanned is abandoned and
+//! scanned again on the next start. If `health_addr` is set, every HTTP request
+//! to it gets 200 while polls succeed, and 503 once the last successful poll is
+//! more than three intervals old.
+//!
+//! Each `notify` sink gets a message per payment found, from its template with
+//! `{index}` (the announcement index) and `{address}` (the stealth address)
+//! filled in, and one when polls start failing. Messages never carry keys or
+//! error details, which can include the RPC URL and its API key; the default
+//! template leaves out the stealth address too. A webhook gets
+//! `{"event": "payment" | "poll_failed", "text": ...}` as JSON.
+
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use anchor_lang::prelude::Pubkey;
+use anyhow::{bail, Context, Result};
+use serde::Deserialize;
+use solana_rpc_client::nonblocking::rpc_client::RpcClient;
+use stealth_pq_client::{Cluster, Scanner, StealthKeys};
+use stealth_pq_examples::{load_keys, rpc};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+use tokio::signal::unix::{signal, SignalKind};
+
+#[derive(Deserialize)]
+struct Config {
+    keys: PathBuf,
+    #[serde(default)]
+    previous_keys: Vec<PathBuf>,
+    state_dir: PathBuf,
+    cluster: Option<String>,
+    #[serde(default = "default_poll_interval")]
+    poll_interval_secs: u64,
+    health_addr: Option<String>,
+    #[serde(default)]
+    notify: Vec<Sink>,
+}
+
+/// Where operator alerts go
+#[derive(Deserialize)]
+struct Sink {
+    kind: SinkKind,
+    url: String,
+    chat_id: Option<String>,
+    template: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum SinkKind {
+    Slack,
+    Telegram,
+    Webhook,
+}
+
+/// Message for a payment when a sink has no template of its own
+const DEFAULT_TEMPLATE: &str = "Stealth payment received (announcement {index})";
+
+/// Longest wait for a sink, so one that hangs doesn't hold up scanning
+const NOTIFY_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Message when polls start failing
+const POLL_FAILED_TEXT: &str = "scand: scanning for payments is failing; see the daemon log";
+
+impl Sink {
+    async fn send(&self, http: &reqwest::Client, event: &str, text: &str) -> reqwest::Result<()> {
+        let body = match self.kind {
+            SinkKind::Slack => serde_json::json!({ "text": text }),
+            SinkKind::Telegram => serde_json::json!({ "chat_id": self.chat_id, "text": text }),
+            SinkKind::Webhook => serde_json::json!({ "event": event, "text": text }),
+        };
+        http.post(&self.url)
+            .json(&body)
+            .send()
+            .await?
+            .error_for_status()?;
+        Ok(())
+    }
+
+    fn payment_text(&self, index: u64, address: &Pubkey) -> String {
+        self.template
+            .as_deref()
+            .unwrap_or(DEFAULT_TEMPLATE)
+            .replace("{index}", &index.to_string())
+            .replace("{address}", &address.to_string())
+    }
+}
+
+fn default_poll_interval() -> u64 {
+    30
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let [config] = args.as_slice() else {
+        bail!("usage: scand <config.toml>");
+    };
+    let config: Config = toml::from_str(
+        &std::fs::read_to_string(config).with_context(|| format!("reading {config}"))?,
+    )
+    .with_context(|| format!("parsing {config}"))?;
+
+    std::fs::create_dir_all(&config.state_dir)
+        .with_context(|| format!("creating {}", config.state_dir.display()))?;
+    let rpc = config
+        .cluster
+        .as_deref()
+        .map_or_else(rpc, |cluster| Cluster::from(cluster).rpc());
+    let keys = load_keys(&config.keys)?;
+    let previous_keys = config
+        .previous_keys
+        .iter()
+        .map(load_keys)
+        .collect::<Result<Vec<_>>>()?;
+    let interval = Duration::from_secs(config.poll_interval_secs);
+    if config
+        .notify
+        .iter()
+        .any(|sink| matches!(sink.kind, SinkKind::Telegram) && sink.chat_id.is_none())
+    {
+        bail!("telegram sinks need a chat_id");
+    }
+    let http = reqwest::Client::builder().timeout(NOTIFY_TIMEOUT).build()?;
+
+    // A starting daemon counts as healthy until its first poll is overdue
+    let last_success = Arc::new(AtomicU64::new(now()));
+    if let Some(addr) = &config.health_addr {
+        let listener = TcpListener::bind(addr)
+            .await
+            .with_context(|| format!("listening on {addr}"))?;
+        tokio::spawn(serve_health(listener, last_success.clone(), interval));
+    }
+
+    let mut terminate = signal(SignalKind::terminate())?;
+    eprintln!("scanning for the keys in {}", config.keys.display());
+    let mut failing = false;
+    loop {
+        let mut found = Vec::new();
+        let result = tokio::select! {
+            result = poll(&rpc, &keys, &previous_keys, &config.state_dir, &mut found) => {
+                Some(result)
+            }
+            _ = terminate.recv() => None,
+            _ = tokio::signal::ctrl_c() => None,
+        };
+        for (index, address) in &found {
+            for sink in &config.notify {
+                notify(&http, sink, "payment", &sink.payment_text(*index, address)).await;
+            }
+        }
+        match result {
+            None => break,
+            Some(Ok(())) => {
+                last_success.store(now(), Ordering::Relaxed);
+                failing = false;
+            }
+            Some(Err(err)) => {
+                eprintln!("poll failed: {err:#}");
+                if !failing {
+                    for sink in &config.notify {
+                        notify(&http, sink, "poll_failed", POLL_FAILED_TEXT).await;
+                    }
+                }
+                failing = true;
+            }
+        }
+        tokio::select! {
+            _ = tokio::time::sleep(interval) => {}
+            _ = terminate.recv() => break,
+            _ = tokio::signal::ctrl_c() => break,
+        }
+    }
+
+    eprintln!("stopped");
+    Ok(())
+}
+
+/// Send one alert, logging rather than failing if the sink can't be reached
+async fn notify(http: &reqwest::Client, sink: &Sink, event: &str, text: &str) {
+    if let Err(err) = sink.send(http, event, text).await {
+        // The URL can hold a bot token
+        eprintln!("notification failed: {}", err.without_url());
+    }
+}
+
+/// Scan from the checkpoint in `state_dir` to the end of the log, recording
+/// payments and the checkpoint after every page. Payments found are also
+/// pushed to `found`, which keeps them if the poll is cut short.
+async fn poll(
+    rpc: &RpcClient,
+    keys: &StealthKeys,
+    previous_keys: &[StealthKeys],
+    state_dir: &Path,
+    found: &mut Vec<(u64, Pubkey)>,
+) -> Result<()> {
+    let checkpoint = state_dir.join("next_index");
+    let from = match std::fs::read_to_string(&checkpoint) {
+        Ok(index) => index.trim().parse().context("reading the checkpoint")?,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => 0,
+        Err(err) => return Err(err.into()),
+    };
+
+    let mut scanner = Scanner::new(rpc, keys)
+        .previous_keys(previous_keys)
+        .start_at(from);
+    while let Some(page) = scanner.next_page().await? {
+        // Recorded without an await in between, so a shutdown can't separate
+        // the payments from the checkpoint
+        let mut lines = String::new();
+        for payment in page.payments {
+            found.push((payment.announcement_index, payment.stealth_address));
+            lines += &format!(
+                "{} {} {}\n",
+                now(),
+                payment.announcement_index,
+                payment.stealth_address
+            );
+        }
+        for unsupported in page.unsupported {
+            eprintln!(
+                "skipping announcement {}: unsupported ML-KEM parameter set",
+                unsupported.announcement_index
+            );
+        }
+        if !lines.is_empty() {
+            std::fs::OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(state_dir.join("payments.log"))?
+                .write_all(lines.as_bytes())?;
+        }
+        std::fs::write(&checkpoint, scanner.next_index().to_string())?;
+    }
+    Ok(())
+}
+
+/// Answer every HTTP request with the daemon's health
+async fn serve_health(listener: TcpListener, last_success: Arc<AtomicU64>, interval: Duration) {
+    let max_age = 3 * interval.as_secs().max(1);
+    loop {
+        let Ok((mut stream, _)) = listener.accept().await else {
+            continue;
+        };
+        let healthy = now().saturating_sub(last_success.load(Ordering::Relaxed)) <= max_age;
+        tokio::spawn(async move {
+            let (status, body) = if healthy {
+                ("200 OK", "ok\n")
+            } else {
+                ("503 Service Unavailable", "stale\n")
+            };
+            // Read the request so closing doesn't reset the connection
+            let _ = stream.read(&mut [0; 1024]).await;
+            let response = format!(
+                "HTTP/1.1 {status}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+                body.len()
+            );
+            let _ = stream.write_all(response.as_bytes()).await;
+        });
+    }
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs())
+}
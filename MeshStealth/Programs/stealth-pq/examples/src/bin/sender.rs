@@ -0,0 +1,81 @@
+//! Pay a wallet's registered meta-address.
+//!
+//! ```text
+//! sender <wallet.json> <recipient wallet> <lamports>
+//! sender <wallet.json> <payment request>         pay a request from `merchant`
+//! ```
+
+use std::str::FromStr;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use anchor_lang::prelude::Pubkey;
+use anyhow::{bail, Context, Result};
+use rand_core::OsRng;
+use stealth_pq_client::{
+    fetch_meta_address, PaymentFlow, PaymentRequest, StealthPayment, DEFAULT_APP_ID,
+};
+use stealth_pq_examples::{from_hex, load_wallet, rpc, send};
+
+const USAGE: &str = "usage: sender <wallet.json> <recipient wallet> <lamports>
+       sender <wallet.json> <payment request>";
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let rpc = rpc();
+    let (wallet, meta_address, recipient, lamports) = match args.as_slice() {
+        [wallet, recipient, lamports] => {
+            let recipient = Pubkey::from_str(recipient).context("invalid recipient wallet")?;
+            let lamports: u64 = lamports.parse().context("invalid amount")?;
+            let meta_address = fetch_meta_address(&rpc, &recipient).await?;
+            (wallet, meta_address, recipient, lamports)
+        }
+        [wallet, request] => {
+            let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs() as i64;
+            let (request, merchant) = PaymentRequest::verify(&from_hex(request.trim())?, now)?;
+            println!(
+                "Request from {merchant}: {} lamports, reference {:?}",
+                request.lamports,
+                String::from_utf8_lossy(&request.reference)
+            );
+            let meta_address = request.meta_address(&rpc).await?;
+            (wallet, meta_address, merchant, request.lamports)
+        }
+        _ => bail!(USAGE),
+    };
+    let wallet = load_wallet(wallet)?;
+    let sender = wallet.pubkey();
+
+    let Some(meta_address) = meta_address else {
+        bail!("{recipient} has no registered meta-address");
+    };
+    if !meta_address.is_hybrid() {
+        bail!("{recipient} has not uploaded its ML-KEM key yet");
+    }
+
+    let payment = StealthPayment::generate(&meta_address, &mut OsRng)?;
+    println!("Paying stealth address {}", payment.stealth_address);
+
+    // Check the whole flow before paying any rent
+    let flow = PaymentFlow::new(&rpc, &sender, &payment, DEFAULT_APP_ID, lamports).await?;
+    let preflight = flow.preflight(&rpc).await?;
+    if !preflight.is_ok() {
+        bail!("payment would fail: {:?}", preflight.problems);
+    }
+    println!(
+        "Rent {} + fees {} + {lamports} lamports, of a {} balance",
+        preflight.rent, preflight.fees, preflight.balance
+    );
+
+    // One transaction per step: the ciphertext alone takes two
+    for (step, instruction) in flow.steps() {
+        let signature = send(&rpc, &wallet, std::slice::from_ref(instruction)).await?;
+        println!("{step:?}: {signature}");
+    }
+    println!(
+        "Sent {lamports} lamports (announcement {})",
+        flow.announcement_index()
+    );
+
+    Ok(())
+}
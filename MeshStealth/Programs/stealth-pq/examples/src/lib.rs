@@ -0,0 +1,165 @@
+//! Helpers shared by the `sender`, `recipient`, `merchant`, `ceremony` and
+//! `scand` examples.
+//!
+//! The examples run the whole payment flow against a local validator:
+//!
+//! ```text
+//! solana-test-validator                 # with the program deployed, e.g. `anchor localnet`
+//! solana-keygen new -o recipient.json && solana airdrop 2 $(solana-keygen pubkey recipient.json)
+//!
+//! cargo run --bin recipient -- register recipient.json recipient.keys
+//! cargo run --bin sender -- ~/.config/solana/id.json $(solana-keygen pubkey recipient.json) 1000000
+//! cargo run --bin recipient -- scan recipient.keys
+//! cargo run --bin recipient -- sweep recipient.json recipient.keys
+//! ```
+//!
+//! To keep the recipient keys offline, generate them with
+//! `cargo run --bin ceremony -- recipient.keys recipient.meta` on the offline
+//! machine and publish with `recipient publish recipient.json recipient.meta`.
+//! `recipient rotate` and `recipient revoke` replace and close the registry
+//! entry.
+//!
+//! A merchant creates a signed payment request with
+//! `cargo run --bin merchant -- invoice recipient.json 1000000 order-42`, and a
+//! sender pays it with `cargo run --bin sender -- ~/.config/solana/id.json <request>`.
+//!
+//! `cargo run --bin scand -- scand.toml` keeps scanning as a service; see its
+//! documentation for the config.
+//!
+//! Set `CLUSTER` to `devnet` or an RPC URL to run against another cluster.
+
+use std::path::Path;
+
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::solana_program::instruction::Instruction;
+use anyhow::{bail, ensure, Context, Result};
+use sha2::{Digest, Sha512};
+use solana_message::Message;
+use solana_rpc_client::nonblocking::rpc_client::RpcClient;
+use solana_signature::Signature;
+use solana_transaction::Transaction;
+use stealth_pq_client::keys::MLKEM_SEED_SIZE;
+use stealth_pq_client::{Cluster, SpendingKey, StealthKeys};
+
+/// Cluster named by `CLUSTER` (a cluster name or RPC URL), or localnet if it
+/// isn't set
+pub fn cluster() -> Cluster {
+    std::env::var("CLUSTER").map_or(Cluster::Localnet, |cluster| Cluster::from(cluster.as_str()))
+}
+
+/// RPC client for [`cluster`]
+pub fn rpc() -> RpcClient {
+    cluster().rpc()
+}
+
+/// Load a `solana-keygen` keypair file as a signing key.
+///
+/// The file holds the Ed25519 seed followed by the public key. The seed's
+/// expanded scalar signs through [`SpendingKey::sign`], so wallets and stealth
+/// addresses go through the same path in [`send`].
+pub fn load_wallet(path: impl AsRef<Path>) -> Result<SpendingKey> {
+    let path = path.as_ref();
+    let bytes: Vec<u8> = serde_json::from_str(
+        &std::fs::read_to_string(path).with_context(|| format!("reading {}", path.display()))?,
+    )
+    .with_context(|| format!("parsing {}", path.display()))?;
+    ensure!(
+        bytes.len() == 64,
+        "{} is not a keypair file",
+        path.display()
+    );
+
+    let mut scalar: [u8; 32] = Sha512::digest(&bytes[..32])[..32].try_into().unwrap();
+    scalar[0] &= 248;
+    scalar[31] &= 127;
+    scalar[31] |= 64;
+
+    let wallet = SpendingKey::from_bytes(&scalar);
+    ensure!(
+        wallet.pubkey().to_bytes() == bytes[32..],
+        "{} has a mismatched public key",
+        path.display()
+    );
+    Ok(wallet)
+}
+
+/// Write recipient keys to `path`: m (32) || v (32) || ML-KEM seed (64)
+pub fn save_keys(path: impl AsRef<Path>, keys: &StealthKeys) -> Result<()> {
+    let Some(mlkem_seed) = keys.mlkem_seed() else {
+        bail!("the examples only use hybrid keys");
+    };
+
+    let mut bytes = Vec::with_capacity(64 + MLKEM_SEED_SIZE);
+    bytes.extend_from_slice(&keys.spending_scalar());
+    bytes.extend_from_slice(&keys.viewing_private_key());
+    bytes.extend_from_slice(&mlkem_seed);
+    std::fs::write(path, bytes)?;
+    Ok(())
+}
+
+/// Read recipient keys written by [`save_keys`]
+pub fn load_keys(path: impl AsRef<Path>) -> Result<StealthKeys> {
+    let path = path.as_ref();
+    let bytes = std::fs::read(path).with_context(|| format!("reading {}", path.display()))?;
+    ensure!(
+        bytes.len() == 64 + MLKEM_SEED_SIZE,
+        "{} is not a recipient key file",
+        path.display()
+    );
+
+    Ok(StealthKeys::from_bytes(
+        bytes[..32].try_into().unwrap(),
+        bytes[32..64].try_into().unwrap(),
+        Some(&bytes[64..]),
+    )?)
+}
+
+/// Lowercase hex of `bytes`
+pub fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+/// Bytes of a hex string written by [`to_hex`]
+pub fn from_hex(hex: &str) -> Result<Vec<u8>> {
+    ensure!(hex.len().is_multiple_of(2), "odd-length hex");
+    hex.as_bytes()
+        .chunks(2)
+        .map(|pair| {
+            std::str::from_utf8(pair)
+                .ok()
+                .and_then(|pair| u8::from_str_radix(pair, 16).ok())
+                .context("invalid hex")
+        })
+        .collect()
+}
+
+/// Send `instructions` in one transaction paid for and signed by `signer`, and
+/// wait for confirmation.
+pub async fn send(
+    rpc: &RpcClient,
+    signer: &SpendingKey,
+    instructions: &[Instruction],
+) -> Result<Signature> {
+    let message = message(rpc, &signer.pubkey(), instructions).await?;
+    let mut transaction = Transaction::new_unsigned(message);
+    transaction.signatures = vec![Signature::from(
+        signer.sign(&transaction.message.serialize()),
+    )];
+
+    Ok(rpc.send_and_confirm_transaction(&transaction).await?)
+}
+
+/// Fee `payer` would pay for a transaction carrying `instructions`
+pub async fn fee(rpc: &RpcClient, payer: &Pubkey, instructions: &[Instruction]) -> Result<u64> {
+    Ok(rpc
+        .get_fee_for_message(&message(rpc, payer, instructions).await?)
+        .await?)
+}
+
+async fn message(rpc: &RpcClient, payer: &Pubkey, instructions: &[Instruction]) -> Result<Message> {
+    Ok(Message::new_with_blockhash(
+        instructions,
+        Some(payer),
+        &rpc.get_latest_blockhash().await?,
+    ))
+}
@@ -1,14 +1,102 @@
 use anchor_lang::prelude::*;
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::solana_program::sysvar::instructions::{
+    self as instructions_sysvar, load_instruction_at_checked,
+};
 use anchor_lang::system_program;
+use anchor_spl::associated_token::AssociatedToken;
+use anchor_spl::token_2022::spl_token_2022::onchain;
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface};
+use solana_sdk_ids::secp256k1_program;
+
+#[cfg(feature = "native-entrypoint")]
+pub mod native;
 
 declare_id!("5YXYyH7i9WnQz1Hzh8kEuxSU5ws3n1Kor2KdTxnJkv6y");
 
 /// MLKEM768 ciphertext size in bytes
 pub const MLKEM_CIPHERTEXT_SIZE: usize = 1088;
 
+/// MLKEM512 ciphertext size in bytes
+pub const MLKEM512_CIPHERTEXT_SIZE: usize = 768;
+
+/// MLKEM1024 ciphertext size in bytes
+pub const MLKEM1024_CIPHERTEXT_SIZE: usize = 1568;
+
+/// `kem_variant` of a CiphertextAccount holding an MLKEM768 ciphertext (the default)
+pub const KEM_VARIANT_ML_KEM_768: u8 = 0;
+
+/// `kem_variant` of a CiphertextAccount holding an MLKEM512 ciphertext
+pub const KEM_VARIANT_ML_KEM_512: u8 = 1;
+
+/// `kem_variant` of a CiphertextAccount holding an MLKEM1024 ciphertext
+pub const KEM_VARIANT_ML_KEM_1024: u8 = 2;
+
+/// Ciphertext size in bytes for a `kem_variant`, or `None` for an unknown variant
+pub const fn mlkem_ciphertext_size(kem_variant: u8) -> Option<usize> {
+    match kem_variant {
+        KEM_VARIANT_ML_KEM_768 => Some(MLKEM_CIPHERTEXT_SIZE),
+        KEM_VARIANT_ML_KEM_512 => Some(MLKEM512_CIPHERTEXT_SIZE),
+        KEM_VARIANT_ML_KEM_1024 => Some(MLKEM1024_CIPHERTEXT_SIZE),
+        _ => None,
+    }
+}
+
 /// X25519 ephemeral public key size in bytes
 pub const EPHEMERAL_PUBKEY_SIZE: usize = 32;
 
+/// MLKEM768 encapsulation (public) key size in bytes
+pub const MLKEM_ENCAPSULATION_KEY_SIZE: usize = 1184;
+
+/// Encrypted return address size in bytes
+pub const ENCRYPTED_RETURN_ADDRESS_SIZE: usize = 32;
+
+/// AEAD authentication tag size in bytes
+pub const AEAD_TAG_SIZE: usize = 16;
+
+/// Maximum size of the TLV extension area in bytes
+pub const MAX_EXTENSIONS_SIZE: usize = 512;
+
+/// Maximum size of the encrypted payment memo in bytes
+pub const MAX_MEMO_SIZE: usize = 128;
+
+/// ERC-5564 secp256k1 stealth meta-address size in bytes: compressed spending
+/// key (33) || compressed viewing key (33)
+pub const EVM_META_ADDRESS_SIZE: usize = 66;
+
+/// App namespace used by announcements that don't belong to a specific integrator.
+/// Its PDAs keep the original ["ciphertext", stealth_pubkey] derivation.
+pub const DEFAULT_APP_ID: u32 = 0;
+
+/// Denominator for shares expressed in basis points
+pub const BPS_DENOMINATOR: u16 = 10_000;
+
+/// Lamports the sponsorship pool pays a fee payer per sponsored claim
+/// (covers a transaction with two signatures at the base fee)
+pub const SPONSORED_CLAIM_FEE_LAMPORTS: u64 = 10_000;
+
+/// Length of the window over which sponsored claims are limited per claimant, in seconds
+pub const SPONSORED_CLAIM_INTERVAL: i64 = 24 * 60 * 60;
+
+/// Maximum number of sponsored claims one claimant is reimbursed for per window
+pub const MAX_SPONSORED_CLAIMS_PER_INTERVAL: u32 = 5;
+
+/// Lamports a stealth address must hold to make a sponsored claim, so only
+/// addresses holding a real payment qualify
+pub const SPONSORED_CLAIM_MIN_BALANCE: u64 = 5_000_000;
+
+/// Maximum number of sponsored claims the pool pays for per epoch
+pub const MAX_SPONSORED_CLAIMS_PER_EPOCH: u32 = 1_000;
+
+/// Capacity of a single ciphertext chunk instruction argument in bytes
+pub const MAX_CHUNK_SIZE: usize = 576;
+
+/// Instruction data format version.
+/// 1: ciphertext chunks passed as length-prefixed `Vec<u8>`
+/// 2: ciphertext chunks passed as fixed-capacity `DataChunk`
+#[constant]
+pub const DATA_FORMAT_VERSION: u8 = 2;
+
 #[program]
 pub mod stealth_pq {
     use super::*;
@@ -17,28 +105,73 @@ pub mod stealth_pq {
     /// Due to Solana transaction size limits (~1232 bytes), ciphertext storage
     /// is split into two phases:
     /// 1. init_ciphertext: Creates the PDA and stores ephemeral key + first chunk
-    /// 2. complete_ciphertext: Stores the remaining ciphertext data
+    /// 2. complete_ciphertext: Stores the remaining ciphertext data (twice for MLKEM1024)
+    ///
+    /// The account is sized for the ciphertext of the chosen ML-KEM parameter set,
+    /// so MLKEM512 announcements cost less rent and MLKEM1024 ones more.
     ///
     /// # Arguments
     /// * `ephemeral_pubkey` - X25519 ephemeral public key (R) used for ECDH
-    /// * `ciphertext_part1` - First 512 bytes of MLKEM768 ciphertext
+    /// * `ciphertext_part1` - First chunk of the ML-KEM ciphertext (up to 576 bytes)
+    /// * `expires_at` - Optional Unix timestamp after which the payment is stale and refundable by the sender
+    /// * `app_id` - Integrator namespace for the announcement (`DEFAULT_APP_ID` if none)
+    /// * `rent_payer_share_bps` - Share of the rent (basis points) returned to the rent payer on close
+    /// * `view_tag` - First byte of SHA-256 over the X25519 shared secret, for filtering without ML-KEM decapsulation
+    /// * `kem_variant` - ML-KEM parameter set of the ciphertext (`KEM_VARIANT_*`)
+    ///
+    /// If the instructions sysvar is passed, the transaction must also contain a
+    /// system transfer of a non-zero amount to the same stealth address, so the
+    /// announcement can't be left without funds. `transfer_to_stealth` and
+    /// `transfer_spl_to_stealth` don't count: they need a finalized announcement,
+    /// and the rest of a hybrid ciphertext doesn't fit in the same transaction.
+    /// Use `init_ciphertext_and_fund` to announce and fund atomically through the
+    /// program.
+    #[allow(clippy::too_many_arguments)]
     pub fn init_ciphertext(
         ctx: Context<StealthTransfer>,
         ephemeral_pubkey: [u8; EPHEMERAL_PUBKEY_SIZE],
-        ciphertext_part1: Vec<u8>,
+        ciphertext_part1: DataChunk,
+        expires_at: Option<i64>,
+        app_id: u32,
+        rent_payer_share_bps: u16,
+        view_tag: u8,
+        kem_variant: u8,
     ) -> Result<()> {
-        require!(
-            ciphertext_part1.len() <= 512,
-            StealthError::InvalidCiphertextLength
-        );
+        let ciphertext_part1 = ciphertext_part1.as_bytes()?;
+
+        if let Some(instructions) = &ctx.accounts.instructions_sysvar {
+            require_funding_transfer(instructions, &ctx.accounts.stealth_address.key())?;
+        }
 
         let ciphertext_account = &mut ctx.accounts.ciphertext_account;
-        ciphertext_account.stealth_pubkey = ctx.accounts.stealth_address.key();
-        ciphertext_account.ephemeral_pubkey = ephemeral_pubkey;
-        ciphertext_account.mlkem_ciphertext[..ciphertext_part1.len()]
-            .copy_from_slice(&ciphertext_part1);
-        ciphertext_account.created_at = Clock::get()?.unix_timestamp;
-        ciphertext_account.bump = ctx.bumps.ciphertext_account;
+        ciphertext_account.initialize(
+            ctx.accounts.stealth_address.key(),
+            ctx.accounts.sender.key(),
+            ephemeral_pubkey,
+            expires_at,
+            app_id,
+            ctx.accounts
+                .rent_payer
+                .as_ref()
+                .unwrap_or(&ctx.accounts.sender)
+                .key(),
+            rent_payer_share_bps,
+            view_tag,
+            kem_variant,
+            ctx.bumps.ciphertext_account,
+        )?;
+        ciphertext_account.write_ciphertext(0, ciphertext_part1)?;
+
+        let sequence = ctx
+            .accounts
+            .namespace_counter
+            .next_sequence(app_id, ctx.bumps.namespace_counter);
+        emit!(AnnouncementEvent {
+            app_id,
+            sequence,
+            stealth_pubkey: ctx.accounts.stealth_address.key(),
+            ciphertext_account: ctx.accounts.ciphertext_account.key(),
+        });
 
         msg!(
             "Initialized ciphertext for stealth address: {}",
@@ -48,32 +181,256 @@ pub mod stealth_pq {
         Ok(())
     }
 
+    /// Create the CiphertextAccount and fund the stealth address in one instruction.
+    ///
+    /// Same as `init_ciphertext` followed by a transfer of `lamports` from the
+    /// sender, so the announcement can't exist unfunded. Finish with
+    /// `complete_and_finalize`; the account stays open for writes until then.
+    ///
+    /// # Arguments
+    /// * `ephemeral_pubkey` - X25519 ephemeral public key (R) used for ECDH
+    /// * `ciphertext_part1` - First chunk of the ML-KEM ciphertext (up to 576 bytes)
+    /// * `expires_at` - Optional Unix timestamp after which the payment is stale and refundable by the sender
+    /// * `app_id` - Integrator namespace for the announcement (`DEFAULT_APP_ID` if none)
+    /// * `rent_payer_share_bps` - Share of the rent (basis points) returned to the rent payer on close
+    /// * `view_tag` - First byte of SHA-256 over the X25519 shared secret
+    /// * `lamports` - Amount of SOL to transfer
+    /// * `kem_variant` - ML-KEM parameter set of the ciphertext (`KEM_VARIANT_*`)
+    #[allow(clippy::too_many_arguments)]
+    pub fn init_ciphertext_and_fund(
+        ctx: Context<InitCiphertextAndFund>,
+        ephemeral_pubkey: [u8; EPHEMERAL_PUBKEY_SIZE],
+        ciphertext_part1: DataChunk,
+        expires_at: Option<i64>,
+        app_id: u32,
+        rent_payer_share_bps: u16,
+        view_tag: u8,
+        lamports: u64,
+        kem_variant: u8,
+    ) -> Result<()> {
+        require!(lamports > 0, StealthError::ZeroTransferAmount);
+        let ciphertext_part1 = ciphertext_part1.as_bytes()?;
+
+        let ciphertext_account = &mut ctx.accounts.ciphertext_account;
+        ciphertext_account.initialize(
+            ctx.accounts.stealth_address.key(),
+            ctx.accounts.sender.key(),
+            ephemeral_pubkey,
+            expires_at,
+            app_id,
+            ctx.accounts
+                .rent_payer
+                .as_ref()
+                .unwrap_or(&ctx.accounts.sender)
+                .key(),
+            rent_payer_share_bps,
+            view_tag,
+            kem_variant,
+            ctx.bumps.ciphertext_account,
+        )?;
+        ciphertext_account.write_ciphertext(0, ciphertext_part1)?;
+
+        system_program::transfer(
+            CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                system_program::Transfer {
+                    from: ctx.accounts.sender.to_account_info(),
+                    to: ctx.accounts.stealth_address.to_account_info(),
+                },
+            ),
+            lamports,
+        )?;
+
+        let sequence = ctx
+            .accounts
+            .namespace_counter
+            .next_sequence(app_id, ctx.bumps.namespace_counter);
+        emit!(AnnouncementEvent {
+            app_id,
+            sequence,
+            stealth_pubkey: ctx.accounts.stealth_address.key(),
+            ciphertext_account: ctx.accounts.ciphertext_account.key(),
+        });
+
+        msg!(
+            "Initialized ciphertext and transferred {} lamports to stealth address: {}",
+            lamports,
+            ctx.accounts.stealth_address.key()
+        );
+
+        Ok(())
+    }
+
     /// Complete ciphertext storage with remaining data.
     ///
+    /// Only the sender recorded at init may write, and only until the account
+    /// is finalized. The same holds for the other announcement writes below.
+    ///
     /// # Arguments
-    /// * `ciphertext_part2` - Remaining bytes of MLKEM768 ciphertext (up to 576 bytes)
-    /// * `offset` - Offset in the ciphertext array to write to
+    /// * `ciphertext_part2` - Remaining bytes of the ML-KEM ciphertext (up to 576 bytes)
+    /// * `offset` - Offset in the ciphertext to write to; the chunk must end within
+    ///   the ciphertext size of the account's `kem_variant`
     pub fn complete_ciphertext(
         ctx: Context<CompleteCiphertext>,
-        ciphertext_part2: Vec<u8>,
+        ciphertext_part2: DataChunk,
         offset: u16,
     ) -> Result<()> {
-        require!(
-            (offset as usize) + ciphertext_part2.len() <= MLKEM_CIPHERTEXT_SIZE,
-            StealthError::InvalidCiphertextLength
+        let ciphertext_part2 = ciphertext_part2.as_bytes()?;
+        ctx.accounts
+            .ciphertext_account
+            .write_ciphertext(offset, ciphertext_part2)?;
+
+        msg!("Completed ciphertext at offset {}", offset);
+
+        Ok(())
+    }
+
+    /// Write the remaining ciphertext and finalize the announcement.
+    ///
+    /// `complete_ciphertext` followed by `finalize_ciphertext`, for senders with
+    /// no metadata to attach; with `init_ciphertext_and_fund` a payment takes
+    /// exactly two transactions.
+    ///
+    /// # Arguments
+    /// * `ciphertext_part2` - Remaining bytes of the ML-KEM ciphertext (up to 576 bytes)
+    /// * `offset` - Offset in the ciphertext to write to
+    pub fn complete_and_finalize(
+        ctx: Context<CompleteCiphertext>,
+        ciphertext_part2: DataChunk,
+        offset: u16,
+    ) -> Result<()> {
+        let ciphertext_part2 = ciphertext_part2.as_bytes()?;
+        let ciphertext_account = &mut ctx.accounts.ciphertext_account;
+        ciphertext_account.write_ciphertext(offset, ciphertext_part2)?;
+        ciphertext_account.finalized = true;
+
+        msg!(
+            "Completed ciphertext at offset {} and finalized for stealth address: {}",
+            offset,
+            ciphertext_account.stealth_pubkey
         );
 
+        Ok(())
+    }
+
+    /// Attach an encrypted return address to the announcement.
+    ///
+    /// The sender encrypts their return address to the hybrid shared secret, so
+    /// only the recipient can read it and use it to refund or reply privately.
+    ///
+    /// # Arguments
+    /// * `encrypted_return_address` - Return address encrypted to the shared secret
+    pub fn set_return_address(
+        ctx: Context<CompleteCiphertext>,
+        encrypted_return_address: [u8; ENCRYPTED_RETURN_ADDRESS_SIZE],
+    ) -> Result<()> {
+        ctx.accounts.ciphertext_account.encrypted_return_address = encrypted_return_address;
+
+        msg!("Set encrypted return address");
+
+        Ok(())
+    }
+
+    /// Attach the AEAD tag covering the announcement's encrypted metadata.
+    ///
+    /// The tag is computed by the sender with a key derived from the hybrid shared
+    /// secret over all encrypted metadata (the return address, the memo and the
+    /// extension area).
+    /// Recipients must verify it before acting on any decrypted metadata.
+    ///
+    /// # Arguments
+    /// * `payload_tag` - AEAD tag over the encrypted metadata
+    pub fn set_payload_tag(
+        ctx: Context<CompleteCiphertext>,
+        payload_tag: [u8; AEAD_TAG_SIZE],
+    ) -> Result<()> {
+        ctx.accounts.ciphertext_account.payload_tag = payload_tag;
+
+        msg!("Set encrypted metadata tag");
+
+        Ok(())
+    }
+
+    /// Attach an encrypted memo to the announcement.
+    ///
+    /// The sender encrypts a short note, such as an order or invoice ID, to the
+    /// hybrid shared secret so the recipient can reconcile the payment. Calling it
+    /// again replaces the memo; an empty memo clears it.
+    ///
+    /// # Arguments
+    /// * `encrypted_memo` - Memo encrypted to the shared secret, zero-padded
+    /// * `len` - Number of meaningful bytes in `encrypted_memo` (up to `MAX_MEMO_SIZE`)
+    pub fn set_memo(
+        ctx: Context<CompleteCiphertext>,
+        encrypted_memo: [u8; MAX_MEMO_SIZE],
+        len: u8,
+    ) -> Result<()> {
+        let len = len as usize;
+        require!(len <= MAX_MEMO_SIZE, StealthError::InvalidMemoLength);
+
         let ciphertext_account = &mut ctx.accounts.ciphertext_account;
+        ciphertext_account.encrypted_memo = [0u8; MAX_MEMO_SIZE];
+        ciphertext_account.encrypted_memo[..len].copy_from_slice(&encrypted_memo[..len]);
+        ciphertext_account.memo_len = len as u8;
+
+        msg!("Set encrypted memo ({} bytes)", len);
+
+        Ok(())
+    }
+
+    /// Write a chunk of the TLV extension area.
+    ///
+    /// The area starts empty and grows (reallocating the account, sender pays the
+    /// extra rent) to cover the highest byte written, up to `MAX_EXTENSIONS_SIZE`.
+    /// See `ExtensionIter` for the entry format and parsing rules.
+    ///
+    /// # Arguments
+    /// * `chunk` - Extension bytes to write
+    /// * `offset` - Offset in the extension area to write to
+    pub fn write_extensions(
+        ctx: Context<WriteExtensions>,
+        chunk: DataChunk,
+        offset: u16,
+    ) -> Result<()> {
+        let chunk = chunk.as_bytes()?;
         let start = offset as usize;
-        let end = start + ciphertext_part2.len();
-        ciphertext_account.mlkem_ciphertext[start..end].copy_from_slice(&ciphertext_part2);
+        let end = start + chunk.len();
+        require!(
+            end <= MAX_EXTENSIONS_SIZE,
+            StealthError::InvalidExtensionLength
+        );
 
-        msg!("Completed ciphertext at offset {}", offset);
+        let extensions = &mut ctx.accounts.ciphertext_account.extensions;
+        if extensions.len() < end {
+            extensions.resize(end, 0);
+        }
+        extensions[start..end].copy_from_slice(chunk);
+
+        msg!("Wrote {} extension bytes at offset {}", chunk.len(), offset);
+
+        Ok(())
+    }
+
+    /// Mark the announcement as complete.
+    ///
+    /// Locks the ciphertext and metadata against further writes. Transfers to
+    /// the stealth address are only accepted once its announcement is finalized,
+    /// so recipients never see funds next to a half-written ciphertext.
+    pub fn finalize_ciphertext(ctx: Context<CompleteCiphertext>) -> Result<()> {
+        ctx.accounts.ciphertext_account.finalized = true;
+
+        msg!(
+            "Finalized ciphertext for stealth address: {}",
+            ctx.accounts.ciphertext_account.stealth_pubkey
+        );
 
         Ok(())
     }
 
-    /// Transfer SOL to a stealth address that has a ciphertext account.
+    /// Transfer SOL to a stealth address that has a finalized ciphertext account.
+    ///
+    /// If the sender passes the global stats account, the transfer is counted
+    /// in the aggregate statistics. Omitting it leaves no trace in the stats.
     ///
     /// # Arguments
     /// * `lamports` - Amount of SOL to transfer
@@ -91,6 +448,10 @@ pub mod stealth_pq {
             lamports,
         )?;
 
+        if let Some(stats) = ctx.accounts.stats.as_mut() {
+            stats.record_transfer(lamports);
+        }
+
         msg!(
             "Transferred {} lamports to stealth address: {}",
             lamports,
@@ -100,151 +461,2517 @@ pub mod stealth_pq {
         Ok(())
     }
 
+    /// Transfer SOL to several stealth addresses in one instruction.
+    ///
+    /// `remaining_accounts` holds one `(stealth_address, ciphertext_account)`
+    /// pair per entry of `amounts`, in the same order. Every pair is checked the
+    /// same way as `transfer_to_stealth` (PDA seeds and finalization) before any
+    /// lamports move.
+    ///
+    /// The batch is all-or-nothing: if any pair or amount is invalid, or the
+    /// sender runs out of lamports part-way through, the instruction fails and the
+    /// whole transaction is rolled back, so no recipient is paid. Batches larger
+    /// than fit in one transaction must be split by the sender, and each such
+    /// transaction succeeds or fails on its own.
+    ///
+    /// # Arguments
+    /// * `amounts` - Lamports to send to each stealth address, in pair order
+    pub fn batch_transfer_to_stealth<'info>(
+        ctx: Context<'_, '_, 'info, 'info, BatchTransferToStealth<'info>>,
+        amounts: Vec<u64>,
+    ) -> Result<()> {
+        let pairs = ctx.remaining_accounts;
+        require!(
+            !amounts.is_empty() && pairs.len() == amounts.len() * 2,
+            StealthError::InvalidBatch
+        );
+
+        for (pair, &lamports) in pairs.chunks_exact(2).zip(&amounts) {
+            let (stealth_address, ciphertext_info) = (&pair[0], &pair[1]);
+            require!(lamports > 0, StealthError::ZeroTransferAmount);
+            require!(
+                stealth_address.is_writable,
+                anchor_lang::error::ErrorCode::ConstraintMut
+            );
+
+            let ciphertext_account = Account::<CiphertextAccount>::try_from(ciphertext_info)?;
+            let expected = Pubkey::create_program_address(
+                &[
+                    b"ciphertext",
+                    stealth_address.key.as_ref(),
+                    CiphertextAccount::app_id_seed(&ciphertext_account.app_id.to_le_bytes()),
+                    &[ciphertext_account.bump],
+                ],
+                ctx.program_id,
+            )
+            .map_err(|_| error!(anchor_lang::error::ErrorCode::ConstraintSeeds))?;
+            require_keys_eq!(
+                expected,
+                ciphertext_info.key(),
+                anchor_lang::error::ErrorCode::ConstraintSeeds
+            );
+            require!(
+                ciphertext_account.finalized,
+                StealthError::CiphertextNotFinalized
+            );
+        }
+
+        let mut total: u64 = 0;
+        for (pair, &lamports) in pairs.chunks_exact(2).zip(&amounts) {
+            system_program::transfer(
+                CpiContext::new(
+                    ctx.accounts.system_program.to_account_info(),
+                    system_program::Transfer {
+                        from: ctx.accounts.sender.to_account_info(),
+                        to: pair[0].clone(),
+                    },
+                ),
+                lamports,
+            )?;
+
+            if let Some(stats) = ctx.accounts.stats.as_mut() {
+                stats.record_transfer(lamports);
+            }
+            total = total.saturating_add(lamports);
+        }
+
+        msg!(
+            "Transferred {} lamports to {} stealth addresses",
+            total,
+            amounts.len()
+        );
+
+        Ok(())
+    }
+
+    /// Transfer SPL tokens to a stealth address that has a finalized ciphertext account.
+    ///
+    /// Works with both the Token and Token-2022 programs. The stealth address's
+    /// associated token account is created if needed, with the sender paying its
+    /// rent, so recipients can receive tokens at an address holding no SOL.
+    /// If the announcement records a token account (`EXT_TYPE_TOKEN_ACCOUNT`), it
+    /// must be the one paid into.
+    ///
+    /// For Token-2022 mints with a transfer fee, the recipient gets less than
+    /// `amount`; the amount actually credited is reported in `SplTransferEvent`.
+    /// For Token-2022 mints with a transfer hook, pass the hook program, its
+    /// extra-account-metas PDA and the accounts that lists as
+    /// `remaining_accounts`; they are forwarded to the token transfer.
+    ///
+    /// # Arguments
+    /// * `amount` - Amount of tokens to transfer, in base units
+    pub fn transfer_spl_to_stealth<'info>(
+        ctx: Context<'_, '_, 'info, 'info, TransferSplToStealth<'info>>,
+        amount: u64,
+    ) -> Result<()> {
+        require!(amount > 0, StealthError::ZeroTransferAmount);
+        if let Some(token_account) = ctx.accounts.ciphertext_account.token_account()? {
+            require_keys_eq!(
+                token_account,
+                ctx.accounts.stealth_token_account.key(),
+                StealthError::TokenAccountMismatch
+            );
+        }
+
+        let balance_before = ctx.accounts.stealth_token_account.amount;
+
+        // Resolves a transfer hook's extra accounts from `remaining_accounts`
+        onchain::invoke_transfer_checked(
+            ctx.accounts.token_program.key,
+            ctx.accounts.sender_token_account.to_account_info(),
+            ctx.accounts.mint.to_account_info(),
+            ctx.accounts.stealth_token_account.to_account_info(),
+            ctx.accounts.sender.to_account_info(),
+            ctx.remaining_accounts,
+            amount,
+            ctx.accounts.mint.decimals,
+            &[],
+        )?;
+
+        ctx.accounts.stealth_token_account.reload()?;
+        let received = ctx
+            .accounts
+            .stealth_token_account
+            .amount
+            .saturating_sub(balance_before);
+
+        emit!(SplTransferEvent {
+            stealth_pubkey: ctx.accounts.stealth_address.key(),
+            mint: ctx.accounts.mint.key(),
+            token_account: ctx.accounts.stealth_token_account.key(),
+            amount,
+            received,
+        });
+
+        msg!(
+            "Transferred {} tokens ({} received) of mint {} to stealth address: {}",
+            amount,
+            received,
+            ctx.accounts.mint.key(),
+            ctx.accounts.stealth_address.key()
+        );
+
+        Ok(())
+    }
+
     /// Reclaim rent by closing the CiphertextAccount PDA.
     ///
     /// Only the stealth address owner (who has the derived spending key) can call this.
-    /// The rent is returned to the stealth address (the signer).
+    /// The rent is returned to the stealth address (the signer), minus the rent
+    /// payer's share if one was set at init; the rent payer account must then be passed.
     ///
     /// This should be called when the recipient is spending from the stealth address,
     /// as they no longer need the ciphertext data.
-    pub fn reclaim_rent(_ctx: Context<ReclaimRent>) -> Result<()> {
-        // Account closure and rent return is handled automatically by Anchor's `close` constraint
+    pub fn reclaim_rent(ctx: Context<ReclaimRent>) -> Result<()> {
+        pay_rent_payer_share(
+            &ctx.accounts.ciphertext_account,
+            ctx.accounts.rent_payer.as_ref(),
+        )?;
+
+        // Account closure and return of the remaining rent is handled by Anchor's `close` constraint
         msg!("Ciphertext account closed, rent reclaimed");
         Ok(())
     }
-}
 
-/// PDA storing MLKEM768 ciphertext for a hybrid stealth transfer.
-///
-/// Seeds: ["ciphertext", stealth_pubkey]
-///
-/// This account is created by the sender when making a stealth transfer,
-/// and closed by the recipient when they spend from the stealth address.
-#[account]
-pub struct CiphertextAccount {
-    /// The stealth address this ciphertext is for (32 bytes)
-    pub stealth_pubkey: Pubkey,
+    /// Rewrite a CiphertextAccount created under the original layout in the current one.
+    ///
+    /// The original layout (stealth key, ephemeral key, MLKEM768 ciphertext,
+    /// timestamp, bump) doesn't deserialize as `CiphertextAccount`, so
+    /// `reclaim_rent` can't close those accounts until they are migrated. Anyone
+    /// may migrate an account; `payer` covers the rent of the larger layout.
+    ///
+    /// The result is a finalized default-namespace ML-KEM-768 announcement without
+    /// expiry or metadata. The original layout recorded no sender and no view tag,
+    /// so `sender` stays zeroed (nobody can write to or refund the account) and
+    /// `view_tag` is 0, which scanners must not filter on.
+    pub fn migrate_ciphertext(ctx: Context<MigrateCiphertext>) -> Result<()> {
+        let info = ctx.accounts.ciphertext_account.to_account_info();
+        let legacy = LegacyCiphertextAccount::decode(&info.try_borrow_data()?)?;
 
-    /// Ephemeral X25519 public key (R) used for ECDH shared secret (32 bytes)
-    pub ephemeral_pubkey: [u8; EPHEMERAL_PUBKEY_SIZE],
+        let expected = Pubkey::create_program_address(
+            &[
+                b"ciphertext",
+                legacy.stealth_pubkey.as_ref(),
+                &[legacy.bump],
+            ],
+            &crate::ID,
+        )
+        .map_err(|_| error!(ErrorCode::ConstraintSeeds))?;
+        require_keys_eq!(info.key(), expected, ErrorCode::ConstraintSeeds);
 
-    /// MLKEM768 ciphertext from encapsulation (1088 bytes)
-    pub mlkem_ciphertext: [u8; MLKEM_CIPHERTEXT_SIZE],
+        let space = CiphertextAccount::init_space(KEM_VARIANT_ML_KEM_768);
+        let rent = Rent::get()?.minimum_balance(space);
+        let shortfall = rent.saturating_sub(info.lamports());
+        if shortfall > 0 {
+            system_program::transfer(
+                CpiContext::new(
+                    ctx.accounts.system_program.to_account_info(),
+                    system_program::Transfer {
+                        from: ctx.accounts.payer.to_account_info(),
+                        to: info.clone(),
+                    },
+                ),
+                shortfall,
+            )?;
+        }
+        info.resize(space)?;
 
-    /// Unix timestamp when the transfer was created (8 bytes)
-    pub created_at: i64,
+        let migrated = legacy.migrate(ctx.accounts.payer.key());
+        migrated.try_serialize(&mut &mut info.try_borrow_mut_data()?[..])?;
 
-    /// Bump seed for PDA derivation (1 byte)
-    pub bump: u8,
-}
+        msg!(
+            "Migrated ciphertext for stealth address: {}",
+            migrated.stealth_pubkey
+        );
+        Ok(())
+    }
 
-impl Default for CiphertextAccount {
-    fn default() -> Self {
-        Self {
-            stealth_pubkey: Pubkey::default(),
-            ephemeral_pubkey: [0u8; EPHEMERAL_PUBKEY_SIZE],
-            mlkem_ciphertext: [0u8; MLKEM_CIPHERTEXT_SIZE],
-            created_at: 0,
-            bump: 0,
-        }
+    /// Close an expired CiphertextAccount on behalf of its sender.
+    ///
+    /// Once `expires_at` has passed, the original sender may close the account;
+    /// the rent goes back in full to the rent payer who deposited it. Intended
+    /// for payments the recipient never picked up.
+    ///
+    /// Transferred SOL and tokens are not refunded: they sit in accounts owned
+    /// by the stealth address, which only the recipient can sign for. Closing
+    /// the announcement removes what the recipient needs to find them, so
+    /// senders should only refund payments they consider lost.
+    pub fn refund_expired(ctx: Context<RefundExpired>) -> Result<()> {
+        let expires_at = ctx.accounts.ciphertext_account.expires_at;
+        let now = Clock::get()?.unix_timestamp;
+        require!(
+            expires_at != 0 && now >= expires_at,
+            StealthError::NotExpired
+        );
+
+        // Account closure and return of the rent is handled by Anchor's `close` constraint
+        msg!(
+            "Expired ciphertext for stealth address {} closed by sender",
+            ctx.accounts.ciphertext_account.stealth_pubkey
+        );
+        Ok(())
     }
-}
 
-impl CiphertextAccount {
-    /// Size of CiphertextAccount in bytes (without Anchor discriminator)
-    /// 32 (pubkey) + 32 (ephemeral) + 1088 (ciphertext) + 8 (timestamp) + 1 (bump) = 1161
-    pub const SIZE: usize = 32 + EPHEMERAL_PUBKEY_SIZE + MLKEM_CIPHERTEXT_SIZE + 8 + 1;
-}
+    /// Reclaim rent with the transaction fee paid from the sponsorship pool.
+    ///
+    /// For recipients with no SOL outside the stealth address: a relayer signs as
+    /// fee payer and is reimbursed `SPONSORED_CLAIM_FEE_LAMPORTS` from the pool.
+    /// A sweep out of the stealth address can ride in the same transaction.
+    ///
+    /// Only finalized announcements whose stealth address holds at least
+    /// `SPONSORED_CLAIM_MIN_BALANCE` qualify, so throwaway addresses can't farm
+    /// reimbursements. Stealth addresses are one-time, so the rate limit is kept
+    /// per claimant instead: the fee payer's ClaimRecord allows
+    /// `MAX_SPONSORED_CLAIMS_PER_INTERVAL` claims per `SPONSORED_CLAIM_INTERVAL`,
+    /// and outlives the ciphertext accounts it was used for. The fee payer pays
+    /// the record's rent, which is more than a window's reimbursements, so fresh
+    /// fee payers don't pay off either. `MAX_SPONSORED_CLAIMS_PER_EPOCH` caps
+    /// claims overall.
+    /// The rent payer's share is handled as in `reclaim_rent`.
+    pub fn sponsored_reclaim_rent(ctx: Context<SponsoredReclaimRent>) -> Result<()> {
+        // Measured before the ciphertext account's rent is returned on close
+        require!(
+            ctx.accounts.stealth_signer.lamports() >= SPONSORED_CLAIM_MIN_BALANCE,
+            StealthError::SponsoredClaimBalanceTooLow
+        );
 
-/// Accounts for the stealth_transfer instruction.
-///
-/// Creates a CiphertextAccount PDA and optionally transfers SOL.
-#[derive(Accounts)]
-pub struct StealthTransfer<'info> {
-    /// The sender who pays for the transaction and rent
-    #[account(mut)]
-    pub sender: Signer<'info>,
+        pay_rent_payer_share(
+            &ctx.accounts.ciphertext_account,
+            ctx.accounts.rent_payer.as_ref(),
+        )?;
 
-    /// The one-time stealth address that will receive funds.
-    /// CHECK: This is a derived stealth address, not an existing account.
-    /// It's intentionally unchecked as it's a fresh address for this transfer.
-    #[account(mut)]
-    pub stealth_address: AccountInfo<'info>,
+        let clock = Clock::get()?;
+        let claim_record = &mut ctx.accounts.claim_record;
+        claim_record.record_claim(clock.unix_timestamp)?;
+        claim_record.claimant = ctx.accounts.fee_payer.key();
+        claim_record.bump = ctx.bumps.claim_record;
 
-    /// PDA storing the MLKEM ciphertext, derived from the stealth address.
-    /// Sender pays rent for this account.
-    #[account(
-        init,
-        payer = sender,
-        space = 8 + CiphertextAccount::SIZE,
-        seeds = [b"ciphertext", stealth_address.key().as_ref()],
-        bump
-    )]
-    pub ciphertext_account: Account<'info, CiphertextAccount>,
+        let sponsor_pool = &mut ctx.accounts.sponsor_pool;
+        if sponsor_pool.epoch != clock.epoch {
+            sponsor_pool.epoch = clock.epoch;
+            sponsor_pool.epoch_claims = 0;
+        }
+        require!(
+            sponsor_pool.epoch_claims < MAX_SPONSORED_CLAIMS_PER_EPOCH,
+            StealthError::SponsorPoolEpochLimit
+        );
+        sponsor_pool.epoch_claims += 1;
 
-    /// System program for account creation and SOL transfers
-    pub system_program: Program<'info, System>,
-}
+        let rent = Rent::get()?;
+        let reimbursement = SPONSORED_CLAIM_FEE_LAMPORTS;
 
-/// Accounts for completing ciphertext storage.
+        let pool = ctx.accounts.sponsor_pool.to_account_info();
+        let pool_floor = rent.minimum_balance(pool.data_len());
+        require!(
+            pool.lamports() >= pool_floor + reimbursement,
+            StealthError::SponsorPoolEmpty
+        );
+        pool.sub_lamports(reimbursement)?;
+        ctx.accounts.fee_payer.add_lamports(reimbursement)?;
+        ctx.accounts.sponsor_pool.total_claims += 1;
+
+        msg!(
+            "Sponsored claim for {}, reimbursed {} lamports",
+            ctx.accounts.stealth_signer.key(),
+            reimbursement
+        );
+
+        Ok(())
+    }
+
+    /// Create the sponsorship pool. Anyone may pay for it, once.
+    pub fn init_sponsor_pool(ctx: Context<InitSponsorPool>) -> Result<()> {
+        ctx.accounts.sponsor_pool.bump = ctx.bumps.sponsor_pool;
+
+        msg!("Initialized sponsorship pool");
+
+        Ok(())
+    }
+
+    /// Donate lamports to the sponsorship pool.
+    ///
+    /// # Arguments
+    /// * `lamports` - Amount of SOL to add to the pool
+    pub fn fund_sponsor_pool(ctx: Context<FundSponsorPool>, lamports: u64) -> Result<()> {
+        require!(lamports > 0, StealthError::ZeroTransferAmount);
+
+        system_program::transfer(
+            CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                system_program::Transfer {
+                    from: ctx.accounts.funder.to_account_info(),
+                    to: ctx.accounts.sponsor_pool.to_account_info(),
+                },
+            ),
+            lamports,
+        )?;
+
+        msg!("Added {} lamports to sponsorship pool", lamports);
+
+        Ok(())
+    }
+
+    /// Create the global announcement log. Anyone may pay for it, once.
+    pub fn init_announcement_log(ctx: Context<InitAnnouncementLog>) -> Result<()> {
+        ctx.accounts.announcement_log.bump = ctx.bumps.announcement_log;
+
+        msg!("Initialized announcement log");
+
+        Ok(())
+    }
+
+    /// Append an announcement to the global log.
+    ///
+    /// Creates an Announcement PDA at the next log index, so recipients can page
+    /// through new announcements by index range instead of scanning every
+    /// CiphertextAccount. The sender pays its rent; it is returned to the
+    /// recipient together with the ciphertext account rent on reclaim.
+    ///
+    /// Logging is opt-in. Only the sender may log a finalized announcement, and
+    /// only once: the log index is recorded in the ciphertext account.
+    pub fn log_announcement(ctx: Context<LogAnnouncement>) -> Result<()> {
+        let ciphertext_account = &mut ctx.accounts.ciphertext_account;
+        let log = &mut ctx.accounts.announcement_log;
+
+        let announcement = &mut ctx.accounts.announcement;
+        announcement.index = log.count;
+        announcement.app_id = ciphertext_account.app_id;
+        announcement.stealth_pubkey = ciphertext_account.stealth_pubkey;
+        announcement.ciphertext_account = ciphertext_account.key();
+        announcement.ephemeral_pubkey = ciphertext_account.ephemeral_pubkey;
+        announcement.view_tag = ciphertext_account.view_tag;
+        announcement.bump = ctx.bumps.announcement;
+        announcement.payer = ctx.accounts.sender.key();
+
+        ciphertext_account.logged = true;
+        ciphertext_account.announcement_index = announcement.index;
+
+        log.count += 1;
+
+        msg!(
+            "Logged announcement {} for stealth address: {}",
+            announcement.index,
+            announcement.stealth_pubkey
+        );
+
+        Ok(())
+    }
+
+    /// Create the global stats account. Anyone may pay for it, once.
+    pub fn init_stats(ctx: Context<InitStats>) -> Result<()> {
+        ctx.accounts.stats.bump = ctx.bumps.stats;
+
+        msg!("Initialized stats account");
+
+        Ok(())
+    }
+
+    /// Return a snapshot of the global stats via the transaction return data.
+    ///
+    /// Lets dashboards read the counters with a simulated transaction instead
+    /// of decoding the account layout.
+    pub fn snapshot_stats(ctx: Context<SnapshotStats>) -> Result<StatsSnapshot> {
+        let stats = &ctx.accounts.stats;
+
+        Ok(StatsSnapshot {
+            transfer_count: stats.transfer_count,
+            total_lamports: stats.total_lamports,
+            slot: Clock::get()?.slot,
+        })
+    }
+
+    /// Publish a stealth meta-address for the signer's wallet.
+    ///
+    /// Senders can then resolve the owner's regular address to stealth keys
+    /// without an out-of-band exchange. The ML-KEM encapsulation key doesn't fit
+    /// in one transaction and is uploaded afterwards with `write_meta_address_key`;
+    /// until then the entry is a classical (X25519-only) meta-address.
+    ///
+    /// # Arguments
+    /// * `spending_pubkey` - Spending public key (M)
+    /// * `viewing_pubkey` - X25519 viewing public key (V)
+    pub fn register_meta_address(
+        ctx: Context<RegisterMetaAddress>,
+        spending_pubkey: [u8; 32],
+        viewing_pubkey: [u8; 32],
+    ) -> Result<()> {
+        let registry = &mut ctx.accounts.meta_address;
+        registry.owner = ctx.accounts.owner.key();
+        registry.bump = ctx.bumps.meta_address;
+        registry.set_keys(spending_pubkey, viewing_pubkey)?;
+
+        emit!(MetaAddressEvent {
+            owner: registry.owner,
+            epoch: registry.epoch,
+        });
+
+        msg!("Registered meta-address for {}", registry.owner);
+
+        Ok(())
+    }
+
+    /// Replace the keys of a registered meta-address.
+    ///
+    /// Bumps the registry epoch and clears the ML-KEM encapsulation key, which
+    /// must be uploaded again for the new key set.
+    ///
+    /// # Arguments
+    /// * `spending_pubkey` - Spending public key (M)
+    /// * `viewing_pubkey` - X25519 viewing public key (V)
+    pub fn update_meta_address(
+        ctx: Context<UpdateMetaAddress>,
+        spending_pubkey: [u8; 32],
+        viewing_pubkey: [u8; 32],
+    ) -> Result<()> {
+        let registry = &mut ctx.accounts.meta_address;
+        registry.set_keys(spending_pubkey, viewing_pubkey)?;
+
+        emit!(MetaAddressEvent {
+            owner: registry.owner,
+            epoch: registry.epoch,
+        });
+
+        msg!(
+            "Updated meta-address for {} to epoch {}",
+            registry.owner,
+            registry.epoch
+        );
+
+        Ok(())
+    }
+
+    /// Write a chunk of the ML-KEM encapsulation key of a registered meta-address.
+    ///
+    /// # Arguments
+    /// * `chunk` - Encapsulation key bytes to write
+    /// * `offset` - Offset in the encapsulation key to write to
+    pub fn write_meta_address_key(
+        ctx: Context<UpdateMetaAddress>,
+        chunk: DataChunk,
+        offset: u16,
+    ) -> Result<()> {
+        let chunk = chunk.as_bytes()?;
+        require!(
+            (offset as usize) + chunk.len() <= MLKEM_ENCAPSULATION_KEY_SIZE,
+            StealthError::InvalidEncapsulationKeyLength
+        );
+
+        let start = offset as usize;
+        let end = start + chunk.len();
+        let registry = &mut ctx.accounts.meta_address;
+        registry.mlkem_encapsulation_key[start..end].copy_from_slice(chunk);
+        registry.updated_at = Clock::get()?.unix_timestamp;
+
+        msg!(
+            "Wrote {} bytes of the encapsulation key at offset {}",
+            chunk.len(),
+            offset
+        );
+
+        Ok(())
+    }
+
+    /// Remove a meta-address from the registry and return its rent to the owner.
+    pub fn close_meta_address(_ctx: Context<CloseMetaAddress>) -> Result<()> {
+        // Account closure and rent return is handled automatically by Anchor's `close` constraint
+        msg!("Meta-address closed");
+        Ok(())
+    }
+
+    /// Link the owner's registered meta-address to an EVM stealth meta-address.
+    ///
+    /// Both sides sign the link: the owner signs the transaction, and the EVM
+    /// account signs `evm_attestation_message` in a secp256k1 precompile
+    /// instruction of the same transaction. Senders on either chain can then
+    /// resolve one identity to both meta-addresses.
+    ///
+    /// # Arguments
+    /// * `evm_address` - The EVM account vouching for the meta-address
+    /// * `evm_meta_address` - Its ERC-5564 stealth meta-address
+    pub fn attest_evm_meta_address(
+        ctx: Context<AttestEvmMetaAddress>,
+        evm_address: [u8; 20],
+        evm_meta_address: [u8; EVM_META_ADDRESS_SIZE],
+    ) -> Result<()> {
+        let owner = ctx.accounts.owner.key();
+        let message = evm_attestation_message(&owner, &evm_meta_address);
+        require!(
+            has_secp256k1_signature(&ctx.accounts.instructions_sysvar, &evm_address, &message),
+            StealthError::MissingEvmSignature
+        );
+
+        let attestation = &mut ctx.accounts.attestation;
+        attestation.owner = owner;
+        attestation.evm_address = evm_address;
+        attestation.evm_meta_address = evm_meta_address;
+        attestation.attested_at = Clock::get()?.unix_timestamp;
+        attestation.bump = ctx.bumps.attestation;
+
+        emit!(EvmAttestationEvent {
+            owner,
+            evm_address,
+            revoked: false,
+        });
+
+        msg!("Linked meta-address of {} to an EVM meta-address", owner);
+
+        Ok(())
+    }
+
+    /// Revoke an EVM attestation and return its rent to the owner.
+    ///
+    /// Only the Solana owner can revoke. To link another EVM meta-address,
+    /// revoke and attest again.
+    pub fn revoke_evm_attestation(ctx: Context<RevokeEvmAttestation>) -> Result<()> {
+        emit!(EvmAttestationEvent {
+            owner: ctx.accounts.owner.key(),
+            evm_address: ctx.accounts.attestation.evm_address,
+            revoked: true,
+        });
+
+        msg!("EVM attestation revoked");
+
+        Ok(())
+    }
+
+    /// Create a sender-owned staging buffer for uploading ciphertext.
+    ///
+    /// The buffer is written across as many transactions as needed and then
+    /// committed into a CiphertextAccount in one step, so a partially written
+    /// announcement never appears under a stealth address. A buffer is reusable:
+    /// committing clears it for the next payment.
+    ///
+    /// # Arguments
+    /// * `buffer_id` - Caller-chosen ID, lets one sender keep several buffers
+    pub fn init_buffer(ctx: Context<InitBuffer>, buffer_id: u64) -> Result<()> {
+        let buffer = &mut ctx.accounts.buffer;
+        buffer.authority = ctx.accounts.authority.key();
+        buffer.buffer_id = buffer_id;
+        buffer.bump = ctx.bumps.buffer;
+
+        msg!("Initialized staging buffer {}", buffer_id);
+
+        Ok(())
+    }
+
+    /// Write a chunk of ciphertext into a staging buffer.
+    ///
+    /// # Arguments
+    /// * `chunk` - Ciphertext bytes to write
+    /// * `offset` - Offset in the buffered ciphertext to write to
+    pub fn write_buffer(ctx: Context<WriteBuffer>, chunk: DataChunk, offset: u16) -> Result<()> {
+        let chunk = chunk.as_bytes()?;
+        require!(
+            (offset as usize) + chunk.len() <= MLKEM_CIPHERTEXT_SIZE,
+            StealthError::InvalidCiphertextLength
+        );
+
+        let start = offset as usize;
+        let end = start + chunk.len();
+        ctx.accounts.buffer.mlkem_ciphertext[start..end].copy_from_slice(chunk);
+
+        msg!(
+            "Wrote {} bytes to staging buffer at offset {}",
+            chunk.len(),
+            offset
+        );
+
+        Ok(())
+    }
+
+    /// Commit a staging buffer into a new CiphertextAccount for a stealth address.
+    ///
+    /// Creates the CiphertextAccount with the full buffered ciphertext and clears
+    /// the buffer so it can be reused. Buffers hold MLKEM768 ciphertexts; use
+    /// `init_ciphertext` for the other parameter sets.
+    ///
+    /// # Arguments
+    /// * `ephemeral_pubkey` - X25519 ephemeral public key (R) used for ECDH
+    /// * `expires_at` - Optional Unix timestamp after which the payment is stale and refundable by the sender
+    /// * `app_id` - Integrator namespace for the announcement (`DEFAULT_APP_ID` if none)
+    /// * `rent_payer_share_bps` - Share of the rent (basis points) returned to the authority on close
+    /// * `view_tag` - First byte of SHA-256 over the X25519 shared secret
+    pub fn commit_buffer(
+        ctx: Context<CommitBuffer>,
+        ephemeral_pubkey: [u8; EPHEMERAL_PUBKEY_SIZE],
+        expires_at: Option<i64>,
+        app_id: u32,
+        rent_payer_share_bps: u16,
+        view_tag: u8,
+    ) -> Result<()> {
+        let buffer = &mut ctx.accounts.buffer;
+        let ciphertext_account = &mut ctx.accounts.ciphertext_account;
+
+        ciphertext_account.initialize(
+            ctx.accounts.stealth_address.key(),
+            ctx.accounts.authority.key(),
+            ephemeral_pubkey,
+            expires_at,
+            app_id,
+            ctx.accounts.authority.key(),
+            rent_payer_share_bps,
+            view_tag,
+            KEM_VARIANT_ML_KEM_768,
+            ctx.bumps.ciphertext_account,
+        )?;
+        ciphertext_account
+            .mlkem_ciphertext
+            .copy_from_slice(&buffer.mlkem_ciphertext);
+        buffer.mlkem_ciphertext = [0u8; MLKEM_CIPHERTEXT_SIZE];
+
+        let sequence = ctx
+            .accounts
+            .namespace_counter
+            .next_sequence(app_id, ctx.bumps.namespace_counter);
+        emit!(AnnouncementEvent {
+            app_id,
+            sequence,
+            stealth_pubkey: ctx.accounts.stealth_address.key(),
+            ciphertext_account: ctx.accounts.ciphertext_account.key(),
+        });
+
+        msg!(
+            "Committed staging buffer {} to stealth address: {}",
+            buffer.buffer_id,
+            ctx.accounts.stealth_address.key()
+        );
+
+        Ok(())
+    }
+
+    /// Close a staging buffer and return its rent to the authority.
+    pub fn close_buffer(_ctx: Context<CloseBuffer>) -> Result<()> {
+        // Account closure and rent return is handled automatically by Anchor's `close` constraint
+        msg!("Staging buffer closed");
+        Ok(())
+    }
+}
+
+/// Move the rent payer's share of a CiphertextAccount's lamports to the rent payer.
+///
+/// Called before Anchor's `close` hands the rest to the stealth signer. The rent
+/// payer account must be passed whenever a share was set.
+fn pay_rent_payer_share(
+    ciphertext_account: &Account<CiphertextAccount>,
+    rent_payer: Option<&AccountInfo>,
+) -> Result<()> {
+    if ciphertext_account.rent_payer_share_bps == 0 {
+        return Ok(());
+    }
+    let rent_payer = rent_payer.ok_or(StealthError::MissingRentPayer)?;
+
+    let share = ciphertext_account.rent_payer_share(ciphertext_account.get_lamports());
+    ciphertext_account.sub_lamports(share)?;
+    rent_payer.add_lamports(share)?;
+
+    msg!(
+        "Returned {} lamports of rent to {}",
+        share,
+        rent_payer.key()
+    );
+
+    Ok(())
+}
+
+/// Message an EVM account signs to link `evm_meta_address` to `owner`'s
+/// registered meta-address.
+///
+/// In EIP-191 `personal_sign` form, so ordinary EVM wallets can sign it: the
+/// text names the Solana wallet and the meta-address in ERC-5564 `st:eth:0x`
+/// notation.
+pub fn evm_attestation_message(
+    owner: &Pubkey,
+    evm_meta_address: &[u8; EVM_META_ADDRESS_SIZE],
+) -> Vec<u8> {
+    const HEX: &[u8; 16] = b"0123456789abcdef";
+
+    let mut text = format!("stealth-pq: link {} to st:eth:0x", owner).into_bytes();
+    for byte in evm_meta_address {
+        text.push(HEX[(byte >> 4) as usize]);
+        text.push(HEX[(byte & 0x0F) as usize]);
+    }
+
+    let mut message = format!("\x19Ethereum Signed Message:\n{}", text.len()).into_bytes();
+    message.extend_from_slice(&text);
+    message
+}
+
+/// Whether the transaction has a secp256k1 precompile instruction verifying a
+/// signature by `evm_address` over `message`.
+///
+/// The runtime checks precompile signatures before the program runs, so only
+/// the signed data needs matching here.
+fn has_secp256k1_signature(
+    instructions: &AccountInfo,
+    evm_address: &[u8; 20],
+    message: &[u8],
+) -> bool {
+    let mut index = 0;
+    while let Ok(ix) = load_instruction_at_checked(index, instructions) {
+        if ix.program_id == secp256k1_program::ID
+            && secp256k1_signs(&ix.data, index, evm_address, message)
+        {
+            return true;
+        }
+        index += 1;
+    }
+
+    false
+}
+
+/// Whether secp256k1 precompile instruction data at `index` in the transaction
+/// carries a signature by `evm_address` over `message`.
+///
+/// Each signature has 11 bytes of offsets after the 1-byte count: signature
+/// offset (u16), its instruction index (u8), address offset (u16), its
+/// instruction index (u8), message offset (u16), message size (u16) and its
+/// instruction index (u8). All three must point into this instruction, so the
+/// signed data can't be taken from another one.
+fn secp256k1_signs(data: &[u8], index: usize, evm_address: &[u8; 20], message: &[u8]) -> bool {
+    let Some((&count, offsets)) = data.split_first() else {
+        return false;
+    };
+
+    offsets
+        .chunks_exact(11)
+        .take(count as usize)
+        .any(|offsets| {
+            let u16_at = |at: usize| u16::from_le_bytes([offsets[at], offsets[at + 1]]) as usize;
+            let own = |at: usize| offsets[at] as usize == index;
+            let (address_offset, message_offset, message_size) = (u16_at(3), u16_at(6), u16_at(8));
+
+            own(2)
+                && own(5)
+                && own(10)
+                && data.get(address_offset..address_offset + 20) == Some(evm_address.as_slice())
+                && data.get(message_offset..message_offset + message_size) == Some(message)
+        })
+}
+
+/// Require a funding transfer to `stealth_address` somewhere in the transaction.
+fn require_funding_transfer(instructions: &AccountInfo, stealth_address: &Pubkey) -> Result<()> {
+    let mut index = 0;
+    while let Ok(ix) = load_instruction_at_checked(index, instructions) {
+        if is_funding_transfer(&ix, stealth_address) {
+            return Ok(());
+        }
+        index += 1;
+    }
+
+    err!(StealthError::MissingFundingTransfer)
+}
+
+/// Whether `ix` is a system transfer of a non-zero amount to `stealth_address`.
+///
+/// The program's own transfers require a finalized announcement, which can't
+/// happen in the transaction that creates it, so they aren't accepted here.
+fn is_funding_transfer(ix: &Instruction, stealth_address: &Pubkey) -> bool {
+    if ix.program_id != system_program::ID {
+        return false;
+    }
+
+    // SystemInstruction::Transfer accounts: [from, to]
+    let to_stealth = ix
+        .accounts
+        .get(1)
+        .is_some_and(|meta| meta.pubkey == *stealth_address);
+    if !to_stealth {
+        return false;
+    }
+
+    // SystemInstruction::Transfer: u32 index 2 || u64 lamports
+    ix.data
+        .strip_prefix(&2u32.to_le_bytes())
+        .and_then(|args| args.get(..8))
+        .is_some_and(|lamports| u64::from_le_bytes(lamports.try_into().unwrap()) > 0)
+}
+
+/// Fixed-capacity byte chunk used for ciphertext instruction arguments.
+///
+/// Always `MAX_CHUNK_SIZE` bytes on the wire; only the first `len` bytes are
+/// meaningful. This keeps instruction data a fixed size per instruction and
+/// avoids a heap allocation for the Borsh `Vec<u8>`.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct DataChunk {
+    /// Number of meaningful bytes in `data`
+    pub len: u16,
+
+    /// Chunk payload, zero-padded after `len`
+    pub data: [u8; MAX_CHUNK_SIZE],
+}
+
+impl DataChunk {
+    /// The meaningful bytes of the chunk.
+    pub fn as_bytes(&self) -> Result<&[u8]> {
+        let len = self.len as usize;
+        require!(len <= MAX_CHUNK_SIZE, StealthError::InvalidCiphertextLength);
+        Ok(&self.data[..len])
+    }
+}
+
+/// PDA storing MLKEM768 ciphertext for a hybrid stealth transfer.
+///
+/// Seeds: ["ciphertext", stealth_pubkey, app_id (u32 LE, omitted for DEFAULT_APP_ID)]
+///
+/// This account is created by the sender when making a stealth transfer,
+/// and closed by the recipient when they spend from the stealth address.
+#[account]
+pub struct CiphertextAccount {
+    /// The stealth address this ciphertext is for (32 bytes)
+    pub stealth_pubkey: Pubkey,
+
+    /// Ephemeral X25519 public key (R) used for ECDH shared secret (32 bytes)
+    pub ephemeral_pubkey: [u8; EPHEMERAL_PUBKEY_SIZE],
+
+    /// Unix timestamp when the transfer was created (8 bytes)
+    pub created_at: i64,
+
+    /// Bump seed for PDA derivation (1 byte)
+    pub bump: u8,
+
+    /// Sender-chosen Unix timestamp after which the payment is considered stale,
+    /// or 0 for no expiry (8 bytes). Wallets may stop surfacing the payment
+    /// after it, and the sender may close the account with `refund_expired`.
+    pub expires_at: i64,
+
+    /// Sender's return address encrypted to the hybrid shared secret, or all
+    /// zeros if the sender did not provide one (32 bytes)
+    pub encrypted_return_address: [u8; ENCRYPTED_RETURN_ADDRESS_SIZE],
+
+    /// AEAD tag over the encrypted metadata, keyed by the hybrid shared secret,
+    /// or all zeros if the announcement carries no encrypted metadata (16 bytes)
+    pub payload_tag: [u8; AEAD_TAG_SIZE],
+
+    /// Integrator namespace, part of the PDA seeds (4 bytes). Scanners that only
+    /// care about one app can filter on this field.
+    pub app_id: u32,
+
+    /// Account that paid the rent for this account (32 bytes)
+    pub rent_payer: Pubkey,
+
+    /// Share of the rent, in basis points, returned to `rent_payer` when the
+    /// recipient closes the account (2 bytes)
+    pub rent_payer_share_bps: u16,
+
+    /// First byte of SHA-256 over the X25519 shared secret (1 byte). Recipients
+    /// compare it before running ML-KEM decapsulation; it matches for 1 in 256
+    /// accounts that aren't theirs. Indexers can filter on it with memcmp at
+    /// `VIEW_TAG_OFFSET`.
+    pub view_tag: u8,
+
+    /// The sender who created the account, the only signer allowed to write
+    /// the rest of the announcement (32 bytes)
+    pub sender: Pubkey,
+
+    /// Whether the sender has finished writing the announcement (1 byte).
+    /// Writes are rejected once set, transfers until then.
+    pub finalized: bool,
+
+    /// ML-KEM parameter set of the ciphertext, one of the `KEM_VARIANT_*`
+    /// constants (1 byte). Set at init and fixes the ciphertext size.
+    pub kem_variant: u8,
+
+    /// Length of `encrypted_memo` in bytes, or 0 if the sender attached no memo (1 byte)
+    pub memo_len: u8,
+
+    /// Memo encrypted to the hybrid shared secret, zero-padded (128 bytes).
+    /// Only the first `memo_len` bytes are meaningful.
+    pub encrypted_memo: [u8; MAX_MEMO_SIZE],
+
+    /// Whether the announcement has been appended to the announcement log (1 byte).
+    /// `log_announcement` rejects a second entry for the same account.
+    pub logged: bool,
+
+    /// Index of the announcement log entry, meaningful only if `logged` (8 bytes)
+    pub announcement_index: u64,
+
+    /// ML-KEM ciphertext from encapsulation (4-byte length prefix + 768, 1088 or
+    /// 1568 bytes, by `kem_variant`). Comes after the fixed-size fields so their
+    /// offsets are the same for every variant.
+    pub mlkem_ciphertext: Vec<u8>,
+
+    /// TLV extension area (4-byte length prefix + up to 512 bytes). Always the
+    /// last field so the account can grow as extensions are written.
+    pub extensions: Vec<u8>,
+}
+
+impl Default for CiphertextAccount {
+    fn default() -> Self {
+        Self {
+            stealth_pubkey: Pubkey::default(),
+            ephemeral_pubkey: [0u8; EPHEMERAL_PUBKEY_SIZE],
+            created_at: 0,
+            bump: 0,
+            expires_at: 0,
+            encrypted_return_address: [0u8; ENCRYPTED_RETURN_ADDRESS_SIZE],
+            payload_tag: [0u8; AEAD_TAG_SIZE],
+            app_id: DEFAULT_APP_ID,
+            rent_payer: Pubkey::default(),
+            rent_payer_share_bps: 0,
+            view_tag: 0,
+            sender: Pubkey::default(),
+            finalized: false,
+            kem_variant: KEM_VARIANT_ML_KEM_768,
+            memo_len: 0,
+            encrypted_memo: [0u8; MAX_MEMO_SIZE],
+            logged: false,
+            announcement_index: 0,
+            mlkem_ciphertext: Vec::new(),
+            extensions: Vec::new(),
+        }
+    }
+}
+
+impl CiphertextAccount {
+    /// Size of CiphertextAccount in bytes with an empty ciphertext and extension area
+    /// (without Anchor discriminator)
+    /// 32 (pubkey) + 32 (ephemeral) + 8 (timestamp) + 1 (bump)
+    /// + 8 (expires_at) + 32 (return address) + 16 (payload tag) + 4 (app_id)
+    /// + 32 (rent payer) + 2 (rent payer share) + 1 (view tag) + 32 (sender) + 1 (finalized)
+    /// + 1 (kem variant) + 1 (memo length) + 128 (memo) + 1 (logged)
+    /// + 8 (announcement index) + 4 (ciphertext length) + 4 (extensions length) = 348
+    pub const SIZE: usize = 32
+        + EPHEMERAL_PUBKEY_SIZE
+        + 8
+        + 1
+        + 8
+        + ENCRYPTED_RETURN_ADDRESS_SIZE
+        + AEAD_TAG_SIZE
+        + 4
+        + 32
+        + 2
+        + 1
+        + 32
+        + 1
+        + 1
+        + 1
+        + MAX_MEMO_SIZE
+        + 1
+        + 8
+        + 4
+        + 4;
+
+    /// Account space (with discriminator) for a ciphertext of `ciphertext_len` bytes
+    /// and an extension area of `extensions_len` bytes
+    pub fn space(ciphertext_len: usize, extensions_len: usize) -> usize {
+        8 + Self::SIZE + ciphertext_len + extensions_len
+    }
+
+    /// Account space (with discriminator) at init for a `kem_variant`.
+    ///
+    /// Unknown variants get the space of an empty ciphertext; `initialize` rejects them.
+    pub fn init_space(kem_variant: u8) -> usize {
+        Self::space(mlkem_ciphertext_size(kem_variant).unwrap_or(0), 0)
+    }
+
+    /// Lamports out of `lamports` owed to the rent payer on close.
+    pub fn rent_payer_share(&self, lamports: u64) -> u64 {
+        (lamports as u128 * self.rent_payer_share_bps as u128 / BPS_DENOMINATOR as u128) as u64
+    }
+
+    /// Iterate over the entries of the TLV extension area.
+    pub fn extensions(&self) -> ExtensionIter<'_> {
+        ExtensionIter::new(&self.extensions)
+    }
+
+    /// Registry epoch recorded by the sender, if any.
+    pub fn registry_epoch(&self) -> Result<Option<u32>> {
+        for extension in self.extensions() {
+            let extension = extension?;
+            if extension.ext_type == EXT_TYPE_REGISTRY_EPOCH {
+                let epoch: [u8; 4] = extension
+                    .value
+                    .try_into()
+                    .map_err(|_| error!(StealthError::MalformedExtension))?;
+                return Ok(Some(u32::from_le_bytes(epoch)));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Token account recorded by the sender for an SPL payment, if any.
+    pub fn token_account(&self) -> Result<Option<Pubkey>> {
+        for extension in self.extensions() {
+            let extension = extension?;
+            if extension.ext_type == EXT_TYPE_TOKEN_ACCOUNT {
+                let key: [u8; 32] = extension
+                    .value
+                    .try_into()
+                    .map_err(|_| error!(StealthError::MalformedExtension))?;
+                return Ok(Some(Pubkey::new_from_array(key)));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Byte offset of `stealth_pubkey` in the account data (after the discriminator)
+    pub const STEALTH_PUBKEY_OFFSET: usize = 8;
+
+    /// Byte offset of `bump` in the account data
+    pub const BUMP_OFFSET: usize = Self::STEALTH_PUBKEY_OFFSET + 32 + EPHEMERAL_PUBKEY_SIZE + 8;
+
+    /// Byte offset of `app_id` in the account data
+    pub const APP_ID_OFFSET: usize =
+        Self::BUMP_OFFSET + 1 + 8 + ENCRYPTED_RETURN_ADDRESS_SIZE + AEAD_TAG_SIZE;
+
+    /// Byte offset of `view_tag` in the account data
+    pub const VIEW_TAG_OFFSET: usize = Self::APP_ID_OFFSET + 4 + 32 + 2;
+
+    /// Byte offset of `sender` in the account data
+    pub const SENDER_OFFSET: usize = Self::VIEW_TAG_OFFSET + 1;
+
+    /// Byte offset of `finalized` in the account data
+    pub const FINALIZED_OFFSET: usize = Self::SENDER_OFFSET + 32;
+
+    /// Byte offset of `kem_variant` in the account data
+    pub const KEM_VARIANT_OFFSET: usize = Self::FINALIZED_OFFSET + 1;
+
+    /// Byte offset of `memo_len` in the account data
+    pub const MEMO_LEN_OFFSET: usize = Self::KEM_VARIANT_OFFSET + 1;
+
+    /// Byte offset of `encrypted_memo` in the account data
+    pub const ENCRYPTED_MEMO_OFFSET: usize = Self::MEMO_LEN_OFFSET + 1;
+
+    /// Byte offset of `logged` in the account data
+    pub const LOGGED_OFFSET: usize = Self::ENCRYPTED_MEMO_OFFSET + MAX_MEMO_SIZE;
+
+    /// Byte offset of `announcement_index` in the account data
+    pub const ANNOUNCEMENT_INDEX_OFFSET: usize = Self::LOGGED_OFFSET + 1;
+
+    /// Byte offset of the `mlkem_ciphertext` length prefix (u32) in the account data
+    pub const MLKEM_CIPHERTEXT_LEN_OFFSET: usize = Self::ANNOUNCEMENT_INDEX_OFFSET + 8;
+
+    /// Byte offset of the `mlkem_ciphertext` bytes in the account data
+    pub const MLKEM_CIPHERTEXT_OFFSET: usize = Self::MLKEM_CIPHERTEXT_LEN_OFFSET + 4;
+
+    /// The encrypted memo, or an empty slice if none was set
+    pub fn memo(&self) -> &[u8] {
+        &self.encrypted_memo[..(self.memo_len as usize).min(MAX_MEMO_SIZE)]
+    }
+
+    /// PDA seed component for an app namespace, given `app_id.to_le_bytes()`.
+    ///
+    /// Empty for `DEFAULT_APP_ID`, so default-namespace addresses are the same
+    /// as before namespacing was introduced.
+    pub fn app_id_seed(app_id: &[u8; 4]) -> &[u8] {
+        if u32::from_le_bytes(*app_id) == DEFAULT_APP_ID {
+            &[]
+        } else {
+            app_id
+        }
+    }
+
+    /// Copy a chunk into the MLKEM ciphertext at `offset`, within the variant's size.
+    fn write_ciphertext(&mut self, offset: u16, chunk: &[u8]) -> Result<()> {
+        let start = offset as usize;
+        let end = start + chunk.len();
+        require!(
+            end <= self.mlkem_ciphertext.len(),
+            StealthError::InvalidCiphertextLength
+        );
+        self.mlkem_ciphertext[start..end].copy_from_slice(chunk);
+        Ok(())
+    }
+
+    /// Set the announcement metadata on a freshly created account.
+    #[allow(clippy::too_many_arguments)]
+    fn initialize(
+        &mut self,
+        stealth_pubkey: Pubkey,
+        sender: Pubkey,
+        ephemeral_pubkey: [u8; EPHEMERAL_PUBKEY_SIZE],
+        expires_at: Option<i64>,
+        app_id: u32,
+        rent_payer: Pubkey,
+        rent_payer_share_bps: u16,
+        view_tag: u8,
+        kem_variant: u8,
+        bump: u8,
+    ) -> Result<()> {
+        let ciphertext_size =
+            mlkem_ciphertext_size(kem_variant).ok_or(StealthError::InvalidKemVariant)?;
+        let now = Clock::get()?.unix_timestamp;
+        if let Some(expires_at) = expires_at {
+            require!(expires_at > now, StealthError::InvalidExpiry);
+        }
+        require!(
+            rent_payer_share_bps <= BPS_DENOMINATOR,
+            StealthError::InvalidRentShare
+        );
+
+        self.stealth_pubkey = stealth_pubkey;
+        self.ephemeral_pubkey = ephemeral_pubkey;
+        self.created_at = now;
+        self.bump = bump;
+        self.expires_at = expires_at.unwrap_or(0);
+        self.app_id = app_id;
+        self.rent_payer = rent_payer;
+        self.rent_payer_share_bps = rent_payer_share_bps;
+        self.view_tag = view_tag;
+        self.sender = sender;
+        self.finalized = false;
+        self.kem_variant = kem_variant;
+        self.mlkem_ciphertext = vec![0u8; ciphertext_size];
+        Ok(())
+    }
+}
+
+/// CiphertextAccount layout before namespaces, ML-KEM variants and announcement
+/// metadata were added. Only read by `migrate_ciphertext`.
+///
+/// Seeds: ["ciphertext", stealth_pubkey], the same as the default namespace today.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct LegacyCiphertextAccount {
+    /// The stealth address this ciphertext is for (32 bytes)
+    pub stealth_pubkey: Pubkey,
+
+    /// Ephemeral X25519 public key (R) (32 bytes)
+    pub ephemeral_pubkey: [u8; EPHEMERAL_PUBKEY_SIZE],
+
+    /// MLKEM768 ciphertext (1088 bytes)
+    pub mlkem_ciphertext: [u8; MLKEM_CIPHERTEXT_SIZE],
+
+    /// Unix timestamp when the transfer was created (8 bytes)
+    pub created_at: i64,
+
+    /// Bump seed for PDA derivation (1 byte)
+    pub bump: u8,
+}
+
+impl LegacyCiphertextAccount {
+    /// Size of the legacy layout in bytes (without Anchor discriminator)
+    /// 32 (pubkey) + 32 (ephemeral) + 1088 (ciphertext) + 8 (timestamp) + 1 (bump) = 1161
+    pub const SIZE: usize = 32 + EPHEMERAL_PUBKEY_SIZE + MLKEM_CIPHERTEXT_SIZE + 8 + 1;
+
+    /// Decode account data (with discriminator) in the legacy layout.
+    ///
+    /// A current-layout account can have the same size, so data that decodes as a
+    /// well-formed `CiphertextAccount` is rejected.
+    pub fn decode(data: &[u8]) -> Result<Self> {
+        require!(
+            data.len() == 8 + Self::SIZE && data[..8] == *CiphertextAccount::DISCRIMINATOR,
+            StealthError::NotLegacyCiphertext
+        );
+        let is_current = CiphertextAccount::try_deserialize(&mut &data[..]).is_ok_and(|account| {
+            mlkem_ciphertext_size(account.kem_variant) == Some(account.mlkem_ciphertext.len())
+        });
+        require!(!is_current, StealthError::NotLegacyCiphertext);
+
+        Self::try_from_slice(&data[8..]).map_err(|_| error!(StealthError::NotLegacyCiphertext))
+    }
+
+    /// The account in the current layout, recording `rent_payer` as the rent payer
+    pub fn migrate(self, rent_payer: Pubkey) -> CiphertextAccount {
+        CiphertextAccount {
+            stealth_pubkey: self.stealth_pubkey,
+            ephemeral_pubkey: self.ephemeral_pubkey,
+            created_at: self.created_at,
+            bump: self.bump,
+            rent_payer,
+            finalized: true,
+            kem_variant: KEM_VARIANT_ML_KEM_768,
+            mlkem_ciphertext: self.mlkem_ciphertext.to_vec(),
+            ..Default::default()
+        }
+    }
+}
+
+/// TLV extension type marking the end of the extension area (remaining bytes are padding)
+pub const EXT_TYPE_END: u8 = 0x00;
+
+/// TLV extension type for an amount encrypted to the hybrid shared secret
+pub const EXT_TYPE_ENCRYPTED_AMOUNT: u8 = 0x01;
+
+/// TLV extension type for a fuzzy message detection clue
+pub const EXT_TYPE_FMD_CLUE: u8 = 0x02;
+
+/// TLV extension type for the registry epoch (u32 LE) of the meta-address the
+/// sender used. Opaque to the program; lets recipients who rotated keys pick the
+/// right key material first.
+pub const EXT_TYPE_REGISTRY_EPOCH: u8 = 0x03;
+
+/// TLV extension type for the associated token account (32 bytes) an SPL payment
+/// is sent to. `transfer_spl_to_stealth` only pays into the recorded account, so
+/// recipients know which token account to check without trying every mint.
+pub const EXT_TYPE_TOKEN_ACCOUNT: u8 = 0x04;
+
+/// First TLV extension type available to applications (0x80..=0xEF)
+pub const EXT_TYPE_APP_DATA_START: u8 = 0x80;
+
+/// First reserved TLV extension type (0xF0..=0xFF)
+pub const EXT_TYPE_RESERVED_START: u8 = 0xF0;
+
+/// A single entry of the TLV extension area.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Extension<'a> {
+    /// Entry type
+    pub ext_type: u8,
+
+    /// Entry value
+    pub value: &'a [u8],
+}
+
+/// Iterator over the TLV extension area of a CiphertextAccount.
+///
+/// Each entry is `type (u8) || length (u16, little-endian) || value`.
+/// Parsing rules for clients:
+/// - Type 0x00 ends the area; any bytes after it are padding.
+/// - 0x01..=0x7F are protocol-defined types, 0x80..=0xEF are application-defined,
+///   0xF0..=0xFF are reserved. Unknown types are skipped, not rejected.
+/// - An entry whose length runs past the end of the area makes the whole area
+///   malformed; the iterator yields `MalformedExtension` and stops.
+pub struct ExtensionIter<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> ExtensionIter<'a> {
+    /// Parse entries from raw extension area bytes.
+    pub fn new(data: &'a [u8]) -> Self {
+        Self { data }
+    }
+}
+
+impl<'a> Iterator for ExtensionIter<'a> {
+    type Item = Result<Extension<'a>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (&ext_type, rest) = self.data.split_first()?;
+        if ext_type == EXT_TYPE_END {
+            self.data = &[];
+            return None;
+        }
+
+        let entry = rest.get(..2).and_then(|len| {
+            let len = u16::from_le_bytes([len[0], len[1]]) as usize;
+            rest[2..].get(..len).map(|value| (value, &rest[2 + len..]))
+        });
+
+        match entry {
+            Some((value, rest)) => {
+                self.data = rest;
+                Some(Ok(Extension { ext_type, value }))
+            }
+            None => {
+                self.data = &[];
+                Some(Err(error!(StealthError::MalformedExtension)))
+            }
+        }
+    }
+}
+
+/// Sender-owned staging buffer for uploading ciphertext across transactions.
+///
+/// Seeds: ["buffer", authority, buffer_id]
+///
+/// Written with `write_buffer` and moved into a CiphertextAccount with
+/// `commit_buffer`. The buffer survives the commit and can be reused for
+/// further payments until the authority closes it.
+#[account]
+pub struct StagingBuffer {
+    /// The sender that owns and writes this buffer (32 bytes)
+    pub authority: Pubkey,
+
+    /// Caller-chosen buffer ID, part of the PDA seeds (8 bytes)
+    pub buffer_id: u64,
+
+    /// Buffered MLKEM768 ciphertext (1088 bytes)
+    pub mlkem_ciphertext: [u8; MLKEM_CIPHERTEXT_SIZE],
+
+    /// Bump seed for PDA derivation (1 byte)
+    pub bump: u8,
+}
+
+impl StagingBuffer {
+    /// Size of StagingBuffer in bytes (without Anchor discriminator)
+    /// 32 (authority) + 8 (buffer_id) + 1088 (ciphertext) + 1 (bump) = 1129
+    pub const SIZE: usize = 32 + 8 + MLKEM_CIPHERTEXT_SIZE + 1;
+}
+
+/// On-chain stealth meta-address of a wallet.
+///
+/// Seeds: ["meta", owner]
+///
+/// The spending, viewing and encapsulation keys are stored back to back, so
+/// account data `[KEYS_OFFSET..KEYS_OFFSET + 1248]` is the hybrid meta-address
+/// (M || V || K_pub) wallets already exchange out of band. An all-zero
+/// encapsulation key means the meta-address is classical only.
+#[account]
+pub struct MetaAddressRegistry {
+    /// The wallet this meta-address belongs to (32 bytes)
+    pub owner: Pubkey,
+
+    /// Spending public key M (32 bytes)
+    pub spending_pubkey: [u8; 32],
+
+    /// X25519 viewing public key V (32 bytes)
+    pub viewing_pubkey: [u8; 32],
+
+    /// MLKEM768 encapsulation key K_pub, or all zeros if not uploaded (1184 bytes)
+    pub mlkem_encapsulation_key: [u8; MLKEM_ENCAPSULATION_KEY_SIZE],
+
+    /// Key set version, starting at 1 and increased by every update (4 bytes).
+    /// Senders record it in the `EXT_TYPE_REGISTRY_EPOCH` extension.
+    pub epoch: u32,
+
+    /// Unix timestamp of the last change (8 bytes)
+    pub updated_at: i64,
+
+    /// Bump seed for PDA derivation (1 byte)
+    pub bump: u8,
+}
+
+impl Default for MetaAddressRegistry {
+    fn default() -> Self {
+        Self {
+            owner: Pubkey::default(),
+            spending_pubkey: [0u8; 32],
+            viewing_pubkey: [0u8; 32],
+            mlkem_encapsulation_key: [0u8; MLKEM_ENCAPSULATION_KEY_SIZE],
+            epoch: 0,
+            updated_at: 0,
+            bump: 0,
+        }
+    }
+}
+
+impl MetaAddressRegistry {
+    /// Size of MetaAddressRegistry in bytes (without Anchor discriminator)
+    /// 32 (owner) + 32 (spending) + 32 (viewing) + 1184 (encapsulation key)
+    /// + 4 (epoch) + 8 (updated_at) + 1 (bump) = 1293
+    pub const SIZE: usize = 32 + 32 + 32 + MLKEM_ENCAPSULATION_KEY_SIZE + 4 + 8 + 1;
+
+    /// Byte offset of the meta-address keys (M || V || K_pub) in the account data
+    pub const KEYS_OFFSET: usize = 8 + 32;
+
+    /// Install a new key set: bumps the epoch and clears the encapsulation key.
+    fn set_keys(&mut self, spending_pubkey: [u8; 32], viewing_pubkey: [u8; 32]) -> Result<()> {
+        self.spending_pubkey = spending_pubkey;
+        self.viewing_pubkey = viewing_pubkey;
+        self.mlkem_encapsulation_key = [0u8; MLKEM_ENCAPSULATION_KEY_SIZE];
+        self.epoch += 1;
+        self.updated_at = Clock::get()?.unix_timestamp;
+        Ok(())
+    }
+}
+
+/// Link between a registered meta-address and an EVM stealth meta-address.
+///
+/// Seeds: ["evm", owner]
+///
+/// Created by `attest_evm_meta_address` once both the owner and the EVM account
+/// have signed, and closed by the owner with `revoke_evm_attestation`.
+#[account]
+pub struct EvmAttestation {
+    /// The Solana wallet whose registered meta-address is linked (32 bytes)
+    pub owner: Pubkey,
+
+    /// The EVM account that signed the link (20 bytes)
+    pub evm_address: [u8; 20],
+
+    /// ERC-5564 stealth meta-address on the EVM side (66 bytes)
+    pub evm_meta_address: [u8; EVM_META_ADDRESS_SIZE],
+
+    /// Unix timestamp of the attestation (8 bytes)
+    pub attested_at: i64,
+
+    /// Bump seed for PDA derivation (1 byte)
+    pub bump: u8,
+}
+
+impl Default for EvmAttestation {
+    fn default() -> Self {
+        Self {
+            owner: Pubkey::default(),
+            evm_address: [0u8; 20],
+            evm_meta_address: [0u8; EVM_META_ADDRESS_SIZE],
+            attested_at: 0,
+            bump: 0,
+        }
+    }
+}
+
+impl EvmAttestation {
+    /// Size of EvmAttestation in bytes (without Anchor discriminator)
+    /// 32 (owner) + 20 (evm address) + 66 (evm meta-address) + 8 (attested_at)
+    /// + 1 (bump) = 127
+    pub const SIZE: usize = 32 + 20 + EVM_META_ADDRESS_SIZE + 8 + 1;
+}
+
+/// Emitted when an EVM attestation is created or revoked.
+#[event]
+pub struct EvmAttestationEvent {
+    /// The Solana wallet of the attestation
+    pub owner: Pubkey,
+
+    /// The EVM account of the attestation
+    pub evm_address: [u8; 20],
+
+    /// Whether the attestation was revoked rather than created
+    pub revoked: bool,
+}
+
+/// Emitted when a meta-address is registered or its keys are replaced.
+#[event]
+pub struct MetaAddressEvent {
+    /// The wallet the meta-address belongs to
+    pub owner: Pubkey,
+
+    /// Epoch of the new key set
+    pub epoch: u32,
+}
+
+/// Per-namespace announcement counter.
+///
+/// Seeds: ["namespace", app_id (u32 LE)]
+///
+/// Created on the first announcement in a namespace. Every announcement takes
+/// the next sequence number, so consumers of `AnnouncementEvent` can detect
+/// missed events by looking for gaps.
+#[account]
+#[derive(Default)]
+pub struct NamespaceCounter {
+    /// The app namespace this counter belongs to (4 bytes)
+    pub app_id: u32,
+
+    /// Sequence number of the latest announcement, 0 if none (8 bytes)
+    pub sequence: u64,
+
+    /// Bump seed for PDA derivation (1 byte)
+    pub bump: u8,
+}
+
+impl NamespaceCounter {
+    /// Size of NamespaceCounter in bytes (without Anchor discriminator)
+    /// 4 (app_id) + 8 (sequence) + 1 (bump) = 13
+    pub const SIZE: usize = 4 + 8 + 1;
+
+    /// Take the next sequence number for an announcement in this namespace.
+    ///
+    /// The first announcement gets sequence 1. `app_id` and `bump` are
+    /// (re)written each time so a freshly created counter is initialized.
+    pub fn next_sequence(&mut self, app_id: u32, bump: u8) -> u64 {
+        self.app_id = app_id;
+        self.bump = bump;
+        self.sequence += 1;
+        self.sequence
+    }
+}
+
+/// Emitted for every new announcement (init_ciphertext and commit_buffer).
+#[event]
+pub struct AnnouncementEvent {
+    /// App namespace of the announcement
+    pub app_id: u32,
+
+    /// Per-namespace sequence number, increasing by one per announcement
+    pub sequence: u64,
+
+    /// The stealth address the announcement is for
+    pub stealth_pubkey: Pubkey,
+
+    /// The CiphertextAccount PDA holding the announcement
+    pub ciphertext_account: Pubkey,
+}
+
+/// Emitted for every `transfer_spl_to_stealth`.
+#[event]
+pub struct SplTransferEvent {
+    /// The stealth address receiving the tokens
+    pub stealth_pubkey: Pubkey,
+
+    /// The token mint
+    pub mint: Pubkey,
+
+    /// The stealth address's associated token account
+    pub token_account: Pubkey,
+
+    /// Amount the sender transferred
+    pub amount: u64,
+
+    /// Amount credited to the stealth address (less than `amount` for mints
+    /// with a transfer fee)
+    pub received: u64,
+}
+
+/// Global counter of the announcement log.
+///
+/// Seeds: ["announcement_log"]
+#[account]
+#[derive(Default)]
+pub struct AnnouncementLog {
+    /// Number of announcements logged, and the index of the next one (8 bytes)
+    pub count: u64,
+
+    /// Bump seed for PDA derivation (1 byte)
+    pub bump: u8,
+}
+
+impl AnnouncementLog {
+    /// Size of AnnouncementLog in bytes (without Anchor discriminator)
+    /// 8 (count) + 1 (bump) = 9
+    pub const SIZE: usize = 8 + 1;
+}
+
+/// Entry of the announcement log.
+///
+/// Seeds: ["announcement", index (u64 LE)]
+///
+/// Holds what a recipient needs to decide whether to fetch the full
+/// CiphertextAccount. Closed along with the ciphertext account on reclaim,
+/// so claimed payments leave gaps in the log.
+#[account]
+#[derive(Default)]
+pub struct Announcement {
+    /// Position in the log, part of the PDA seeds (8 bytes)
+    pub index: u64,
+
+    /// App namespace of the announcement (4 bytes)
+    pub app_id: u32,
+
+    /// The stealth address the announcement is for (32 bytes)
+    pub stealth_pubkey: Pubkey,
+
+    /// The CiphertextAccount PDA holding the full announcement (32 bytes)
+    pub ciphertext_account: Pubkey,
+
+    /// Ephemeral X25519 public key (R), copied from the ciphertext account (32 bytes)
+    pub ephemeral_pubkey: [u8; EPHEMERAL_PUBKEY_SIZE],
+
+    /// View tag, copied from the ciphertext account (1 byte)
+    pub view_tag: u8,
+
+    /// Bump seed for PDA derivation (1 byte)
+    pub bump: u8,
+
+    /// Account that paid the entry's rent, refunded by `refund_expired` (32 bytes)
+    pub payer: Pubkey,
+}
+
+impl Announcement {
+    /// Size of Announcement in bytes (without Anchor discriminator)
+    /// 8 (index) + 4 (app_id) + 32 (stealth_pubkey) + 32 (ciphertext_account)
+    /// + 32 (ephemeral) + 1 (view_tag) + 1 (bump) + 32 (payer) = 142
+    pub const SIZE: usize = 8 + 4 + 32 + 32 + EPHEMERAL_PUBKEY_SIZE + 1 + 1 + 32;
+}
+
+/// Protocol sponsorship pool paying fees for sponsored claims.
+///
+/// Seeds: ["sponsor_pool"]
+///
+/// Funded by anyone via `fund_sponsor_pool`; the lamports above rent exemption
+/// are available to reimburse fee payers in `sponsored_reclaim_rent`.
+#[account]
+#[derive(Default)]
+pub struct SponsorPool {
+    /// Number of sponsored claims paid out (8 bytes)
+    pub total_claims: u64,
+
+    /// Bump seed for PDA derivation (1 byte)
+    pub bump: u8,
+
+    /// Epoch that `epoch_claims` counts claims for (8 bytes)
+    pub epoch: u64,
+
+    /// Sponsored claims paid out in `epoch` (4 bytes)
+    pub epoch_claims: u32,
+}
+
+impl SponsorPool {
+    /// Size of SponsorPool in bytes (without Anchor discriminator)
+    /// 8 (total_claims) + 1 (bump) + 8 (epoch) + 4 (epoch_claims) = 21
+    pub const SIZE: usize = 8 + 1 + 8 + 4;
+}
+
+/// Rate-limit record for sponsored claims reimbursed to one claimant.
+///
+/// Seeds: ["claim", claimant]
+#[account]
+#[derive(Default)]
+pub struct ClaimRecord {
+    /// The fee payer this record limits (32 bytes)
+    pub claimant: Pubkey,
+
+    /// Unix timestamp at which the current window started (8 bytes)
+    pub window_start: i64,
+
+    /// Sponsored claims in the current window (4 bytes)
+    pub window_claims: u32,
+
+    /// Bump seed for PDA derivation (1 byte)
+    pub bump: u8,
+}
+
+impl ClaimRecord {
+    /// Size of ClaimRecord in bytes (without Anchor discriminator)
+    /// 32 (claimant) + 8 (window_start) + 4 (window_claims) + 1 (bump) = 45
+    pub const SIZE: usize = 32 + 8 + 4 + 1;
+
+    /// Count a claim at `now`, starting a new window once the current one has passed
+    pub fn record_claim(&mut self, now: i64) -> Result<()> {
+        if now >= self.window_start.saturating_add(SPONSORED_CLAIM_INTERVAL) {
+            self.window_start = now;
+            self.window_claims = 0;
+        }
+        require!(
+            self.window_claims < MAX_SPONSORED_CLAIMS_PER_INTERVAL,
+            StealthError::ClaimRateLimited
+        );
+        self.window_claims += 1;
+        Ok(())
+    }
+}
+
+/// Global opt-in transfer statistics.
+///
+/// Seeds: ["stats"]
+///
+/// Only transfers whose sender passes this account to `transfer_to_stealth`
+/// are counted, so the totals are a lower bound on protocol usage.
+#[account]
+#[derive(Default)]
+pub struct StatsAccount {
+    /// Number of opted-in transfers (8 bytes)
+    pub transfer_count: u64,
+
+    /// Total lamports moved by opted-in transfers (8 bytes)
+    pub total_lamports: u64,
+
+    /// Bump seed for PDA derivation (1 byte)
+    pub bump: u8,
+}
+
+impl StatsAccount {
+    /// Size of StatsAccount in bytes (without Anchor discriminator)
+    /// 8 (transfer_count) + 8 (total_lamports) + 1 (bump) = 17
+    pub const SIZE: usize = 8 + 8 + 1;
+
+    /// Byte offset of `transfer_count` in the account data (after the discriminator)
+    pub const TRANSFER_COUNT_OFFSET: usize = 8;
+
+    /// Byte offset of `total_lamports` in the account data
+    pub const TOTAL_LAMPORTS_OFFSET: usize = Self::TRANSFER_COUNT_OFFSET + 8;
+
+    /// Byte offset of `bump` in the account data
+    pub const BUMP_OFFSET: usize = Self::TOTAL_LAMPORTS_OFFSET + 8;
+
+    /// Count one transfer of `lamports`. Saturates rather than failing the transfer.
+    pub fn record_transfer(&mut self, lamports: u64) {
+        self.transfer_count = self.transfer_count.saturating_add(1);
+        self.total_lamports = self.total_lamports.saturating_add(lamports);
+    }
+}
+
+/// Stats returned by `snapshot_stats`.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct StatsSnapshot {
+    /// Number of opted-in transfers
+    pub transfer_count: u64,
+
+    /// Total lamports moved by opted-in transfers
+    pub total_lamports: u64,
+
+    /// Slot at which the snapshot was taken
+    pub slot: u64,
+}
+
+/// Accounts for the stealth_transfer instruction.
+///
+/// Creates a CiphertextAccount PDA and optionally transfers SOL.
+#[derive(Accounts)]
+#[instruction(
+    ephemeral_pubkey: [u8; EPHEMERAL_PUBKEY_SIZE],
+    ciphertext_part1: DataChunk,
+    expires_at: Option<i64>,
+    app_id: u32,
+    rent_payer_share_bps: u16,
+    view_tag: u8,
+    kem_variant: u8,
+)]
+pub struct StealthTransfer<'info> {
+    /// The sender making the payment, and paying rent if there's no rent payer
+    #[account(mut)]
+    pub sender: Signer<'info>,
+
+    /// Pays rent for the new accounts instead of the sender, e.g. an
+    /// application/employer account sponsoring the announcement.
+    #[account(mut)]
+    pub rent_payer: Option<Signer<'info>>,
+
+    /// The one-time stealth address that will receive funds.
+    /// CHECK: This is a derived stealth address, not an existing account.
+    /// It's intentionally unchecked as it's a fresh address for this transfer.
+    #[account(mut)]
+    pub stealth_address: AccountInfo<'info>,
+
+    /// PDA storing the MLKEM ciphertext, derived from the stealth address.
+    /// The rent payer pays rent for this account.
+    #[account(
+        init,
+        payer = rent_payer.as_ref().unwrap_or(&sender),
+        space = CiphertextAccount::init_space(kem_variant),
+        seeds = [
+            b"ciphertext",
+            stealth_address.key().as_ref(),
+            CiphertextAccount::app_id_seed(&app_id.to_le_bytes()),
+        ],
+        bump
+    )]
+    pub ciphertext_account: Account<'info, CiphertextAccount>,
+
+    /// Announcement counter for the app namespace, created on first use
+    #[account(
+        init_if_needed,
+        payer = rent_payer.as_ref().unwrap_or(&sender),
+        space = 8 + NamespaceCounter::SIZE,
+        seeds = [b"namespace", app_id.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub namespace_counter: Box<Account<'info, NamespaceCounter>>,
+
+    /// System program for account creation and SOL transfers
+    pub system_program: Program<'info, System>,
+
+    /// Instructions sysvar; pass it to require a funding transfer in the same transaction.
+    /// CHECK: Address is checked against the instructions sysvar ID.
+    #[account(address = instructions_sysvar::ID)]
+    pub instructions_sysvar: Option<AccountInfo<'info>>,
+}
+
+/// Accounts for the init_ciphertext_and_fund instruction.
+///
+/// Same as `StealthTransfer`, without the instructions sysvar: the transfer is part of the instruction.
+#[derive(Accounts)]
+#[instruction(
+    ephemeral_pubkey: [u8; EPHEMERAL_PUBKEY_SIZE],
+    ciphertext_part1: DataChunk,
+    expires_at: Option<i64>,
+    app_id: u32,
+    rent_payer_share_bps: u16,
+    view_tag: u8,
+    lamports: u64,
+    kem_variant: u8,
+)]
+pub struct InitCiphertextAndFund<'info> {
+    /// The sender making the payment, and paying rent if there's no rent payer
+    #[account(mut)]
+    pub sender: Signer<'info>,
+
+    /// Pays rent for the new accounts instead of the sender, e.g. an
+    /// application/employer account sponsoring the announcement.
+    #[account(mut)]
+    pub rent_payer: Option<Signer<'info>>,
+
+    /// The one-time stealth address that will receive funds.
+    /// CHECK: This is a derived stealth address, not an existing account.
+    /// It's intentionally unchecked as it's a fresh address for this transfer.
+    #[account(mut)]
+    pub stealth_address: AccountInfo<'info>,
+
+    /// PDA storing the MLKEM ciphertext, derived from the stealth address.
+    #[account(
+        init,
+        payer = rent_payer.as_ref().unwrap_or(&sender),
+        space = CiphertextAccount::init_space(kem_variant),
+        seeds = [
+            b"ciphertext",
+            stealth_address.key().as_ref(),
+            CiphertextAccount::app_id_seed(&app_id.to_le_bytes()),
+        ],
+        bump
+    )]
+    pub ciphertext_account: Account<'info, CiphertextAccount>,
+
+    /// Announcement counter for the app namespace, created on first use
+    #[account(
+        init_if_needed,
+        payer = rent_payer.as_ref().unwrap_or(&sender),
+        space = 8 + NamespaceCounter::SIZE,
+        seeds = [b"namespace", app_id.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub namespace_counter: Box<Account<'info, NamespaceCounter>>,
+
+    /// System program for account creation and SOL transfers
+    pub system_program: Program<'info, System>,
+}
+
+/// Accounts for writing to an existing CiphertextAccount (complete_ciphertext,
+/// complete_and_finalize, set_return_address, set_payload_tag, finalize_ciphertext).
+#[derive(Accounts)]
+pub struct CompleteCiphertext<'info> {
+    /// The sender who initiated the transfer
+    #[account(mut)]
+    pub sender: Signer<'info>,
+
+    /// The existing CiphertextAccount PDA, still being written by the sender
+    #[account(
+        mut,
+        has_one = sender,
+        constraint = !ciphertext_account.finalized @ StealthError::CiphertextFinalized,
+        seeds = [
+            b"ciphertext",
+            ciphertext_account.stealth_pubkey.as_ref(),
+            CiphertextAccount::app_id_seed(&ciphertext_account.app_id.to_le_bytes()),
+        ],
+        bump = ciphertext_account.bump,
+    )]
+    pub ciphertext_account: Account<'info, CiphertextAccount>,
+}
+
+/// Accounts for writing the TLV extension area of a CiphertextAccount.
+#[derive(Accounts)]
+#[instruction(chunk: DataChunk, offset: u16)]
+pub struct WriteExtensions<'info> {
+    /// The sender who initiated the transfer and pays for the extra space
+    #[account(mut)]
+    pub sender: Signer<'info>,
+
+    /// The existing CiphertextAccount PDA, grown to fit the written range
+    #[account(
+        mut,
+        has_one = sender,
+        constraint = !ciphertext_account.finalized @ StealthError::CiphertextFinalized,
+        seeds = [
+            b"ciphertext",
+            ciphertext_account.stealth_pubkey.as_ref(),
+            CiphertextAccount::app_id_seed(&ciphertext_account.app_id.to_le_bytes()),
+        ],
+        bump = ciphertext_account.bump,
+        realloc = CiphertextAccount::space(
+            ciphertext_account.mlkem_ciphertext.len(),
+            ciphertext_account
+                .extensions
+                .len()
+                .max(offset as usize + chunk.len as usize)
+        ),
+        realloc::payer = sender,
+        realloc::zero = false,
+    )]
+    pub ciphertext_account: Account<'info, CiphertextAccount>,
+
+    /// System program for the rent top-up
+    pub system_program: Program<'info, System>,
+}
+
+/// Accounts for transferring SOL to a stealth address.
+#[derive(Accounts)]
+pub struct TransferToStealth<'info> {
+    /// The sender who pays for the transfer
+    #[account(mut)]
+    pub sender: Signer<'info>,
+
+    /// The stealth address receiving the SOL.
+    /// CHECK: Unchecked as it's a derived stealth address.
+    #[account(mut)]
+    pub stealth_address: AccountInfo<'info>,
+
+    /// Verify a finalized ciphertext account exists for this stealth address
+    #[account(
+        constraint = ciphertext_account.finalized @ StealthError::CiphertextNotFinalized,
+        seeds = [
+            b"ciphertext",
+            stealth_address.key().as_ref(),
+            CiphertextAccount::app_id_seed(&ciphertext_account.app_id.to_le_bytes()),
+        ],
+        bump = ciphertext_account.bump,
+    )]
+    pub ciphertext_account: Account<'info, CiphertextAccount>,
+
+    /// System program for SOL transfer
+    pub system_program: Program<'info, System>,
+
+    /// Global stats account; pass it to opt in to the aggregate statistics
+    #[account(
+        mut,
+        seeds = [b"stats"],
+        bump = stats.bump,
+    )]
+    pub stats: Option<Account<'info, StatsAccount>>,
+}
+
+/// Accounts for a batch of SOL transfers to stealth addresses.
+///
+/// The `(stealth_address, ciphertext_account)` pairs are passed as remaining
+/// accounts, with each stealth address writable.
+#[derive(Accounts)]
+pub struct BatchTransferToStealth<'info> {
+    /// The sender who pays for every transfer in the batch
+    #[account(mut)]
+    pub sender: Signer<'info>,
+
+    /// System program for SOL transfers
+    pub system_program: Program<'info, System>,
+
+    /// Global stats account; pass it to count each transfer in the batch
+    #[account(
+        mut,
+        seeds = [b"stats"],
+        bump = stats.bump,
+    )]
+    pub stats: Option<Account<'info, StatsAccount>>,
+}
+
+/// Accounts for transferring SPL tokens to a stealth address.
+#[derive(Accounts)]
+pub struct TransferSplToStealth<'info> {
+    /// The sender who owns the source tokens and pays for the token account
+    #[account(mut)]
+    pub sender: Signer<'info>,
+
+    /// The stealth address receiving the tokens.
+    /// CHECK: Unchecked as it's a derived stealth address.
+    pub stealth_address: AccountInfo<'info>,
+
+    /// Verify a finalized ciphertext account exists for this stealth address
+    #[account(
+        constraint = ciphertext_account.finalized @ StealthError::CiphertextNotFinalized,
+        seeds = [
+            b"ciphertext",
+            stealth_address.key().as_ref(),
+            CiphertextAccount::app_id_seed(&ciphertext_account.app_id.to_le_bytes()),
+        ],
+        bump = ciphertext_account.bump,
+    )]
+    pub ciphertext_account: Box<Account<'info, CiphertextAccount>>,
+
+    /// The token mint
+    #[account(mint::token_program = token_program)]
+    pub mint: Box<InterfaceAccount<'info, Mint>>,
+
+    /// The sender's token account for the mint
+    #[account(
+        mut,
+        token::mint = mint,
+        token::authority = sender,
+        token::token_program = token_program,
+    )]
+    pub sender_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// The stealth address's associated token account, created on first use
+    #[account(
+        init_if_needed,
+        payer = sender,
+        associated_token::mint = mint,
+        associated_token::authority = stealth_address,
+        associated_token::token_program = token_program,
+    )]
+    pub stealth_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Token or Token-2022 program owning the mint
+    pub token_program: Interface<'info, TokenInterface>,
+
+    /// Associated token account program for creating the stealth token account
+    pub associated_token_program: Program<'info, AssociatedToken>,
+
+    /// System program for account creation
+    pub system_program: Program<'info, System>,
+}
+
+/// Accounts for creating the global announcement log.
+#[derive(Accounts)]
+pub struct InitAnnouncementLog<'info> {
+    /// Pays rent for the log account
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    /// The announcement log PDA to create
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + AnnouncementLog::SIZE,
+        seeds = [b"announcement_log"],
+        bump
+    )]
+    pub announcement_log: Account<'info, AnnouncementLog>,
+
+    /// System program for account creation
+    pub system_program: Program<'info, System>,
+}
+
+/// Accounts for appending an announcement to the log.
+#[derive(Accounts)]
+pub struct LogAnnouncement<'info> {
+    /// The sender of the announcement, pays rent for the log entry
+    #[account(mut)]
+    pub sender: Signer<'info>,
+
+    /// The finalized announcement to log, not yet in the log
+    #[account(
+        mut,
+        has_one = sender,
+        constraint = ciphertext_account.finalized @ StealthError::CiphertextNotFinalized,
+        constraint = !ciphertext_account.logged @ StealthError::AlreadyLogged,
+        seeds = [
+            b"ciphertext",
+            ciphertext_account.stealth_pubkey.as_ref(),
+            CiphertextAccount::app_id_seed(&ciphertext_account.app_id.to_le_bytes()),
+        ],
+        bump = ciphertext_account.bump,
+    )]
+    pub ciphertext_account: Box<Account<'info, CiphertextAccount>>,
+
+    /// The global announcement log
+    #[account(
+        mut,
+        seeds = [b"announcement_log"],
+        bump = announcement_log.bump,
+    )]
+    pub announcement_log: Account<'info, AnnouncementLog>,
+
+    /// The log entry PDA to create, at the next index
+    #[account(
+        init,
+        payer = sender,
+        space = 8 + Announcement::SIZE,
+        seeds = [b"announcement", announcement_log.count.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub announcement: Account<'info, Announcement>,
+
+    /// System program for account creation
+    pub system_program: Program<'info, System>,
+}
+
+/// Accounts for creating the global stats account.
+#[derive(Accounts)]
+pub struct InitStats<'info> {
+    /// Pays rent for the stats account
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    /// The stats PDA to create
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + StatsAccount::SIZE,
+        seeds = [b"stats"],
+        bump
+    )]
+    pub stats: Account<'info, StatsAccount>,
+
+    /// System program for account creation
+    pub system_program: Program<'info, System>,
+}
+
+/// Accounts for reading the global stats.
+#[derive(Accounts)]
+pub struct SnapshotStats<'info> {
+    /// The stats PDA
+    #[account(
+        seeds = [b"stats"],
+        bump = stats.bump,
+    )]
+    pub stats: Account<'info, StatsAccount>,
+}
+
+/// Accounts for the reclaim_rent instruction.
+///
+/// Closes the CiphertextAccount and returns rent to the stealth address.
+#[derive(Accounts)]
+pub struct ReclaimRent<'info> {
+    /// The stealth address owner (must be the signer).
+    /// This proves they have the derived spending key.
+    /// Rent is returned to this account.
+    #[account(mut)]
+    pub stealth_signer: Signer<'info>,
+
+    /// The CiphertextAccount to close.
+    /// Must belong to this stealth address (verified by seeds).
+    #[account(
+        mut,
+        close = stealth_signer,
+        seeds = [
+            b"ciphertext",
+            stealth_signer.key().as_ref(),
+            CiphertextAccount::app_id_seed(&ciphertext_account.app_id.to_le_bytes()),
+        ],
+        bump = ciphertext_account.bump,
+    )]
+    pub ciphertext_account: Account<'info, CiphertextAccount>,
+
+    /// Receives its share of the rent; required if a share was set at init.
+    /// CHECK: Address is checked against the rent payer recorded in the ciphertext account.
+    #[account(mut, address = ciphertext_account.rent_payer)]
+    pub rent_payer: Option<AccountInfo<'info>>,
+
+    /// Announcement log entry for this ciphertext account, closed along with it
+    #[account(
+        mut,
+        close = stealth_signer,
+        constraint = announcement.ciphertext_account == ciphertext_account.key(),
+    )]
+    pub announcement: Option<Account<'info, Announcement>>,
+}
+
+/// Accounts for the refund_expired instruction.
+#[derive(Accounts)]
+pub struct RefundExpired<'info> {
+    /// The sender who created the announcement
+    #[account(mut)]
+    pub sender: Signer<'info>,
+
+    /// The expired CiphertextAccount to close
+    #[account(
+        mut,
+        has_one = sender,
+        has_one = rent_payer,
+        close = rent_payer,
+        seeds = [
+            b"ciphertext",
+            ciphertext_account.stealth_pubkey.as_ref(),
+            CiphertextAccount::app_id_seed(&ciphertext_account.app_id.to_le_bytes()),
+        ],
+        bump = ciphertext_account.bump,
+    )]
+    pub ciphertext_account: Account<'info, CiphertextAccount>,
+
+    /// Receives the rent; pass the sender again if the sender paid it.
+    /// CHECK: Checked against the rent payer recorded in the ciphertext account.
+    #[account(mut)]
+    pub rent_payer: AccountInfo<'info>,
+
+    /// Announcement log entry for this ciphertext account, closed along with it.
+    /// Its rent goes back to the sender, who paid it.
+    #[account(
+        mut,
+        close = sender,
+        constraint = announcement.ciphertext_account == ciphertext_account.key(),
+        constraint = announcement.payer == sender.key(),
+    )]
+    pub announcement: Option<Account<'info, Announcement>>,
+}
+
+/// Accounts for the migrate_ciphertext instruction.
+#[derive(Accounts)]
+pub struct MigrateCiphertext<'info> {
+    /// Pays the rent for the larger layout
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    /// CiphertextAccount in the legacy layout
+    /// CHECK: Layout and seeds are checked in the handler; it can't be loaded as a `CiphertextAccount`.
+    #[account(mut, owner = crate::ID)]
+    pub ciphertext_account: UncheckedAccount<'info>,
+
+    /// System program for the rent transfer
+    pub system_program: Program<'info, System>,
+}
+
+/// Accounts for the sponsored_reclaim_rent instruction.
+#[derive(Accounts)]
+pub struct SponsoredReclaimRent<'info> {
+    /// Relayer paying the transaction fee, reimbursed from the pool
+    #[account(mut)]
+    pub fee_payer: Signer<'info>,
+
+    /// The stealth address owner; rent is returned to this account
+    #[account(mut)]
+    pub stealth_signer: Signer<'info>,
+
+    /// The CiphertextAccount to close; only finalized announcements qualify
+    #[account(
+        mut,
+        close = stealth_signer,
+        seeds = [
+            b"ciphertext",
+            stealth_signer.key().as_ref(),
+            CiphertextAccount::app_id_seed(&ciphertext_account.app_id.to_le_bytes()),
+        ],
+        bump = ciphertext_account.bump,
+        constraint = ciphertext_account.finalized @ StealthError::CiphertextNotFinalized,
+    )]
+    pub ciphertext_account: Account<'info, CiphertextAccount>,
+
+    /// The sponsorship pool
+    #[account(
+        mut,
+        seeds = [b"sponsor_pool"],
+        bump = sponsor_pool.bump,
+    )]
+    pub sponsor_pool: Account<'info, SponsorPool>,
+
+    /// Rate-limit record for the fee payer, created (at its expense) on its first claim
+    #[account(
+        init_if_needed,
+        payer = fee_payer,
+        space = 8 + ClaimRecord::SIZE,
+        seeds = [b"claim", fee_payer.key().as_ref()],
+        bump
+    )]
+    pub claim_record: Account<'info, ClaimRecord>,
+
+    /// System program for creating the claim record
+    pub system_program: Program<'info, System>,
+
+    /// Receives its share of the rent; required if a share was set at init.
+    /// CHECK: Address is checked against the rent payer recorded in the ciphertext account.
+    #[account(mut, address = ciphertext_account.rent_payer)]
+    pub rent_payer: Option<AccountInfo<'info>>,
+
+    /// Announcement log entry for this ciphertext account, closed along with it
+    #[account(
+        mut,
+        close = stealth_signer,
+        constraint = announcement.ciphertext_account == ciphertext_account.key(),
+    )]
+    pub announcement: Option<Account<'info, Announcement>>,
+}
+
+/// Accounts for creating the sponsorship pool.
+#[derive(Accounts)]
+pub struct InitSponsorPool<'info> {
+    /// Pays rent for the pool account
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    /// The sponsorship pool PDA to create
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + SponsorPool::SIZE,
+        seeds = [b"sponsor_pool"],
+        bump
+    )]
+    pub sponsor_pool: Account<'info, SponsorPool>,
+
+    /// System program for account creation
+    pub system_program: Program<'info, System>,
+}
+
+/// Accounts for funding the sponsorship pool.
 #[derive(Accounts)]
-pub struct CompleteCiphertext<'info> {
-    /// The sender who initiated the transfer
+pub struct FundSponsorPool<'info> {
+    /// The donor
     #[account(mut)]
-    pub sender: Signer<'info>,
+    pub funder: Signer<'info>,
 
-    /// The existing CiphertextAccount PDA
+    /// The sponsorship pool
     #[account(
         mut,
-        seeds = [b"ciphertext", ciphertext_account.stealth_pubkey.as_ref()],
-        bump = ciphertext_account.bump,
+        seeds = [b"sponsor_pool"],
+        bump = sponsor_pool.bump,
     )]
-    pub ciphertext_account: Account<'info, CiphertextAccount>,
+    pub sponsor_pool: Account<'info, SponsorPool>,
+
+    /// System program for the transfer
+    pub system_program: Program<'info, System>,
 }
 
-/// Accounts for transferring SOL to a stealth address.
+/// Accounts for registering a meta-address.
 #[derive(Accounts)]
-pub struct TransferToStealth<'info> {
-    /// The sender who pays for the transfer
+pub struct RegisterMetaAddress<'info> {
+    /// The wallet publishing its meta-address, who pays the rent
     #[account(mut)]
-    pub sender: Signer<'info>,
+    pub owner: Signer<'info>,
 
-    /// The stealth address receiving the SOL.
-    /// CHECK: Unchecked as it's a derived stealth address.
+    /// The registry entry PDA to create
+    #[account(
+        init,
+        payer = owner,
+        space = 8 + MetaAddressRegistry::SIZE,
+        seeds = [b"meta", owner.key().as_ref()],
+        bump
+    )]
+    pub meta_address: Box<Account<'info, MetaAddressRegistry>>,
+
+    /// System program for account creation
+    pub system_program: Program<'info, System>,
+}
+
+/// Accounts for changing a registered meta-address
+/// (update_meta_address, write_meta_address_key).
+#[derive(Accounts)]
+pub struct UpdateMetaAddress<'info> {
+    /// The meta-address owner
+    pub owner: Signer<'info>,
+
+    /// The owner's registry entry
+    #[account(
+        mut,
+        has_one = owner,
+        seeds = [b"meta", owner.key().as_ref()],
+        bump = meta_address.bump,
+    )]
+    pub meta_address: Box<Account<'info, MetaAddressRegistry>>,
+}
+
+/// Accounts for closing a meta-address.
+#[derive(Accounts)]
+pub struct CloseMetaAddress<'info> {
+    /// The meta-address owner, who receives the rent
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    /// The registry entry to close
+    #[account(
+        mut,
+        close = owner,
+        has_one = owner,
+        seeds = [b"meta", owner.key().as_ref()],
+        bump = meta_address.bump,
+    )]
+    pub meta_address: Box<Account<'info, MetaAddressRegistry>>,
+}
+
+/// Accounts for attesting an EVM meta-address.
+#[derive(Accounts)]
+pub struct AttestEvmMetaAddress<'info> {
+    /// The meta-address owner, who pays the rent
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    /// The owner's registry entry; only registered meta-addresses can be linked
+    #[account(
+        has_one = owner,
+        seeds = [b"meta", owner.key().as_ref()],
+        bump = meta_address.bump,
+    )]
+    pub meta_address: Box<Account<'info, MetaAddressRegistry>>,
+
+    /// The attestation PDA to create
+    #[account(
+        init,
+        payer = owner,
+        space = 8 + EvmAttestation::SIZE,
+        seeds = [b"evm", owner.key().as_ref()],
+        bump
+    )]
+    pub attestation: Account<'info, EvmAttestation>,
+
+    /// Instructions sysvar, checked for the secp256k1 precompile instruction
+    /// CHECK: address constraint pins the instructions sysvar
+    #[account(address = instructions_sysvar::ID)]
+    pub instructions_sysvar: AccountInfo<'info>,
+
+    /// System program for account creation
+    pub system_program: Program<'info, System>,
+}
+
+/// Accounts for revoking an EVM attestation.
+#[derive(Accounts)]
+pub struct RevokeEvmAttestation<'info> {
+    /// The meta-address owner, who receives the rent
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    /// The attestation to close
+    #[account(
+        mut,
+        close = owner,
+        has_one = owner,
+        seeds = [b"evm", owner.key().as_ref()],
+        bump = attestation.bump,
+    )]
+    pub attestation: Account<'info, EvmAttestation>,
+}
+
+/// Accounts for creating a staging buffer.
+#[derive(Accounts)]
+#[instruction(buffer_id: u64)]
+pub struct InitBuffer<'info> {
+    /// The sender who owns the buffer and pays its rent
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    /// The staging buffer PDA to create
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + StagingBuffer::SIZE,
+        seeds = [b"buffer", authority.key().as_ref(), buffer_id.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub buffer: Box<Account<'info, StagingBuffer>>,
+
+    /// System program for account creation
+    pub system_program: Program<'info, System>,
+}
+
+/// Accounts for writing to a staging buffer.
+#[derive(Accounts)]
+pub struct WriteBuffer<'info> {
+    /// The buffer authority
+    pub authority: Signer<'info>,
+
+    /// The staging buffer owned by the authority
+    #[account(
+        mut,
+        has_one = authority,
+        seeds = [b"buffer", authority.key().as_ref(), buffer.buffer_id.to_le_bytes().as_ref()],
+        bump = buffer.bump,
+    )]
+    pub buffer: Box<Account<'info, StagingBuffer>>,
+}
+
+/// Accounts for committing a staging buffer into a CiphertextAccount.
+#[derive(Accounts)]
+#[instruction(
+    ephemeral_pubkey: [u8; EPHEMERAL_PUBKEY_SIZE],
+    expires_at: Option<i64>,
+    app_id: u32,
+)]
+pub struct CommitBuffer<'info> {
+    /// The buffer authority, who pays rent for the CiphertextAccount
     #[account(mut)]
+    pub authority: Signer<'info>,
+
+    /// The one-time stealth address the ciphertext is for.
+    /// CHECK: This is a derived stealth address, not an existing account.
     pub stealth_address: AccountInfo<'info>,
 
-    /// Verify the ciphertext account exists for this stealth address
+    /// The staging buffer holding the ciphertext
     #[account(
-        seeds = [b"ciphertext", stealth_address.key().as_ref()],
-        bump = ciphertext_account.bump,
+        mut,
+        has_one = authority,
+        seeds = [b"buffer", authority.key().as_ref(), buffer.buffer_id.to_le_bytes().as_ref()],
+        bump = buffer.bump,
     )]
-    pub ciphertext_account: Account<'info, CiphertextAccount>,
+    pub buffer: Box<Account<'info, StagingBuffer>>,
 
-    /// System program for SOL transfer
+    /// PDA storing the MLKEM ciphertext, derived from the stealth address
+    #[account(
+        init,
+        payer = authority,
+        space = CiphertextAccount::space(MLKEM_CIPHERTEXT_SIZE, 0),
+        seeds = [
+            b"ciphertext",
+            stealth_address.key().as_ref(),
+            CiphertextAccount::app_id_seed(&app_id.to_le_bytes()),
+        ],
+        bump
+    )]
+    pub ciphertext_account: Box<Account<'info, CiphertextAccount>>,
+
+    /// Announcement counter for the app namespace, created on first use
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = 8 + NamespaceCounter::SIZE,
+        seeds = [b"namespace", app_id.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub namespace_counter: Box<Account<'info, NamespaceCounter>>,
+
+    /// System program for account creation
     pub system_program: Program<'info, System>,
 }
 
-/// Accounts for the reclaim_rent instruction.
-///
-/// Closes the CiphertextAccount and returns rent to the stealth address.
+/// Accounts for closing a staging buffer.
 #[derive(Accounts)]
-pub struct ReclaimRent<'info> {
-    /// The stealth address owner (must be the signer).
-    /// This proves they have the derived spending key.
-    /// Rent is returned to this account.
+pub struct CloseBuffer<'info> {
+    /// The buffer authority, who receives the rent
     #[account(mut)]
-    pub stealth_signer: Signer<'info>,
+    pub authority: Signer<'info>,
 
-    /// The CiphertextAccount to close.
-    /// Must belong to this stealth address (verified by seeds).
+    /// The staging buffer to close
     #[account(
         mut,
-        close = stealth_signer,
-        seeds = [b"ciphertext", stealth_signer.key().as_ref()],
-        bump = ciphertext_account.bump,
+        close = authority,
+        has_one = authority,
+        seeds = [b"buffer", authority.key().as_ref(), buffer.buffer_id.to_le_bytes().as_ref()],
+        bump = buffer.bump,
     )]
-    pub ciphertext_account: Account<'info, CiphertextAccount>,
+    pub buffer: Box<Account<'info, StagingBuffer>>,
 }
 
 /// Custom errors for the stealth-pq program
@@ -255,6 +2982,69 @@ pub enum StealthError {
 
     #[msg("Transfer amount must be greater than zero.")]
     ZeroTransferAmount,
+
+    #[msg("Expiry must be in the future.")]
+    InvalidExpiry,
+
+    #[msg("Invalid extension length or offset.")]
+    InvalidExtensionLength,
+
+    #[msg("Malformed extension entry.")]
+    MalformedExtension,
+
+    #[msg("Memo exceeds the maximum memo size.")]
+    InvalidMemoLength,
+
+    #[msg("Sponsorship pool has insufficient funds.")]
+    SponsorPoolEmpty,
+
+    #[msg("Sponsored claim rate limit reached for this fee payer.")]
+    ClaimRateLimited,
+
+    #[msg("Stealth address balance is too low for a sponsored claim.")]
+    SponsoredClaimBalanceTooLow,
+
+    #[msg("Sponsorship pool has paid out its claim limit for this epoch.")]
+    SponsorPoolEpochLimit,
+
+    #[msg("No funding transfer to the stealth address in this transaction.")]
+    MissingFundingTransfer,
+
+    #[msg("Rent payer share must be at most 10000 basis points.")]
+    InvalidRentShare,
+
+    #[msg("The rent payer account is required to close this ciphertext account.")]
+    MissingRentPayer,
+
+    #[msg("Invalid encapsulation key length or offset.")]
+    InvalidEncapsulationKeyLength,
+
+    #[msg("The ciphertext account is finalized and can no longer be written.")]
+    CiphertextFinalized,
+
+    #[msg("The ciphertext account must be finalized before transfers.")]
+    CiphertextNotFinalized,
+
+    #[msg("The ciphertext account has no expiry or has not expired yet.")]
+    NotExpired,
+
+    #[msg("Batch accounts must be one (stealth address, ciphertext account) pair per amount.")]
+    InvalidBatch,
+
+    #[msg("Unknown ML-KEM variant.")]
+    InvalidKemVariant,
+
+    #[msg("The announcement is already in the announcement log.")]
+    AlreadyLogged,
+
+    #[msg("The account is not a CiphertextAccount in the legacy layout.")]
+    NotLegacyCiphertext,
+
+    #[msg("The token account doesn't match the one recorded in the announcement.")]
+    TokenAccountMismatch,
+
+    #[msg("No secp256k1 signature by the EVM account over the attestation message.")]
+    MissingEvmSignature,
 }
 
 #[cfg(test)]
@@ -264,9 +3054,497 @@ mod tests {
     #[test]
     fn test_ciphertext_account_size() {
         // Verify our size calculation is correct
-        assert_eq!(CiphertextAccount::SIZE, 1161);
+        assert_eq!(CiphertextAccount::SIZE, 348);
+
+        // With Anchor discriminator (8 bytes), total space needed per variant
+        assert_eq!(CiphertextAccount::init_space(KEM_VARIANT_ML_KEM_768), 1444);
+        assert_eq!(CiphertextAccount::init_space(KEM_VARIANT_ML_KEM_512), 1124);
+        assert_eq!(CiphertextAccount::init_space(KEM_VARIANT_ML_KEM_1024), 1924);
+    }
+
+    /// Account data as created by the original program: discriminator, stealth
+    /// key, ephemeral key, MLKEM768 ciphertext, created_at, bump
+    fn legacy_fixture(stealth_pubkey: Pubkey) -> Vec<u8> {
+        let mut data = CiphertextAccount::DISCRIMINATOR.to_vec();
+        data.extend_from_slice(stealth_pubkey.as_ref());
+        data.extend_from_slice(&[0xAB; EPHEMERAL_PUBKEY_SIZE]);
+        data.extend((0..MLKEM_CIPHERTEXT_SIZE).map(|i| (i * 7 + 3) as u8));
+        data.extend_from_slice(&1_700_000_000i64.to_le_bytes());
+        data.push(254);
+        data
+    }
+
+    #[test]
+    fn test_legacy_ciphertext_decodes() {
+        assert_eq!(LegacyCiphertextAccount::SIZE, 1161);
+
+        let stealth_pubkey = Pubkey::new_unique();
+        let data = legacy_fixture(stealth_pubkey);
+        assert_eq!(data.len(), 8 + LegacyCiphertextAccount::SIZE);
+
+        // Not loadable in the current layout ...
+        assert!(
+            !CiphertextAccount::try_deserialize(&mut &data[..]).is_ok_and(|account| {
+                mlkem_ciphertext_size(account.kem_variant) == Some(account.mlkem_ciphertext.len())
+            })
+        );
+
+        // ... but decodes as legacy and migrates to a finalized default-namespace announcement
+        let legacy = LegacyCiphertextAccount::decode(&data).unwrap();
+        assert_eq!(legacy.stealth_pubkey, stealth_pubkey);
+        assert_eq!(legacy.created_at, 1_700_000_000);
+        assert_eq!(legacy.bump, 254);
+
+        let rent_payer = Pubkey::new_unique();
+        let migrated = legacy.clone().migrate(rent_payer);
+        assert_eq!(migrated.stealth_pubkey, stealth_pubkey);
+        assert_eq!(migrated.ephemeral_pubkey, [0xAB; EPHEMERAL_PUBKEY_SIZE]);
+        assert_eq!(migrated.mlkem_ciphertext, legacy.mlkem_ciphertext);
+        assert_eq!(migrated.created_at, 1_700_000_000);
+        assert_eq!(migrated.bump, 254);
+        assert_eq!(migrated.app_id, DEFAULT_APP_ID);
+        assert_eq!(migrated.rent_payer, rent_payer);
+        assert_eq!(migrated.rent_payer_share_bps, 0);
+        assert_eq!(migrated.sender, Pubkey::default());
+        assert_eq!(migrated.expires_at, 0);
+        assert!(migrated.finalized);
+        assert_eq!(migrated.kem_variant, KEM_VARIANT_ML_KEM_768);
+
+        let mut rewritten = Vec::new();
+        migrated.try_serialize(&mut rewritten).unwrap();
+        assert_eq!(
+            rewritten.len(),
+            CiphertextAccount::init_space(KEM_VARIANT_ML_KEM_768)
+        );
+        let reloaded = CiphertextAccount::try_deserialize(&mut &rewritten[..]).unwrap();
+        assert_eq!(reloaded.mlkem_ciphertext, migrated.mlkem_ciphertext);
+    }
+
+    #[test]
+    fn test_legacy_decode_rejects_other_accounts() {
+        // Current-layout account padded to the legacy size (a 512 account with extensions)
+        let mut account = CiphertextAccount {
+            kem_variant: KEM_VARIANT_ML_KEM_512,
+            mlkem_ciphertext: vec![0x11; MLKEM512_CIPHERTEXT_SIZE],
+            ..Default::default()
+        };
+        account.extensions = vec![
+            0;
+            8 + LegacyCiphertextAccount::SIZE
+                - CiphertextAccount::init_space(KEM_VARIANT_ML_KEM_512)
+        ];
+        let mut data = Vec::new();
+        account.try_serialize(&mut data).unwrap();
+        assert_eq!(data.len(), 8 + LegacyCiphertextAccount::SIZE);
+        assert!(LegacyCiphertextAccount::decode(&data).is_err());
+
+        let mut wrong_discriminator = legacy_fixture(Pubkey::new_unique());
+        wrong_discriminator[0] ^= 0xFF;
+        assert!(LegacyCiphertextAccount::decode(&wrong_discriminator).is_err());
+
+        let mut truncated = legacy_fixture(Pubkey::new_unique());
+        truncated.pop();
+        assert!(LegacyCiphertextAccount::decode(&truncated).is_err());
+    }
+
+    #[test]
+    fn test_mlkem_ciphertext_size() {
+        assert_eq!(mlkem_ciphertext_size(KEM_VARIANT_ML_KEM_512), Some(768));
+        assert_eq!(mlkem_ciphertext_size(KEM_VARIANT_ML_KEM_768), Some(1088));
+        assert_eq!(mlkem_ciphertext_size(KEM_VARIANT_ML_KEM_1024), Some(1568));
+        assert_eq!(mlkem_ciphertext_size(3), None);
+    }
+
+    #[test]
+    fn test_staging_buffer_size() {
+        assert_eq!(StagingBuffer::SIZE, 1129);
+    }
+
+    #[test]
+    fn test_meta_address_registry_layout() {
+        assert_eq!(MetaAddressRegistry::SIZE, 1293);
+
+        let registry = MetaAddressRegistry {
+            spending_pubkey: [0xAA; 32],
+            viewing_pubkey: [0xBB; 32],
+            mlkem_encapsulation_key: [0xCC; MLKEM_ENCAPSULATION_KEY_SIZE],
+            ..Default::default()
+        };
+
+        let mut data = Vec::new();
+        registry.try_serialize(&mut data).unwrap();
+        assert_eq!(data.len(), 8 + MetaAddressRegistry::SIZE);
+
+        // M || V || K_pub, as in the off-chain hybrid meta-address
+        let keys = &data[MetaAddressRegistry::KEYS_OFFSET..][..64 + MLKEM_ENCAPSULATION_KEY_SIZE];
+        assert_eq!(keys[..32], [0xAA; 32]);
+        assert_eq!(keys[32..64], [0xBB; 32]);
+        assert_eq!(keys[64..], [0xCC; MLKEM_ENCAPSULATION_KEY_SIZE]);
+    }
+
+    #[test]
+    fn test_ciphertext_account_offsets() {
+        // The native entrypoint reads these offsets directly from account data
+        let account = CiphertextAccount {
+            stealth_pubkey: Pubkey::new_from_array([0xAA; 32]),
+            bump: 0xFE,
+            app_id: 0x0102_0304,
+            view_tag: 0x5A,
+            sender: Pubkey::new_from_array([0xDD; 32]),
+            finalized: true,
+            kem_variant: KEM_VARIANT_ML_KEM_1024,
+            memo_len: 3,
+            encrypted_memo: [0xEE; MAX_MEMO_SIZE],
+            logged: true,
+            announcement_index: 0x0A0B_0C0D,
+            mlkem_ciphertext: vec![0xCC; MLKEM1024_CIPHERTEXT_SIZE],
+            ..Default::default()
+        };
+        assert_eq!(account.memo(), [0xEE; 3]);
+
+        let mut data = Vec::new();
+        account.try_serialize(&mut data).unwrap();
+
+        assert_eq!(
+            data.len(),
+            CiphertextAccount::init_space(KEM_VARIANT_ML_KEM_1024)
+        );
+        assert_eq!(
+            data[CiphertextAccount::STEALTH_PUBKEY_OFFSET..][..32],
+            [0xAA; 32]
+        );
+        assert_eq!(
+            data[CiphertextAccount::MLKEM_CIPHERTEXT_LEN_OFFSET..][..4],
+            (MLKEM1024_CIPHERTEXT_SIZE as u32).to_le_bytes()
+        );
+        assert_eq!(
+            data[CiphertextAccount::MLKEM_CIPHERTEXT_OFFSET..][..MLKEM1024_CIPHERTEXT_SIZE],
+            [0xCC; MLKEM1024_CIPHERTEXT_SIZE]
+        );
+        assert_eq!(data[CiphertextAccount::BUMP_OFFSET], 0xFE);
+        assert_eq!(
+            data[CiphertextAccount::APP_ID_OFFSET..][..4],
+            0x0102_0304u32.to_le_bytes()
+        );
+        assert_eq!(data[CiphertextAccount::VIEW_TAG_OFFSET], 0x5A);
+        assert_eq!(data[CiphertextAccount::SENDER_OFFSET..][..32], [0xDD; 32]);
+        assert_eq!(data[CiphertextAccount::FINALIZED_OFFSET], 1);
+        assert_eq!(
+            data[CiphertextAccount::KEM_VARIANT_OFFSET],
+            KEM_VARIANT_ML_KEM_1024
+        );
+        assert_eq!(data[CiphertextAccount::MEMO_LEN_OFFSET], 3);
+        assert_eq!(
+            data[CiphertextAccount::ENCRYPTED_MEMO_OFFSET..][..MAX_MEMO_SIZE],
+            [0xEE; MAX_MEMO_SIZE]
+        );
+        assert_eq!(data[CiphertextAccount::LOGGED_OFFSET], 1);
+        assert_eq!(
+            data[CiphertextAccount::ANNOUNCEMENT_INDEX_OFFSET..][..8],
+            0x0A0B_0C0Du64.to_le_bytes()
+        );
+    }
+
+    #[test]
+    fn test_claim_record_window() {
+        let mut record = ClaimRecord::default();
+        let start = 1_700_000_000;
+
+        for _ in 0..MAX_SPONSORED_CLAIMS_PER_INTERVAL {
+            record.record_claim(start).unwrap();
+        }
+        assert!(record
+            .record_claim(start + SPONSORED_CLAIM_INTERVAL - 1)
+            .is_err());
+        assert_eq!(record.window_claims, MAX_SPONSORED_CLAIMS_PER_INTERVAL);
+
+        // The next window starts at the first claim after the interval
+        record
+            .record_claim(start + SPONSORED_CLAIM_INTERVAL)
+            .unwrap();
+        assert_eq!(record.window_start, start + SPONSORED_CLAIM_INTERVAL);
+        assert_eq!(record.window_claims, 1);
+    }
+
+    #[test]
+    fn test_rent_payer_share() {
+        let account = |rent_payer_share_bps| CiphertextAccount {
+            rent_payer_share_bps,
+            ..Default::default()
+        };
+
+        assert_eq!(account(0).rent_payer_share(9_693_120), 0);
+        assert_eq!(account(2_500).rent_payer_share(9_693_120), 2_423_280);
+        assert_eq!(
+            account(BPS_DENOMINATOR).rent_payer_share(9_693_120),
+            9_693_120
+        );
+        assert_eq!(
+            account(BPS_DENOMINATOR).rent_payer_share(u64::MAX),
+            u64::MAX
+        );
+    }
+
+    #[test]
+    fn test_app_id_seed() {
+        // The default namespace keeps the original seeds
+        assert!(CiphertextAccount::app_id_seed(&DEFAULT_APP_ID.to_le_bytes()).is_empty());
+        assert_eq!(
+            CiphertextAccount::app_id_seed(&7u32.to_le_bytes()),
+            &[7, 0, 0, 0]
+        );
+    }
+
+    #[test]
+    fn test_namespace_counter_sequence() {
+        assert_eq!(NamespaceCounter::SIZE, 13);
+
+        let mut counter = NamespaceCounter::default();
+        assert_eq!(counter.next_sequence(7, 250), 1);
+        assert_eq!(counter.next_sequence(7, 250), 2);
+        assert_eq!(counter.app_id, 7);
+        assert_eq!(counter.bump, 250);
+
+        let mut data = Vec::new();
+        counter.try_serialize(&mut data).unwrap();
+        assert_eq!(data.len(), 8 + NamespaceCounter::SIZE);
+    }
+
+    #[test]
+    fn test_is_funding_transfer() {
+        use anchor_lang::solana_program::instruction::AccountMeta;
+
+        let stealth = Pubkey::new_unique();
+        let accounts = vec![
+            AccountMeta::new(Pubkey::new_unique(), true),
+            AccountMeta::new(stealth, false),
+        ];
+
+        let mut data = 2u32.to_le_bytes().to_vec();
+        data.extend_from_slice(&1_000u64.to_le_bytes());
+        let system_transfer =
+            Instruction::new_with_bytes(system_program::ID, &data, accounts.clone());
+        assert!(is_funding_transfer(&system_transfer, &stealth));
+        assert!(!is_funding_transfer(
+            &system_transfer,
+            &Pubkey::new_unique()
+        ));
+
+        // The program's transfers need a finalized announcement, so they can't fund it
+        let mut data = instruction::TransferToStealth::DISCRIMINATOR.to_vec();
+        data.extend_from_slice(&1_000u64.to_le_bytes());
+        let transfer = Instruction::new_with_bytes(crate::ID, &data, accounts.clone());
+        assert!(!is_funding_transfer(&transfer, &stealth));
+
+        // Zero amounts and other instructions don't count
+        let mut data = 2u32.to_le_bytes().to_vec();
+        data.extend_from_slice(&0u64.to_le_bytes());
+        let zero_transfer =
+            Instruction::new_with_bytes(system_program::ID, &data, accounts.clone());
+        assert!(!is_funding_transfer(&zero_transfer, &stealth));
+
+        let mut data = instruction::TransferSplToStealth::DISCRIMINATOR.to_vec();
+        data.extend_from_slice(&1_000u64.to_le_bytes());
+        let spl_transfer = Instruction::new_with_bytes(crate::ID, &data, accounts.clone());
+        assert!(!is_funding_transfer(&spl_transfer, &stealth));
+
+        let other = Instruction::new_with_bytes(
+            crate::ID,
+            instruction::ReclaimRent::DISCRIMINATOR,
+            accounts,
+        );
+        assert!(!is_funding_transfer(&other, &stealth));
+    }
+
+    #[test]
+    fn test_evm_attestation_layout() {
+        assert_eq!(EvmAttestation::SIZE, 127);
+
+        let mut data = Vec::new();
+        EvmAttestation::default().serialize(&mut data).unwrap();
+        assert_eq!(data.len(), EvmAttestation::SIZE);
+
+        let owner = Pubkey::new_unique();
+        let message = evm_attestation_message(&owner, &[0xAB; EVM_META_ADDRESS_SIZE]);
+        let text = format!("stealth-pq: link {} to st:eth:0x{}", owner, "ab".repeat(66));
+        assert_eq!(
+            message,
+            format!("\x19Ethereum Signed Message:\n{}{}", text.len(), text).into_bytes()
+        );
+    }
+
+    /// Secp256k1 precompile data with one signature, laid out as the client
+    /// builds it, with every offset pointing into instruction `index`
+    fn secp256k1_fixture(index: u8, evm_address: &[u8; 20], message: &[u8]) -> Vec<u8> {
+        let mut data = vec![1u8];
+        data.extend_from_slice(&32u16.to_le_bytes());
+        data.push(index);
+        data.extend_from_slice(&12u16.to_le_bytes());
+        data.push(index);
+        data.extend_from_slice(&97u16.to_le_bytes());
+        data.extend_from_slice(&(message.len() as u16).to_le_bytes());
+        data.push(index);
+        data.extend_from_slice(evm_address);
+        data.extend_from_slice(&[0x11; 65]);
+        data.extend_from_slice(message);
+        data
+    }
+
+    #[test]
+    fn test_secp256k1_signs() {
+        let evm_address = [0x42; 20];
+        let message = evm_attestation_message(&Pubkey::new_unique(), &[2; EVM_META_ADDRESS_SIZE]);
+
+        let data = secp256k1_fixture(0, &evm_address, &message);
+        assert!(secp256k1_signs(&data, 0, &evm_address, &message));
+        assert!(!secp256k1_signs(&data, 0, &[0x43; 20], &message));
+        assert!(!secp256k1_signs(&data, 0, &evm_address, b"another message"));
+
+        // Offsets into another instruction could point at data nobody signed
+        assert!(!secp256k1_signs(&data, 1, &evm_address, &message));
+        let data = secp256k1_fixture(1, &evm_address, &message);
+        assert!(!secp256k1_signs(&data, 0, &evm_address, &message));
+
+        // Truncated data or a zero count verifies nothing
+        assert!(!secp256k1_signs(&data[..100], 1, &evm_address, &message));
+        let mut data = data;
+        data[0] = 0;
+        assert!(!secp256k1_signs(&data, 1, &evm_address, &message));
+        assert!(!secp256k1_signs(&[], 1, &evm_address, &message));
+    }
+
+    #[test]
+    fn test_announcement_log_sizes() {
+        assert_eq!(AnnouncementLog::SIZE, 9);
+        assert_eq!(Announcement::SIZE, 142);
+
+        let mut data = Vec::new();
+        Announcement::default().try_serialize(&mut data).unwrap();
+        assert_eq!(data.len(), 8 + Announcement::SIZE);
+    }
+
+    #[test]
+    fn test_sponsorship_sizes() {
+        assert_eq!(SponsorPool::SIZE, 21);
+        assert_eq!(ClaimRecord::SIZE, 45);
+
+        let mut data = Vec::new();
+        ClaimRecord::default().try_serialize(&mut data).unwrap();
+        assert_eq!(data.len(), 8 + ClaimRecord::SIZE);
+    }
+
+    #[test]
+    fn test_stats_account_layout() {
+        assert_eq!(StatsAccount::SIZE, 17);
+
+        let mut stats = StatsAccount {
+            bump: 253,
+            ..Default::default()
+        };
+        stats.record_transfer(1_000);
+        stats.record_transfer(u64::MAX);
+
+        let mut data = Vec::new();
+        stats.try_serialize(&mut data).unwrap();
+        assert_eq!(data.len(), 8 + StatsAccount::SIZE);
+        assert_eq!(
+            data[StatsAccount::TRANSFER_COUNT_OFFSET..][..8],
+            2u64.to_le_bytes()
+        );
+        assert_eq!(
+            data[StatsAccount::TOTAL_LAMPORTS_OFFSET..][..8],
+            u64::MAX.to_le_bytes()
+        );
+        assert_eq!(data[StatsAccount::BUMP_OFFSET], 253);
+    }
+
+    #[test]
+    fn test_extension_parsing() {
+        let mut area = vec![EXT_TYPE_ENCRYPTED_AMOUNT, 8, 0];
+        area.extend_from_slice(&[7u8; 8]);
+        area.extend_from_slice(&[EXT_TYPE_APP_DATA_START, 0, 0]);
+        area.extend_from_slice(&[EXT_TYPE_END, 0xFF, 0xFF]);
+
+        let entries: Vec<_> = ExtensionIter::new(&area).map(|e| e.unwrap()).collect();
+        assert_eq!(
+            entries,
+            vec![
+                Extension {
+                    ext_type: EXT_TYPE_ENCRYPTED_AMOUNT,
+                    value: &[7u8; 8],
+                },
+                Extension {
+                    ext_type: EXT_TYPE_APP_DATA_START,
+                    value: &[],
+                },
+            ]
+        );
+
+        // Empty area has no entries
+        assert_eq!(ExtensionIter::new(&[]).count(), 0);
+    }
+
+    #[test]
+    fn test_registry_epoch_extension() {
+        let mut account = CiphertextAccount::default();
+        assert_eq!(account.registry_epoch().unwrap(), None);
+
+        account.extensions = vec![EXT_TYPE_FMD_CLUE, 1, 0, 0xFF];
+        account
+            .extensions
+            .extend_from_slice(&[EXT_TYPE_REGISTRY_EPOCH, 4, 0, 9, 0, 0, 0]);
+        assert_eq!(account.registry_epoch().unwrap(), Some(9));
+
+        // Wrong value length
+        account.extensions = vec![EXT_TYPE_REGISTRY_EPOCH, 2, 0, 9, 0];
+        assert!(account.registry_epoch().is_err());
+    }
+
+    #[test]
+    fn test_token_account_extension() {
+        let mut account = CiphertextAccount::default();
+        assert_eq!(account.token_account().unwrap(), None);
+
+        let token_account = Pubkey::new_unique();
+        account.extensions = vec![EXT_TYPE_REGISTRY_EPOCH, 4, 0, 9, 0, 0, 0];
+        account
+            .extensions
+            .extend_from_slice(&[EXT_TYPE_TOKEN_ACCOUNT, 32, 0]);
+        account.extensions.extend_from_slice(token_account.as_ref());
+        assert_eq!(account.token_account().unwrap(), Some(token_account));
+
+        account.extensions = vec![EXT_TYPE_TOKEN_ACCOUNT, 1, 0, 0];
+        assert!(account.token_account().is_err());
+    }
+
+    #[test]
+    fn test_extension_parsing_truncated() {
+        // Declares 4 bytes of value but only 2 follow
+        let area = [EXT_TYPE_FMD_CLUE, 4, 0, 1, 2];
+        let mut iter = ExtensionIter::new(&area);
+        assert!(iter.next().unwrap().is_err());
+        assert!(iter.next().is_none());
+
+        // Missing length bytes
+        let area = [EXT_TYPE_FMD_CLUE, 4];
+        assert!(ExtensionIter::new(&area).next().unwrap().is_err());
+    }
+
+    #[test]
+    fn test_data_chunk_bounds() {
+        let mut chunk = DataChunk {
+            len: 3,
+            data: [0u8; MAX_CHUNK_SIZE],
+        };
+        chunk.data[..3].copy_from_slice(&[1, 2, 3]);
+        assert_eq!(chunk.as_bytes().unwrap(), &[1, 2, 3]);
+
+        chunk.len = MAX_CHUNK_SIZE as u16 + 1;
+        assert!(chunk.as_bytes().is_err());
 
-        // With Anchor discriminator (8 bytes), total space needed
-        assert_eq!(8 + CiphertextAccount::SIZE, 1169);
+        // Fixed wire size: 2 (len) + MAX_CHUNK_SIZE (data)
+        let mut encoded = Vec::new();
+        chunk.serialize(&mut encoded).unwrap();
+        assert_eq!(encoded.len(), 2 + MAX_CHUNK_SIZE);
     }
 }
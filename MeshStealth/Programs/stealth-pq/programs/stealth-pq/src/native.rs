@@ -0,0 +1,264 @@
+//! Native entrypoint for the hot-path instructions.
+//!
+//! Built with the `native-entrypoint` feature. `complete_ciphertext` and
+//! `transfer_to_stealth` are decoded straight from the raw instruction data and
+//! account buffers, skipping Anchor's account deserialization (and, for
+//! `complete_ciphertext`, the re-serialization of the whole CiphertextAccount on
+//! exit). Every other instruction is forwarded to the Anchor dispatcher, so the
+//! account layouts and instruction data format are the same under either build.
+//!
+//! The checks below mirror the Anchor constraints on `CompleteCiphertext` and
+//! `TransferToStealth` (including the optional stats account and the sender and
+//! finalization checks) and return the same error codes.
+
+use anchor_lang::error::ErrorCode;
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::entrypoint::ProgramResult;
+use anchor_lang::system_program;
+use anchor_lang::Discriminator;
+
+use crate::{instruction, CiphertextAccount, StatsAccount, StealthError, MAX_CHUNK_SIZE};
+
+anchor_lang::solana_program::entrypoint!(process_instruction);
+
+/// Program entrypoint: fast-paths the hot instructions, forwards the rest to Anchor.
+pub fn process_instruction<'info>(
+    program_id: &Pubkey,
+    accounts: &'info [AccountInfo<'info>],
+    data: &[u8],
+) -> ProgramResult {
+    let result = if let Some(args) =
+        data.strip_prefix(instruction::CompleteCiphertext::DISCRIMINATOR)
+    {
+        complete_ciphertext(program_id, accounts, args)
+    } else if let Some(args) = data.strip_prefix(instruction::TransferToStealth::DISCRIMINATOR) {
+        transfer_to_stealth(program_id, accounts, args)
+    } else {
+        return crate::entry(program_id, accounts, data);
+    };
+
+    result.map_err(|e| {
+        e.log();
+        e.into()
+    })
+}
+
+/// Native `complete_ciphertext`: writes the chunk directly into the account data.
+fn complete_ciphertext(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    mut args: &[u8],
+) -> Result<()> {
+    require_keys_eq!(*program_id, crate::ID, ErrorCode::DeclaredProgramIdMismatch);
+
+    let [sender, ciphertext_account, ..] = accounts else {
+        return err!(ErrorCode::AccountNotEnoughKeys);
+    };
+
+    // Instruction data: ciphertext_part2 (DataChunk: u16 len || [u8; MAX_CHUNK_SIZE]) || offset (u16)
+    let len = u16::from_le_bytes(take(&mut args, 2)?.try_into().unwrap()) as usize;
+    let chunk = take(&mut args, MAX_CHUNK_SIZE)?;
+    let offset = u16::from_le_bytes(take(&mut args, 2)?.try_into().unwrap());
+
+    require!(len <= MAX_CHUNK_SIZE, StealthError::InvalidCiphertextLength);
+    let ciphertext_part2 = &chunk[..len];
+
+    require!(sender.is_signer, ErrorCode::AccountNotSigner);
+    require!(sender.is_writable, ErrorCode::ConstraintMut);
+    require!(ciphertext_account.is_writable, ErrorCode::ConstraintMut);
+
+    let mut account_data = ciphertext_account.try_borrow_mut_data()?;
+    check_ciphertext_account(program_id, ciphertext_account, &account_data, None)?;
+
+    require!(
+        account_data[CiphertextAccount::SENDER_OFFSET..][..32] == sender.key.to_bytes(),
+        ErrorCode::ConstraintHasOne
+    );
+    require!(
+        account_data[CiphertextAccount::FINALIZED_OFFSET] == 0,
+        StealthError::CiphertextFinalized
+    );
+
+    // The ciphertext is sized for the account's KEM variant at init; its length
+    // prefix bounds the write
+    let ciphertext_len = u32::from_le_bytes(
+        account_data[CiphertextAccount::MLKEM_CIPHERTEXT_LEN_OFFSET..][..4]
+            .try_into()
+            .unwrap(),
+    ) as usize;
+    require!(
+        (offset as usize) + ciphertext_part2.len() <= ciphertext_len,
+        StealthError::InvalidCiphertextLength
+    );
+
+    let start = CiphertextAccount::MLKEM_CIPHERTEXT_OFFSET + offset as usize;
+    let end = start + ciphertext_part2.len();
+    account_data
+        .get_mut(start..end)
+        .ok_or(ErrorCode::AccountDidNotDeserialize)?
+        .copy_from_slice(ciphertext_part2);
+
+    msg!("Completed ciphertext at offset {}", offset);
+
+    Ok(())
+}
+
+/// Native `transfer_to_stealth`: validates the PDA in place and CPIs the system transfer.
+fn transfer_to_stealth<'info>(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo<'info>],
+    mut args: &[u8],
+) -> Result<()> {
+    require_keys_eq!(*program_id, crate::ID, ErrorCode::DeclaredProgramIdMismatch);
+
+    let [sender, stealth_address, ciphertext_account, system_program_account, rest @ ..] = accounts
+    else {
+        return err!(ErrorCode::AccountNotEnoughKeys);
+    };
+
+    // Optional stats account: absent, or the program ID as placeholder, means no opt-in
+    let stats = rest.first().filter(|stats| stats.key != program_id);
+
+    // Instruction data: lamports (u64)
+    let lamports = u64::from_le_bytes(take(&mut args, 8)?.try_into().unwrap());
+
+    require!(sender.is_signer, ErrorCode::AccountNotSigner);
+    require!(sender.is_writable, ErrorCode::ConstraintMut);
+    require!(stealth_address.is_writable, ErrorCode::ConstraintMut);
+    require_keys_eq!(
+        system_program_account.key(),
+        system_program::ID,
+        ErrorCode::InvalidProgramId
+    );
+
+    {
+        let account_data = ciphertext_account.try_borrow_data()?;
+        check_ciphertext_account(
+            program_id,
+            ciphertext_account,
+            &account_data,
+            Some(stealth_address.key),
+        )?;
+        require!(
+            account_data[CiphertextAccount::FINALIZED_OFFSET] != 0,
+            StealthError::CiphertextNotFinalized
+        );
+    }
+
+    require!(lamports > 0, StealthError::ZeroTransferAmount);
+
+    system_program::transfer(
+        CpiContext::new(
+            system_program_account.clone(),
+            system_program::Transfer {
+                from: sender.clone(),
+                to: stealth_address.clone(),
+            },
+        ),
+        lamports,
+    )?;
+
+    if let Some(stats) = stats {
+        record_stats(program_id, stats, lamports)?;
+    }
+
+    msg!(
+        "Transferred {} lamports to stealth address: {}",
+        lamports,
+        stealth_address.key()
+    );
+
+    Ok(())
+}
+
+/// Verify owner, discriminator and PDA seeds of a CiphertextAccount.
+///
+/// The seeds use `stealth_address` when given, otherwise the stored `stealth_pubkey`,
+/// plus the stored `app_id`, matching the `seeds` constraints on the corresponding
+/// Anchor accounts structs.
+fn check_ciphertext_account(
+    program_id: &Pubkey,
+    account: &AccountInfo,
+    data: &[u8],
+    stealth_address: Option<&Pubkey>,
+) -> Result<()> {
+    require_keys_eq!(
+        *account.owner,
+        *program_id,
+        ErrorCode::AccountOwnedByWrongProgram
+    );
+    require!(
+        data.starts_with(CiphertextAccount::DISCRIMINATOR),
+        ErrorCode::AccountDiscriminatorMismatch
+    );
+    require!(
+        data.len() >= 8 + CiphertextAccount::SIZE,
+        ErrorCode::AccountDidNotDeserialize
+    );
+
+    let stored_stealth = &data[CiphertextAccount::STEALTH_PUBKEY_OFFSET..][..32];
+    let seed_stealth = stealth_address.map_or(stored_stealth, |key| key.as_ref());
+    let bump = data[CiphertextAccount::BUMP_OFFSET];
+    let app_id: [u8; 4] = data[CiphertextAccount::APP_ID_OFFSET..][..4]
+        .try_into()
+        .unwrap();
+
+    let expected = Pubkey::create_program_address(
+        &[
+            b"ciphertext",
+            seed_stealth,
+            CiphertextAccount::app_id_seed(&app_id),
+            &[bump],
+        ],
+        program_id,
+    )
+    .map_err(|_| error!(ErrorCode::ConstraintSeeds))?;
+    require_keys_eq!(expected, account.key(), ErrorCode::ConstraintSeeds);
+
+    Ok(())
+}
+
+/// Verify the stats PDA and count the transfer in place.
+fn record_stats(program_id: &Pubkey, stats: &AccountInfo, lamports: u64) -> Result<()> {
+    require!(stats.is_writable, ErrorCode::ConstraintMut);
+    require_keys_eq!(
+        *stats.owner,
+        *program_id,
+        ErrorCode::AccountOwnedByWrongProgram
+    );
+
+    let mut data = stats.try_borrow_mut_data()?;
+    require!(
+        data.starts_with(StatsAccount::DISCRIMINATOR),
+        ErrorCode::AccountDiscriminatorMismatch
+    );
+    require!(
+        data.len() >= 8 + StatsAccount::SIZE,
+        ErrorCode::AccountDidNotDeserialize
+    );
+
+    let bump = data[StatsAccount::BUMP_OFFSET];
+    let expected = Pubkey::create_program_address(&[b"stats", &[bump]], program_id)
+        .map_err(|_| error!(ErrorCode::ConstraintSeeds))?;
+    require_keys_eq!(expected, stats.key(), ErrorCode::ConstraintSeeds);
+
+    let transfer_count = &mut data[StatsAccount::TRANSFER_COUNT_OFFSET..][..8];
+    let count = u64::from_le_bytes((&*transfer_count).try_into().unwrap());
+    transfer_count.copy_from_slice(&count.saturating_add(1).to_le_bytes());
+
+    let total_lamports = &mut data[StatsAccount::TOTAL_LAMPORTS_OFFSET..][..8];
+    let total = u64::from_le_bytes((&*total_lamports).try_into().unwrap());
+    total_lamports.copy_from_slice(&total.saturating_add(lamports).to_le_bytes());
+
+    Ok(())
+}
+
+/// Split `n` bytes off the front of the instruction data.
+fn take<'a>(data: &mut &'a [u8], n: usize) -> Result<&'a [u8]> {
+    if data.len() < n {
+        return err!(ErrorCode::InstructionDidNotDeserialize);
+    }
+    let (head, tail) = data.split_at(n);
+    *data = tail;
+    Ok(head)
+}
@@ -0,0 +1,428 @@
+//! Fixtures for testing integrations against the stealth-pq program and client.
+//!
+//! - [`recipient`] and [`meta_address`]: canned recipient keys, the same for the
+//!   same seed on every run
+//! - [`Announced`]: the accounts the program would hold for a payment, to feed
+//!   decoders and scanners without a validator
+//! - [`airdrop`] and [`funded_wallet`]: funding wallets on localnet or devnet
+//!
+//! The program itself is exercised by the Anchor suite in `tests/` against a
+//! local validator; this crate doesn't wrap `solana-program-test`.
+
+use std::time::Duration;
+
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::{AccountSerialize, Discriminator};
+use rand_chacha::ChaCha20Rng;
+use rand_core::SeedableRng;
+use solana_rpc_client::nonblocking::rpc_client::RpcClient;
+use stealth_pq::{Announcement, CiphertextAccount, KEM_VARIANT_ML_KEM_768};
+use stealth_pq_client::{
+    pda, Error, MetaAddress, Result, SpendingKey, StealthKeys, StealthPayment,
+};
+
+/// Lamports [`funded_wallet`] requests: enough for the rent and fees of a few
+/// payments
+pub const FUNDED_LAMPORTS: u64 = 2_000_000_000;
+
+/// How often [`airdrop`] polls for confirmation
+const CONFIRM_INTERVAL: Duration = Duration::from_millis(500);
+
+/// How many times [`airdrop`] polls before giving up
+const CONFIRM_ATTEMPTS: u32 = 60;
+
+/// Deterministic RNG for fixtures
+pub fn rng(seed: u64) -> ChaCha20Rng {
+    ChaCha20Rng::seed_from_u64(seed)
+}
+
+/// Recipient keys derived from `seed`, hybrid unless `post_quantum` is false
+pub fn recipient(seed: u64, post_quantum: bool) -> StealthKeys {
+    StealthKeys::generate(&mut rng(seed), post_quantum)
+}
+
+/// Hybrid meta-address of [`recipient`]`(seed, true)`
+pub fn meta_address(seed: u64) -> MetaAddress {
+    recipient(seed, true).meta_address()
+}
+
+/// Wallet key derived from `seed`, signing through [`SpendingKey::sign`].
+///
+/// A raw scalar rather than a `solana-keygen` seed, so it can't be written out
+/// as a keypair file.
+pub fn wallet(seed: u64) -> SpendingKey {
+    let mut scalar = [0u8; 32];
+    rand_core::RngCore::fill_bytes(&mut rng(seed), &mut scalar);
+    // Reduce below the group order; a top byte under 0x10 is enough
+    scalar[31] &= 0x0f;
+    SpendingKey::from_bytes(&scalar)
+}
+
+/// A payment with the accounts the program would hold once it is announced,
+/// finalized and logged.
+pub struct Announced {
+    /// The sender-side derivation
+    pub payment: StealthPayment,
+
+    /// CiphertextAccount PDA of the payment
+    pub ciphertext_account: Pubkey,
+
+    /// Serialized CiphertextAccount, discriminator included
+    pub ciphertext_data: Vec<u8>,
+
+    /// Announcement log entry PDA
+    pub announcement: Pubkey,
+
+    /// Serialized Announcement, discriminator included
+    pub announcement_data: Vec<u8>,
+}
+
+impl Announced {
+    /// Fabricate the accounts of a payment from `sender` to `meta_address`,
+    /// logged at `index` in `app_id`.
+    pub fn new(
+        meta_address: &MetaAddress,
+        sender: &Pubkey,
+        app_id: u32,
+        index: u64,
+        seed: u64,
+    ) -> Result<Self> {
+        let payment = StealthPayment::generate(meta_address, &mut rng(seed))?;
+        let (ciphertext_account, ciphertext_bump) =
+            pda::ciphertext_account(&payment.stealth_address, app_id);
+        let (announcement, announcement_bump) = pda::announcement(index);
+
+        let ciphertext = CiphertextAccount {
+            stealth_pubkey: payment.stealth_address,
+            ephemeral_pubkey: payment.ephemeral_pubkey,
+            bump: ciphertext_bump,
+            app_id,
+            rent_payer: *sender,
+            view_tag: payment.view_tag,
+            sender: *sender,
+            finalized: true,
+            kem_variant: KEM_VARIANT_ML_KEM_768,
+            logged: true,
+            announcement_index: index,
+            mlkem_ciphertext: payment.mlkem_ciphertext.clone().unwrap_or_default(),
+            ..CiphertextAccount::default()
+        };
+        let entry = Announcement {
+            index,
+            app_id,
+            stealth_pubkey: payment.stealth_address,
+            ciphertext_account,
+            ephemeral_pubkey: payment.ephemeral_pubkey,
+            view_tag: payment.view_tag,
+            bump: announcement_bump,
+            payer: *sender,
+        };
+
+        Ok(Self {
+            payment,
+            ciphertext_account,
+            ciphertext_data: serialize(&ciphertext),
+            announcement,
+            announcement_data: serialize(&entry),
+        })
+    }
+}
+
+fn serialize<T: AccountSerialize + Discriminator>(account: &T) -> Vec<u8> {
+    let mut data = Vec::with_capacity(T::DISCRIMINATOR.len() + 2048);
+    account
+        .try_serialize(&mut data)
+        .expect("serializing into a Vec doesn't fail");
+    data
+}
+
+/// Request an airdrop and wait until it is confirmed.
+///
+/// Works on localnet and devnet; devnet rate-limits requests and caps each at a
+/// few SOL.
+pub async fn airdrop(rpc: &RpcClient, address: &Pubkey, lamports: u64) -> Result<()> {
+    let signature = rpc.request_airdrop(address, lamports).await?;
+    for _ in 0..CONFIRM_ATTEMPTS {
+        if rpc.confirm_transaction(&signature).await? {
+            return Ok(());
+        }
+        tokio::time::sleep(CONFIRM_INTERVAL).await;
+    }
+    Err(Error::Unconfirmed(signature.to_string()))
+}
+
+/// [`wallet`]`(seed)`, funded with [`FUNDED_LAMPORTS`]
+pub async fn funded_wallet(rpc: &RpcClient, seed: u64) -> Result<SpendingKey> {
+    let wallet = wallet(seed);
+    airdrop(rpc, &wallet.pubkey(), FUNDED_LAMPORTS).await?;
+    Ok(wallet)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anchor_lang::AccountDeserialize;
+
+    #[test]
+    fn test_fixtures_are_deterministic() {
+        assert_eq!(meta_address(1).to_bytes(), meta_address(1).to_bytes());
+        assert_ne!(meta_address(1).to_bytes(), meta_address(2).to_bytes());
+        assert!(!recipient(1, false).has_post_quantum());
+        assert_eq!(wallet(3).pubkey(), wallet(3).pubkey());
+    }
+
+    #[test]
+    fn test_announced_accounts_are_detected() {
+        let keys = recipient(1, true);
+        let sender = wallet(2).pubkey();
+        let announced = Announced::new(&keys.meta_address(), &sender, 4, 9, 3).unwrap();
+
+        let entry =
+            Announcement::try_deserialize(&mut announced.announcement_data.as_slice()).unwrap();
+        assert_eq!(entry.index, 9);
+        assert_eq!(entry.ciphertext_account, announced.ciphertext_account);
+
+        let ciphertext =
+            CiphertextAccount::try_deserialize(&mut announced.ciphertext_data.as_slice()).unwrap();
+        assert!(ciphertext.finalized);
+        assert_eq!(ciphertext.sender, sender);
+
+        let spending_key = keys
+            .detect(
+                &ciphertext.stealth_pubkey,
+                &ciphertext.ephemeral_pubkey,
+                Some(&ciphertext.mlkem_ciphertext),
+            )
+            .unwrap()
+            .unwrap();
+        assert_eq!(spending_key.pubkey(), announced.payment.stealth_address);
+    }
+
+    /// JSON `value` of an account holding `data`, as `getAccountInfo` and
+    /// `getMultipleAccounts` return it
+    fn account_json(data: &[u8]) -> serde_json::Value {
+        use base64::Engine;
+
+        serde_json::json!({
+            "data": [base64::engine::general_purpose::STANDARD.encode(data), "base64"],
+            "executable": false,
+            "lamports": 1_000_000,
+            "owner": stealth_pq::ID.to_string(),
+            "rentEpoch": 0,
+            "space": data.len(),
+        })
+    }
+
+    /// Mock RPC holding an announcement log with `announced` as its only entry
+    fn rpc_with(announced: &Announced) -> RpcClient {
+        use solana_rpc_client::mock_sender::MocksMap;
+        use solana_rpc_client_api::request::RpcRequest;
+
+        let log = serialize(&stealth_pq::AnnouncementLog {
+            count: 1,
+            bump: 255,
+        });
+        let response = |value| serde_json::json!({ "context": { "slot": 1 }, "value": value });
+
+        let mut mocks = MocksMap::default();
+        mocks.insert(RpcRequest::GetAccountInfo, response(account_json(&log)));
+        for data in [&announced.announcement_data, &announced.ciphertext_data] {
+            mocks.insert(
+                RpcRequest::GetMultipleAccounts,
+                response(serde_json::json!([account_json(data)])),
+            );
+        }
+        RpcClient::new_mock_with_mocks_map("succeeds", mocks)
+    }
+
+    #[tokio::test]
+    async fn test_scanner_detects_payments_to_previous_keys() {
+        use stealth_pq_client::Scanner;
+
+        let current = recipient(1, true);
+        let previous = [recipient(2, true)];
+        let sender = wallet(3).pubkey();
+        let announced = Announced::new(&previous[0].meta_address(), &sender, 0, 0, 4).unwrap();
+
+        // A sender that read the registry before the rotation paid the old keys
+        let page = Scanner::new(&rpc_with(&announced), &current)
+            .next_page()
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(page.payments.is_empty());
+
+        let rpc = rpc_with(&announced);
+        let mut scanner = Scanner::new(&rpc, &current).previous_keys(&previous);
+        let page = scanner.next_page().await.unwrap().unwrap();
+        assert_eq!(page.payments.len(), 1);
+        assert!(page.payments[0].previous_keys);
+        assert_eq!(
+            page.payments[0].spending_key.pubkey(),
+            announced.payment.stealth_address
+        );
+        assert_eq!(scanner.next_index(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_scanner_pauses_and_reports_progress() {
+        use stealth_pq_client::{ScanControl, ScanProgress, Scanner};
+
+        let keys = recipient(1, true);
+        let announced = Announced::new(&keys.meta_address(), &wallet(2).pubkey(), 0, 0, 3).unwrap();
+        let rpc = rpc_with(&announced);
+        let control = ScanControl::default();
+        let mut progress = Vec::new();
+        let mut scanner = Scanner::new(&rpc, &keys)
+            .control(control.clone())
+            .on_progress(|update| progress.push(update));
+
+        control.pause();
+        assert!(
+            tokio::time::timeout(Duration::from_millis(20), scanner.next_page())
+                .await
+                .is_err()
+        );
+
+        // Resuming from elsewhere wakes the waiting scanner
+        let (page, ()) = tokio::join!(scanner.next_page(), async {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+            control.resume();
+        });
+        assert_eq!(page.unwrap().unwrap().payments.len(), 1);
+        drop(scanner);
+        assert_eq!(
+            progress,
+            [ScanProgress {
+                next_index: 1,
+                log_count: 1,
+            }]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_sweep_is_signed_offline() {
+        use solana_rpc_client::mock_sender::MocksMap;
+        use solana_rpc_client_api::request::RpcRequest;
+        use stealth_pq_client::{offline, PartialTransaction};
+
+        let keys = recipient(1, true);
+        let fee_payer = wallet(2);
+        let announced = Announced::new(&keys.meta_address(), &fee_payer.pubkey(), 0, 0, 3).unwrap();
+        let response = |value| serde_json::json!({ "context": { "slot": 1 }, "value": value });
+        let mut mocks = MocksMap::default();
+        mocks.insert(
+            RpcRequest::GetAccountInfo,
+            response(account_json(&announced.announcement_data)),
+        );
+        mocks.insert(
+            RpcRequest::GetMultipleAccounts,
+            response(serde_json::json!([
+                account_json(&announced.ciphertext_data),
+                account_json(&[]),
+            ])),
+        );
+        let rpc = RpcClient::new_mock_with_mocks_map("succeeds", mocks);
+
+        let online = offline::sweep(
+            &rpc,
+            &announced.announcement,
+            &fee_payer.pubkey(),
+            &fee_payer.pubkey(),
+        )
+        .await
+        .unwrap();
+        // The stealth balance plus the rent of both closed accounts
+        let transfer = &online.message().instructions[1].data;
+        assert_eq!(transfer[4..], 3_000_000u64.to_le_bytes());
+
+        let mut offline = PartialTransaction::from_bytes(&online.to_bytes()).unwrap();
+        assert_eq!(offline.sign_stealth(&keys).unwrap(), 1);
+        let mut online = online;
+        assert!(online.sign(&fee_payer));
+        online
+            .combine(&PartialTransaction::from_bytes(&offline.to_bytes()).unwrap())
+            .unwrap();
+        assert!(online.into_transaction().is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_watchtower_alerts() {
+        use solana_rpc_client::mock_sender::MocksMap;
+        use solana_rpc_client_api::request::RpcRequest;
+        use stealth_pq_client::watch::Alert;
+        use stealth_pq_client::Watchtower;
+
+        let sender = wallet(1).pubkey();
+        let watched = Announced::new(&meta_address(2), &sender, 0, 0, 3).unwrap();
+        // A later payment by a sender reusing the watched payment's ephemeral key
+        let reused = Announcement {
+            index: 1,
+            stealth_pubkey: Pubkey::new_unique(),
+            ephemeral_pubkey: watched.payment.ephemeral_pubkey,
+            ..Announcement::default()
+        };
+        let log = serialize(&stealth_pq::AnnouncementLog {
+            count: 2,
+            bump: 255,
+        });
+        let response = |value| serde_json::json!({ "context": { "slot": 1 }, "value": value });
+
+        let mut mocks = MocksMap::default();
+        mocks.insert(
+            RpcRequest::GetMultipleAccounts,
+            response(serde_json::json!([
+                account_json(&[]),
+                account_json(&watched.ciphertext_data),
+            ])),
+        );
+        mocks.insert(
+            RpcRequest::GetMultipleAccounts,
+            response(serde_json::json!([
+                account_json(&watched.announcement_data),
+                account_json(&serialize(&reused)),
+            ])),
+        );
+        // The second check: swept and closed, and nothing new in the log
+        mocks.insert(
+            RpcRequest::GetMultipleAccounts,
+            response(serde_json::json!([null, null])),
+        );
+        mocks.insert(RpcRequest::GetAccountInfo, response(account_json(&log)));
+        let rpc = RpcClient::new_mock_with_mocks_map("succeeds", mocks);
+
+        let stealth_address = watched.payment.stealth_address;
+        let mut watchtower = Watchtower::new(&rpc);
+        watchtower.watch(stealth_address, 0);
+        assert_eq!(
+            watchtower.check().await.unwrap(),
+            [Alert::EphemeralKeyReused {
+                stealth_address,
+                announcement_index: 1,
+                other_address: reused.stealth_pubkey,
+            }]
+        );
+        assert_eq!(watchtower.next_index(), 2);
+
+        assert_eq!(
+            watchtower.check().await.unwrap(),
+            [
+                Alert::FundsMoved {
+                    stealth_address,
+                    before: 1_000_000,
+                    after: 0,
+                },
+                Alert::CiphertextClosed {
+                    stealth_address,
+                    ciphertext_account: watched.ciphertext_account,
+                },
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_airdrop_confirms() {
+        let rpc = RpcClient::new_mock("succeeds".to_string());
+        funded_wallet(&rpc, 1).await.unwrap();
+    }
+}
@@ -1,7 +1,24 @@
 import * as anchor from "@coral-xyz/anchor";
 import { Program, BN } from "@coral-xyz/anchor";
 import { StealthPq } from "../target/types/stealth_pq";
-import { Keypair, LAMPORTS_PER_SOL, PublicKey, SystemProgram } from "@solana/web3.js";
+import {
+  Keypair,
+  LAMPORTS_PER_SOL,
+  PublicKey,
+  Secp256k1Program,
+  SystemProgram,
+  SYSVAR_INSTRUCTIONS_PUBKEY,
+  Transaction,
+} from "@solana/web3.js";
+import {
+  ASSOCIATED_TOKEN_PROGRAM_ID,
+  TOKEN_PROGRAM_ID,
+  createMint,
+  getAccount,
+  getAssociatedTokenAddressSync,
+  getOrCreateAssociatedTokenAccount,
+  mintTo,
+} from "@solana/spl-token";
 import { expect } from "chai";
 
 describe("stealth-pq", () => {
@@ -14,7 +31,20 @@ describe("stealth-pq", () => {
   // Test constants
   const EPHEMERAL_PUBKEY_SIZE = 32;
   const MLKEM_CIPHERTEXT_SIZE = 1088;
-  const CHUNK_SIZE = 512; // Max chunk size per transaction
+  const MLKEM512_CIPHERTEXT_SIZE = 768;
+  const MLKEM1024_CIPHERTEXT_SIZE = 1568;
+  const CHUNK_SIZE = 512; // Bytes of ciphertext written by init_ciphertext
+  const MAX_CHUNK_SIZE = 576; // Capacity of a DataChunk argument
+  const DEFAULT_APP_ID = 0; // Namespace with the original PDA seeds
+  const MLKEM_ENCAPSULATION_KEY_SIZE = 1184;
+  const MAX_MEMO_SIZE = 128;
+  const EVM_META_ADDRESS_SIZE = 66; // ERC-5564 compressed spending || viewing keys
+  const SPONSORED_CLAIM_MIN_BALANCE = 5_000_000; // Lamports a stealth address needs for a sponsored claim
+  const MAX_SPONSORED_CLAIMS_PER_INTERVAL = 5; // Sponsored claims per fee payer per day
+  const VIEW_TAG_OFFSET = 175; // Offset of view_tag in CiphertextAccount data
+  const KEM_VARIANT_ML_KEM_768 = 0;
+  const KEM_VARIANT_ML_KEM_512 = 1;
+  const KEM_VARIANT_ML_KEM_1024 = 2;
 
   // Helper to generate random bytes as Buffer
   function randomBytes(size: number): Buffer {
@@ -25,21 +55,85 @@ describe("stealth-pq", () => {
     return buf;
   }
 
-  // Helper to derive CiphertextAccount PDA
-  function deriveCiphertextPDA(stealthAddress: PublicKey): [PublicKey, number] {
+  // Helper to encode bytes as a fixed-capacity DataChunk argument
+  function toChunk(bytes: Buffer): { len: number; data: number[] } {
+    const data = Buffer.alloc(MAX_CHUNK_SIZE);
+    bytes.copy(data);
+    return { len: bytes.length, data: Array.from(data) };
+  }
+
+  // Helper to derive CiphertextAccount PDA (the app_id seed is omitted for the default namespace)
+  function deriveCiphertextPDA(
+    stealthAddress: PublicKey,
+    appId: number = DEFAULT_APP_ID
+  ): [PublicKey, number] {
+    const seeds = [Buffer.from("ciphertext"), stealthAddress.toBuffer()];
+    if (appId !== DEFAULT_APP_ID) {
+      const appIdSeed = Buffer.alloc(4);
+      appIdSeed.writeUInt32LE(appId);
+      seeds.push(appIdSeed);
+    }
+    return PublicKey.findProgramAddressSync(seeds, program.programId);
+  }
+
+  // Helper to derive StagingBuffer PDA
+  function deriveBufferPDA(authority: PublicKey, bufferId: BN): [PublicKey, number] {
     return PublicKey.findProgramAddressSync(
-      [Buffer.from("ciphertext"), stealthAddress.toBuffer()],
+      [Buffer.from("buffer"), authority.toBuffer(), bufferId.toArrayLike(Buffer, "le", 8)],
       program.programId
     );
   }
 
-  // Helper to perform a complete stealth transfer (init + complete + transfer)
-  async function performStealthTransfer(
+  // Helper to derive the NamespaceCounter PDA for an app namespace
+  function deriveNamespaceCounterPDA(appId: number): [PublicKey, number] {
+    const appIdSeed = Buffer.alloc(4);
+    appIdSeed.writeUInt32LE(appId);
+    return PublicKey.findProgramAddressSync(
+      [Buffer.from("namespace"), appIdSeed],
+      program.programId
+    );
+  }
+
+  // Helper to derive the MetaAddressRegistry PDA of a wallet
+  function deriveMetaAddressPDA(owner: PublicKey): [PublicKey, number] {
+    return PublicKey.findProgramAddressSync(
+      [Buffer.from("meta"), owner.toBuffer()],
+      program.programId
+    );
+  }
+
+  function deriveEvmAttestationPDA(owner: PublicKey): [PublicKey, number] {
+    return PublicKey.findProgramAddressSync(
+      [Buffer.from("evm"), owner.toBuffer()],
+      program.programId
+    );
+  }
+
+  // Helper to derive the global StatsAccount PDA
+  function deriveStatsPDA(): [PublicKey, number] {
+    return PublicKey.findProgramAddressSync([Buffer.from("stats")], program.programId);
+  }
+
+  // Helper to derive the global AnnouncementLog PDA
+  function deriveAnnouncementLogPDA(): [PublicKey, number] {
+    return PublicKey.findProgramAddressSync([Buffer.from("announcement_log")], program.programId);
+  }
+
+  // Helper to derive the Announcement PDA at a log index
+  function deriveAnnouncementPDA(index: BN): [PublicKey, number] {
+    return PublicKey.findProgramAddressSync(
+      [Buffer.from("announcement"), index.toArrayLike(Buffer, "le", 8)],
+      program.programId
+    );
+  }
+
+  // Helper to write a full ciphertext (init + complete), leaving the account unfinalized
+  async function writeCiphertext(
     stealthKeypair: Keypair,
     ephemeralPubkey: Buffer,
     mlkemCiphertext: Buffer,
-    lamports: number
-  ): Promise<void> {
+    viewTag = 0
+  ): Promise<PublicKey> {
     const [ciphertextPDA] = deriveCiphertextPDA(stealthKeypair.publicKey);
 
     // Split ciphertext into chunks
@@ -48,9 +142,18 @@ describe("stealth-pq", () => {
 
     // Step 1: Initialize ciphertext account with first chunk
     await program.methods
-      .initCiphertext(Array.from(ephemeralPubkey), Buffer.from(part1))
+      .initCiphertext(
+        Array.from(ephemeralPubkey),
+        toChunk(part1),
+        null,
+        DEFAULT_APP_ID,
+        0,
+        viewTag,
+        KEM_VARIANT_ML_KEM_768
+      )
       .accounts({
         sender: provider.wallet.publicKey,
+        rentPayer: provider.wallet.publicKey,
         stealthAddress: stealthKeypair.publicKey,
         ciphertextAccount: ciphertextPDA,
         systemProgram: SystemProgram.programId,
@@ -59,14 +162,44 @@ describe("stealth-pq", () => {
 
     // Step 2: Complete ciphertext with remaining data
     await program.methods
-      .completeCiphertext(Buffer.from(part2), CHUNK_SIZE)
+      .completeCiphertext(toChunk(part2), CHUNK_SIZE)
+      .accounts({
+        sender: provider.wallet.publicKey,
+        ciphertextAccount: ciphertextPDA,
+      })
+      .rpc();
+
+    return ciphertextPDA;
+  }
+
+  // Helper to mark a ciphertext account as ready for transfers
+  async function finalizeCiphertext(ciphertextPDA: PublicKey): Promise<void> {
+    await program.methods
+      .finalizeCiphertext()
       .accounts({
         sender: provider.wallet.publicKey,
         ciphertextAccount: ciphertextPDA,
       })
       .rpc();
+  }
+
+  // Helper to perform a complete stealth transfer (init + complete + finalize + transfer)
+  async function performStealthTransfer(
+    stealthKeypair: Keypair,
+    ephemeralPubkey: Buffer,
+    mlkemCiphertext: Buffer,
+    lamports: number,
+    viewTag = 0
+  ): Promise<void> {
+    const ciphertextPDA = await writeCiphertext(
+      stealthKeypair,
+      ephemeralPubkey,
+      mlkemCiphertext,
+      viewTag
+    );
+    await finalizeCiphertext(ciphertextPDA);
 
-    // Step 3: Transfer SOL if specified
+    // Transfer SOL if specified
     if (lamports > 0) {
       await program.methods
         .transferToStealth(new BN(lamports))
@@ -89,9 +222,18 @@ describe("stealth-pq", () => {
       const [ciphertextPDA, bump] = deriveCiphertextPDA(stealthAddress.publicKey);
 
       const tx = await program.methods
-        .initCiphertext(Array.from(ephemeralPubkey), Buffer.from(part1))
+        .initCiphertext(
+          Array.from(ephemeralPubkey),
+          toChunk(part1),
+          null,
+          DEFAULT_APP_ID,
+          0,
+          0,
+          KEM_VARIANT_ML_KEM_768
+        )
         .accounts({
           sender: provider.wallet.publicKey,
+          rentPayer: provider.wallet.publicKey,
           stealthAddress: stealthAddress.publicKey,
           ciphertextAccount: ciphertextPDA,
           systemProgram: SystemProgram.programId,
@@ -107,82 +249,289 @@ describe("stealth-pq", () => {
       expect(Buffer.from(ciphertextAccount.ephemeralPubkey).equals(ephemeralPubkey)).to.be.true;
       expect(ciphertextAccount.bump).to.equal(bump);
       expect(ciphertextAccount.createdAt.toNumber()).to.be.greaterThan(0);
+      expect(ciphertextAccount.expiresAt.toNumber()).to.equal(0);
     });
-  });
 
-  describe("complete_ciphertext", () => {
-    it("stores remaining ciphertext data", async () => {
+    it("stores the sender-chosen expiry hint", async () => {
       const stealthAddress = Keypair.generate();
       const ephemeralPubkey = randomBytes(EPHEMERAL_PUBKEY_SIZE);
-      const fullCiphertext = randomBytes(MLKEM_CIPHERTEXT_SIZE);
-      const part1 = fullCiphertext.slice(0, CHUNK_SIZE);
-      const part2 = fullCiphertext.slice(CHUNK_SIZE);
+      const part1 = randomBytes(CHUNK_SIZE);
+      const expiresAt = new BN(Math.floor(Date.now() / 1000) + 30 * 24 * 60 * 60);
 
       const [ciphertextPDA] = deriveCiphertextPDA(stealthAddress.publicKey);
 
-      // Initialize
       await program.methods
-        .initCiphertext(Array.from(ephemeralPubkey), Buffer.from(part1))
+        .initCiphertext(
+          Array.from(ephemeralPubkey),
+          toChunk(part1),
+          expiresAt,
+          DEFAULT_APP_ID,
+          0,
+          0,
+          KEM_VARIANT_ML_KEM_768
+        )
         .accounts({
           sender: provider.wallet.publicKey,
+          rentPayer: provider.wallet.publicKey,
           stealthAddress: stealthAddress.publicKey,
           ciphertextAccount: ciphertextPDA,
           systemProgram: SystemProgram.programId,
         })
         .rpc();
 
-      // Complete
-      const tx = await program.methods
-        .completeCiphertext(Buffer.from(part2), CHUNK_SIZE)
+      const ciphertextAccount = await program.account.ciphertextAccount.fetch(ciphertextPDA);
+      expect(ciphertextAccount.expiresAt.eq(expiresAt)).to.be.true;
+    });
+
+    it("stores the view tag at a fixed offset for memcmp filters", async () => {
+      const stealthAddress = Keypair.generate();
+      const viewTag = 0xa7;
+
+      await performStealthTransfer(
+        stealthAddress,
+        randomBytes(EPHEMERAL_PUBKEY_SIZE),
+        randomBytes(MLKEM_CIPHERTEXT_SIZE),
+        0,
+        viewTag
+      );
+
+      const [ciphertextPDA] = deriveCiphertextPDA(stealthAddress.publicKey);
+      const ciphertextAccount = await program.account.ciphertextAccount.fetch(ciphertextPDA);
+      expect(ciphertextAccount.viewTag).to.equal(viewTag);
+
+      const matches = await provider.connection.getProgramAccounts(program.programId, {
+        filters: [
+          { memcmp: { offset: VIEW_TAG_OFFSET, bytes: anchor.utils.bytes.bs58.encode([viewTag]) } },
+          { memcmp: { offset: 8, bytes: stealthAddress.publicKey.toBase58() } },
+        ],
+      });
+      expect(matches.map((m) => m.pubkey.toBase58())).to.deep.equal([ciphertextPDA.toBase58()]);
+    });
+
+    it("rejects an expiry in the past", async () => {
+      const stealthAddress = Keypair.generate();
+      const ephemeralPubkey = randomBytes(EPHEMERAL_PUBKEY_SIZE);
+      const part1 = randomBytes(CHUNK_SIZE);
+
+      const [ciphertextPDA] = deriveCiphertextPDA(stealthAddress.publicKey);
+
+      try {
+        await program.methods
+          .initCiphertext(
+            Array.from(ephemeralPubkey),
+            toChunk(part1),
+            new BN(1),
+            DEFAULT_APP_ID,
+            0,
+            0,
+            KEM_VARIANT_ML_KEM_768
+          )
+          .accounts({
+            sender: provider.wallet.publicKey,
+            rentPayer: provider.wallet.publicKey,
+            stealthAddress: stealthAddress.publicKey,
+            ciphertextAccount: ciphertextPDA,
+            systemProgram: SystemProgram.programId,
+          })
+          .rpc();
+
+        expect.fail("Expected error for past expiry");
+      } catch (err: any) {
+        expect(err.toString()).to.include("InvalidExpiry");
+      }
+    });
+
+    it("rejects a chunk whose length exceeds its capacity", async () => {
+      const stealthAddress = Keypair.generate();
+      const ephemeralPubkey = randomBytes(EPHEMERAL_PUBKEY_SIZE);
+      const chunk = toChunk(randomBytes(CHUNK_SIZE));
+      chunk.len = MAX_CHUNK_SIZE + 1;
+
+      const [ciphertextPDA] = deriveCiphertextPDA(stealthAddress.publicKey);
+
+      try {
+        await program.methods
+          .initCiphertext(
+            Array.from(ephemeralPubkey),
+            chunk,
+            null,
+            DEFAULT_APP_ID,
+            0,
+            0,
+            KEM_VARIANT_ML_KEM_768
+          )
+          .accounts({
+            sender: provider.wallet.publicKey,
+            rentPayer: provider.wallet.publicKey,
+            stealthAddress: stealthAddress.publicKey,
+            ciphertextAccount: ciphertextPDA,
+            systemProgram: SystemProgram.programId,
+          })
+          .rpc();
+
+        expect.fail("Expected error for oversized chunk length");
+      } catch (err: any) {
+        expect(err.toString()).to.include("InvalidCiphertextLength");
+      }
+    });
+  });
+
+  describe("funding guard", () => {
+    // init_ciphertext with the instructions sysvar passed, enabling the guard
+    function initWithGuard(stealthKeypair: Keypair) {
+      return program.methods
+        .initCiphertext(
+          Array.from(randomBytes(EPHEMERAL_PUBKEY_SIZE)),
+          toChunk(randomBytes(CHUNK_SIZE)),
+          null,
+          DEFAULT_APP_ID,
+          0,
+          0,
+          KEM_VARIANT_ML_KEM_768
+        )
         .accounts({
           sender: provider.wallet.publicKey,
-          ciphertextAccount: ciphertextPDA,
+          rentPayer: provider.wallet.publicKey,
+          stealthAddress: stealthKeypair.publicKey,
+          ciphertextAccount: deriveCiphertextPDA(stealthKeypair.publicKey)[0],
+          systemProgram: SystemProgram.programId,
+          instructionsSysvar: SYSVAR_INSTRUCTIONS_PUBKEY,
+        });
+    }
+
+    it("accepts an announcement funded in the same transaction", async () => {
+      const stealthKeypair = Keypair.generate();
+      const lamports = 0.01 * LAMPORTS_PER_SOL;
+
+      const tx = new Transaction().add(
+        await initWithGuard(stealthKeypair).instruction(),
+        SystemProgram.transfer({
+          fromPubkey: provider.wallet.publicKey,
+          toPubkey: stealthKeypair.publicKey,
+          lamports,
         })
-        .rpc();
+      );
+      await provider.sendAndConfirm(tx);
 
-      console.log("complete_ciphertext tx:", tx);
+      const stealthBalance = await provider.connection.getBalance(stealthKeypair.publicKey);
+      expect(stealthBalance).to.equal(lamports);
+    });
 
-      // Verify full ciphertext is stored
-      const ciphertextAccount = await program.account.ciphertextAccount.fetch(ciphertextPDA);
-      const storedCiphertext = Buffer.from(ciphertextAccount.mlkemCiphertext);
+    it("rejects an announcement without a funding transfer", async () => {
+      try {
+        await initWithGuard(Keypair.generate()).rpc();
+        expect.fail("Expected error for unfunded announcement");
+      } catch (err: any) {
+        expect(err.toString()).to.include("MissingFundingTransfer");
+      }
+    });
 
-      expect(storedCiphertext.equals(fullCiphertext)).to.be.true;
+    it("doesn't count transfer_to_stealth, which needs a finalized announcement", async () => {
+      const stealthKeypair = Keypair.generate();
+
+      const tx = new Transaction().add(
+        await initWithGuard(stealthKeypair).instruction(),
+        await program.methods
+          .transferToStealth(new BN(0.01 * LAMPORTS_PER_SOL))
+          .accounts({
+            sender: provider.wallet.publicKey,
+            stealthAddress: stealthKeypair.publicKey,
+            ciphertextAccount: deriveCiphertextPDA(stealthKeypair.publicKey)[0],
+            systemProgram: SystemProgram.programId,
+          })
+          .instruction()
+      );
+
+      try {
+        await provider.sendAndConfirm(tx);
+        expect.fail("Expected error for a program transfer as the funding transfer");
+      } catch (err: any) {
+        expect((err.logs ?? []).join("\n")).to.include("MissingFundingTransfer");
+      }
+
+      const stealthBalance = await provider.connection.getBalance(stealthKeypair.publicKey);
+      expect(stealthBalance).to.equal(0);
     });
   });
 
-  describe("transfer_to_stealth", () => {
-    it("transfers SOL to stealth address", async () => {
+  describe("rent sponsorship", () => {
+    it("lets a third party pay the announcement rent", async () => {
+      const sponsor = Keypair.generate();
       const stealthKeypair = Keypair.generate();
-      const ephemeralPubkey = randomBytes(EPHEMERAL_PUBKEY_SIZE);
-      const mlkemCiphertext = randomBytes(MLKEM_CIPHERTEXT_SIZE);
-      const lamports = 0.1 * LAMPORTS_PER_SOL;
-
       const [ciphertextPDA] = deriveCiphertextPDA(stealthKeypair.publicKey);
 
-      // Setup: init and complete ciphertext
-      const part1 = mlkemCiphertext.slice(0, CHUNK_SIZE);
-      const part2 = mlkemCiphertext.slice(CHUNK_SIZE);
+      const airdrop = await provider.connection.requestAirdrop(sponsor.publicKey, LAMPORTS_PER_SOL);
+      await provider.connection.confirmTransaction(airdrop);
+
+      const senderBalanceBefore = await provider.connection.getBalance(provider.wallet.publicKey);
 
       await program.methods
-        .initCiphertext(Array.from(ephemeralPubkey), Buffer.from(part1))
+        .initCiphertext(
+          Array.from(randomBytes(EPHEMERAL_PUBKEY_SIZE)),
+          toChunk(randomBytes(CHUNK_SIZE)),
+          null,
+          DEFAULT_APP_ID,
+          0,
+          0,
+          KEM_VARIANT_ML_KEM_768
+        )
         .accounts({
           sender: provider.wallet.publicKey,
+          rentPayer: sponsor.publicKey,
           stealthAddress: stealthKeypair.publicKey,
           ciphertextAccount: ciphertextPDA,
           systemProgram: SystemProgram.programId,
         })
+        .signers([sponsor])
         .rpc();
 
+      const rent = await provider.connection.getBalance(ciphertextPDA);
+      const sponsorBalance = await provider.connection.getBalance(sponsor.publicKey);
+      expect(sponsorBalance).to.be.at.most(LAMPORTS_PER_SOL - rent);
+
+      // The sender only pays the transaction fee
+      const senderBalanceAfter = await provider.connection.getBalance(provider.wallet.publicKey);
+      expect(senderBalanceBefore - senderBalanceAfter).to.be.lessThan(rent);
+    });
+  });
+
+  describe("app namespaces", () => {
+    it("stores announcements for an app under a separate PDA", async () => {
+      const stealthKeypair = Keypair.generate();
+      const ephemeralPubkey = randomBytes(EPHEMERAL_PUBKEY_SIZE);
+      const part1 = randomBytes(CHUNK_SIZE);
+      const appId = 42;
+      const lamports = 0.01 * LAMPORTS_PER_SOL;
+
+      const [ciphertextPDA] = deriveCiphertextPDA(stealthKeypair.publicKey, appId);
+      const [defaultPDA] = deriveCiphertextPDA(stealthKeypair.publicKey);
+      expect(ciphertextPDA.equals(defaultPDA)).to.be.false;
+
       await program.methods
-        .completeCiphertext(Buffer.from(part2), CHUNK_SIZE)
+        .initCiphertext(
+          Array.from(ephemeralPubkey),
+          toChunk(part1),
+          null,
+          appId,
+          0,
+          0,
+          KEM_VARIANT_ML_KEM_768
+        )
         .accounts({
           sender: provider.wallet.publicKey,
+          rentPayer: provider.wallet.publicKey,
+          stealthAddress: stealthKeypair.publicKey,
           ciphertextAccount: ciphertextPDA,
+          systemProgram: SystemProgram.programId,
         })
         .rpc();
 
-      // Transfer SOL
-      const tx = await program.methods
+      const ciphertextAccount = await program.account.ciphertextAccount.fetch(ciphertextPDA);
+      expect(ciphertextAccount.appId).to.equal(appId);
+
+      // Later instructions derive the seeds from the stored app_id
+      await finalizeCiphertext(ciphertextPDA);
+      await program.methods
         .transferToStealth(new BN(lamports))
         .accounts({
           sender: provider.wallet.publicKey,
@@ -192,73 +541,130 @@ describe("stealth-pq", () => {
         })
         .rpc();
 
-      console.log("transfer_to_stealth tx:", tx);
-
-      // Verify SOL was transferred
       const stealthBalance = await provider.connection.getBalance(stealthKeypair.publicKey);
       expect(stealthBalance).to.equal(lamports);
     });
-  });
 
-  describe("reclaim_rent", () => {
-    it("closes ciphertext account and returns rent to stealth address", async () => {
-      const stealthKeypair = Keypair.generate();
-      const ephemeralPubkey = randomBytes(EPHEMERAL_PUBKEY_SIZE);
-      const mlkemCiphertext = randomBytes(MLKEM_CIPHERTEXT_SIZE);
-      const transferAmount = 0.1 * LAMPORTS_PER_SOL;
+    it("emits sequence-numbered announcement events per namespace", async () => {
+      const appId = 4242;
+      const eventParser = new anchor.EventParser(program.programId, program.coder);
+      const sequences: number[] = [];
 
-      await performStealthTransfer(
-        stealthKeypair,
-        ephemeralPubkey,
-        mlkemCiphertext,
-        transferAmount
-      );
+      for (let i = 0; i < 2; i++) {
+        const stealthKeypair = Keypair.generate();
+        const [ciphertextPDA] = deriveCiphertextPDA(stealthKeypair.publicKey, appId);
 
-      const [ciphertextPDA] = deriveCiphertextPDA(stealthKeypair.publicKey);
+        const tx = await program.methods
+          .initCiphertext(
+            Array.from(randomBytes(EPHEMERAL_PUBKEY_SIZE)),
+            toChunk(randomBytes(CHUNK_SIZE)),
+            null,
+            appId,
+            0,
+            0,
+            KEM_VARIANT_ML_KEM_768
+          )
+          .accounts({
+            sender: provider.wallet.publicKey,
+            rentPayer: provider.wallet.publicKey,
+            stealthAddress: stealthKeypair.publicKey,
+            ciphertextAccount: ciphertextPDA,
+            systemProgram: SystemProgram.programId,
+          })
+          .rpc({ commitment: "confirmed" });
 
-      // Get balance before reclaim
-      const stealthBalanceBefore = await provider.connection.getBalance(stealthKeypair.publicKey);
-      const ciphertextRent = await provider.connection.getBalance(ciphertextPDA);
+        const txInfo = await provider.connection.getTransaction(tx, {
+          commitment: "confirmed",
+          maxSupportedTransactionVersion: 0,
+        });
+        const events = [...eventParser.parseLogs(txInfo!.meta!.logMessages!)];
+        expect(events).to.have.length(1);
+        expect(events[0].name).to.equal("announcementEvent");
+        expect(events[0].data.appId).to.equal(appId);
+        expect(events[0].data.stealthPubkey.equals(stealthKeypair.publicKey)).to.be.true;
+        sequences.push(events[0].data.sequence.toNumber());
+      }
 
-      console.log("Stealth balance before reclaim:", stealthBalanceBefore / LAMPORTS_PER_SOL, "SOL");
-      console.log("Ciphertext account rent:", ciphertextRent / LAMPORTS_PER_SOL, "SOL");
+      expect(sequences).to.deep.equal([1, 2]);
 
-      // Reclaim rent
-      const tx = await program.methods
-        .reclaimRent()
+      const [counterPDA] = deriveNamespaceCounterPDA(appId);
+      const counter = await program.account.namespaceCounter.fetch(counterPDA);
+      expect(counter.sequence.toNumber()).to.equal(2);
+    });
+  });
+
+  describe("complete_ciphertext", () => {
+    it("stores remaining ciphertext data", async () => {
+      const stealthAddress = Keypair.generate();
+      const ephemeralPubkey = randomBytes(EPHEMERAL_PUBKEY_SIZE);
+      const fullCiphertext = randomBytes(MLKEM_CIPHERTEXT_SIZE);
+      const part1 = fullCiphertext.slice(0, CHUNK_SIZE);
+      const part2 = fullCiphertext.slice(CHUNK_SIZE);
+
+      const [ciphertextPDA] = deriveCiphertextPDA(stealthAddress.publicKey);
+
+      // Initialize
+      await program.methods
+        .initCiphertext(
+          Array.from(ephemeralPubkey),
+          toChunk(part1),
+          null,
+          DEFAULT_APP_ID,
+          0,
+          0,
+          KEM_VARIANT_ML_KEM_768
+        )
         .accounts({
-          stealthSigner: stealthKeypair.publicKey,
+          sender: provider.wallet.publicKey,
+          rentPayer: provider.wallet.publicKey,
+          stealthAddress: stealthAddress.publicKey,
           ciphertextAccount: ciphertextPDA,
+          systemProgram: SystemProgram.programId,
         })
-        .signers([stealthKeypair])
         .rpc();
 
-      console.log("reclaim_rent tx:", tx);
+      // Complete
+      const tx = await program.methods
+        .completeCiphertext(toChunk(part2), CHUNK_SIZE)
+        .accounts({
+          sender: provider.wallet.publicKey,
+          ciphertextAccount: ciphertextPDA,
+        })
+        .rpc();
 
-      // Verify ciphertext account was closed
-      const ciphertextAccountInfo = await provider.connection.getAccountInfo(ciphertextPDA);
-      expect(ciphertextAccountInfo).to.be.null;
+      console.log("complete_ciphertext tx:", tx);
 
-      // Verify rent was returned
-      const stealthBalanceAfter = await provider.connection.getBalance(stealthKeypair.publicKey);
-      console.log("Stealth balance after reclaim:", stealthBalanceAfter / LAMPORTS_PER_SOL, "SOL");
+      // Verify full ciphertext is stored
+      const ciphertextAccount = await program.account.ciphertextAccount.fetch(ciphertextPDA);
+      const storedCiphertext = Buffer.from(ciphertextAccount.mlkemCiphertext);
 
-      expect(stealthBalanceAfter).to.be.greaterThan(stealthBalanceBefore);
+      expect(storedCiphertext.equals(fullCiphertext)).to.be.true;
+      expect(ciphertextAccount.sender.toBase58()).to.equal(provider.wallet.publicKey.toBase58());
+      expect(ciphertextAccount.finalized).to.be.false;
     });
 
-    it("fails if non-stealth-address tries to reclaim", async () => {
+    it("rejects writes from anyone but the original sender", async () => {
       const stealthKeypair = Keypair.generate();
-      const ephemeralPubkey = randomBytes(EPHEMERAL_PUBKEY_SIZE);
-      const mlkemCiphertext = randomBytes(MLKEM_CIPHERTEXT_SIZE);
-
-      await performStealthTransfer(stealthKeypair, ephemeralPubkey, mlkemCiphertext, 0);
-
-      const [ciphertextPDA] = deriveCiphertextPDA(stealthKeypair.publicKey);
+      await program.methods
+        .initCiphertext(
+          Array.from(randomBytes(EPHEMERAL_PUBKEY_SIZE)),
+          toChunk(randomBytes(CHUNK_SIZE)),
+          null,
+          DEFAULT_APP_ID,
+          0,
+          0,
+          KEM_VARIANT_ML_KEM_768
+        )
+        .accounts({
+          sender: provider.wallet.publicKey,
+          rentPayer: provider.wallet.publicKey,
+          stealthAddress: stealthKeypair.publicKey,
+          ciphertextAccount: deriveCiphertextPDA(stealthKeypair.publicKey)[0],
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
 
-      // Try to reclaim with a different keypair
       const attacker = Keypair.generate();
-
-      // Airdrop some SOL to attacker
       const airdropSig = await provider.connection.requestAirdrop(
         attacker.publicKey,
         0.1 * LAMPORTS_PER_SOL
@@ -267,19 +673,1682 @@ describe("stealth-pq", () => {
 
       try {
         await program.methods
-          .reclaimRent()
+          .completeCiphertext(toChunk(randomBytes(MLKEM_CIPHERTEXT_SIZE - CHUNK_SIZE)), CHUNK_SIZE)
           .accounts({
-            stealthSigner: attacker.publicKey,
-            ciphertextAccount: ciphertextPDA,
+            sender: attacker.publicKey,
+            ciphertextAccount: deriveCiphertextPDA(stealthKeypair.publicKey)[0],
           })
           .signers([attacker])
           .rpc();
 
-        expect.fail("Expected error for unauthorized reclaim");
+        expect.fail("Expected error for write by another signer");
       } catch (err: any) {
-        expect(err.toString()).to.include("ConstraintSeeds");
+        expect(err.toString()).to.include("ConstraintHasOne");
       }
     });
+
+    it("rejects writes after finalization", async () => {
+      const stealthKeypair = Keypair.generate();
+      await performStealthTransfer(
+        stealthKeypair,
+        randomBytes(EPHEMERAL_PUBKEY_SIZE),
+        randomBytes(MLKEM_CIPHERTEXT_SIZE),
+        0
+      );
+
+      try {
+        await program.methods
+          .completeCiphertext(toChunk(randomBytes(MLKEM_CIPHERTEXT_SIZE - CHUNK_SIZE)), CHUNK_SIZE)
+          .accounts({
+            sender: provider.wallet.publicKey,
+            ciphertextAccount: deriveCiphertextPDA(stealthKeypair.publicKey)[0],
+          })
+          .rpc();
+
+        expect.fail("Expected error for write after finalization");
+      } catch (err: any) {
+        expect(err.toString()).to.include("CiphertextFinalized");
+      }
+    });
+  });
+
+  describe("ML-KEM variants", () => {
+    async function initVariant(stealthAddress: PublicKey, part1: Buffer, kemVariant: number) {
+      await program.methods
+        .initCiphertext(
+          Array.from(randomBytes(EPHEMERAL_PUBKEY_SIZE)),
+          toChunk(part1),
+          null,
+          DEFAULT_APP_ID,
+          0,
+          0,
+          kemVariant
+        )
+        .accounts({
+          sender: provider.wallet.publicKey,
+          rentPayer: provider.wallet.publicKey,
+          stealthAddress,
+          ciphertextAccount: deriveCiphertextPDA(stealthAddress)[0],
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+    }
+
+    async function complete(stealthAddress: PublicKey, chunk: Buffer, offset: number) {
+      await program.methods
+        .completeCiphertext(toChunk(chunk), offset)
+        .accounts({
+          sender: provider.wallet.publicKey,
+          ciphertextAccount: deriveCiphertextPDA(stealthAddress)[0],
+        })
+        .rpc();
+    }
+
+    it("stores an ML-KEM-1024 ciphertext over two completes", async () => {
+      const stealthAddress = Keypair.generate().publicKey;
+      const fullCiphertext = randomBytes(MLKEM1024_CIPHERTEXT_SIZE);
+      const secondEnd = CHUNK_SIZE + MAX_CHUNK_SIZE;
+
+      await initVariant(stealthAddress, fullCiphertext.slice(0, CHUNK_SIZE), KEM_VARIANT_ML_KEM_1024);
+      await complete(stealthAddress, fullCiphertext.slice(CHUNK_SIZE, secondEnd), CHUNK_SIZE);
+      await complete(stealthAddress, fullCiphertext.slice(secondEnd), secondEnd);
+
+      const [ciphertextPDA] = deriveCiphertextPDA(stealthAddress);
+      const ciphertextAccount = await program.account.ciphertextAccount.fetch(ciphertextPDA);
+      expect(ciphertextAccount.kemVariant).to.equal(KEM_VARIANT_ML_KEM_1024);
+      expect(Buffer.from(ciphertextAccount.mlkemCiphertext).equals(fullCiphertext)).to.be.true;
+    });
+
+    it("sizes ML-KEM-512 accounts for the smaller ciphertext", async () => {
+      const small = Keypair.generate().publicKey;
+      const standard = Keypair.generate().publicKey;
+      await initVariant(small, randomBytes(CHUNK_SIZE), KEM_VARIANT_ML_KEM_512);
+      await initVariant(standard, randomBytes(CHUNK_SIZE), KEM_VARIANT_ML_KEM_768);
+
+      const smallInfo = await provider.connection.getAccountInfo(deriveCiphertextPDA(small)[0]);
+      const standardInfo = await provider.connection.getAccountInfo(deriveCiphertextPDA(standard)[0]);
+      expect(standardInfo!.data.length - smallInfo!.data.length).to.equal(
+        MLKEM_CIPHERTEXT_SIZE - MLKEM512_CIPHERTEXT_SIZE
+      );
+      expect(smallInfo!.lamports).to.be.lessThan(standardInfo!.lamports);
+    });
+
+    it("rejects writes past the declared variant's ciphertext size", async () => {
+      const stealthAddress = Keypair.generate().publicKey;
+      await initVariant(stealthAddress, randomBytes(CHUNK_SIZE), KEM_VARIANT_ML_KEM_512);
+
+      try {
+        await complete(stealthAddress, randomBytes(MLKEM_CIPHERTEXT_SIZE - CHUNK_SIZE), CHUNK_SIZE);
+        expect.fail("Expected error for an MLKEM768-sized write to an MLKEM512 account");
+      } catch (err: any) {
+        expect(err.toString()).to.include("InvalidCiphertextLength");
+      }
+
+      await complete(stealthAddress, randomBytes(MLKEM512_CIPHERTEXT_SIZE - CHUNK_SIZE), CHUNK_SIZE);
+    });
+
+    it("rejects an unknown variant", async () => {
+      try {
+        await initVariant(Keypair.generate().publicKey, randomBytes(CHUNK_SIZE), 3);
+        expect.fail("Expected error for an unknown KEM variant");
+      } catch (err: any) {
+        expect(err.toString()).to.include("InvalidKemVariant");
+      }
+    });
+  });
+
+  describe("set_return_address", () => {
+    it("stores the encrypted return address", async () => {
+      const stealthKeypair = Keypair.generate();
+      const encryptedReturnAddress = randomBytes(32);
+
+      await writeCiphertext(
+        stealthKeypair,
+        randomBytes(EPHEMERAL_PUBKEY_SIZE),
+        randomBytes(MLKEM_CIPHERTEXT_SIZE)
+      );
+
+      const [ciphertextPDA] = deriveCiphertextPDA(stealthKeypair.publicKey);
+
+      await program.methods
+        .setReturnAddress(Array.from(encryptedReturnAddress))
+        .accounts({
+          sender: provider.wallet.publicKey,
+          ciphertextAccount: ciphertextPDA,
+        })
+        .rpc();
+
+      const ciphertextAccount = await program.account.ciphertextAccount.fetch(ciphertextPDA);
+      expect(Buffer.from(ciphertextAccount.encryptedReturnAddress).equals(encryptedReturnAddress)).to.be
+        .true;
+    });
+  });
+
+  describe("set_payload_tag", () => {
+    it("stores the AEAD tag over the encrypted metadata", async () => {
+      const stealthKeypair = Keypair.generate();
+      const payloadTag = randomBytes(16);
+
+      await writeCiphertext(
+        stealthKeypair,
+        randomBytes(EPHEMERAL_PUBKEY_SIZE),
+        randomBytes(MLKEM_CIPHERTEXT_SIZE)
+      );
+
+      const [ciphertextPDA] = deriveCiphertextPDA(stealthKeypair.publicKey);
+
+      await program.methods
+        .setPayloadTag(Array.from(payloadTag))
+        .accounts({
+          sender: provider.wallet.publicKey,
+          ciphertextAccount: ciphertextPDA,
+        })
+        .rpc();
+
+      const ciphertextAccount = await program.account.ciphertextAccount.fetch(ciphertextPDA);
+      expect(Buffer.from(ciphertextAccount.payloadTag).equals(payloadTag)).to.be.true;
+    });
+  });
+
+  describe("set_memo", () => {
+    function setMemo(stealthAddress: PublicKey, encryptedMemo: Buffer, len = encryptedMemo.length) {
+      const padded = Buffer.alloc(MAX_MEMO_SIZE);
+      encryptedMemo.copy(padded);
+      return program.methods
+        .setMemo(Array.from(padded), len)
+        .accounts({
+          sender: provider.wallet.publicKey,
+          ciphertextAccount: deriveCiphertextPDA(stealthAddress)[0],
+        })
+        .rpc();
+    }
+
+    it("stores the encrypted memo until it is replaced", async () => {
+      const stealthKeypair = Keypair.generate();
+      await writeCiphertext(
+        stealthKeypair,
+        randomBytes(EPHEMERAL_PUBKEY_SIZE),
+        randomBytes(MLKEM_CIPHERTEXT_SIZE)
+      );
+      const [ciphertextPDA] = deriveCiphertextPDA(stealthKeypair.publicKey);
+
+      const memo = randomBytes(MAX_MEMO_SIZE);
+      await setMemo(stealthKeypair.publicKey, memo);
+      let ciphertextAccount = await program.account.ciphertextAccount.fetch(ciphertextPDA);
+      expect(ciphertextAccount.memoLen).to.equal(MAX_MEMO_SIZE);
+      expect(Buffer.from(ciphertextAccount.encryptedMemo).equals(memo)).to.be.true;
+
+      // A shorter memo replaces the old one, padding included
+      const shorter = randomBytes(24);
+      await setMemo(stealthKeypair.publicKey, shorter);
+      ciphertextAccount = await program.account.ciphertextAccount.fetch(ciphertextPDA);
+      const stored = Buffer.from(ciphertextAccount.encryptedMemo);
+      expect(ciphertextAccount.memoLen).to.equal(shorter.length);
+      expect(stored.subarray(0, shorter.length).equals(shorter)).to.be.true;
+      expect(stored.subarray(shorter.length).every((b) => b === 0)).to.be.true;
+    });
+
+    it("rejects memos longer than the maximum size", async () => {
+      const stealthKeypair = Keypair.generate();
+      await writeCiphertext(
+        stealthKeypair,
+        randomBytes(EPHEMERAL_PUBKEY_SIZE),
+        randomBytes(MLKEM_CIPHERTEXT_SIZE)
+      );
+
+      try {
+        await setMemo(stealthKeypair.publicKey, randomBytes(MAX_MEMO_SIZE), MAX_MEMO_SIZE + 1);
+        expect.fail("Expected error for an oversized memo");
+      } catch (err: any) {
+        expect(err.toString()).to.include("InvalidMemoLength");
+      }
+    });
+
+    it("rejects memos after finalization", async () => {
+      const stealthKeypair = Keypair.generate();
+      await performStealthTransfer(
+        stealthKeypair,
+        randomBytes(EPHEMERAL_PUBKEY_SIZE),
+        randomBytes(MLKEM_CIPHERTEXT_SIZE),
+        0
+      );
+
+      try {
+        await setMemo(stealthKeypair.publicKey, randomBytes(16));
+        expect.fail("Expected error for a memo after finalization");
+      } catch (err: any) {
+        expect(err.toString()).to.include("CiphertextFinalized");
+      }
+    });
+  });
+
+  describe("write_extensions", () => {
+    it("grows the account and stores TLV entries", async () => {
+      const stealthKeypair = Keypair.generate();
+
+      await writeCiphertext(
+        stealthKeypair,
+        randomBytes(EPHEMERAL_PUBKEY_SIZE),
+        randomBytes(MLKEM_CIPHERTEXT_SIZE)
+      );
+
+      const [ciphertextPDA] = deriveCiphertextPDA(stealthKeypair.publicKey);
+      const sizeBefore = (await provider.connection.getAccountInfo(ciphertextPDA))!.data.length;
+
+      // type 0x01 (encrypted amount), length 8, value
+      const entry = Buffer.concat([Buffer.from([0x01, 8, 0]), randomBytes(8)]);
+
+      await program.methods
+        .writeExtensions(toChunk(entry), 0)
+        .accounts({
+          sender: provider.wallet.publicKey,
+          ciphertextAccount: ciphertextPDA,
+        })
+        .rpc();
+
+      const ciphertextAccount = await program.account.ciphertextAccount.fetch(ciphertextPDA);
+      expect(Buffer.from(ciphertextAccount.extensions).equals(entry)).to.be.true;
+
+      const sizeAfter = (await provider.connection.getAccountInfo(ciphertextPDA))!.data.length;
+      expect(sizeAfter).to.equal(sizeBefore + entry.length);
+    });
+
+    it("rejects writes past the extension capacity", async () => {
+      const stealthKeypair = Keypair.generate();
+
+      await writeCiphertext(
+        stealthKeypair,
+        randomBytes(EPHEMERAL_PUBKEY_SIZE),
+        randomBytes(MLKEM_CIPHERTEXT_SIZE)
+      );
+
+      const [ciphertextPDA] = deriveCiphertextPDA(stealthKeypair.publicKey);
+
+      try {
+        await program.methods
+          .writeExtensions(toChunk(randomBytes(16)), 500)
+          .accounts({
+            sender: provider.wallet.publicKey,
+            ciphertextAccount: ciphertextPDA,
+          })
+          .rpc();
+
+        expect.fail("Expected error for write past extension capacity");
+      } catch (err: any) {
+        expect(err.toString()).to.include("InvalidExtensionLength");
+      }
+    });
+  });
+
+  describe("transfer_to_stealth", () => {
+    it("transfers SOL to stealth address", async () => {
+      const stealthKeypair = Keypair.generate();
+      const ephemeralPubkey = randomBytes(EPHEMERAL_PUBKEY_SIZE);
+      const mlkemCiphertext = randomBytes(MLKEM_CIPHERTEXT_SIZE);
+      const lamports = 0.1 * LAMPORTS_PER_SOL;
+
+      const [ciphertextPDA] = deriveCiphertextPDA(stealthKeypair.publicKey);
+
+      // Setup: init and complete ciphertext
+      const part1 = mlkemCiphertext.slice(0, CHUNK_SIZE);
+      const part2 = mlkemCiphertext.slice(CHUNK_SIZE);
+
+      await program.methods
+        .initCiphertext(
+          Array.from(ephemeralPubkey),
+          toChunk(part1),
+          null,
+          DEFAULT_APP_ID,
+          0,
+          0,
+          KEM_VARIANT_ML_KEM_768
+        )
+        .accounts({
+          sender: provider.wallet.publicKey,
+          rentPayer: provider.wallet.publicKey,
+          stealthAddress: stealthKeypair.publicKey,
+          ciphertextAccount: ciphertextPDA,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+
+      await program.methods
+        .completeCiphertext(toChunk(part2), CHUNK_SIZE)
+        .accounts({
+          sender: provider.wallet.publicKey,
+          ciphertextAccount: ciphertextPDA,
+        })
+        .rpc();
+
+      await finalizeCiphertext(ciphertextPDA);
+
+      // Transfer SOL
+      const tx = await program.methods
+        .transferToStealth(new BN(lamports))
+        .accounts({
+          sender: provider.wallet.publicKey,
+          stealthAddress: stealthKeypair.publicKey,
+          ciphertextAccount: ciphertextPDA,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+
+      console.log("transfer_to_stealth tx:", tx);
+
+      // Verify SOL was transferred
+      const stealthBalance = await provider.connection.getBalance(stealthKeypair.publicKey);
+      expect(stealthBalance).to.equal(lamports);
+    });
+
+    it("rejects transfers before the ciphertext is finalized", async () => {
+      const stealthKeypair = Keypair.generate();
+      const ciphertextPDA = await writeCiphertext(
+        stealthKeypair,
+        randomBytes(EPHEMERAL_PUBKEY_SIZE),
+        randomBytes(MLKEM_CIPHERTEXT_SIZE)
+      );
+
+      try {
+        await program.methods
+          .transferToStealth(new BN(0.01 * LAMPORTS_PER_SOL))
+          .accounts({
+            sender: provider.wallet.publicKey,
+            stealthAddress: stealthKeypair.publicKey,
+            ciphertextAccount: ciphertextPDA,
+            systemProgram: SystemProgram.programId,
+          })
+          .rpc();
+
+        expect.fail("Expected error for transfer to an unfinalized announcement");
+      } catch (err: any) {
+        expect(err.toString()).to.include("CiphertextNotFinalized");
+      }
+    });
+  });
+
+  describe("batch_transfer_to_stealth", () => {
+    async function announce(finalize = true): Promise<{ stealth: PublicKey; pda: PublicKey }> {
+      const stealthKeypair = Keypair.generate();
+      const pda = await writeCiphertext(
+        stealthKeypair,
+        randomBytes(EPHEMERAL_PUBKEY_SIZE),
+        randomBytes(MLKEM_CIPHERTEXT_SIZE)
+      );
+      if (finalize) {
+        await finalizeCiphertext(pda);
+      }
+      return { stealth: stealthKeypair.publicKey, pda };
+    }
+
+    function batch(recipients: { stealth: PublicKey; pda: PublicKey }[], amounts: number[]) {
+      return program.methods
+        .batchTransferToStealth(amounts.map((lamports) => new BN(lamports)))
+        .accounts({
+          sender: provider.wallet.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .remainingAccounts(
+          recipients.flatMap(({ stealth, pda }) => [
+            { pubkey: stealth, isSigner: false, isWritable: true },
+            { pubkey: pda, isSigner: false, isWritable: false },
+          ])
+        );
+    }
+
+    it("pays every recipient in one transaction", async () => {
+      const recipients = [await announce(), await announce(), await announce()];
+      const amounts = [0.01, 0.02, 0.03].map((sol) => sol * LAMPORTS_PER_SOL);
+
+      await batch(recipients, amounts).rpc();
+
+      for (const [i, { stealth }] of recipients.entries()) {
+        expect(await provider.connection.getBalance(stealth)).to.equal(amounts[i]);
+      }
+    });
+
+    it("pays nobody if any announcement is not finalized", async () => {
+      const recipients = [await announce(), await announce(false)];
+
+      try {
+        await batch(recipients, [0.01 * LAMPORTS_PER_SOL, 0.01 * LAMPORTS_PER_SOL]).rpc();
+        expect.fail("Expected error for a batch with an unfinalized announcement");
+      } catch (err: any) {
+        expect(err.toString()).to.include("CiphertextNotFinalized");
+      }
+
+      expect(await provider.connection.getBalance(recipients[0].stealth)).to.equal(0);
+    });
+
+    it("rejects a ciphertext account that belongs to another recipient", async () => {
+      const [first, second] = [await announce(), await announce()];
+
+      try {
+        await batch([{ stealth: first.stealth, pda: second.pda }], [0.01 * LAMPORTS_PER_SOL]).rpc();
+        expect.fail("Expected error for a mismatched ciphertext account");
+      } catch (err: any) {
+        expect(err.toString()).to.include("ConstraintSeeds");
+      }
+    });
+
+    it("rejects amounts that don't match the account pairs", async () => {
+      const recipient = await announce();
+
+      try {
+        await batch([recipient], [0.01 * LAMPORTS_PER_SOL, 0.01 * LAMPORTS_PER_SOL]).rpc();
+        expect.fail("Expected error for a malformed batch");
+      } catch (err: any) {
+        expect(err.toString()).to.include("InvalidBatch");
+      }
+    });
+  });
+
+  describe("init_ciphertext_and_fund", () => {
+    it("announces and pays in two transactions", async () => {
+      const stealthKeypair = Keypair.generate();
+      const mlkemCiphertext = randomBytes(MLKEM_CIPHERTEXT_SIZE);
+      const lamports = 0.02 * LAMPORTS_PER_SOL;
+      const [ciphertextPDA] = deriveCiphertextPDA(stealthKeypair.publicKey);
+
+      await program.methods
+        .initCiphertextAndFund(
+          Array.from(randomBytes(EPHEMERAL_PUBKEY_SIZE)),
+          toChunk(mlkemCiphertext.slice(0, CHUNK_SIZE)),
+          null,
+          DEFAULT_APP_ID,
+          0,
+          0,
+          new BN(lamports),
+          KEM_VARIANT_ML_KEM_768
+        )
+        .accounts({
+          sender: provider.wallet.publicKey,
+          rentPayer: provider.wallet.publicKey,
+          stealthAddress: stealthKeypair.publicKey,
+          ciphertextAccount: ciphertextPDA,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+
+      expect(await provider.connection.getBalance(stealthKeypair.publicKey)).to.equal(lamports);
+      expect((await program.account.ciphertextAccount.fetch(ciphertextPDA)).finalized).to.be.false;
+
+      await program.methods
+        .completeAndFinalize(toChunk(mlkemCiphertext.slice(CHUNK_SIZE)), CHUNK_SIZE)
+        .accounts({
+          sender: provider.wallet.publicKey,
+          ciphertextAccount: ciphertextPDA,
+        })
+        .rpc();
+
+      const ciphertextAccount = await program.account.ciphertextAccount.fetch(ciphertextPDA);
+      expect(ciphertextAccount.finalized).to.be.true;
+      expect(Buffer.from(ciphertextAccount.mlkemCiphertext).equals(mlkemCiphertext)).to.be.true;
+    });
+
+    it("rejects a zero amount", async () => {
+      const stealthKeypair = Keypair.generate();
+
+      try {
+        await program.methods
+          .initCiphertextAndFund(
+            Array.from(randomBytes(EPHEMERAL_PUBKEY_SIZE)),
+            toChunk(randomBytes(CHUNK_SIZE)),
+            null,
+            DEFAULT_APP_ID,
+            0,
+            0,
+            new BN(0),
+            KEM_VARIANT_ML_KEM_768
+          )
+          .accounts({
+            sender: provider.wallet.publicKey,
+            rentPayer: provider.wallet.publicKey,
+            stealthAddress: stealthKeypair.publicKey,
+            ciphertextAccount: deriveCiphertextPDA(stealthKeypair.publicKey)[0],
+            systemProgram: SystemProgram.programId,
+          })
+          .rpc();
+
+        expect.fail("Expected error for zero amount");
+      } catch (err: any) {
+        expect(err.toString()).to.include("ZeroTransferAmount");
+      }
+
+      // Nothing is left behind
+      const [ciphertextPDA] = deriveCiphertextPDA(stealthKeypair.publicKey);
+      expect(await provider.connection.getAccountInfo(ciphertextPDA)).to.be.null;
+    });
+  });
+
+  describe("transfer_spl_to_stealth", () => {
+    const payer = (provider.wallet as anchor.Wallet).payer;
+    let mint: PublicKey;
+    let senderTokenAccount: PublicKey;
+
+    before(async () => {
+      mint = await createMint(provider.connection, payer, payer.publicKey, null, 6);
+      const account = await getOrCreateAssociatedTokenAccount(
+        provider.connection,
+        payer,
+        mint,
+        payer.publicKey
+      );
+      senderTokenAccount = account.address;
+      await mintTo(provider.connection, payer, mint, senderTokenAccount, payer, 1_000_000);
+    });
+
+    function transferSpl(stealthAddress: PublicKey, amount: number) {
+      const [ciphertextPDA] = deriveCiphertextPDA(stealthAddress);
+      return program.methods
+        .transferSplToStealth(new BN(amount))
+        .accounts({
+          sender: provider.wallet.publicKey,
+          stealthAddress,
+          ciphertextAccount: ciphertextPDA,
+          mint,
+          senderTokenAccount,
+          stealthTokenAccount: getAssociatedTokenAddressSync(mint, stealthAddress, true),
+          tokenProgram: TOKEN_PROGRAM_ID,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+    }
+
+    it("creates the stealth token account and transfers tokens", async () => {
+      const stealthKeypair = Keypair.generate();
+      await performStealthTransfer(
+        stealthKeypair,
+        randomBytes(EPHEMERAL_PUBKEY_SIZE),
+        randomBytes(MLKEM_CIPHERTEXT_SIZE),
+        0
+      );
+
+      await transferSpl(stealthKeypair.publicKey, 1_000);
+      // A second payment reuses the token account
+      await transferSpl(stealthKeypair.publicKey, 500);
+
+      const stealthTokenAccount = await getAccount(
+        provider.connection,
+        getAssociatedTokenAddressSync(mint, stealthKeypair.publicKey, true)
+      );
+      expect(stealthTokenAccount.owner.toBase58()).to.equal(stealthKeypair.publicKey.toBase58());
+      expect(Number(stealthTokenAccount.amount)).to.equal(1_500);
+
+      // The recipient needs no SOL to hold the tokens
+      const stealthBalance = await provider.connection.getBalance(stealthKeypair.publicKey);
+      expect(stealthBalance).to.equal(0);
+    });
+
+    it("only pays into the token account recorded in the announcement", async () => {
+      const stealthKeypair = Keypair.generate();
+      const ciphertextPDA = await writeCiphertext(
+        stealthKeypair,
+        randomBytes(EPHEMERAL_PUBKEY_SIZE),
+        randomBytes(MLKEM_CIPHERTEXT_SIZE)
+      );
+
+      // type 0x04 (token account), length 32, the stealth address's ATA for `mint`
+      const tokenAccount = getAssociatedTokenAddressSync(mint, stealthKeypair.publicKey, true);
+      await program.methods
+        .writeExtensions(toChunk(Buffer.concat([Buffer.from([0x04, 32, 0]), tokenAccount.toBuffer()])), 0)
+        .accounts({
+          sender: provider.wallet.publicKey,
+          ciphertextAccount: ciphertextPDA,
+        })
+        .rpc();
+      await finalizeCiphertext(ciphertextPDA);
+
+      // The sender pays the recorded token account's rent
+      const senderBalanceBefore = await provider.connection.getBalance(provider.wallet.publicKey);
+      await transferSpl(stealthKeypair.publicKey, 1_000);
+      const rent = await provider.connection.getBalance(tokenAccount);
+      const senderBalanceAfter = await provider.connection.getBalance(provider.wallet.publicKey);
+      expect(senderBalanceBefore - senderBalanceAfter).to.be.at.least(rent);
+      expect(Number((await getAccount(provider.connection, tokenAccount)).amount)).to.equal(1_000);
+
+      // Tokens of another mint would land in a token account the recipient isn't told about
+      const otherMint = await createMint(provider.connection, payer, payer.publicKey, null, 6);
+      const otherSenderAccount = await getOrCreateAssociatedTokenAccount(
+        provider.connection,
+        payer,
+        otherMint,
+        payer.publicKey
+      );
+      await mintTo(provider.connection, payer, otherMint, otherSenderAccount.address, payer, 1_000);
+
+      try {
+        await program.methods
+          .transferSplToStealth(new BN(1_000))
+          .accounts({
+            sender: provider.wallet.publicKey,
+            stealthAddress: stealthKeypair.publicKey,
+            ciphertextAccount: ciphertextPDA,
+            mint: otherMint,
+            senderTokenAccount: otherSenderAccount.address,
+            stealthTokenAccount: getAssociatedTokenAddressSync(otherMint, stealthKeypair.publicKey, true),
+            tokenProgram: TOKEN_PROGRAM_ID,
+            associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+            systemProgram: SystemProgram.programId,
+          })
+          .rpc();
+        expect.fail("Expected error for a token account not in the announcement");
+      } catch (err: any) {
+        expect(err.toString()).to.include("TokenAccountMismatch");
+      }
+    });
+
+    it("rejects a zero amount", async () => {
+      const stealthKeypair = Keypair.generate();
+      await performStealthTransfer(
+        stealthKeypair,
+        randomBytes(EPHEMERAL_PUBKEY_SIZE),
+        randomBytes(MLKEM_CIPHERTEXT_SIZE),
+        0
+      );
+
+      try {
+        await transferSpl(stealthKeypair.publicKey, 0);
+        expect.fail("Expected error for zero amount");
+      } catch (err: any) {
+        expect(err.toString()).to.include("ZeroTransferAmount");
+      }
+    });
+  });
+
+  describe("stats", () => {
+    const [statsPDA] = deriveStatsPDA();
+
+    before(async () => {
+      await program.methods
+        .initStats()
+        .accounts({
+          payer: provider.wallet.publicKey,
+          stats: statsPDA,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+    });
+
+    it("counts only transfers that opt in", async () => {
+      const optedIn = Keypair.generate();
+      const optedOut = Keypair.generate();
+      const lamports = 0.01 * LAMPORTS_PER_SOL;
+
+      for (const keypair of [optedIn, optedOut]) {
+        await performStealthTransfer(
+          keypair,
+          randomBytes(EPHEMERAL_PUBKEY_SIZE),
+          randomBytes(MLKEM_CIPHERTEXT_SIZE),
+          0
+        );
+      }
+
+      const before = await program.account.statsAccount.fetch(statsPDA);
+
+      await program.methods
+        .transferToStealth(new BN(lamports))
+        .accounts({
+          sender: provider.wallet.publicKey,
+          stealthAddress: optedIn.publicKey,
+          ciphertextAccount: deriveCiphertextPDA(optedIn.publicKey)[0],
+          systemProgram: SystemProgram.programId,
+          stats: statsPDA,
+        })
+        .rpc();
+
+      await program.methods
+        .transferToStealth(new BN(lamports))
+        .accounts({
+          sender: provider.wallet.publicKey,
+          stealthAddress: optedOut.publicKey,
+          ciphertextAccount: deriveCiphertextPDA(optedOut.publicKey)[0],
+          systemProgram: SystemProgram.programId,
+          stats: null,
+        })
+        .rpc();
+
+      const after = await program.account.statsAccount.fetch(statsPDA);
+      expect(after.transferCount.sub(before.transferCount).toNumber()).to.equal(1);
+      expect(after.totalLamports.sub(before.totalLamports).toNumber()).to.equal(lamports);
+    });
+
+    it("returns a snapshot of the counters", async () => {
+      const stats = await program.account.statsAccount.fetch(statsPDA);
+      const snapshot = await program.methods.snapshotStats().accounts({ stats: statsPDA }).view();
+
+      expect(snapshot.transferCount.eq(stats.transferCount)).to.be.true;
+      expect(snapshot.totalLamports.eq(stats.totalLamports)).to.be.true;
+      expect(snapshot.slot.toNumber()).to.be.greaterThan(0);
+    });
+  });
+
+  describe("announcement log", () => {
+    const [logPDA] = deriveAnnouncementLogPDA();
+
+    before(async () => {
+      await program.methods
+        .initAnnouncementLog()
+        .accounts({
+          payer: provider.wallet.publicKey,
+          announcementLog: logPDA,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+    });
+
+    async function logAnnouncement(stealthKeypair: Keypair): Promise<PublicKey> {
+      const { count } = await program.account.announcementLog.fetch(logPDA);
+      const [announcementPDA] = deriveAnnouncementPDA(count);
+
+      await program.methods
+        .logAnnouncement()
+        .accounts({
+          sender: provider.wallet.publicKey,
+          ciphertextAccount: deriveCiphertextPDA(stealthKeypair.publicKey)[0],
+          announcementLog: logPDA,
+          announcement: announcementPDA,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+
+      return announcementPDA;
+    }
+
+    it("appends announcements at sequential indices", async () => {
+      const first = Keypair.generate();
+      const second = Keypair.generate();
+      const ephemeralPubkey = randomBytes(EPHEMERAL_PUBKEY_SIZE);
+
+      await performStealthTransfer(first, ephemeralPubkey, randomBytes(MLKEM_CIPHERTEXT_SIZE), 0, 0x2a);
+      await performStealthTransfer(
+        second,
+        randomBytes(EPHEMERAL_PUBKEY_SIZE),
+        randomBytes(MLKEM_CIPHERTEXT_SIZE),
+        0
+      );
+
+      const before = await program.account.announcementLog.fetch(logPDA);
+      const firstPDA = await logAnnouncement(first);
+      const secondPDA = await logAnnouncement(second);
+
+      const after = await program.account.announcementLog.fetch(logPDA);
+      expect(after.count.sub(before.count).toNumber()).to.equal(2);
+
+      const announcement = await program.account.announcement.fetch(firstPDA);
+      expect(announcement.index.eq(before.count)).to.be.true;
+      expect(announcement.stealthPubkey.toBase58()).to.equal(first.publicKey.toBase58());
+      expect(announcement.ciphertextAccount.toBase58()).to.equal(
+        deriveCiphertextPDA(first.publicKey)[0].toBase58()
+      );
+      expect(Buffer.from(announcement.ephemeralPubkey)).to.deep.equal(ephemeralPubkey);
+      expect(announcement.viewTag).to.equal(0x2a);
+      expect(announcement.payer.toBase58()).to.equal(provider.wallet.publicKey.toBase58());
+
+      const next = await program.account.announcement.fetch(secondPDA);
+      expect(next.index.eq(before.count.addn(1))).to.be.true;
+    });
+
+    it("records the log index in the ciphertext account", async () => {
+      const stealthKeypair = Keypair.generate();
+      await performStealthTransfer(
+        stealthKeypair,
+        randomBytes(EPHEMERAL_PUBKEY_SIZE),
+        randomBytes(MLKEM_CIPHERTEXT_SIZE),
+        0
+      );
+      const announcementPDA = await logAnnouncement(stealthKeypair);
+
+      const ciphertext = await program.account.ciphertextAccount.fetch(
+        deriveCiphertextPDA(stealthKeypair.publicKey)[0]
+      );
+      const announcement = await program.account.announcement.fetch(announcementPDA);
+      expect(ciphertext.logged).to.be.true;
+      expect(ciphertext.announcementIndex.eq(announcement.index)).to.be.true;
+    });
+
+    it("rejects logging an announcement twice", async () => {
+      const stealthKeypair = Keypair.generate();
+      await performStealthTransfer(
+        stealthKeypair,
+        randomBytes(EPHEMERAL_PUBKEY_SIZE),
+        randomBytes(MLKEM_CIPHERTEXT_SIZE),
+        0
+      );
+      await logAnnouncement(stealthKeypair);
+
+      try {
+        await logAnnouncement(stealthKeypair);
+        expect.fail("Expected error for duplicate log entry");
+      } catch (err: any) {
+        expect(err.toString()).to.include("AlreadyLogged");
+      }
+    });
+
+    it("rejects logging an unfinalized announcement", async () => {
+      const stealthKeypair = Keypair.generate();
+      await writeCiphertext(
+        stealthKeypair,
+        randomBytes(EPHEMERAL_PUBKEY_SIZE),
+        randomBytes(MLKEM_CIPHERTEXT_SIZE)
+      );
+
+      try {
+        await logAnnouncement(stealthKeypair);
+        expect.fail("Expected error for unfinalized announcement");
+      } catch (err: any) {
+        expect(err.toString()).to.include("CiphertextNotFinalized");
+      }
+    });
+
+    it("rejects logging by another signer", async () => {
+      const stealthKeypair = Keypair.generate();
+      await performStealthTransfer(
+        stealthKeypair,
+        randomBytes(EPHEMERAL_PUBKEY_SIZE),
+        randomBytes(MLKEM_CIPHERTEXT_SIZE),
+        0
+      );
+
+      const attacker = Keypair.generate();
+      const airdropSig = await provider.connection.requestAirdrop(
+        attacker.publicKey,
+        0.1 * LAMPORTS_PER_SOL
+      );
+      await provider.connection.confirmTransaction(airdropSig);
+
+      const { count } = await program.account.announcementLog.fetch(logPDA);
+      try {
+        await program.methods
+          .logAnnouncement()
+          .accounts({
+            sender: attacker.publicKey,
+            ciphertextAccount: deriveCiphertextPDA(stealthKeypair.publicKey)[0],
+            announcementLog: logPDA,
+            announcement: deriveAnnouncementPDA(count)[0],
+            systemProgram: SystemProgram.programId,
+          })
+          .signers([attacker])
+          .rpc();
+
+        expect.fail("Expected error for log by another signer");
+      } catch (err: any) {
+        expect(err.toString()).to.include("ConstraintHasOne");
+      }
+    });
+
+    it("closes the log entry on reclaim", async () => {
+      const stealthKeypair = Keypair.generate();
+      await performStealthTransfer(
+        stealthKeypair,
+        randomBytes(EPHEMERAL_PUBKEY_SIZE),
+        randomBytes(MLKEM_CIPHERTEXT_SIZE),
+        0.01 * LAMPORTS_PER_SOL
+      );
+      const announcementPDA = await logAnnouncement(stealthKeypair);
+
+      await program.methods
+        .reclaimRent()
+        .accounts({
+          stealthSigner: stealthKeypair.publicKey,
+          ciphertextAccount: deriveCiphertextPDA(stealthKeypair.publicKey)[0],
+          rentPayer: null,
+          announcement: announcementPDA,
+        })
+        .signers([stealthKeypair])
+        .rpc();
+
+      expect(await provider.connection.getAccountInfo(announcementPDA)).to.be.null;
+    });
+
+    it("rejects closing another payment's log entry", async () => {
+      const logged = Keypair.generate();
+      const other = Keypair.generate();
+      for (const keypair of [logged, other]) {
+        await performStealthTransfer(
+          keypair,
+          randomBytes(EPHEMERAL_PUBKEY_SIZE),
+          randomBytes(MLKEM_CIPHERTEXT_SIZE),
+          0.01 * LAMPORTS_PER_SOL
+        );
+      }
+      const announcementPDA = await logAnnouncement(logged);
+
+      try {
+        await program.methods
+          .reclaimRent()
+          .accounts({
+            stealthSigner: other.publicKey,
+            ciphertextAccount: deriveCiphertextPDA(other.publicKey)[0],
+            rentPayer: null,
+            announcement: announcementPDA,
+          })
+          .signers([other])
+          .rpc();
+
+        expect.fail("Expected error for mismatched log entry");
+      } catch (err: any) {
+        expect(err.toString()).to.include("ConstraintRaw");
+      }
+    });
+  });
+
+  describe("reclaim_rent", () => {
+    it("closes ciphertext account and returns rent to stealth address", async () => {
+      const stealthKeypair = Keypair.generate();
+      const ephemeralPubkey = randomBytes(EPHEMERAL_PUBKEY_SIZE);
+      const mlkemCiphertext = randomBytes(MLKEM_CIPHERTEXT_SIZE);
+      const transferAmount = 0.1 * LAMPORTS_PER_SOL;
+
+      await performStealthTransfer(
+        stealthKeypair,
+        ephemeralPubkey,
+        mlkemCiphertext,
+        transferAmount
+      );
+
+      const [ciphertextPDA] = deriveCiphertextPDA(stealthKeypair.publicKey);
+
+      // Get balance before reclaim
+      const stealthBalanceBefore = await provider.connection.getBalance(stealthKeypair.publicKey);
+      const ciphertextRent = await provider.connection.getBalance(ciphertextPDA);
+
+      console.log("Stealth balance before reclaim:", stealthBalanceBefore / LAMPORTS_PER_SOL, "SOL");
+      console.log("Ciphertext account rent:", ciphertextRent / LAMPORTS_PER_SOL, "SOL");
+
+      // Reclaim rent
+      const tx = await program.methods
+        .reclaimRent()
+        .accounts({
+          stealthSigner: stealthKeypair.publicKey,
+          ciphertextAccount: ciphertextPDA,
+        })
+        .signers([stealthKeypair])
+        .rpc();
+
+      console.log("reclaim_rent tx:", tx);
+
+      // Verify ciphertext account was closed
+      const ciphertextAccountInfo = await provider.connection.getAccountInfo(ciphertextPDA);
+      expect(ciphertextAccountInfo).to.be.null;
+
+      // Verify rent was returned
+      const stealthBalanceAfter = await provider.connection.getBalance(stealthKeypair.publicKey);
+      console.log("Stealth balance after reclaim:", stealthBalanceAfter / LAMPORTS_PER_SOL, "SOL");
+
+      expect(stealthBalanceAfter).to.be.greaterThan(stealthBalanceBefore);
+    });
+
+    it("fails if non-stealth-address tries to reclaim", async () => {
+      const stealthKeypair = Keypair.generate();
+      const ephemeralPubkey = randomBytes(EPHEMERAL_PUBKEY_SIZE);
+      const mlkemCiphertext = randomBytes(MLKEM_CIPHERTEXT_SIZE);
+
+      await performStealthTransfer(stealthKeypair, ephemeralPubkey, mlkemCiphertext, 0);
+
+      const [ciphertextPDA] = deriveCiphertextPDA(stealthKeypair.publicKey);
+
+      // Try to reclaim with a different keypair
+      const attacker = Keypair.generate();
+
+      // Airdrop some SOL to attacker
+      const airdropSig = await provider.connection.requestAirdrop(
+        attacker.publicKey,
+        0.1 * LAMPORTS_PER_SOL
+      );
+      await provider.connection.confirmTransaction(airdropSig);
+
+      try {
+        await program.methods
+          .reclaimRent()
+          .accounts({
+            stealthSigner: attacker.publicKey,
+            ciphertextAccount: ciphertextPDA,
+          })
+          .signers([attacker])
+          .rpc();
+
+        expect.fail("Expected error for unauthorized reclaim");
+      } catch (err: any) {
+        expect(err.toString()).to.include("ConstraintSeeds");
+      }
+    });
+  });
+
+  describe("refund_expired", () => {
+    async function clusterTime(): Promise<number> {
+      const slot = await provider.connection.getSlot();
+      return (await provider.connection.getBlockTime(slot))!;
+    }
+
+    async function initExpiring(
+      stealthKeypair: Keypair,
+      expiresAt: number,
+      rentPayer?: Keypair
+    ): Promise<PublicKey> {
+      const [ciphertextPDA] = deriveCiphertextPDA(stealthKeypair.publicKey);
+      await program.methods
+        .initCiphertext(
+          Array.from(randomBytes(EPHEMERAL_PUBKEY_SIZE)),
+          toChunk(randomBytes(CHUNK_SIZE)),
+          new BN(expiresAt),
+          DEFAULT_APP_ID,
+          0,
+          0,
+          KEM_VARIANT_ML_KEM_768
+        )
+        .accounts({
+          sender: provider.wallet.publicKey,
+          rentPayer: rentPayer?.publicKey ?? provider.wallet.publicKey,
+          stealthAddress: stealthKeypair.publicKey,
+          ciphertextAccount: ciphertextPDA,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers(rentPayer ? [rentPayer] : [])
+        .rpc();
+      return ciphertextPDA;
+    }
+
+    function refund(
+      ciphertextPDA: PublicKey,
+      rentPayer = provider.wallet.publicKey,
+      announcement: PublicKey | null = null
+    ) {
+      return program.methods
+        .refundExpired()
+        .accounts({
+          sender: provider.wallet.publicKey,
+          ciphertextAccount: ciphertextPDA,
+          rentPayer,
+          announcement,
+        })
+        .rpc();
+    }
+
+    async function waitUntil(timestamp: number): Promise<void> {
+      while ((await clusterTime()) < timestamp) {
+        await new Promise((resolve) => setTimeout(resolve, 500));
+      }
+    }
+
+    it("lets the sender close the account after expiry", async () => {
+      const expiresAt = (await clusterTime()) + 2;
+      const ciphertextPDA = await initExpiring(Keypair.generate(), expiresAt);
+
+      await waitUntil(expiresAt);
+
+      const rent = await provider.connection.getBalance(ciphertextPDA);
+      const balanceBefore = await provider.connection.getBalance(provider.wallet.publicKey);
+      await refund(ciphertextPDA);
+
+      expect(await provider.connection.getAccountInfo(ciphertextPDA)).to.be.null;
+      const balanceAfter = await provider.connection.getBalance(provider.wallet.publicKey);
+      // Rent back, minus the transaction fee
+      expect(balanceAfter).to.be.greaterThan(balanceBefore + rent - 10_000);
+    });
+
+    it("returns the log entry's rent to the sender who logged it", async () => {
+      const sponsor = Keypair.generate();
+      const airdrop = await provider.connection.requestAirdrop(sponsor.publicKey, LAMPORTS_PER_SOL);
+      await provider.connection.confirmTransaction(airdrop);
+
+      const stealthKeypair = Keypair.generate();
+      const expiresAt = (await clusterTime()) + 4;
+      const ciphertextPDA = await initExpiring(stealthKeypair, expiresAt, sponsor);
+      await finalizeCiphertext(ciphertextPDA);
+
+      const [logPDA] = deriveAnnouncementLogPDA();
+      const { count } = await program.account.announcementLog.fetch(logPDA);
+      const [announcementPDA] = deriveAnnouncementPDA(count);
+      await program.methods
+        .logAnnouncement()
+        .accounts({
+          sender: provider.wallet.publicKey,
+          ciphertextAccount: ciphertextPDA,
+          announcementLog: logPDA,
+          announcement: announcementPDA,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+
+      await waitUntil(expiresAt);
+
+      const rent = await provider.connection.getBalance(ciphertextPDA);
+      const logRent = await provider.connection.getBalance(announcementPDA);
+      const sponsorBefore = await provider.connection.getBalance(sponsor.publicKey);
+      const senderBefore = await provider.connection.getBalance(provider.wallet.publicKey);
+      await refund(ciphertextPDA, sponsor.publicKey, announcementPDA);
+
+      expect(await provider.connection.getAccountInfo(ciphertextPDA)).to.be.null;
+      expect(await provider.connection.getAccountInfo(announcementPDA)).to.be.null;
+      // The sponsor only gets the ciphertext account's rent back
+      const sponsorAfter = await provider.connection.getBalance(sponsor.publicKey);
+      expect(sponsorAfter - sponsorBefore).to.equal(rent);
+      // The log entry's rent, minus the transaction fee
+      const senderAfter = await provider.connection.getBalance(provider.wallet.publicKey);
+      expect(senderAfter).to.be.greaterThan(senderBefore + logRent - 10_000);
+    });
+
+    it("rejects a refund before expiry", async () => {
+      const ciphertextPDA = await initExpiring(Keypair.generate(), (await clusterTime()) + 3600);
+
+      try {
+        await refund(ciphertextPDA);
+        expect.fail("Expected error for refund before expiry");
+      } catch (err: any) {
+        expect(err.toString()).to.include("NotExpired");
+      }
+    });
+
+    it("rejects a refund of an announcement without expiry", async () => {
+      const stealthKeypair = Keypair.generate();
+      await performStealthTransfer(
+        stealthKeypair,
+        randomBytes(EPHEMERAL_PUBKEY_SIZE),
+        randomBytes(MLKEM_CIPHERTEXT_SIZE),
+        0
+      );
+
+      try {
+        await refund(deriveCiphertextPDA(stealthKeypair.publicKey)[0]);
+        expect.fail("Expected error for refund without expiry");
+      } catch (err: any) {
+        expect(err.toString()).to.include("NotExpired");
+      }
+    });
+  });
+
+  describe("rent split", () => {
+    // Announces to the stealth address with the sponsor paying rent and keeping `shareBps` on close
+    function announceWithSplit(sponsor: Keypair, stealthKeypair: Keypair, shareBps: number) {
+      return program.methods
+        .initCiphertext(
+          Array.from(randomBytes(EPHEMERAL_PUBKEY_SIZE)),
+          toChunk(randomBytes(CHUNK_SIZE)),
+          null,
+          DEFAULT_APP_ID,
+          shareBps,
+          0,
+          KEM_VARIANT_ML_KEM_768
+        )
+        .accounts({
+          sender: provider.wallet.publicKey,
+          rentPayer: sponsor.publicKey,
+          stealthAddress: stealthKeypair.publicKey,
+          ciphertextAccount: deriveCiphertextPDA(stealthKeypair.publicKey)[0],
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([sponsor])
+        .rpc();
+    }
+
+    const sponsor = Keypair.generate();
+
+    before(async () => {
+      const airdrop = await provider.connection.requestAirdrop(sponsor.publicKey, LAMPORTS_PER_SOL);
+      await provider.connection.confirmTransaction(airdrop);
+    });
+
+    it("returns the rent payer's share on close", async () => {
+      const stealthKeypair = Keypair.generate();
+      const [ciphertextPDA] = deriveCiphertextPDA(stealthKeypair.publicKey);
+
+      await announceWithSplit(sponsor, stealthKeypair, 2500);
+
+      const account = await program.account.ciphertextAccount.fetch(ciphertextPDA);
+      expect(account.rentPayer.toBase58()).to.equal(sponsor.publicKey.toBase58());
+      expect(account.rentPayerShareBps).to.equal(2500);
+
+      const rent = await provider.connection.getBalance(ciphertextPDA);
+      const sponsorBalanceBefore = await provider.connection.getBalance(sponsor.publicKey);
+
+      await program.methods
+        .reclaimRent()
+        .accounts({
+          stealthSigner: stealthKeypair.publicKey,
+          ciphertextAccount: ciphertextPDA,
+          rentPayer: sponsor.publicKey,
+        })
+        .signers([stealthKeypair])
+        .rpc();
+
+      const share = Math.floor((rent * 2500) / 10000);
+      const sponsorBalanceAfter = await provider.connection.getBalance(sponsor.publicKey);
+      expect(sponsorBalanceAfter - sponsorBalanceBefore).to.equal(share);
+
+      const stealthBalance = await provider.connection.getBalance(stealthKeypair.publicKey);
+      expect(stealthBalance).to.equal(rent - share);
+    });
+
+    it("charges the sender the rent when the rent payer is omitted", async () => {
+      const stealthKeypair = Keypair.generate();
+      const [ciphertextPDA] = deriveCiphertextPDA(stealthKeypair.publicKey);
+      const senderBalanceBefore = await provider.connection.getBalance(provider.wallet.publicKey);
+
+      await program.methods
+        .initCiphertext(
+          Array.from(randomBytes(EPHEMERAL_PUBKEY_SIZE)),
+          toChunk(randomBytes(CHUNK_SIZE)),
+          null,
+          DEFAULT_APP_ID,
+          0,
+          0,
+          KEM_VARIANT_ML_KEM_768
+        )
+        .accounts({
+          sender: provider.wallet.publicKey,
+          rentPayer: null,
+          stealthAddress: stealthKeypair.publicKey,
+          ciphertextAccount: ciphertextPDA,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+
+      const account = await program.account.ciphertextAccount.fetch(ciphertextPDA);
+      expect(account.rentPayer.toBase58()).to.equal(provider.wallet.publicKey.toBase58());
+
+      const rent = await provider.connection.getBalance(ciphertextPDA);
+      const senderBalanceAfter = await provider.connection.getBalance(provider.wallet.publicKey);
+      expect(senderBalanceBefore - senderBalanceAfter).to.be.at.least(rent);
+    });
+
+    it("requires the rent payer account when a share is set", async () => {
+      const stealthKeypair = Keypair.generate();
+
+      await announceWithSplit(sponsor, stealthKeypair, 2500);
+
+      try {
+        await program.methods
+          .reclaimRent()
+          .accounts({
+            stealthSigner: stealthKeypair.publicKey,
+            ciphertextAccount: deriveCiphertextPDA(stealthKeypair.publicKey)[0],
+          })
+          .signers([stealthKeypair])
+          .rpc();
+        expect.fail("Expected error for missing rent payer");
+      } catch (err: any) {
+        expect(err.toString()).to.include("MissingRentPayer");
+      }
+    });
+
+    it("rejects a share above 100%", async () => {
+      try {
+        await announceWithSplit(sponsor, Keypair.generate(), 10001);
+        expect.fail("Expected error for invalid rent share");
+      } catch (err: any) {
+        expect(err.toString()).to.include("InvalidRentShare");
+      }
+    });
+  });
+
+  describe("sponsored claims", () => {
+    const [sponsorPoolPDA] = PublicKey.findProgramAddressSync(
+      [Buffer.from("sponsor_pool")],
+      program.programId
+    );
+
+    before(async () => {
+      await program.methods
+        .initSponsorPool()
+        .accounts({
+          payer: provider.wallet.publicKey,
+          sponsorPool: sponsorPoolPDA,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+
+      await program.methods
+        .fundSponsorPool(new BN(0.1 * LAMPORTS_PER_SOL))
+        .accounts({
+          funder: provider.wallet.publicKey,
+          sponsorPool: sponsorPoolPDA,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+    });
+
+    // Sends sponsored_reclaim_rent with the relayer as transaction fee payer
+    async function sponsoredReclaim(stealthKeypair: Keypair, relayer: Keypair): Promise<string> {
+      const [claimRecordPDA] = PublicKey.findProgramAddressSync(
+        [Buffer.from("claim"), relayer.publicKey.toBuffer()],
+        program.programId
+      );
+
+      const tx = new anchor.web3.Transaction().add(
+        await program.methods
+          .sponsoredReclaimRent()
+          .accounts({
+            feePayer: relayer.publicKey,
+            stealthSigner: stealthKeypair.publicKey,
+            ciphertextAccount: deriveCiphertextPDA(stealthKeypair.publicKey)[0],
+            sponsorPool: sponsorPoolPDA,
+            claimRecord: claimRecordPDA,
+            systemProgram: SystemProgram.programId,
+          })
+          .instruction()
+      );
+      tx.feePayer = relayer.publicKey;
+
+      return anchor.web3.sendAndConfirmTransaction(provider.connection, tx, [
+        relayer,
+        stealthKeypair,
+      ]);
+    }
+
+    // A payment above the minimum balance
+    const PAYMENT_LAMPORTS = 0.01 * LAMPORTS_PER_SOL;
+
+    async function fundedRelayer(): Promise<Keypair> {
+      const relayer = Keypair.generate();
+      const airdrop = await provider.connection.requestAirdrop(relayer.publicKey, LAMPORTS_PER_SOL);
+      await provider.connection.confirmTransaction(airdrop);
+      return relayer;
+    }
+
+    // Plain system transfer, as an attacker would fund their own throwaway keypairs
+    async function fundAddress(address: PublicKey, lamports: number): Promise<void> {
+      await provider.sendAndConfirm(
+        new anchor.web3.Transaction().add(
+          SystemProgram.transfer({ fromPubkey: provider.wallet.publicKey, toPubkey: address, lamports })
+        )
+      );
+    }
+
+    async function claimError(stealthKeypair: Keypair, relayer: Keypair): Promise<string> {
+      try {
+        await sponsoredReclaim(stealthKeypair, relayer);
+      } catch (err: any) {
+        // Sent without the Anchor provider, so the error name is only in the logs
+        return (err.logs ?? []).join("\n");
+      }
+      return expect.fail("Expected the sponsored claim to be rejected");
+    }
+
+    async function fundedPayment(): Promise<Keypair> {
+      const stealthKeypair = Keypair.generate();
+      await performStealthTransfer(
+        stealthKeypair,
+        randomBytes(EPHEMERAL_PUBKEY_SIZE),
+        randomBytes(MLKEM_CIPHERTEXT_SIZE),
+        PAYMENT_LAMPORTS
+      );
+      return stealthKeypair;
+    }
+
+    it("reimburses the relayer from the pool", async () => {
+      const relayer = await fundedRelayer();
+      const stealthKeypair = await fundedPayment();
+
+      // The first claim pays for the relayer's ClaimRecord
+      await sponsoredReclaim(await fundedPayment(), relayer);
+
+      const relayerBalanceBefore = await provider.connection.getBalance(relayer.publicKey);
+      const poolBefore = await program.account.sponsorPool.fetch(sponsorPoolPDA);
+
+      // The relayer signs the transaction as fee payer; the recipient holds only the payment
+      await sponsoredReclaim(stealthKeypair, relayer);
+
+      const relayerBalanceAfter = await provider.connection.getBalance(relayer.publicKey);
+      expect(relayerBalanceAfter).to.be.at.least(relayerBalanceBefore);
+
+      const poolAfter = await program.account.sponsorPool.fetch(sponsorPoolPDA);
+      expect(poolAfter.totalClaims.sub(poolBefore.totalClaims).toNumber()).to.equal(1);
+    });
+
+    it("rate-limits per relayer across stealth addresses", async () => {
+      const relayer = await fundedRelayer();
+
+      for (let i = 0; i < MAX_SPONSORED_CLAIMS_PER_INTERVAL; i++) {
+        await sponsoredReclaim(await fundedPayment(), relayer);
+      }
+
+      // Each claim closed its ciphertext account; the limit lives in the relayer's record
+      expect(await claimError(await fundedPayment(), relayer)).to.include("ClaimRateLimited");
+
+      // Another relayer isn't limited by the first one's claims
+      await sponsoredReclaim(await fundedPayment(), await fundedRelayer());
+    });
+
+    it("rejects fresh throwaway stealth addresses", async () => {
+      const relayer = await fundedRelayer();
+      const poolBefore = await program.account.sponsorPool.fetch(sponsorPoolPDA);
+
+      // Below the minimum balance
+      const dust = 0.002 * LAMPORTS_PER_SOL;
+
+      // The sybil loop: a new keypair per claim, each with an unfinalized announcement
+      for (let i = 0; i < 3; i++) {
+        const stealthKeypair = Keypair.generate();
+        await fundAddress(stealthKeypair.publicKey, dust);
+        await writeCiphertext(
+          stealthKeypair,
+          randomBytes(EPHEMERAL_PUBKEY_SIZE),
+          randomBytes(MLKEM_CIPHERTEXT_SIZE)
+        );
+        expect(await claimError(stealthKeypair, relayer)).to.include("CiphertextNotFinalized");
+      }
+
+      // Finalizing isn't enough without a real payment at the stealth address
+      const unfunded = Keypair.generate();
+      await fundAddress(unfunded.publicKey, dust);
+      await performStealthTransfer(
+        unfunded,
+        randomBytes(EPHEMERAL_PUBKEY_SIZE),
+        randomBytes(MLKEM_CIPHERTEXT_SIZE),
+        0
+      );
+      expect(await claimError(unfunded, relayer)).to.include("SponsoredClaimBalanceTooLow");
+
+      const poolAfter = await program.account.sponsorPool.fetch(sponsorPoolPDA);
+      expect(poolAfter.totalClaims.eq(poolBefore.totalClaims)).to.be.true;
+      expect(SPONSORED_CLAIM_MIN_BALANCE).to.be.lessThan(PAYMENT_LAMPORTS);
+    });
+  });
+
+  describe("meta-address registry", () => {
+    const owner = provider.wallet.publicKey;
+    const [metaAddressPDA] = deriveMetaAddressPDA(owner);
+
+    async function writeEncapsulationKey(key: Buffer) {
+      for (let offset = 0; offset < key.length; offset += MAX_CHUNK_SIZE) {
+        await program.methods
+          .writeMetaAddressKey(toChunk(key.slice(offset, offset + MAX_CHUNK_SIZE)), offset)
+          .accounts({ owner, metaAddress: metaAddressPDA })
+          .rpc();
+      }
+    }
+
+    it("registers a hybrid meta-address", async () => {
+      const spending = randomBytes(32);
+      const viewing = randomBytes(32);
+      const encapsulationKey = randomBytes(MLKEM_ENCAPSULATION_KEY_SIZE);
+
+      await program.methods
+        .registerMetaAddress(Array.from(spending), Array.from(viewing))
+        .accounts({ owner, metaAddress: metaAddressPDA, systemProgram: SystemProgram.programId })
+        .rpc();
+      await writeEncapsulationKey(encapsulationKey);
+
+      const registry = await program.account.metaAddressRegistry.fetch(metaAddressPDA);
+      expect(registry.owner.toBase58()).to.equal(owner.toBase58());
+      expect(Buffer.from(registry.spendingPubkey)).to.deep.equal(spending);
+      expect(Buffer.from(registry.viewingPubkey)).to.deep.equal(viewing);
+      expect(Buffer.from(registry.mlkemEncapsulationKey)).to.deep.equal(encapsulationKey);
+      expect(registry.epoch).to.equal(1);
+    });
+
+    it("bumps the epoch and clears the encapsulation key on update", async () => {
+      const spending = randomBytes(32);
+
+      await program.methods
+        .updateMetaAddress(Array.from(spending), Array.from(randomBytes(32)))
+        .accounts({ owner, metaAddress: metaAddressPDA })
+        .rpc();
+
+      const registry = await program.account.metaAddressRegistry.fetch(metaAddressPDA);
+      expect(Buffer.from(registry.spendingPubkey)).to.deep.equal(spending);
+      expect(registry.mlkemEncapsulationKey.every((b: number) => b === 0)).to.be.true;
+      expect(registry.epoch).to.equal(2);
+    });
+
+    it("rejects writes past the encapsulation key", async () => {
+      try {
+        await program.methods
+          .writeMetaAddressKey(toChunk(randomBytes(32)), MLKEM_ENCAPSULATION_KEY_SIZE - 16)
+          .accounts({ owner, metaAddress: metaAddressPDA })
+          .rpc();
+        expect.fail("Expected error for write past the encapsulation key");
+      } catch (err: any) {
+        expect(err.toString()).to.include("InvalidEncapsulationKeyLength");
+      }
+    });
+
+    const [attestationPDA] = deriveEvmAttestationPDA(owner);
+    const evmMetaAddress = randomBytes(EVM_META_ADDRESS_SIZE);
+
+    // EIP-191 personal_sign message, as built by evm_attestation_message
+    const text = `stealth-pq: link ${owner.toBase58()} to st:eth:0x${evmMetaAddress.toString("hex")}`;
+    const message = Buffer.from(`\x19Ethereum Signed Message:\n${text.length}${text}`);
+
+    // The precompile instruction must come first, so its offsets point at index 0
+    function attest(signed: Buffer, evmAddress: Buffer | null) {
+      const secp = Secp256k1Program.createInstructionWithPrivateKey({
+        privateKey: randomBytes(32),
+        message: signed,
+      });
+      return program.methods
+        .attestEvmMetaAddress(Array.from(evmAddress ?? secp.data.slice(12, 32)), Array.from(evmMetaAddress))
+        .accounts({
+          owner,
+          metaAddress: metaAddressPDA,
+          attestation: attestationPDA,
+          instructionsSysvar: SYSVAR_INSTRUCTIONS_PUBKEY,
+          systemProgram: SystemProgram.programId,
+        })
+        .preInstructions([secp]);
+    }
+
+    it("rejects an EVM attestation signed over another message", async () => {
+      try {
+        await attest(Buffer.from("something else"), null).rpc();
+        expect.fail("Expected error for a signature over another message");
+      } catch (err: any) {
+        expect(err.toString()).to.include("MissingEvmSignature");
+      }
+    });
+
+    it("rejects an EVM attestation signed by another account", async () => {
+      try {
+        await attest(message, randomBytes(20)).rpc();
+        expect.fail("Expected error for a signature by another account");
+      } catch (err: any) {
+        expect(err.toString()).to.include("MissingEvmSignature");
+      }
+    });
+
+    it("links the registered meta-address to the signing EVM account", async () => {
+      await attest(message, null).rpc();
+
+      const attestation = await program.account.evmAttestation.fetch(attestationPDA);
+      expect(attestation.owner.toBase58()).to.equal(owner.toBase58());
+      expect(Buffer.from(attestation.evmMetaAddress)).to.deep.equal(evmMetaAddress);
+      expect(attestation.attestedAt.toNumber()).to.be.greaterThan(0);
+    });
+
+    it("closes the EVM attestation on revoke", async () => {
+      await program.methods
+        .revokeEvmAttestation()
+        .accounts({ owner, attestation: attestationPDA })
+        .rpc();
+
+      expect(await provider.connection.getAccountInfo(attestationPDA)).to.be.null;
+    });
+
+    it("closes the entry and returns rent to the owner", async () => {
+      await program.methods
+        .closeMetaAddress()
+        .accounts({ owner, metaAddress: metaAddressPDA })
+        .rpc();
+
+      const account = await provider.connection.getAccountInfo(metaAddressPDA);
+      expect(account).to.be.null;
+    });
+  });
+
+  describe("staging buffer", () => {
+    const bufferId = new BN(Date.now());
+
+    // Helper to upload a full ciphertext into the staging buffer
+    async function writeBuffer(bufferPDA: PublicKey, mlkemCiphertext: Buffer): Promise<void> {
+      for (let offset = 0; offset < MLKEM_CIPHERTEXT_SIZE; offset += CHUNK_SIZE) {
+        await program.methods
+          .writeBuffer(toChunk(mlkemCiphertext.slice(offset, offset + CHUNK_SIZE)), offset)
+          .accounts({
+            authority: provider.wallet.publicKey,
+            buffer: bufferPDA,
+          })
+          .rpc();
+      }
+    }
+
+    it("commits a buffered ciphertext and can be reused", async () => {
+      const [bufferPDA] = deriveBufferPDA(provider.wallet.publicKey, bufferId);
+
+      await program.methods
+        .initBuffer(bufferId)
+        .accounts({
+          authority: provider.wallet.publicKey,
+          buffer: bufferPDA,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+
+      for (let i = 0; i < 2; i++) {
+        const stealthAddress = Keypair.generate();
+        const ephemeralPubkey = randomBytes(EPHEMERAL_PUBKEY_SIZE);
+        const mlkemCiphertext = randomBytes(MLKEM_CIPHERTEXT_SIZE);
+        const [ciphertextPDA] = deriveCiphertextPDA(stealthAddress.publicKey);
+
+        await writeBuffer(bufferPDA, mlkemCiphertext);
+
+        // Nothing is visible under the stealth address until the commit
+        expect(await provider.connection.getAccountInfo(ciphertextPDA)).to.be.null;
+
+        await program.methods
+          .commitBuffer(Array.from(ephemeralPubkey), null, DEFAULT_APP_ID, 0, 0)
+          .accounts({
+            authority: provider.wallet.publicKey,
+            stealthAddress: stealthAddress.publicKey,
+            buffer: bufferPDA,
+            ciphertextAccount: ciphertextPDA,
+            systemProgram: SystemProgram.programId,
+          })
+          .rpc();
+
+        const ciphertextAccount = await program.account.ciphertextAccount.fetch(ciphertextPDA);
+        expect(Buffer.from(ciphertextAccount.mlkemCiphertext).equals(mlkemCiphertext)).to.be.true;
+        expect(Buffer.from(ciphertextAccount.ephemeralPubkey).equals(ephemeralPubkey)).to.be.true;
+
+        // The buffer is cleared for the next payment
+        const buffer = await program.account.stagingBuffer.fetch(bufferPDA);
+        expect(Buffer.from(buffer.mlkemCiphertext).equals(Buffer.alloc(MLKEM_CIPHERTEXT_SIZE))).to.be.true;
+      }
+    });
+
+    it("closes the buffer and returns rent to the authority", async () => {
+      const [bufferPDA] = deriveBufferPDA(provider.wallet.publicKey, bufferId);
+
+      await program.methods
+        .closeBuffer()
+        .accounts({
+          authority: provider.wallet.publicKey,
+          buffer: bufferPDA,
+        })
+        .rpc();
+
+      expect(await provider.connection.getAccountInfo(bufferPDA)).to.be.null;
+    });
   });
 
   describe("full flow", () => {